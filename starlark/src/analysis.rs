@@ -24,6 +24,7 @@ pub use types::EvalMessage;
 pub use types::EvalSeverity;
 pub use types::Lint;
 pub use unused_loads::remove::remove_unused_loads;
+pub use unused_loads::remove::unused_load_removals;
 
 use crate::analysis::types::LintT;
 use crate::syntax::AstModule;
@@ -128,17 +129,13 @@ def good1(items):
         assert!(res[4].problem.contains("`e`"));
         assert!(res[5].problem.contains("`f`"));
         assert!(res[6].original.contains("all({\"a\": a for a in []})"));
-        assert!(
-            res[7]
-                .problem
-                .contains("`any(list({}))` allocates a new list")
-        );
+        assert!(res[7]
+            .problem
+            .contains("`any(list({}))` allocates a new list"));
         assert!(res[8].original.contains("all({\"e\": e for e in []})"));
-        assert!(
-            res[9]
-                .problem
-                .contains("`any(list({}))` allocates a new list")
-        );
+        assert!(res[9]
+            .problem
+            .contains("`any(list({}))` allocates a new list"));
     }
 
     #[test]
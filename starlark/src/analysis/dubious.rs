@@ -65,6 +65,7 @@ fn duplicate_dictionary_key(module: &AstModule, res: &mut Vec<LintT<Dubious>>) {
         Int(StarlarkInt),
         Float(u64),
         String(&'a str),
+        Bytes(&'a [u8]),
         Identifier(&'a str),
     }
 
@@ -85,6 +86,7 @@ fn duplicate_dictionary_key(module: &AstModule, res: &mut Vec<LintT<Dubious>>) {
                     }
                 }
                 AstLiteral::String(x) => Some((Key::String(&x.node), x.span)),
+                AstLiteral::Bytes(x) => Some((Key::Bytes(&x.node), x.span)),
                 AstLiteral::Ellipsis => None,
             },
             Expr::Identifier(x) => Some((Key::Identifier(&x.node.ident), x.span)),
@@ -184,9 +186,7 @@ mod tests {
         duplicate_dictionary_key(&m, &mut res);
         assert_eq!(
             res.map(|x| x.problem.about()),
-            &[
-                "\"no1\"", "42", "\"no2\"", "123", "0.25", "no3", "no3", "no4"
-            ]
+            &["\"no1\"", "42", "\"no2\"", "123", "0.25", "no3", "no3", "no4"]
         );
     }
 
@@ -20,8 +20,11 @@
 use starlark_syntax::syntax::ast::Argument;
 use starlark_syntax::syntax::ast::AstExpr;
 use starlark_syntax::syntax::ast::AstLiteral;
+use starlark_syntax::syntax::ast::AstNoPayload;
 use starlark_syntax::syntax::ast::Expr;
+use starlark_syntax::syntax::ast::Stmt;
 use starlark_syntax::syntax::module::AstModuleFields;
+use starlark_syntax::syntax::uniplate::Visit;
 
 use crate::codemap::Span;
 use crate::codemap::Spanned;
@@ -36,6 +39,56 @@ pub trait AstModuleFindCallName {
     /// NOTE: If the AST is exposed in the future, this function may be removed and implemented
     ///       by specific programs instead.
     fn find_function_call_with_name(&self, name: &str) -> Option<Span>;
+
+    /// Find all the calls of a function by its name, e.g. `foo(1)` but not `x.foo(1)`,
+    /// in source order.
+    fn find_function_calls(&self) -> Vec<FunctionCall>;
+}
+
+/// A call of a function by name, as found by [`AstModuleFindCallName::find_function_calls`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCall {
+    /// The name of the function that is called.
+    pub name: String,
+    /// The location of the name of the function in the call.
+    pub span: Span,
+    /// The name of the innermost `def` the call is in, or `None` for calls at the
+    /// top level of the module.
+    pub caller: Option<Spanned<String>>,
+}
+
+fn visit_calls(
+    node: Visit<AstNoPayload>,
+    caller: Option<&Spanned<String>>,
+    res: &mut Vec<FunctionCall>,
+) {
+    match node {
+        Visit::Stmt(Spanned {
+            node: Stmt::Def(def),
+            ..
+        }) => {
+            let caller = Spanned {
+                node: def.name.ident.clone(),
+                span: def.name.span,
+            };
+            node.visit_children(|x| visit_calls(x, Some(&caller), res));
+            return;
+        }
+        Visit::Expr(Spanned {
+            node: Expr::Call(function, _),
+            ..
+        }) => {
+            if let Expr::Identifier(ident) = &function.node {
+                res.push(FunctionCall {
+                    name: ident.node.ident.clone(),
+                    span: function.span,
+                    caller: caller.cloned(),
+                });
+            }
+        }
+        _ => {}
+    }
+    node.visit_children(|x| visit_calls(x, caller, res));
 }
 
 impl AstModuleFindCallName for AstModule {
@@ -82,6 +135,13 @@ impl AstModuleFindCallName for AstModule {
             .visit_expr(|x| visit_expr(&mut ret, name, x));
         ret
     }
+
+    fn find_function_calls(&self) -> Vec<FunctionCall> {
+        let mut res = Vec::new();
+        visit_calls(Visit::Stmt(self.statement()), None, &mut res);
+        res.sort_by_key(|call| call.span.begin());
+        res
+    }
 }
 
 #[cfg(test)]
@@ -124,4 +184,45 @@ def x(name = "foo_name"):
         assert_eq!(None, module.find_function_call_with_name("bar_name"));
         Ok(())
     }
+
+    #[test]
+    fn finds_function_calls_and_their_callers() -> anyhow::Result<()> {
+        let contents = r#"
+def outer():
+    def inner():
+        return foo(1)
+    return bar(inner(), x.baz())
+
+qux(outer)
+"#;
+
+        let module = AstModule::parse(
+            "foo.star",
+            contents.to_owned(),
+            &Dialect::AllOptionsInternal,
+        )
+        .unwrap();
+
+        let calls: Vec<(String, u32, Option<String>)> = module
+            .find_function_calls()
+            .into_iter()
+            .map(|call| {
+                (
+                    call.name,
+                    module.codemap().resolve_span(call.span).begin.line as u32,
+                    call.caller.map(|caller| caller.node),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("foo".to_owned(), 3, Some("inner".to_owned())),
+                ("bar".to_owned(), 4, Some("outer".to_owned())),
+                ("inner".to_owned(), 4, Some("outer".to_owned())),
+                ("qux".to_owned(), 6, None),
+            ],
+            calls
+        );
+        Ok(())
+    }
 }
@@ -23,6 +23,7 @@ use starlark_syntax::syntax::ast::DefP;
 use starlark_syntax::syntax::ast::Expr;
 use starlark_syntax::syntax::ast::ForP;
 use starlark_syntax::syntax::ast::Stmt;
+use starlark_syntax::syntax::ast::WhileP;
 use starlark_syntax::syntax::module::AstModuleFields;
 use thiserror::Error;
 
@@ -100,6 +101,30 @@ fn is_fail(x: &AstExpr) -> bool {
     }
 }
 
+// `while True:` without a `break` never finishes normally
+fn is_infinite_loop(x: &AstStmt) -> bool {
+    fn has_break(x: &AstStmt) -> bool {
+        match &**x {
+            Stmt::Break => true,
+            // A `break` inside these does not exit our loop
+            Stmt::For(..) | Stmt::While(..) | Stmt::Def(..) => false,
+            _ => {
+                let mut res = false;
+                x.visit_stmt(|x| res = res || has_break(x));
+                res
+            }
+        }
+    }
+
+    match &**x {
+        Stmt::While(WhileP { cond, body }) => match &**cond {
+            Expr::Identifier(name) => name.node.ident == "True" && !has_break(body),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 fn has_effect(x: &AstExpr) -> bool {
     match &**x {
         Expr::Literal(x) => {
@@ -107,7 +132,7 @@ fn has_effect(x: &AstExpr) -> bool {
             matches!(x, AstLiteral::String(_))
         }
         Expr::Lambda(_) => false,
-        Expr::If(_) | Expr::Tuple(_) | Expr::List(_) | Expr::Dict(_) => {
+        Expr::If(_) | Expr::Tuple(_) | Expr::List(_) | Expr::Dict(_) | Expr::Set(_) => {
             let mut res = false;
             x.visit_expr(|x| res = res || has_effect(x));
             res
@@ -120,6 +145,7 @@ fn final_return(x: &AstStmt) -> bool {
     match &**x {
         Stmt::Return(_) => true,
         Stmt::Expression(x) if is_fail(x) => true,
+        Stmt::While(..) => is_infinite_loop(x),
         Stmt::Statements(xs) => match xs.last() {
             None => false,
             Some(x) => final_return(x),
@@ -192,7 +218,8 @@ fn stmt(codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>>) {
     x.visit_stmt(|x| stmt(codemap, x, res));
 }
 
-// Returns true if the code aborts this sequence early, due to return, fail, break or continue
+// Returns true if the code aborts this sequence early, due to return, fail, break, continue
+// or an infinite loop
 fn reachable(codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>>) -> bool {
     match &**x {
         Stmt::Break | Stmt::Continue | Stmt::Return(_) => true,
@@ -222,6 +249,12 @@ fn reachable(codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>>) ->
             let abort2 = reachable(codemap, y, res);
             abort1 && abort2
         }
+        Stmt::While(..) => {
+            x.visit_stmt(|x| {
+                reachable(codemap, x, res);
+            });
+            is_infinite_loop(x)
+        }
         // For all remaining constructs, visit their children to accumulate errors,
         // but even if they are present with returns, you don't guarantee the code with inner returns
         // gets executed.
@@ -260,7 +293,9 @@ fn redundant(codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>>) {
 
     fn f(codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>>) {
         match &**x {
-            Stmt::For(ForP { body, .. }) => check(true, codemap, body, res),
+            Stmt::For(ForP { body, .. }) | Stmt::While(WhileP { body, .. }) => {
+                check(true, codemap, body, res)
+            }
             Stmt::Def(DefP { body, .. }) => check(false, codemap, body, res),
             _ => {}
         }
@@ -379,13 +414,21 @@ def yes3():
     pass
 def yes4() -> str:
     fail("die")
+def yes5() -> str:
+    while True:
+        if x:
+            return "x"
+def no5() -> str:
+    while True:
+        if x:
+            break
 "#,
         );
         let mut res = Vec::new();
         stmt(m.codemap(), m.statement(), &mut res);
         assert_eq!(
             res.map(|x| x.problem.about()),
-            &["no1", "no2", "no3", "no4"]
+            &["no1", "no2", "no3", "no4", "no5"]
         );
     }
 
@@ -426,13 +469,23 @@ def f():
     def g():
         return 5
     reachable
+def test7():
+    while True:
+        for x in xs:
+            break
+    no5
+def test8():
+    while True:
+        if x:
+            break
+    reachable
 "#,
         );
         let mut res = Vec::new();
         reachable(m.codemap(), m.statement(), &mut res);
         assert_eq!(
             res.map(|x| x.problem.about()),
-            &["no1", "no2", "no3", "no4"]
+            &["no1", "no2", "no3", "no4", "no5"]
         );
     }
 
@@ -469,13 +522,16 @@ def test7():
         if x:
             continue
         return
+def test8(): # 30
+    while x:
+        continue # bad: 32
 "#,
         );
         let mut res = Vec::new();
         redundant(m.codemap(), m.statement(), &mut res);
         assert_eq!(
             res.map(|x| x.location.resolve_span().begin.line),
-            &[3, 9, 19]
+            &[3, 9, 19, 32]
         );
     }
 
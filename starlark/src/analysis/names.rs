@@ -43,6 +43,7 @@ use starlark_syntax::syntax::ast::ForClause;
 use starlark_syntax::syntax::ast::ForP;
 use starlark_syntax::syntax::ast::LoadArgP;
 use starlark_syntax::syntax::ast::Stmt;
+use starlark_syntax::syntax::ast::WhileP;
 use starlark_syntax::syntax::module::AstModuleFields;
 use thiserror::Error;
 
@@ -437,6 +438,7 @@ impl<'a> State<'a> {
             }
             Expr::ListComprehension(a, b, c) => self.comprehension(a, None, b, c),
             Expr::DictComprehension(a, b, c) => self.comprehension(&a.0, Some(&a.1), b, c),
+            Expr::SetComprehension(a, b, c) => self.comprehension(a, None, b, c),
             _ => expr.visit_expr(|x| self.expr(x)),
         }
     }
@@ -505,6 +507,12 @@ impl<'a> State<'a> {
                     me.stmt(body);
                 });
             }
+            Stmt::While(WhileP { cond, body }) => {
+                self.loops(|me| {
+                    me.expr(cond);
+                    me.stmt(body);
+                });
+            }
             Stmt::Def(x) => {
                 for p in &x.params {
                     p.node.visit_expr(|e| self.expr(e));
@@ -531,7 +539,7 @@ impl<'a> State<'a> {
             Stmt::Break | Stmt::Continue => {
                 self.set_abort(Abort::Loop);
             }
-            Stmt::Pass => {}
+            Stmt::Pass | Stmt::Error => {}
         }
     }
 
@@ -80,12 +80,13 @@ fn match_inefficient_bool_check(codemap: &CodeMap, x: &AstExpr, res: &mut Vec<Li
             {
                 match &**arg {
                     // any([blah for blah in blahs])
-                    Expr::ListComprehension(_, _, _) | Expr::DictComprehension(_, _, _) => res
-                        .push(LintT::new(
-                            codemap,
-                            x.span,
-                            Performance::EagerAndInefficientBoolCheck(f.node.ident.clone()),
-                        )),
+                    Expr::ListComprehension(_, _, _)
+                    | Expr::DictComprehension(_, _, _)
+                    | Expr::SetComprehension(_, _, _) => res.push(LintT::new(
+                        codemap,
+                        x.span,
+                        Performance::EagerAndInefficientBoolCheck(f.node.ident.clone()),
+                    )),
                     // any(list(_get_some_dict()))
                     Expr::Call(any_call, _) => match &***any_call {
                         Expr::Identifier(any_id)
@@ -20,6 +20,7 @@ use starlark_syntax::codemap::Pos;
 use starlark_syntax::codemap::Span;
 
 use crate::analysis::unused_loads::find::find_unused_loads;
+use crate::analysis::unused_loads::find::UnusedLoad;
 
 struct Out<'a> {
     codemap: &'a CodeMap,
@@ -49,6 +50,29 @@ impl<'a> Out<'a> {
     }
 }
 
+/// The spans to delete to remove the unused loads: whole `load` statements if none
+/// of the loaded symbols are used, otherwise the unused arguments of the statement.
+pub fn unused_load_removals(name: &str, program: &str) -> crate::Result<Vec<Span>> {
+    let (_, unused_loads) = find_unused_loads(name, program)?;
+    Ok(removals(unused_loads))
+}
+
+fn removals(unused_loads: Vec<UnusedLoad>) -> Vec<Span> {
+    let mut spans = Vec::new();
+    for load in unused_loads {
+        if load.all_unused() {
+            spans.push(load.load.span);
+        } else {
+            spans.extend(
+                load.unused_args
+                    .iter()
+                    .map(|arg| arg.span_with_trailing_comma()),
+            );
+        }
+    }
+    spans
+}
+
 /// Return `None` if there is no unused loads.
 pub fn remove_unused_loads(name: &str, program: &str) -> crate::Result<Option<String>> {
     let (codemap, unused_loads) = find_unused_loads(name, program)?;
@@ -62,14 +86,8 @@ pub fn remove_unused_loads(name: &str, program: &str) -> crate::Result<Option<St
         pos: Pos::new(0),
     };
 
-    for load in unused_loads {
-        if load.all_unused() {
-            out.skip_span(load.load.span);
-        } else {
-            for arg in load.unused_args {
-                out.skip_span(arg.span_with_trailing_comma());
-            }
-        }
+    for span in removals(unused_loads) {
+        out.skip_span(span);
     }
 
     out.append_to(codemap.full_span().end());
@@ -20,5 +20,16 @@ mod adapter;
 
 mod evaluate;
 mod inspect;
+pub(crate) mod recording;
+mod replay;
+mod server;
 
 pub use adapter::*;
+pub use recording::RecordedEvent;
+pub use recording::RecordedFrame;
+pub use recording::RecordedLocation;
+pub use recording::RecordedVariable;
+pub use recording::Recording;
+pub use replay::prepare_replay_adapter;
+pub use server::DapServer;
+pub use server::DapThread;
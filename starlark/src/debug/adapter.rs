@@ -399,19 +399,44 @@ pub trait DapAdapter: Debug + Send + 'static {
     ///
     /// See <https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Evaluate>
     fn evaluate(&self, expr: &str) -> anyhow::Result<EvaluateExprInfo>;
+
+    /// Steps backwards to the previous statement in the current function.
+    /// Only supported when replaying a recording.
+    ///
+    /// See <https://microsoft.github.io/debug-adapter-protocol/specification#Requests_StepBack>
+    fn step_back(&self) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "Stepping back is only supported when replaying"
+        ))
+    }
+
+    /// Runs backwards until the previous breakpoint.
+    /// Only supported when replaying a recording.
+    ///
+    /// See <https://microsoft.github.io/debug-adapter-protocol/specification#Requests_ReverseContinue>
+    fn reverse_continue(&self) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "Reverse continue is only supported when replaying"
+        ))
+    }
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub(crate) struct Breakpoint {
-    span: FileSpan,
-    condition: Option<String>,
+    pub(crate) span: FileSpan,
+    pub(crate) condition: Option<String>,
 }
 
 /// Breakpoints resolved to their spans.
 #[derive(Debug)]
-pub struct ResolvedBreakpoints(Vec<Option<Breakpoint>>);
+pub struct ResolvedBreakpoints(pub(crate) Vec<Option<Breakpoint>>);
 
 impl ResolvedBreakpoints {
+    /// No breakpoints, for clearing all the breakpoints of a file.
+    pub(crate) fn empty() -> Self {
+        ResolvedBreakpoints(Vec::new())
+    }
+
     /// Converts resolved breakpoints to a SetBreakpointsResponseBody. The breakpoints should've been resolved from the corresponding SetBreakpointsRequest.
     pub fn to_response(&self) -> SetBreakpointsResponseBody {
         implementation::resolved_breakpoints_to_dap(self)
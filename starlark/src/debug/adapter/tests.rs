@@ -241,10 +241,6 @@ print(x)
             let eval_result =
                 s.spawn(move || -> crate::Result<_> { eval_with_hook(ast, eval_hook) });
             controller.wait_for_eval_stopped(1, TIMEOUT);
-            // TODO(cjhopman): we currently hit breakpoints on top-level statements twice (once for the gc bytecode, once for the actual statement).
-            adapter.continue_()?;
-            controller.wait_for_eval_stopped(2, TIMEOUT);
-
             adapter.continue_()?;
 
             join_timeout(eval_result, TIMEOUT)?;
@@ -301,9 +297,6 @@ print(x)
                 s.spawn(move || -> crate::Result<_> { eval_with_hook(ast, eval_hook) });
             controller.wait_for_eval_stopped(1, TIMEOUT);
             adapter.continue_()?;
-            // TODO(cjhopman): we currently hit breakpoints on top-level statements twice (once for the gc bytecode, once for the actual statement).
-            controller.wait_for_eval_stopped(2, TIMEOUT);
-            adapter.continue_()?;
 
             join_timeout(eval_result, TIMEOUT)?;
             Ok(())
@@ -338,24 +331,18 @@ print(x)
             let eval_result =
                 s.spawn(move || -> crate::Result<_> { eval_with_hook(ast, eval_hook) });
             controller.wait_for_eval_stopped(1, TIMEOUT);
-            // TODO(cjhopman): we currently hit breakpoints on top-level statements twice (once for the gc bytecode, once for the actual statement).
-            adapter.continue_()?;
-            controller.wait_for_eval_stopped(2, TIMEOUT);
 
             assert_eq!("1", adapter.evaluate("x[0]")?.result);
             assert_eq!("2", adapter.evaluate("x[1]")?.result);
             assert_eq!("3", adapter.evaluate("x[2]")?.result);
             adapter.step(StepKind::Over)?;
-            controller.wait_for_eval_stopped(3, TIMEOUT);
+            controller.wait_for_eval_stopped(2, TIMEOUT);
             assert_eq!("2", adapter.evaluate("x[0]")?.result);
             assert_eq!("3", adapter.evaluate("x[1]")?.result);
             assert_eq!("4", adapter.evaluate("x[2]")?.result);
 
-            // TODO(cjhopman): we currently hit breakpoints on top-level statements twice (once for the gc bytecode, once for the actual statement).
-            adapter.step(StepKind::Over)?;
-            controller.wait_for_eval_stopped(4, TIMEOUT);
             adapter.step(StepKind::Over)?;
-            controller.wait_for_eval_stopped(5, TIMEOUT);
+            controller.wait_for_eval_stopped(3, TIMEOUT);
             assert_eq!("3", adapter.evaluate("x[0]")?.result);
             assert_eq!("4", adapter.evaluate("x[1]")?.result);
             assert_eq!("5", adapter.evaluate("x[2]")?.result);
@@ -365,6 +352,48 @@ print(x)
         })
     }
 
+    #[test]
+    fn test_step_over_statements_on_one_line() -> crate::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let file_contents = "
+x = [0] # line 2
+x[0] += 1; x[0] += 1
+print(x)
+        ";
+        dap_test_template(|s, controller, adapter, eval_hook| {
+            let ast = AstModule::parse(
+                "test.bzl",
+                file_contents.to_owned(),
+                &Dialect::AllOptionsInternal,
+            )?;
+            let breakpoints =
+                resolve_breakpoints(&breakpoints_args("test.bzl", &[(2, None)]), &ast)?;
+            adapter.set_breakpoints("test.bzl", &breakpoints)?;
+            let eval_result =
+                s.spawn(move || -> crate::Result<_> { eval_with_hook(ast, eval_hook) });
+            controller.wait_for_eval_stopped(1, TIMEOUT);
+
+            // Each statement on the line is a step of its own.
+            adapter.step(StepKind::Over)?;
+            controller.wait_for_eval_stopped(2, TIMEOUT);
+            assert_eq!("0", adapter.evaluate("x[0]")?.result);
+
+            adapter.step(StepKind::Over)?;
+            controller.wait_for_eval_stopped(3, TIMEOUT);
+            assert_eq!("1", adapter.evaluate("x[0]")?.result);
+
+            adapter.step(StepKind::Over)?;
+            controller.wait_for_eval_stopped(4, TIMEOUT);
+            assert_eq!("2", adapter.evaluate("x[0]")?.result);
+            adapter.continue_()?;
+            join_timeout(eval_result, TIMEOUT)?;
+            Ok(())
+        })
+    }
+
     #[test]
     fn test_step_into() -> crate::Result<()> {
         if is_wasm() {
@@ -393,9 +422,6 @@ print(x)
             let eval_result =
                 s.spawn(move || -> crate::Result<_> { eval_with_hook(ast, eval_hook) });
             controller.wait_for_eval_stopped(1, TIMEOUT);
-            // TODO(cjhopman): we currently hit breakpoints on top-level statements twice (once for the gc bytecode, once for the actual statement).
-            adapter.continue_()?;
-            controller.wait_for_eval_stopped(2, TIMEOUT);
 
             assert_eq!("1", adapter.evaluate("x[0]")?.result);
             assert_eq!("2", adapter.evaluate("x[1]")?.result);
@@ -403,34 +429,30 @@ print(x)
 
             // into adjust
             adapter.step(StepKind::Into)?;
-            controller.wait_for_eval_stopped(3, TIMEOUT);
+            controller.wait_for_eval_stopped(2, TIMEOUT);
             assert_eq!("1", adapter.evaluate("y[0]")?.result);
             assert_eq!("2", adapter.evaluate("y[1]")?.result);
             assert_eq!("3", adapter.evaluate("y[2]")?.result);
 
             // into should go to next line
             adapter.step(StepKind::Into)?;
-            controller.wait_for_eval_stopped(4, TIMEOUT);
+            controller.wait_for_eval_stopped(3, TIMEOUT);
             assert_eq!("2", adapter.evaluate("y[0]")?.result);
             assert_eq!("2", adapter.evaluate("y[1]")?.result);
             assert_eq!("3", adapter.evaluate("y[2]")?.result);
 
             // two more intos should get us out of the function call
             adapter.step(StepKind::Into)?;
-            controller.wait_for_eval_stopped(5, TIMEOUT);
+            controller.wait_for_eval_stopped(4, TIMEOUT);
             adapter.step(StepKind::Into)?;
-            controller.wait_for_eval_stopped(6, TIMEOUT);
+            controller.wait_for_eval_stopped(5, TIMEOUT);
             assert_eq!("2", adapter.evaluate("x[0]")?.result);
             assert_eq!("3", adapter.evaluate("x[1]")?.result);
             assert_eq!("4", adapter.evaluate("x[2]")?.result);
 
             // and once more back into the function
             adapter.step(StepKind::Into)?;
-            controller.wait_for_eval_stopped(7, TIMEOUT);
-
-            // TODO(cjhopman): unfortunately, gc being marked as statements causes us to need to step_into again.
-            adapter.step(StepKind::Into)?;
-            controller.wait_for_eval_stopped(8, TIMEOUT);
+            controller.wait_for_eval_stopped(6, TIMEOUT);
 
             assert_eq!("2", adapter.evaluate("y[0]")?.result);
             assert_eq!("3", adapter.evaluate("y[1]")?.result);
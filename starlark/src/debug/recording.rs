@@ -0,0 +1,245 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Recording of evaluations, so they can be replayed in a debugger after the fact.
+//!
+//! Recording is enabled with [`Evaluator::enable_recording`]. Before every statement the
+//! evaluator records the statement location, the call stack and a snapshot of the local
+//! variables (as shown by the debugger), and it records the result of every call to a native
+//! function or method. Calls the compiler turns into dedicated instructions (such as `len` or
+//! `type`) are not recorded. The resulting [`Recording`] can be written to a file and replayed
+//! with [`prepare_replay_adapter`](crate::debug::prepare_replay_adapter).
+
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::codemap::FileSpan;
+use crate::codemap::FileSpanRef;
+use crate::debug::PathSegment;
+use crate::debug::Variable;
+use crate::eval::Evaluator;
+use crate::values::Value;
+
+/// A source location in a recording. Lines and columns are 0-based.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedLocation {
+    /// File name.
+    pub file: String,
+    /// Line the span begins on.
+    pub begin_line: usize,
+    /// Column the span begins at.
+    pub begin_column: usize,
+    /// Line the span ends on.
+    pub end_line: usize,
+    /// Column the span ends at.
+    pub end_column: usize,
+}
+
+impl RecordedLocation {
+    pub(crate) fn from_file_span_ref(span: FileSpanRef) -> Self {
+        let resolved = span.resolve_span();
+        RecordedLocation {
+            file: span.filename().to_owned(),
+            begin_line: resolved.begin.line,
+            begin_column: resolved.begin.column,
+            end_line: resolved.end.line,
+            end_column: resolved.end.column,
+        }
+    }
+
+    fn from_file_span(span: &FileSpan) -> Self {
+        Self::from_file_span_ref(span.as_ref())
+    }
+}
+
+/// An entry on the call stack at the time a statement was recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// The name of the function.
+    pub name: String,
+    /// The location the function was called from, if known.
+    pub location: Option<RecordedLocation>,
+}
+
+/// A snapshot of a variable, as displayed by the debugger.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedVariable {
+    /// Name of the variable.
+    pub name: String,
+    /// The value as a string.
+    pub value: String,
+    /// The type of the value.
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+
+/// A single step of a recorded evaluation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    /// The evaluator is about to execute a statement.
+    Stmt {
+        /// The statement.
+        location: RecordedLocation,
+        /// The call stack, outermost first.
+        frames: Vec<RecordedFrame>,
+        /// Local variables in scope (module variables at the top level).
+        locals: Vec<RecordedVariable>,
+    },
+    /// A native function or method returned.
+    NativeCall {
+        /// Name of the function, or `type.method` for methods.
+        function: String,
+        /// The returned value, if the call succeeded.
+        result: Option<String>,
+        /// The error, if the call failed.
+        error: Option<String>,
+    },
+}
+
+/// A recorded evaluation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+    /// The events, in the order they happened.
+    pub events: Vec<RecordedEvent>,
+}
+
+impl Recording {
+    /// Write the recording to a file, one JSON event per line.
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        for event in &self.events {
+            serde_json::to_writer(&mut file, event)?;
+            file.write_all(b"\n")?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Read a recording previously written with [`write`](Recording::write).
+    pub fn read(path: &Path) -> anyhow::Result<Recording> {
+        let mut events = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                events.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Recording { events })
+    }
+}
+
+/// Recording state of an evaluator.
+pub(crate) struct Recorder(Option<Vec<RecordedEvent>>);
+
+impl Recorder {
+    pub(crate) fn new() -> Self {
+        Recorder(None)
+    }
+
+    pub(crate) fn enable(&mut self) {
+        self.0 = Some(Vec::new());
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub(crate) fn take(&mut self) -> Option<Recording> {
+        self.0.take().map(|events| Recording { events })
+    }
+
+    fn push(&mut self, event: RecordedEvent) {
+        if let Some(events) = &mut self.0 {
+            events.push(event);
+        }
+    }
+
+    /// Record the result of a call to a native function.
+    #[inline]
+    pub(crate) fn native_call<'v>(&mut self, function: &str, res: &crate::Result<Value<'v>>) {
+        #[cold]
+        #[inline(never)]
+        fn record<'v>(me: &mut Recorder, function: &str, res: &crate::Result<Value<'v>>) {
+            me.push(RecordedEvent::NativeCall {
+                function: function.to_owned(),
+                result: res.as_ref().ok().map(|v| v.to_repr()),
+                error: res.as_ref().err().map(|e| format!("{:#}", e)),
+            });
+        }
+
+        if self.enabled() {
+            record(self, function, res);
+        }
+    }
+
+    /// Record the result of a call to a native method, named `type.method` in the recording.
+    #[inline]
+    pub(crate) fn native_method_call<'v>(
+        &mut self,
+        this: Value<'v>,
+        method: &str,
+        res: &crate::Result<Value<'v>>,
+    ) {
+        if self.enabled() {
+            self.native_call(&format!("{}.{}", this.get_type(), method), res);
+        }
+    }
+}
+
+/// Record a statement, called from `before_stmt`.
+pub(crate) fn record_stmt(span: FileSpanRef, eval: &mut Evaluator) {
+    if !eval.recorder.enabled() {
+        return;
+    }
+    let frames = eval
+        .call_stack()
+        .into_frames()
+        .into_iter()
+        .map(|frame| RecordedFrame {
+            location: frame
+                .location
+                .as_ref()
+                .map(RecordedLocation::from_file_span),
+            name: frame.name,
+        })
+        .collect();
+    let locals = eval
+        .local_variables()
+        .into_iter()
+        .map(|(name, value)| {
+            let var = Variable::from_value(PathSegment::Attr(name), value);
+            RecordedVariable {
+                name: var.name.to_string(),
+                value: var.value,
+                type_: var.type_,
+            }
+        })
+        .collect();
+    eval.recorder.push(RecordedEvent::Stmt {
+        location: RecordedLocation::from_file_span_ref(span),
+        frames,
+        locals,
+    });
+}
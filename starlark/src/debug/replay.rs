@@ -0,0 +1,509 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A [`DapAdapter`] which replays a [`Recording`] rather than a live evaluation.
+//!
+//! Since the whole evaluation is known up front, the replay can move backwards as well as
+//! forwards, see [`DapAdapter::step_back`] and [`DapAdapter::reverse_continue`].
+
+use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+use debugserver_types::*;
+
+use crate::debug::recording::RecordedFrame;
+use crate::debug::recording::RecordedLocation;
+use crate::debug::recording::RecordedVariable;
+use crate::debug::DapAdapter;
+use crate::debug::DapAdapterClient;
+use crate::debug::EvaluateExprInfo;
+use crate::debug::InspectVariableInfo;
+use crate::debug::PathSegment;
+use crate::debug::RecordedEvent;
+use crate::debug::Recording;
+use crate::debug::ResolvedBreakpoints;
+use crate::debug::ScopesInfo;
+use crate::debug::StepKind;
+use crate::debug::Variable;
+use crate::debug::VariablePath;
+use crate::debug::VariablesInfo;
+
+/// Creates a DapAdapter which replays a recording.
+///
+/// The replay starts before the first statement: use [`DapAdapter::step`] to stop on the first
+/// statement, or [`DapAdapter::continue_`] to run to the first breakpoint. Running forwards
+/// stops at the last statement of the recording, running backwards stops at the first.
+/// Breakpoint conditions can't be evaluated against a recording, so all breakpoints are
+/// treated as unconditional.
+///
+/// The variables of a statement are its local variables, followed by the results of the native
+/// functions and methods called since the previous statement, named like `str()`.
+pub fn prepare_replay_adapter(
+    recording: Recording,
+    client: Box<dyn DapAdapterClient>,
+) -> anyhow::Result<impl DapAdapter> {
+    let mut stmts = Vec::new();
+    let mut native_calls = Vec::new();
+    for event in recording.events {
+        match event {
+            RecordedEvent::Stmt {
+                location,
+                frames,
+                locals,
+            } => stmts.push(ReplayStmt {
+                location,
+                frames,
+                locals,
+                native_calls: mem::take(&mut native_calls),
+            }),
+            RecordedEvent::NativeCall {
+                function,
+                result,
+                error,
+            } => native_calls.push(match (result, error) {
+                (Some(result), _) => RecordedVariable {
+                    name: format!("{}()", function),
+                    value: result,
+                    type_: "returned".to_owned(),
+                },
+                (None, error) => RecordedVariable {
+                    name: format!("{}()", function),
+                    value: error.unwrap_or_default(),
+                    type_: "failed".to_owned(),
+                },
+            }),
+        }
+    }
+    if stmts.is_empty() {
+        return Err(anyhow::anyhow!("The recording contains no statements"));
+    }
+    Ok(ReplayDapAdapter {
+        client: Arc::from(client),
+        stmts,
+        state: Mutex::new(ReplayState {
+            position: None,
+            breakpoints: HashMap::new(),
+        }),
+    })
+}
+
+#[derive(Debug)]
+struct ReplayStmt {
+    location: RecordedLocation,
+    frames: Vec<RecordedFrame>,
+    locals: Vec<RecordedVariable>,
+    /// Results of the native calls made since the previous statement.
+    native_calls: Vec<RecordedVariable>,
+}
+
+#[derive(Debug)]
+struct ReplayState {
+    /// Index into `stmts`, or `None` before the replay started.
+    position: Option<usize>,
+    /// Breakpoint locations by file name.
+    breakpoints: HashMap<String, Vec<RecordedLocation>>,
+}
+
+impl ReplayState {
+    fn is_breakpoint(&self, stmt: &ReplayStmt) -> bool {
+        self.breakpoints
+            .get(&stmt.location.file)
+            .is_some_and(|locs| locs.contains(&stmt.location))
+    }
+}
+
+#[derive(Debug)]
+struct ReplayDapAdapter {
+    client: Arc<dyn DapAdapterClient>,
+    stmts: Vec<ReplayStmt>,
+    state: Mutex<ReplayState>,
+}
+
+fn convert_frame(id: usize, name: String, location: Option<&RecordedLocation>) -> StackFrame {
+    let mut s = StackFrame {
+        id: id as i64,
+        name,
+        column: 0,
+        line: 0,
+        end_column: None,
+        end_line: None,
+        module_id: None,
+        presentation_hint: None,
+        source: None,
+    };
+    if let Some(loc) = location {
+        s.line = loc.begin_line as i64 + 1;
+        s.column = loc.begin_column as i64 + 1;
+        s.end_line = Some(loc.end_line as i64 + 1);
+        s.end_column = Some(loc.end_column as i64 + 1);
+        s.source = Some(Source {
+            path: Some(loc.file.clone()),
+            ..Source::default()
+        })
+    }
+    s
+}
+
+impl ReplayDapAdapter {
+    fn current(&self) -> anyhow::Result<&ReplayStmt> {
+        match self.state.lock().unwrap().position {
+            Some(i) => Ok(&self.stmts[i]),
+            None => Err(anyhow::anyhow!("The replay has not started")),
+        }
+    }
+
+    /// Moves to the first statement matching `pred` in the given direction,
+    /// or to the end of the recording in that direction if there is none.
+    fn move_to(
+        &self,
+        forwards: bool,
+        pred: impl Fn(&ReplayState, &ReplayStmt) -> bool,
+    ) -> anyhow::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let last = self.stmts.len() - 1;
+        let next = if forwards {
+            let start = state.position.map_or(0, |i| i + 1);
+            (start..=last)
+                .find(|&i| pred(&state, &self.stmts[i]))
+                .unwrap_or(last)
+        } else {
+            let end = state.position.unwrap_or(0);
+            (0..end)
+                .rev()
+                .find(|&i| pred(&state, &self.stmts[i]))
+                .unwrap_or(0)
+        };
+        state.position = Some(next);
+        drop(state);
+
+        // Like a live evaluation, report the stop asynchronously,
+        // so the client gets the response to its request first.
+        let client = self.client.clone();
+        thread::spawn(move || client.event_stopped());
+        Ok(())
+    }
+
+    fn depth(&self) -> usize {
+        self.current().map_or(0, |stmt| stmt.frames.len())
+    }
+}
+
+impl DapAdapter for ReplayDapAdapter {
+    fn set_breakpoints(
+        &self,
+        source: &str,
+        breakpoints: &ResolvedBreakpoints,
+    ) -> anyhow::Result<()> {
+        let locations: Vec<RecordedLocation> = breakpoints
+            .0
+            .iter()
+            .flatten()
+            .map(|b| RecordedLocation::from_file_span_ref(b.span.as_ref()))
+            .collect();
+        let mut state = self.state.lock().unwrap();
+        if locations.is_empty() {
+            state.breakpoints.remove(source);
+        } else {
+            state.breakpoints.insert(source.to_owned(), locations);
+        }
+        Ok(())
+    }
+
+    fn top_frame(&self) -> anyhow::Result<Option<StackFrame>> {
+        let stmt = self.current()?;
+        let name = stmt.frames.last().map_or(String::new(), |f| f.name.clone());
+        Ok(Some(convert_frame(0, name, Some(&stmt.location))))
+    }
+
+    fn stack_trace(&self, _: StackTraceArguments) -> anyhow::Result<StackTraceResponseBody> {
+        // Same conversion as for a live evaluation: frames record the location of the call,
+        // but DAP wants the location each frame is at.
+        let stmt = self.current()?;
+        let mut next = Some(&stmt.location);
+        let mut res = Vec::with_capacity(stmt.frames.len() + 1);
+        for (i, x) in stmt.frames.iter().rev().enumerate() {
+            res.push(convert_frame(i, x.name.clone(), next));
+            next = x.location.as_ref();
+        }
+        res.push(convert_frame(stmt.frames.len(), "Root".to_owned(), next));
+        Ok(StackTraceResponseBody {
+            total_frames: Some(res.len() as i64),
+            stack_frames: res,
+        })
+    }
+
+    fn scopes(&self) -> anyhow::Result<ScopesInfo> {
+        let stmt = self.current()?;
+        Ok(ScopesInfo {
+            num_locals: stmt.locals.len() + stmt.native_calls.len(),
+        })
+    }
+
+    fn variables(&self) -> anyhow::Result<VariablesInfo> {
+        let stmt = self.current()?;
+        Ok(VariablesInfo {
+            locals: stmt
+                .locals
+                .iter()
+                .chain(&stmt.native_calls)
+                .map(|var| Variable {
+                    name: PathSegment::Attr(var.name.clone()),
+                    value: var.value.clone(),
+                    type_: var.type_.clone(),
+                    has_children: false,
+                })
+                .collect(),
+        })
+    }
+
+    fn inspect_variable(&self, _path: VariablePath) -> anyhow::Result<InspectVariableInfo> {
+        Err(anyhow::anyhow!(
+            "Recordings only contain the values of local variables"
+        ))
+    }
+
+    fn continue_(&self) -> anyhow::Result<()> {
+        self.move_to(true, |state, stmt| state.is_breakpoint(stmt))
+    }
+
+    fn step(&self, kind: StepKind) -> anyhow::Result<()> {
+        let depth = self.depth();
+        match kind {
+            StepKind::Into => self.move_to(true, |_, _| true),
+            StepKind::Over => self.move_to(true, |_, stmt| stmt.frames.len() <= depth),
+            StepKind::Out => self.move_to(true, |_, stmt| stmt.frames.len() < depth),
+        }
+    }
+
+    fn evaluate(&self, expr: &str) -> anyhow::Result<EvaluateExprInfo> {
+        let expr = expr.trim();
+        match self.current()?.locals.iter().find(|var| var.name == expr) {
+            Some(var) => Ok(EvaluateExprInfo {
+                result: var.value.clone(),
+                type_: var.type_.clone(),
+                has_children: false,
+            }),
+            None => Err(anyhow::anyhow!(
+                "Only local variables can be evaluated in a recording, `{}` is not one",
+                expr
+            )),
+        }
+    }
+
+    fn step_back(&self) -> anyhow::Result<()> {
+        let depth = self.depth();
+        self.move_to(false, |_, stmt| stmt.frames.len() <= depth)
+    }
+
+    fn reverse_continue(&self) -> anyhow::Result<()> {
+        self.move_to(false, |state, stmt| state.is_breakpoint(stmt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use debugserver_types::*;
+
+    use crate::debug::prepare_replay_adapter;
+    use crate::debug::resolve_breakpoints;
+    use crate::debug::DapAdapter;
+    use crate::debug::DapAdapterClient;
+    use crate::debug::RecordedEvent;
+    use crate::debug::Recording;
+    use crate::debug::StepKind;
+    use crate::environment::Globals;
+    use crate::environment::Module;
+    use crate::eval::Evaluator;
+    use crate::syntax::AstModule;
+    use crate::syntax::Dialect;
+    use crate::wasm::is_wasm;
+
+    #[derive(Debug)]
+    struct Client(Mutex<mpsc::Sender<()>>);
+
+    impl DapAdapterClient for Client {
+        fn event_stopped(&self) -> crate::Result<()> {
+            self.0.lock().unwrap().send(()).unwrap();
+            Ok(())
+        }
+    }
+
+    const PROGRAM: &str = "
+def f(x):
+    y = x * 2
+    return str(y)
+a = f(1)
+b = f(2)
+";
+
+    fn record() -> (AstModule, Recording) {
+        let ast = AstModule::parse("test.bzl", PROGRAM.to_owned(), &Dialect::Standard).unwrap();
+        let module = Module::new();
+        let mut eval = Evaluator::new(&module);
+        eval.enable_recording().unwrap();
+        eval.eval_module(ast.clone(), &Globals::standard()).unwrap();
+        (ast, eval.take_recording().unwrap())
+    }
+
+    fn breakpoint_args(lines: &[i64]) -> SetBreakpointsArguments {
+        SetBreakpointsArguments {
+            breakpoints: Some(
+                lines
+                    .iter()
+                    .map(|line| SourceBreakpoint {
+                        column: None,
+                        condition: None,
+                        hit_condition: None,
+                        line: *line,
+                        log_message: None,
+                    })
+                    .collect(),
+            ),
+            lines: None,
+            source: Source {
+                path: Some("test.bzl".to_owned()),
+                ..Source::default()
+            },
+            source_modified: None,
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let (_, recording) = record();
+        let native_calls: Vec<_> = recording
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                RecordedEvent::NativeCall {
+                    function, result, ..
+                } => Some((function.as_str(), result.as_deref())),
+                RecordedEvent::Stmt { .. } => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![("str", Some("\"2\"")), ("str", Some("\"4\""))],
+            native_calls
+        );
+
+        let path =
+            std::env::temp_dir().join(format!("recording_test_{}.jsonl", std::process::id()));
+        recording.write(&path).unwrap();
+        assert_eq!(recording, Recording::read(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_record_repeated_events() {
+        let program = "
+def f(x):
+    for _ in [1, 1]:
+        _ = 1
+    return [str(x), str(x), x.upper()]
+f('a')
+";
+        let ast = AstModule::parse("test.bzl", program.to_owned(), &Dialect::Standard).unwrap();
+        let module = Module::new();
+        let mut eval = Evaluator::new(&module);
+        eval.enable_recording().unwrap();
+        eval.eval_module(ast, &Globals::standard()).unwrap();
+        let recording = eval.take_recording().unwrap();
+
+        let native_calls: Vec<_> = recording
+            .events
+            .iter()
+            .filter_map(|e| match e {
+                RecordedEvent::NativeCall { function, .. } => Some(function.as_str()),
+                RecordedEvent::Stmt { .. } => None,
+            })
+            .collect();
+        assert_eq!(vec!["str", "str", "string.upper"], native_calls);
+        let loop_stmts = recording
+            .events
+            .iter()
+            .filter(
+                |e| matches!(e, RecordedEvent::Stmt { location, .. } if location.begin_line == 3),
+            )
+            .count();
+        assert_eq!(2, loop_stmts);
+    }
+
+    #[test]
+    fn test_replay() {
+        if is_wasm() {
+            return;
+        }
+
+        let (ast, recording) = record();
+        let (sender, stopped) = mpsc::channel();
+        let adapter =
+            prepare_replay_adapter(recording, Box::new(Client(Mutex::new(sender)))).unwrap();
+        let wait = || stopped.recv_timeout(Duration::from_secs(10)).unwrap();
+        let line = || adapter.top_frame().unwrap().unwrap().line;
+        let eval = |expr| adapter.evaluate(expr).unwrap().result;
+
+        adapter
+            .set_breakpoints(
+                "test.bzl",
+                &resolve_breakpoints(&breakpoint_args(&[4]), &ast).unwrap(),
+            )
+            .unwrap();
+
+        adapter.continue_().unwrap();
+        wait();
+        assert_eq!(4, line());
+        assert_eq!("2", eval("y"));
+
+        adapter.continue_().unwrap();
+        wait();
+        assert_eq!(4, line());
+        assert_eq!("4", eval("y"));
+
+        adapter.step_back().unwrap();
+        wait();
+        assert_eq!(3, line());
+        assert_eq!("2", eval("x"));
+
+        adapter.reverse_continue().unwrap();
+        wait();
+        assert_eq!(4, line());
+        assert_eq!("2", eval("y"));
+
+        adapter.step(StepKind::Out).unwrap();
+        wait();
+        assert_eq!(6, line());
+        assert_eq!("2", eval("a"));
+        assert!(adapter.evaluate("b").is_err());
+        let vars = adapter.variables().unwrap().locals;
+        let str_call = vars.iter().find(|v| v.name.to_string() == "str()").unwrap();
+        assert_eq!("\"2\"", str_call.value);
+        assert_eq!(vars.len(), adapter.scopes().unwrap().num_locals);
+
+        adapter.reverse_continue().unwrap();
+        wait();
+        adapter.reverse_continue().unwrap();
+        wait();
+        assert_eq!(2, line());
+    }
+}
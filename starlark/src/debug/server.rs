@@ -0,0 +1,676 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A DAP server which runs inside an embedding host and accepts debugger connections over TCP.
+//!
+//! The `starlark --dap` binary launches a single file and talks to the client over stdio.
+//! [`DapServer`] is the library equivalent for long-running hosts: the host registers each
+//! [`Evaluator`] it starts with [`DapServer::attach`], and a client which attaches to the
+//! server sees every registered evaluator as a separate DAP thread.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::IpAddr;
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::net::Shutdown;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicI64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use std::thread;
+
+use debugserver_types::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use starlark_syntax::error::StarlarkResultExt;
+
+use crate::debug::dap_capabilities;
+use crate::debug::prepare_dap_adapter;
+use crate::debug::resolve_breakpoints;
+use crate::debug::DapAdapter;
+use crate::debug::DapAdapterClient;
+use crate::debug::DapAdapterEvalHook;
+use crate::debug::ResolvedBreakpoints;
+use crate::debug::StepKind;
+use crate::debug::VariablePath;
+use crate::eval::Evaluator;
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
+
+#[cfg(test)]
+mod tests;
+
+/// Frame ids handed out to the client encode the thread they belong to,
+/// so that `scopes` and `evaluate` requests can be routed to the right evaluator.
+const FRAMES_PER_THREAD: i64 = 1 << 16;
+
+/// A debug adapter protocol server listening on a TCP socket.
+///
+/// The server accepts one client at a time. Evaluations are registered with
+/// [`attach`](DapServer::attach) and unregistered when the returned [`DapThread`] is dropped.
+/// Breakpoints set by the client apply to all registered evaluators, including those attached
+/// after the breakpoints were set. When the client disconnects, all breakpoints are cleared
+/// and paused evaluators are resumed. Once every clone of the server is dropped, the client
+/// is disconnected and the server stops listening.
+#[derive(Debug, Clone)]
+pub struct DapServer {
+    state: Arc<ServerState>,
+    listener: Arc<Listener>,
+}
+
+/// Owned by all clones of a [`DapServer`], stopping the accept loop when dropped.
+#[derive(Debug)]
+struct Listener {
+    state: Arc<ServerState>,
+    local_addr: SocketAddr,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.state.shutdown.store(true, Ordering::SeqCst);
+        if let Some(stream) = self.state.connection.stream.lock().unwrap().as_ref() {
+            // Makes `serve` see the end of the stream.
+            drop(stream.shutdown(Shutdown::Both));
+        }
+        // The accept loop is blocked waiting for a client, so wake it up with one.
+        let mut addr = self.local_addr;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr.ip() {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            });
+        }
+        drop(TcpStream::connect(addr));
+    }
+}
+
+/// An evaluator registered with a [`DapServer`].
+///
+/// The evaluator stays visible to the client as a DAP thread until this value is dropped.
+#[derive(Debug)]
+pub struct DapThread {
+    id: i64,
+    state: Arc<ServerState>,
+}
+
+#[derive(Debug)]
+struct ServerState {
+    dialect: Dialect,
+    next_thread_id: AtomicI64,
+    /// Breakpoints as last set by the client, replayed onto newly attached evaluators.
+    /// Always locked before `threads`.
+    breakpoints: Mutex<HashMap<String, ResolvedBreakpoints>>,
+    threads: Mutex<BTreeMap<i64, Arc<AttachedThread>>>,
+    connection: Connection,
+    /// What the variable references handed out to the client point to.
+    variables: Mutex<VariableRefs>,
+    shutdown: AtomicBool,
+}
+
+/// The thread a variable reference belongs to, and the variable it expands,
+/// or `None` for the locals scope.
+#[derive(Debug)]
+struct VariableRef {
+    thread: i64,
+    path: Option<VariablePath>,
+}
+
+/// Variable references are only valid while their thread is paused,
+/// so they are forgotten as soon as it resumes.
+#[derive(Debug, Default)]
+struct VariableRefs {
+    last: i64,
+    refs: HashMap<i64, VariableRef>,
+}
+
+impl VariableRefs {
+    fn add(&mut self, thread: i64, path: Option<VariablePath>) -> i64 {
+        self.last += 1;
+        self.refs.insert(self.last, VariableRef { thread, path });
+        self.last
+    }
+}
+
+#[derive(Debug)]
+struct AttachedThread {
+    name: String,
+    adapter: Mutex<Box<dyn DapAdapter>>,
+    status: Arc<ThreadStatus>,
+}
+
+#[derive(Debug, Default)]
+struct ThreadStatus {
+    paused: AtomicBool,
+    detached: AtomicBool,
+}
+
+/// The currently connected client, if any.
+#[derive(Debug, Default)]
+struct Connection {
+    stream: Mutex<Option<TcpStream>>,
+    seq: AtomicI64,
+}
+
+impl Connection {
+    fn is_connected(&self) -> bool {
+        self.stream.lock().unwrap().is_some()
+    }
+
+    fn send(&self, mut message: serde_json::Value) {
+        let mut stream = self.stream.lock().unwrap();
+        if let Some(writer) = stream.as_mut() {
+            message["seq"] = self.seq.fetch_add(1, Ordering::SeqCst).into();
+            let s = message.to_string();
+            let res = write!(writer, "Content-Length: {}\r\n\r\n{}", s.len(), s)
+                .and_then(|()| writer.flush());
+            if res.is_err() {
+                // The reader side will notice the broken connection and clean up.
+                *stream = None;
+            }
+        }
+    }
+
+    fn event(&self, event: &str, body: impl Serialize) {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }))
+    }
+}
+
+/// The client given to the adapter of each attached evaluator.
+#[derive(Debug)]
+struct ThreadClient {
+    id: i64,
+    status: Arc<ThreadStatus>,
+    state: Weak<ServerState>,
+}
+
+impl DapAdapterClient for ThreadClient {
+    fn event_stopped(&self) -> crate::Result<()> {
+        if self.status.detached.load(Ordering::SeqCst) {
+            // The adapter is gone, so the evaluator will resume straight away.
+            return Ok(());
+        }
+        self.status.paused.store(true, Ordering::SeqCst);
+        let Some(state) = self.state.upgrade() else {
+            return Ok(());
+        };
+        if state.connection.is_connected() {
+            state.connection.event(
+                "stopped",
+                StoppedEventBody {
+                    reason: "breakpoint".to_owned(),
+                    thread_id: Some(self.id),
+                    description: None,
+                    all_threads_stopped: Some(false),
+                    preserve_focus_hint: None,
+                    text: None,
+                },
+            );
+        } else {
+            // Nobody is there to resume us (e.g. a step was in progress when the client
+            // disconnected). We are called from the evaluator thread, which will block
+            // waiting for a command, so resume it from elsewhere.
+            let id = self.id;
+            thread::spawn(move || state.resume(id));
+        }
+        Ok(())
+    }
+}
+
+impl DapServer {
+    /// Binds a server to the given address and starts accepting clients on a background thread.
+    ///
+    /// Use port `0` to let the operating system choose a port, and
+    /// [`local_addr`](DapServer::local_addr) to find out which one it picked.
+    /// Files are parsed with `dialect` to resolve breakpoints set by the client.
+    pub fn bind(addr: impl ToSocketAddrs, dialect: Dialect) -> anyhow::Result<DapServer> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+        let state = Arc::new(ServerState {
+            dialect,
+            next_thread_id: AtomicI64::new(1),
+            breakpoints: Mutex::new(HashMap::new()),
+            threads: Mutex::new(BTreeMap::new()),
+            connection: Connection::default(),
+            variables: Mutex::new(VariableRefs::default()),
+            shutdown: AtomicBool::new(false),
+        });
+        let weak = Arc::downgrade(&state);
+        thread::Builder::new()
+            .name("starlark-dap-server".to_owned())
+            .spawn(move || accept_loop(listener, weak))?;
+        Ok(DapServer {
+            listener: Arc::new(Listener {
+                state: state.clone(),
+                local_addr,
+            }),
+            state,
+        })
+    }
+
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.listener.local_addr
+    }
+
+    /// Registers an evaluator with the server, reported to the client as a thread called `name`.
+    ///
+    /// The evaluator must not have other DAP hooks installed.
+    /// It is unregistered when the returned [`DapThread`] is dropped, which should happen
+    /// once the evaluation is done.
+    pub fn attach(&self, name: impl Into<String>, eval: &mut Evaluator<'_, '_, '_>) -> DapThread {
+        let id = self.state.next_thread_id.fetch_add(1, Ordering::SeqCst);
+        let status = Arc::new(ThreadStatus::default());
+        let (adapter, hook) = prepare_dap_adapter(Box::new(ThreadClient {
+            id,
+            status: status.clone(),
+            state: Arc::downgrade(&self.state),
+        }));
+        Box::new(hook).add_dap_hooks(eval);
+
+        {
+            let breakpoints = self.state.breakpoints.lock().unwrap();
+            for (source, resolved) in breakpoints.iter() {
+                // Setting breakpoints on a fresh adapter can't fail.
+                drop(adapter.set_breakpoints(source, resolved));
+            }
+            self.state.threads.lock().unwrap().insert(
+                id,
+                Arc::new(AttachedThread {
+                    name: name.into(),
+                    adapter: Mutex::new(Box::new(adapter)),
+                    status,
+                }),
+            );
+        }
+
+        self.state.connection.event(
+            "thread",
+            ThreadEventBody {
+                reason: "started".to_owned(),
+                thread_id: id,
+            },
+        );
+        DapThread {
+            id,
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl DapThread {
+    /// The DAP thread id of this evaluator.
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+}
+
+impl Drop for DapThread {
+    fn drop(&mut self) {
+        let thread = self.state.threads.lock().unwrap().remove(&self.id);
+        if let Some(thread) = thread {
+            thread.status.detached.store(true, Ordering::SeqCst);
+        }
+        self.state.connection.event(
+            "thread",
+            ThreadEventBody {
+                reason: "exited".to_owned(),
+                thread_id: self.id,
+            },
+        );
+    }
+}
+
+fn accept_loop(listener: TcpListener, state: Weak<ServerState>) {
+    for stream in listener.incoming() {
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        if state.shutdown.load(Ordering::SeqCst) {
+            // Every `DapServer` has been dropped.
+            return;
+        }
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let Ok(writer) = stream.try_clone() else {
+            continue;
+        };
+        *state.connection.stream.lock().unwrap() = Some(writer);
+        state.serve(BufReader::new(stream));
+        state.reset();
+    }
+}
+
+/// Reads one `Content-Length` framed message, or `None` if the client went away.
+fn read_message(reader: &mut impl BufRead) -> Option<serde_json::Value> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(v) = line.strip_prefix("Content-Length:") {
+            len = Some(v.trim().parse::<usize>().ok()?);
+        }
+    }
+    let mut body = vec![0u8; len?];
+    reader.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+fn arg<T: DeserializeOwned>(r: &Request) -> anyhow::Result<T> {
+    Ok(serde_json::from_value(
+        r.arguments.clone().unwrap_or_else(|| json!({})),
+    )?)
+}
+
+fn frame_thread(frame_id: i64) -> i64 {
+    frame_id / FRAMES_PER_THREAD
+}
+
+impl ServerState {
+    fn serve(&self, mut reader: impl BufRead) {
+        while let Some(message) = read_message(&mut reader) {
+            let Ok(r) = serde_json::from_value::<Request>(message) else {
+                continue;
+            };
+            let res = self.dispatch(&r);
+            self.connection.send(json!({
+                "type": "response",
+                "request_seq": r.seq,
+                "command": r.command,
+                "success": res.is_ok(),
+                "message": res.as_ref().err().map(|e| format!("{:#}", e)),
+                "body": res.as_ref().ok(),
+            }));
+            match r.command.as_str() {
+                // The spec wants `initialized` after the `initialize` response.
+                "initialize" => self.connection.event("initialized", json!({})),
+                "disconnect" => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// Forgets about the client that just went away, resuming anything it left paused.
+    fn reset(&self) {
+        *self.connection.stream.lock().unwrap() = None;
+        self.variables.lock().unwrap().refs.clear();
+        let mut breakpoints = self.breakpoints.lock().unwrap();
+        let threads = self.threads.lock().unwrap();
+        for thread in threads.values() {
+            let adapter = thread.adapter.lock().unwrap();
+            for source in breakpoints.keys() {
+                drop(adapter.set_breakpoints(source, &ResolvedBreakpoints::empty()));
+            }
+        }
+        breakpoints.clear();
+        let ids: Vec<i64> = threads.keys().copied().collect();
+        drop(threads);
+        drop(breakpoints);
+        for id in ids {
+            self.resume(id);
+        }
+    }
+
+    fn thread(&self, id: i64) -> anyhow::Result<Arc<AttachedThread>> {
+        self.threads
+            .lock()
+            .unwrap()
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Unknown thread: {}", id))
+    }
+
+    /// Runs `f` on the adapter of a thread, which must be paused, as otherwise
+    /// the adapter would block until the evaluator next stops.
+    fn with_paused<T>(
+        &self,
+        id: i64,
+        f: impl FnOnce(&dyn DapAdapter) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let thread = self.thread(id)?;
+        let adapter = thread.adapter.lock().unwrap();
+        if !thread.status.paused.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!("Thread {} is not paused", id));
+        }
+        f(&**adapter)
+    }
+
+    fn resume_with(
+        &self,
+        id: i64,
+        f: impl FnOnce(&dyn DapAdapter) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let thread = self.thread(id)?;
+        let adapter = thread.adapter.lock().unwrap();
+        if !thread.status.paused.swap(false, Ordering::SeqCst) {
+            return Err(anyhow::anyhow!("Thread {} is not paused", id));
+        }
+        self.variables
+            .lock()
+            .unwrap()
+            .refs
+            .retain(|_, r| r.thread != id);
+        f(&**adapter)
+    }
+
+    fn resume(&self, id: i64) {
+        drop(self.resume_with(id, |adapter| adapter.continue_()));
+    }
+
+    fn first_paused(&self) -> anyhow::Result<i64> {
+        self.threads
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, t)| t.status.paused.load(Ordering::SeqCst))
+            .map(|(id, _)| *id)
+            .ok_or_else(|| anyhow::anyhow!("No thread is paused"))
+    }
+
+    fn set_breakpoints(
+        &self,
+        args: SetBreakpointsArguments,
+    ) -> anyhow::Result<SetBreakpointsResponseBody> {
+        let source =
+            args.source.path.clone().ok_or_else(|| {
+                anyhow::anyhow!("Breakpoints can only be set on sources with a path")
+            })?;
+        let ast = AstModule::parse_file(Path::new(&source), &self.dialect).into_anyhow_result()?;
+        let resolved = resolve_breakpoints(&args, &ast)?;
+        let response = resolved.to_response();
+
+        let mut breakpoints = self.breakpoints.lock().unwrap();
+        for thread in self.threads.lock().unwrap().values() {
+            thread
+                .adapter
+                .lock()
+                .unwrap()
+                .set_breakpoints(&source, &resolved)?;
+        }
+        breakpoints.insert(source, resolved);
+        Ok(response)
+    }
+
+    fn stack_trace(&self, args: StackTraceArguments) -> anyhow::Result<StackTraceResponseBody> {
+        let id = args.thread_id;
+        let mut res = self.with_paused(id, |adapter| adapter.stack_trace(args))?;
+        for frame in &mut res.stack_frames {
+            frame.id += id * FRAMES_PER_THREAD;
+        }
+        Ok(res)
+    }
+
+    fn scopes(&self, args: ScopesArguments) -> anyhow::Result<ScopesResponseBody> {
+        let id = frame_thread(args.frame_id);
+        let scopes_info = self.with_paused(id, |adapter| adapter.scopes())?;
+        let variables_reference = self.variables.lock().unwrap().add(id, None);
+        Ok(ScopesResponseBody {
+            scopes: vec![debugserver_types::Scope {
+                name: "Locals".to_owned(),
+                named_variables: Some(scopes_info.num_locals as i64),
+                // Only the locals of the innermost frame are available.
+                variables_reference,
+                expensive: false,
+                column: None,
+                end_column: None,
+                end_line: None,
+                indexed_variables: None,
+                line: None,
+                source: None,
+            }],
+        })
+    }
+
+    fn variables(&self, args: VariablesArguments) -> anyhow::Result<VariablesResponseBody> {
+        let (thread, path) = {
+            let variables = self.variables.lock().unwrap();
+            let r = variables
+                .refs
+                .get(&args.variables_reference)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Unknown variables reference: {}", args.variables_reference)
+                })?;
+            (r.thread, r.path.clone())
+        };
+        let vars = match &path {
+            None => {
+                self.with_paused(thread, |adapter| adapter.variables())?
+                    .locals
+            }
+            Some(path) => {
+                self.with_paused(thread, |adapter| adapter.inspect_variable(path.clone()))?
+                    .sub_values
+            }
+        };
+        let mut refs = self.variables.lock().unwrap();
+        Ok(VariablesResponseBody {
+            variables: vars
+                .into_iter()
+                .map(|var| {
+                    let child = var.has_children.then(|| match &path {
+                        None => VariablePath::new_local(var.name.to_string()),
+                        Some(path) => path.make_child(var.name.clone()),
+                    });
+                    let reference = child.map_or(0, |child| refs.add(thread, Some(child)));
+                    debugserver_types::Variable {
+                        variables_reference: reference,
+                        ..var.to_dap()
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    fn evaluate(&self, args: EvaluateArguments) -> anyhow::Result<EvaluateResponseBody> {
+        let id = match args.frame_id {
+            Some(frame_id) => frame_thread(frame_id),
+            None => self.first_paused()?,
+        };
+        let expr_result = self.with_paused(id, |adapter| adapter.evaluate(&args.expression))?;
+        Ok(EvaluateResponseBody {
+            indexed_variables: None,
+            named_variables: None,
+            presentation_hint: None,
+            result: expr_result.result,
+            type_: Some(expr_result.type_),
+            variables_reference: 0.0,
+        })
+    }
+
+    fn threads(&self) -> ThreadsResponseBody {
+        ThreadsResponseBody {
+            threads: self
+                .threads
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(id, thread)| Thread {
+                    id: *id,
+                    name: thread.name.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    fn dispatch(&self, r: &Request) -> anyhow::Result<serde_json::Value> {
+        fn ok(v: impl Serialize) -> anyhow::Result<serde_json::Value> {
+            Ok(serde_json::to_value(v)?)
+        }
+
+        match r.command.as_str() {
+            "initialize" => ok(dap_capabilities()),
+            "attach" | "configurationDone" | "setExceptionBreakpoints" | "disconnect" => {
+                ok(json!({}))
+            }
+            "launch" => Err(anyhow::anyhow!(
+                "This server only supports attaching to running evaluations"
+            )),
+            "setBreakpoints" => ok(self.set_breakpoints(arg(r)?)?),
+            "threads" => ok(self.threads()),
+            "stackTrace" => ok(self.stack_trace(arg(r)?)?),
+            "scopes" => ok(self.scopes(arg(r)?)?),
+            "variables" => ok(self.variables(arg(r)?)?),
+            "evaluate" => ok(self.evaluate(arg(r)?)?),
+            "continue" => {
+                let args: ContinueArguments = arg(r)?;
+                self.resume_with(args.thread_id, |adapter| adapter.continue_())?;
+                ok(ContinueResponseBody {
+                    all_threads_continued: Some(false),
+                })
+            }
+            "next" => {
+                let args: NextArguments = arg(r)?;
+                self.resume_with(args.thread_id, |adapter| adapter.step(StepKind::Over))?;
+                ok(json!({}))
+            }
+            "stepIn" => {
+                let args: StepInArguments = arg(r)?;
+                self.resume_with(args.thread_id, |adapter| adapter.step(StepKind::Into))?;
+                ok(json!({}))
+            }
+            "stepOut" => {
+                let args: StepOutArguments = arg(r)?;
+                self.resume_with(args.thread_id, |adapter| adapter.step(StepKind::Out))?;
+                ok(json!({}))
+            }
+            _ => Err(anyhow::anyhow!("Unknown command: {}", r.command)),
+        }
+    }
+}
@@ -0,0 +1,337 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpStream;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::json;
+use serde_json::Value;
+
+use crate::debug::server::read_message;
+use crate::debug::DapServer;
+use crate::environment::Globals;
+use crate::environment::Module;
+use crate::eval::Evaluator;
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
+use crate::wasm::is_wasm;
+
+struct TestClient {
+    writer: TcpStream,
+    reader: BufReader<TcpStream>,
+    seq: i64,
+    pending: VecDeque<Value>,
+}
+
+impl TestClient {
+    fn connect(server: &DapServer) -> Self {
+        let stream = TcpStream::connect(server.local_addr()).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        Self {
+            writer: stream.try_clone().unwrap(),
+            reader: BufReader::new(stream),
+            seq: 1,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Waits for a message matching `pred`, keeping any others for later calls.
+    fn recv_until(&mut self, pred: impl Fn(&Value) -> bool) -> Value {
+        if let Some(i) = self.pending.iter().position(&pred) {
+            return self.pending.remove(i).unwrap();
+        }
+        loop {
+            let message = read_message(&mut self.reader).expect("connection closed");
+            if pred(&message) {
+                return message;
+            }
+            self.pending.push_back(message);
+        }
+    }
+
+    fn event(&mut self, event: &str) -> Value {
+        self.recv_until(|m| m["type"] == "event" && m["event"] == event)
+    }
+
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        let seq = self.seq;
+        self.seq += 1;
+        let s = json!({
+            "type": "request",
+            "seq": seq,
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        write!(self.writer, "Content-Length: {}\r\n\r\n{}", s.len(), s).unwrap();
+        self.recv_until(|m| m["type"] == "response" && m["request_seq"] == seq)
+    }
+}
+
+#[test]
+fn test_attach_and_break() {
+    if is_wasm() {
+        return;
+    }
+
+    let path = std::env::temp_dir().join(format!("dap_server_test_{}.star", std::process::id()));
+    fs::write(&path, "x = 1\ny = x + 1\nz = y + 1\n").unwrap();
+    let path_str = path.to_str().unwrap().to_owned();
+
+    let server = DapServer::bind("127.0.0.1:0", Dialect::Standard).unwrap();
+    let mut client = TestClient::connect(&server);
+
+    let init = client.request("initialize", json!({"adapterID": "starlark"}));
+    assert_eq!(init["success"], true);
+    client.event("initialized");
+    client.request("attach", json!({}));
+    let bps = client.request(
+        "setBreakpoints",
+        json!({"source": {"path": path_str}, "breakpoints": [{"line": 2}]}),
+    );
+    assert_eq!(bps["body"]["breakpoints"][0]["verified"], true);
+    client.request("configurationDone", json!({}));
+
+    let eval_thread = {
+        let server = server.clone();
+        let path = path.clone();
+        thread::spawn(move || {
+            let ast = AstModule::parse_file(&path, &Dialect::Standard).unwrap();
+            let module = Module::new();
+            let mut eval = Evaluator::new(&module);
+            let _thread = server.attach("worker", &mut eval);
+            eval.eval_module(ast, &Globals::standard()).unwrap();
+            module.get("z").unwrap().unpack_i32().unwrap()
+        })
+    };
+
+    let started = client.event("thread");
+    assert_eq!(started["body"]["reason"], "started");
+    let thread_id = started["body"]["threadId"].clone();
+
+    let threads = client.request("threads", json!({}));
+    assert_eq!(threads["body"]["threads"][0]["name"], "worker");
+
+    let mut stops = 0;
+    loop {
+        let message = client.recv_until(|m| m["event"] == "stopped" || m["event"] == "thread");
+        if message["event"] == "thread" {
+            assert_eq!(message["body"]["reason"], "exited");
+            break;
+        }
+        stops += 1;
+        assert_eq!(message["body"]["threadId"], thread_id);
+        let trace = client.request("stackTrace", json!({"threadId": thread_id}));
+        assert_eq!(trace["body"]["stackFrames"][0]["line"], 2);
+        let frame_id = trace["body"]["stackFrames"][0]["id"].clone();
+        let x = client.request("evaluate", json!({"expression": "x", "frameId": frame_id}));
+        assert_eq!(x["body"]["result"], "1");
+        let resumed = client.request("continue", json!({"threadId": thread_id}));
+        assert_eq!(resumed["success"], true);
+    }
+    assert_eq!(stops, 1);
+    assert_eq!(eval_thread.join().unwrap(), 3);
+
+    let running = client.request("stackTrace", json!({"threadId": thread_id}));
+    assert_eq!(running["success"], false);
+
+    client.request("disconnect", json!({}));
+    fs::remove_file(&path).unwrap();
+}
+
+/// Starts evaluating `path` on a new thread, attached to `server` as a thread called `name`,
+/// returning the value of `z` at the end.
+fn spawn_eval(server: &DapServer, name: &str, path: &Path) -> thread::JoinHandle<i32> {
+    let server = server.clone();
+    let name = name.to_owned();
+    let path = path.to_owned();
+    thread::spawn(move || {
+        let ast = AstModule::parse_file(&path, &Dialect::Standard).unwrap();
+        let module = Module::new();
+        let mut eval = Evaluator::new(&module);
+        let _thread = server.attach(name, &mut eval);
+        eval.eval_module(ast, &Globals::standard()).unwrap();
+        module.get("z").unwrap().unpack_i32().unwrap()
+    })
+}
+
+#[test]
+fn test_two_evaluators() {
+    if is_wasm() {
+        return;
+    }
+
+    let dir = std::env::temp_dir();
+    let pid = std::process::id();
+    let path_a = dir.join(format!("dap_server_test_a_{pid}.star"));
+    let path_b = dir.join(format!("dap_server_test_b_{pid}.star"));
+    fs::write(&path_a, "x = 1\ny = x + 1\nz = y + 1\n").unwrap();
+    fs::write(&path_b, "x = 10\ny = x + 1\nz = y + 1\n").unwrap();
+
+    let server = DapServer::bind("127.0.0.1:0", Dialect::Standard).unwrap();
+    let mut client = TestClient::connect(&server);
+    client.request("initialize", json!({"adapterID": "starlark"}));
+    client.request("attach", json!({}));
+    for path in [&path_a, &path_b] {
+        client.request(
+            "setBreakpoints",
+            json!({"source": {"path": path.to_str().unwrap()}, "breakpoints": [{"line": 2}]}),
+        );
+    }
+
+    let eval_a = spawn_eval(&server, "a", &path_a);
+    let eval_b = spawn_eval(&server, "b", &path_b);
+
+    // Both evaluators stop once, each on its own thread, while the other stays paused.
+    let mut stopped = Vec::new();
+    for _ in 0..2 {
+        let message = client.recv_until(|m| m["event"] == "stopped");
+        stopped.push(message["body"]["threadId"].clone());
+    }
+    assert_ne!(stopped[0], stopped[1]);
+
+    let threads = client.request("threads", json!({}));
+    let mut names: Vec<(Value, Value)> = threads["body"]["threads"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| (t["id"].clone(), t["name"].clone()))
+        .collect();
+    names.sort_by_key(|(id, _)| id.as_i64());
+    assert_eq!(2, names.len());
+    assert_ne!(names[0].0, names[1].0);
+
+    for thread_id in &stopped {
+        let name = &names.iter().find(|(id, _)| id == thread_id).unwrap().1;
+        let trace = client.request("stackTrace", json!({"threadId": thread_id}));
+        let frame_id = trace["body"]["stackFrames"][0]["id"].clone();
+        let x = client.request("evaluate", json!({"expression": "x", "frameId": frame_id}));
+        let expected = if name == "a" { "1" } else { "10" };
+        assert_eq!(x["body"]["result"], expected);
+    }
+
+    // Resuming one thread leaves the other paused.
+    let first = stopped[0].clone();
+    client.request("continue", json!({"threadId": first}));
+    let exited = client.recv_until(|m| m["event"] == "thread" && m["body"]["reason"] == "exited");
+    assert_eq!(exited["body"]["threadId"], first);
+    let trace = client.request("stackTrace", json!({"threadId": stopped[1]}));
+    assert_eq!(trace["success"], true);
+    client.request("continue", json!({"threadId": stopped[1]}));
+
+    assert_eq!(eval_a.join().unwrap(), 3);
+    assert_eq!(eval_b.join().unwrap(), 12);
+
+    client.request("disconnect", json!({}));
+    fs::remove_file(&path_a).unwrap();
+    fs::remove_file(&path_b).unwrap();
+}
+
+#[test]
+fn test_nested_variables() {
+    if is_wasm() {
+        return;
+    }
+
+    let path =
+        std::env::temp_dir().join(format!("dap_server_test_vars_{}.star", std::process::id()));
+    fs::write(
+        &path,
+        "def f():\n    x = {\"k\": [1, 2]}\n    return len(x)\nz = f()\n",
+    )
+    .unwrap();
+    let path_str = path.to_str().unwrap().to_owned();
+
+    let server = DapServer::bind("127.0.0.1:0", Dialect::Standard).unwrap();
+    let mut client = TestClient::connect(&server);
+    client.request("initialize", json!({"adapterID": "starlark"}));
+    client.request("attach", json!({}));
+    client.request(
+        "setBreakpoints",
+        json!({"source": {"path": path_str}, "breakpoints": [{"line": 3}]}),
+    );
+    let eval_thread = spawn_eval(&server, "worker", &path);
+
+    let stopped = client.event("stopped");
+    let thread_id = stopped["body"]["threadId"].clone();
+    let trace = client.request("stackTrace", json!({"threadId": thread_id}));
+    let frame_id = trace["body"]["stackFrames"][0]["id"].clone();
+    let scopes = client.request("scopes", json!({"frameId": frame_id}));
+    let locals_ref = scopes["body"]["scopes"][0]["variablesReference"].clone();
+
+    let locals = client.request("variables", json!({"variablesReference": locals_ref}));
+    let x = &locals["body"]["variables"][0];
+    assert_eq!(x["name"], "x");
+    let x_ref = x["variablesReference"].clone();
+    assert_ne!(x_ref, 0);
+    assert_ne!(x_ref, locals_ref);
+    assert_ne!(x_ref, thread_id);
+
+    let entries = client.request("variables", json!({"variablesReference": x_ref}));
+    let k = &entries["body"]["variables"][0];
+    assert_eq!(k["name"], "\"k\"");
+    let list = client.request(
+        "variables",
+        json!({"variablesReference": k["variablesReference"]}),
+    );
+    let items = list["body"]["variables"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[1]["value"], "2");
+    assert_eq!(items[1]["variablesReference"], 0);
+
+    client.request("continue", json!({"threadId": thread_id}));
+    assert_eq!(eval_thread.join().unwrap(), 1);
+    // References don't survive the thread resuming.
+    let stale = client.request("variables", json!({"variablesReference": x_ref}));
+    assert_eq!(stale["success"], false);
+
+    client.request("disconnect", json!({}));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_drop_stops_listening() {
+    if is_wasm() {
+        return;
+    }
+
+    let server = DapServer::bind("127.0.0.1:0", Dialect::Standard).unwrap();
+    let addr = server.local_addr();
+    let mut client = TestClient::connect(&server);
+    client.request("initialize", json!({"adapterID": "starlark"}));
+    client.event("initialized");
+    drop(server);
+
+    // The connected client is disconnected.
+    assert!(read_message(&mut client.reader).is_none());
+    // The port is closed once the accept loop has noticed.
+    for _ in 0..1000 {
+        if TcpStream::connect(addr).is_err() {
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    panic!("The server is still listening on {addr}");
+}
@@ -107,6 +107,12 @@ impl Globals {
         GlobalsBuilder::extended_by(extensions).build()
     }
 
+    /// Create a [`Globals`] with the builtins of a named dialect preset,
+    /// as listed by [`LibraryExtension::for_dialect_name`].
+    pub fn for_dialect_name(name: &str) -> Option<Self> {
+        LibraryExtension::for_dialect_name(name).map(Self::extended_by)
+    }
+
     /// This function is only safe if you first call `heap` and keep a reference to it.
     /// Therefore, don't expose it on the public API.
     pub(crate) fn get<'v>(&'v self, name: &str) -> Option<Value<'v>> {
@@ -358,6 +364,8 @@ pub(crate) fn common_documentation<'a>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::assert::Assert;
+    use crate::syntax::Dialect;
 
     #[test]
     fn test_send_sync()
@@ -365,4 +373,46 @@ mod tests {
         Globals: Send + Sync,
     {
     }
+
+    #[test]
+    fn test_for_dialect_name() {
+        for name in Dialect::NAMES {
+            assert!(Dialect::from_name(name).is_some(), "{name}");
+            assert!(Globals::for_dialect_name(name).is_some(), "{name}");
+        }
+        assert!(Dialect::from_name("python").is_none());
+        assert!(Globals::for_dialect_name("python").is_none());
+
+        let bazel = Globals::for_dialect_name("bazel").unwrap();
+        assert!(bazel.get_frozen("struct").is_some());
+        assert!(bazel.get_frozen("set").is_some());
+        assert!(bazel.get_frozen("record").is_none());
+        let buck2 = Globals::for_dialect_name("buck2").unwrap();
+        assert!(buck2.get_frozen("record").is_some());
+        assert!(buck2.get_frozen("set").is_none());
+    }
+
+    #[test]
+    fn test_dialect_presets() {
+        let mut a = Assert::new();
+        a.dialect(&Dialect::Bazel);
+        a.globals(Globals::for_dialect_name("bazel").unwrap());
+        a.eq("'{\"x\":1}'", "json.encode(struct(x = 1))");
+        a.fail("for x in []:\n    pass", "outside `def`");
+        a.fail(
+            "def f(n):\n    return f(n - 1) if n else 0\nf(1)",
+            "called recursively",
+        );
+
+        let mut a = Assert::new();
+        a.dialect(&Dialect::StarlarkGo);
+        a.globals(Globals::for_dialect_name("starlark-go").unwrap());
+        a.eq("b'hi'", "'hi'.encode()");
+        a.eq("set([1])", "set([1, 1])");
+
+        let mut a = Assert::new();
+        a.dialect(&Dialect::Buck2);
+        a.globals(Globals::for_dialect_name("buck2").unwrap());
+        a.pass("def f(x: int) -> int:\n    return x\nR = record(x = int)\nf(R(x = 1).x)");
+    }
 }
@@ -42,6 +42,7 @@ pub use runtime::profile::data::ProfileData;
 pub use runtime::profile::mode::ProfileMode;
 pub use soft_error::SoftErrorHandler;
 pub use starlark_syntax::call_stack::CallStack;
+use starlark_syntax::eval_exception::EvalException;
 use starlark_syntax::slice_vec_ext::SliceExt;
 use starlark_syntax::syntax::module::AstModule;
 use starlark_syntax::syntax::module::AstModuleFields;
@@ -58,6 +59,7 @@ pub use crate::eval::params::param_specs;
 use crate::eval::runtime::arguments::ArgNames;
 use crate::eval::runtime::arguments::ArgumentsFull;
 use crate::eval::runtime::evaluator;
+use crate::eval::runtime::evaluator::EvaluatorError;
 use crate::syntax::DialectTypes;
 use crate::values::Value;
 
@@ -68,6 +70,18 @@ impl<'v, 'a, 'e> Evaluator<'v, 'a, 'e> {
         #[cfg(not(target_arch = "wasm32"))]
         let start = Instant::now();
 
+        // Modules from `AstModule::parse_with_recovery` may contain error statements.
+        if let Some(span) = ast.first_error_stmt() {
+            return Err(EvalException::new(
+                crate::Error::new_kind(crate::ErrorKind::Parser(anyhow::Error::new(
+                    EvaluatorError::SyntaxError,
+                ))),
+                span,
+                ast.codemap(),
+            )
+            .into_error());
+        }
+
         let (codemap, statement, dialect, typecheck) = ast.into_parts();
 
         let codemap = self.module_env.frozen_heap().alloc_any(codemap.dupe());
@@ -108,6 +122,7 @@ impl<'v, 'a, 'e> Evaluator<'v, 'a, 'e> {
                     .frozen_heap()
                     .alloc_any_slice(&scope_names.parent),
                 globals,
+                dialect.enable_bytes,
             )),
         );
 
@@ -128,6 +143,8 @@ impl<'v, 'a, 'e> Evaluator<'v, 'a, 'e> {
             codemap,
             eval: self,
             check_types: dialect.enable_types == DialectTypes::Enable,
+            enable_recursion: dialect.enable_recursion,
+            enable_bytes: dialect.enable_bytes,
             top_level_stmt_count,
             typecheck,
         };
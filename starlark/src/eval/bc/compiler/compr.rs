@@ -22,8 +22,10 @@ use crate::eval::bc::compiler::if_compiler::write_if_then;
 use crate::eval::bc::compiler::stmt::write_for;
 use crate::eval::bc::instr_impl::InstrComprDictInsert;
 use crate::eval::bc::instr_impl::InstrComprListAppend;
+use crate::eval::bc::instr_impl::InstrComprSetInsert;
 use crate::eval::bc::instr_impl::InstrDictNew;
 use crate::eval::bc::instr_impl::InstrListNew;
+use crate::eval::bc::instr_impl::InstrSetNew;
 use crate::eval::bc::stack_ptr::BcSlotOut;
 use crate::eval::bc::writer::BcWriter;
 use crate::eval::compiler::compr::ClauseCompiled;
@@ -96,6 +98,18 @@ impl ComprCompiled {
                         });
                     });
                 }
+                ComprCompiled::Set(ref expr, ref clauses) => {
+                    bc.write_instr::<InstrSetNew>(span, temp.to_out());
+                    let (first, rem) = clauses.split_last();
+                    first.write_bc(bc, rem, |bc| {
+                        expr.write_bc_cb(bc, |expr_slot, bc| {
+                            bc.write_instr::<InstrComprSetInsert>(
+                                expr.span,
+                                (temp.to_in(), expr_slot),
+                            )
+                        });
+                    });
+                }
             };
             bc.write_mov(span, temp.to_in(), target);
         });
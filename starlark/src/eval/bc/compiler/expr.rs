@@ -23,6 +23,7 @@ use starlark_syntax::slice_vec_ext::SliceExt;
 
 use crate::collections::Hashed;
 use crate::collections::SmallMap;
+use crate::collections::SmallSet;
 use crate::eval::bc::compiler::if_compiler::write_if_else;
 use crate::eval::bc::instr_impl::*;
 use crate::eval::bc::slow_arg::BcInstrSlowArg;
@@ -138,6 +139,11 @@ impl ExprCompiled {
                     v.mark_definitely_assigned_after(bc);
                 }
             }
+            ExprCompiled::Set(xs) => {
+                for x in xs {
+                    x.mark_definitely_assigned_after(bc);
+                }
+            }
             ExprCompiled::Compr(compr) => compr.mark_definitely_assigned_after(bc),
             ExprCompiled::If(c_t_f) => {
                 let (c, _t, _f) = &**c_t_f;
@@ -256,6 +262,36 @@ impl IrSpanned<ExprCompiled> {
         }
     }
 
+    fn try_set_of_consts(xs: &[IrSpanned<ExprCompiled>]) -> Option<SmallSet<FrozenValue>> {
+        let mut res = SmallSet::with_capacity(xs.len());
+        for x in xs {
+            // Unhashable elements fail at runtime.
+            res.insert_hashed(x.as_value()?.get_hashed().ok()?);
+        }
+        Some(res)
+    }
+
+    fn write_set(
+        span: FrameSpan,
+        xs: &[IrSpanned<ExprCompiled>],
+        target: BcSlotOut,
+        bc: &mut BcWriter,
+    ) {
+        if xs.is_empty() {
+            bc.write_instr::<InstrSetNew>(span, target);
+        } else if let Some(s) = Self::try_set_of_consts(xs) {
+            bc.write_instr::<InstrSetOfConsts>(span, (s, target));
+        } else {
+            let spans = xs.map(|x| x.span);
+            write_exprs(xs, bc, |xs, bc| {
+                bc.write_instr_explicit::<InstrSetNPop>(
+                    BcInstrSlowArg { span, spans },
+                    (xs, target),
+                );
+            });
+        }
+    }
+
     fn write_not(expr: &IrSpanned<ExprCompiled>, target: BcSlotOut, bc: &mut BcWriter) {
         expr.write_bc_cb(bc, |slot, bc| {
             bc.write_instr::<InstrNot>(expr.span, (slot, target));
@@ -335,6 +371,7 @@ impl IrSpanned<ExprCompiled> {
                 }
             }
             ExprCompiled::Dict(ref xs) => Self::write_dict(span, xs, target, bc),
+            ExprCompiled::Set(ref xs) => Self::write_set(span, xs, target, bc),
             ExprCompiled::Compr(ref compr) => compr.write_bc(span, target, bc),
             ExprCompiled::Slice(l_start_stop_step) => {
                 let (l, start, stop, step) = &**l_start_stop_step;
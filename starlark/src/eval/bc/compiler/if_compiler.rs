@@ -52,6 +52,22 @@ pub(crate) fn write_if_then(
     wr(c, maybe_not, t, |_| unreachable!(), bc);
 }
 
+/// Write the condition of a `while` loop.
+///
+/// Generated code falls through if the condition is true. Returned are addresses
+/// of instructions which jump when the condition is false, the caller needs to patch these.
+pub(crate) fn write_loop_cond(cond: &IrSpanned<ExprCompiled>, bc: &mut BcWriter) -> Vec<PatchAddr> {
+    if let Some(true) = cond.as_value().and_then(|v| v.to_value().unpack_bool()) {
+        // `while True:`, nothing to check.
+        return Vec::new();
+    }
+    let mut t = Vec::new();
+    let mut f = Vec::new();
+    write_cond(cond, MaybeNot::Id, &mut t, &mut f, bc);
+    bc.patch_addrs(t);
+    f
+}
+
 /// Common code for writing if-then or if-then-else expression or statement.
 fn write_if_else_impl<T, F>(
     cond: &IrSpanned<ExprCompiled>,
@@ -18,6 +18,7 @@
 use crate::eval::bc::bytecode::Bc;
 use crate::eval::bc::compiler::if_compiler::write_if_else;
 use crate::eval::bc::compiler::if_compiler::write_if_then;
+use crate::eval::bc::compiler::if_compiler::write_loop_cond;
 use crate::eval::bc::instr_impl::InstrCheckType;
 use crate::eval::bc::instr_impl::InstrPossibleGc;
 use crate::eval::bc::instr_impl::InstrReturn;
@@ -119,6 +120,11 @@ impl StmtCompiled {
                 let (_var, over, _body) = &**var_over_body;
                 over.mark_definitely_assigned_after(bc);
             }
+            StmtCompiled::While(cond_body) => {
+                // The condition is evaluated at least once.
+                let (cond, _body) = &**cond_body;
+                cond.mark_definitely_assigned_after(bc);
+            }
             StmtCompiled::Break => {}
             StmtCompiled::Continue => {}
         }
@@ -135,7 +141,11 @@ impl StmtCompiled {
 
 impl IrSpanned<StmtCompiled> {
     fn write_bc(&self, compiler: &StmtCompileContext, bc: &mut BcWriter) {
-        bc.mark_before_stmt(self.span);
+        // A GC point has the span of the statement it precedes,
+        // which would otherwise be seen twice by statement hooks.
+        if !matches!(self.node, StmtCompiled::PossibleGc) {
+            bc.mark_before_stmt(self.span);
+        }
         self.write_bc_inner(compiler, bc);
         self.mark_definitely_assigned_after(bc);
     }
@@ -244,6 +254,14 @@ impl IrSpanned<StmtCompiled> {
                 let (assign, over, body) = &**assign_over_body;
                 write_for(over, assign, span, bc, |bc| body.write_bc(compiler, bc));
             }
+            StmtCompiled::While(cond_body) => {
+                let (cond, body) = &**cond_body;
+                bc.write_while(
+                    span,
+                    |bc| write_loop_cond(cond, bc),
+                    |bc| body.write_bc(compiler, bc),
+                );
+            }
             StmtCompiled::Break => {
                 bc.write_break(span);
             }
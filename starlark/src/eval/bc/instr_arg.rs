@@ -27,6 +27,7 @@ use itertools::Itertools;
 use crate::collections::symbol::symbol::Symbol;
 use crate::collections::Hashed;
 use crate::collections::SmallMap;
+use crate::collections::SmallSet;
 use crate::environment::slots::ModuleSlotId;
 use crate::eval::bc::addr::BcAddr;
 use crate::eval::bc::addr::BcAddrOffset;
@@ -667,6 +668,27 @@ impl BcInstrArg for SmallMap<FrozenValue, FrozenValue> {
     fn visit_jump_addr(_param: &Self, _ip: BcAddr, _consumer: &mut dyn FnMut(BcAddr)) {}
 }
 
+impl BcInstrArg for SmallSet<FrozenValue> {
+    fn fmt_append(
+        param: &Self,
+        _ip: BcAddr,
+        _end_arg: Option<&BcInstrEndArg>,
+        f: &mut dyn Write,
+    ) -> fmt::Result {
+        write!(f, " {{")?;
+        for (i, v) in param.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", TruncateValueRepr(*v))?;
+        }
+        write!(f, "}}")?;
+        Ok(())
+    }
+
+    fn visit_jump_addr(_param: &Self, _ip: BcAddr, _consumer: &mut dyn FnMut(BcAddr)) {}
+}
+
 impl BcInstrArg for InstrDefData {
     fn fmt_append(
         _param: &Self,
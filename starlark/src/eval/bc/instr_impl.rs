@@ -27,6 +27,7 @@ use crate::coerce::coerce;
 use crate::collections::symbol::symbol::Symbol;
 use crate::collections::Hashed;
 use crate::collections::SmallMap;
+use crate::collections::SmallSet;
 use crate::const_frozen_string;
 use crate::environment::slots::ModuleSlotId;
 use crate::eval::bc::addr::BcAddrOffset;
@@ -73,6 +74,7 @@ use crate::eval::ParametersSpec;
 use crate::values::dict::Dict;
 use crate::values::int::pointer_i32::PointerI32;
 use crate::values::layout::value_not_special::FrozenValueNotSpecial;
+use crate::values::set::value::SetData;
 use crate::values::string::dot_format::format_one;
 use crate::values::string::interpolation::percent_s_one;
 use crate::values::types::known_methods::KnownMethod;
@@ -912,6 +914,9 @@ pub(crate) struct InstrDictConstKeysImpl;
 pub(crate) struct InstrDictNPopImpl;
 pub(crate) struct InstrListNewImpl;
 pub(crate) struct InstrDictNewImpl;
+pub(crate) struct InstrSetNewImpl;
+pub(crate) struct InstrSetNPopImpl;
+pub(crate) struct InstrSetOfConstsImpl;
 
 pub(crate) type InstrTupleNPop = InstrNoFlow<InstrTupleNPopImpl>;
 pub(crate) type InstrListNew = InstrNoFlow<InstrListNewImpl>;
@@ -921,6 +926,9 @@ pub(crate) type InstrDictNew = InstrNoFlow<InstrDictNewImpl>;
 pub(crate) type InstrDictOfConsts = InstrNoFlow<InstrDictOfConstsImpl>;
 pub(crate) type InstrDictConstKeys = InstrNoFlow<InstrDictConstKeysImpl>;
 pub(crate) type InstrDictNPop = InstrNoFlow<InstrDictNPopImpl>;
+pub(crate) type InstrSetNew = InstrNoFlow<InstrSetNewImpl>;
+pub(crate) type InstrSetNPop = InstrNoFlow<InstrSetNPopImpl>;
+pub(crate) type InstrSetOfConsts = InstrNoFlow<InstrSetOfConstsImpl>;
 
 impl InstrNoFlowImpl for InstrTupleNPopImpl {
     type Arg = (BcSlotInRange, BcSlotOut);
@@ -1077,8 +1085,72 @@ impl InstrNoFlowImpl for InstrDictNewImpl {
     }
 }
 
+impl InstrNoFlowImpl for InstrSetNewImpl {
+    type Arg = BcSlotOut;
+
+    #[inline(always)]
+    fn run_with_args<'v>(
+        eval: &mut Evaluator<'v, '_, '_>,
+        frame: BcFramePtr<'v>,
+        _: BcPtrAddr,
+        target: &BcSlotOut,
+    ) -> crate::Result<()> {
+        let set = eval.heap().alloc(SetData::default());
+        frame.set_bc_slot(*target, set);
+        Ok(())
+    }
+}
+
+impl InstrNoFlowImpl for InstrSetNPopImpl {
+    type Arg = (BcSlotInRange, BcSlotOut);
+
+    fn run_with_args<'v>(
+        eval: &mut Evaluator<'v, '_, '_>,
+        frame: BcFramePtr<'v>,
+        ip: BcPtrAddr,
+        (npops, target): &(BcSlotInRange, BcSlotOut),
+    ) -> crate::Result<()> {
+        let items = frame.get_bc_slot_range(*npops);
+        let mut content = SmallSet::with_capacity(items.len());
+        for (i, item) in items.iter().enumerate() {
+            match item.get_hashed() {
+                Ok(item) => {
+                    content.insert_hashed(item);
+                }
+                Err(e) => {
+                    let spans = &Bc::slow_arg_at_ptr(ip).spans;
+                    return Err(add_span_to_expr_error(e, spans[i], eval).into_error());
+                }
+            }
+        }
+        let set = eval.heap().alloc(SetData { content });
+        frame.set_bc_slot(*target, set);
+        Ok(())
+    }
+}
+
+impl InstrNoFlowImpl for InstrSetOfConstsImpl {
+    type Arg = (SmallSet<FrozenValue>, BcSlotOut);
+
+    #[inline(always)]
+    fn run_with_args<'v>(
+        eval: &mut Evaluator<'v, '_, '_>,
+        frame: BcFramePtr<'v>,
+        _: BcPtrAddr,
+        (values, target): &(SmallSet<FrozenValue>, BcSlotOut),
+    ) -> crate::Result<()> {
+        let content: &SmallSet<Value> = coerce(values);
+        let set = eval.heap().alloc(SetData {
+            content: content.clone(),
+        });
+        frame.set_bc_slot(*target, set);
+        Ok(())
+    }
+}
+
 pub(crate) struct InstrComprListAppend;
 pub(crate) struct InstrComprDictInsert;
+pub(crate) struct InstrComprSetInsert;
 
 impl BcInstr for InstrComprListAppend {
     type Arg = (BcSlotIn, BcSlotIn);
@@ -1125,6 +1197,29 @@ impl BcInstr for InstrComprDictInsert {
     }
 }
 
+impl BcInstr for InstrComprSetInsert {
+    type Arg = (BcSlotIn, BcSlotIn);
+
+    #[inline(always)]
+    fn run<'v, 'b>(
+        _eval: &mut Evaluator<'v, '_, '_>,
+        frame: BcFramePtr<'v>,
+        ip: BcPtrAddr<'b>,
+        (set, item): &(BcSlotIn, BcSlotIn),
+    ) -> InstrControl<'v, 'b> {
+        let set = frame.get_bc_slot(*set);
+        let item = frame.get_bc_slot(*item);
+        let item = match item.get_hashed() {
+            Ok(item) => item,
+            Err(e) => return InstrControl::Err(e),
+        };
+        // SAFETY: in generated bytecode this slot can be only occupied by a mutable set.
+        let mut set = unsafe { SetData::from_value_unchecked_mut(set) };
+        set.add_hashed(item);
+        InstrControl::Next(ip.add_instr::<Self>())
+    }
+}
+
 pub(crate) struct InstrCheckTypeImpl;
 pub(crate) type InstrCheckType = InstrNoFlow<InstrCheckTypeImpl>;
 
@@ -1154,6 +1249,8 @@ impl InstrNoFlowImpl for InstrCheckTypeImpl {
 }
 
 pub(crate) struct InstrBr;
+/// Jump back to the condition of a `while` loop.
+pub(crate) struct InstrBrBack;
 pub(crate) struct InstrIfBr;
 pub(crate) struct InstrIfNotBr;
 
@@ -1171,6 +1268,20 @@ impl BcInstr for InstrBr {
     }
 }
 
+impl BcInstr for InstrBrBack {
+    type Arg = BcAddrOffsetNeg;
+
+    #[inline(always)]
+    fn run<'v, 'b>(
+        _eval: &mut Evaluator<'v, '_, '_>,
+        _frame: BcFramePtr<'v>,
+        ip: BcPtrAddr<'b>,
+        target: &BcAddrOffsetNeg,
+    ) -> InstrControl<'v, 'b> {
+        InstrControl::Next(ip.add_rel_neg(*target))
+    }
+}
+
 impl BcInstr for InstrIfBr {
     type Arg = (BcSlotIn, BcAddrOffset);
 
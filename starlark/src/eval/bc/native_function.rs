@@ -56,6 +56,8 @@ impl BcNativeFunction {
         args: &Arguments<'v, '_>,
         eval: &mut Evaluator<'v, '_, '_>,
     ) -> crate::Result<Value<'v>> {
-        self.imp.invoke(eval, args)
+        let res = self.imp.invoke(eval, args);
+        eval.recorder.native_call(&self.fun.name, &res);
+        res
     }
 }
@@ -88,10 +88,15 @@ pub(crate) enum BcOpcode {
     DictNPop,
     DictOfConsts,
     DictConstKeys,
+    SetNew,
+    SetNPop,
+    SetOfConsts,
     ComprListAppend,
     ComprDictInsert,
+    ComprSetInsert,
     CheckType,
     Br,
+    BrBack,
     IfBr,
     IfNotBr,
     Iter,
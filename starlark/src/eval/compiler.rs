@@ -89,6 +89,10 @@ pub(crate) struct Compiler<'v, 'a, 'e, 'x> {
     pub(crate) globals: FrozenRef<'static, Globals>,
     pub(crate) codemap: FrozenRef<'static, CodeMap>,
     pub(crate) check_types: bool,
+    /// [`Dialect::enable_recursion`](crate::syntax::Dialect::enable_recursion).
+    pub(crate) enable_recursion: bool,
+    /// [`Dialect::enable_bytes`](crate::syntax::Dialect::enable_bytes).
+    pub(crate) enable_bytes: bool,
     pub(crate) top_level_stmt_count: usize,
     /// Set with `@starlark-rust: typecheck`.
     pub(crate) typecheck: bool,
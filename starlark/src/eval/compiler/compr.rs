@@ -64,6 +64,20 @@ impl Compiler<'_, '_, '_, '_> {
         )))
     }
 
+    pub fn set_comprehension(
+        &mut self,
+        x: &CstExpr,
+        for_: &ForClauseP<CstPayload>,
+        clauses: &[ClauseP<CstPayload>],
+    ) -> Result<ExprCompiled, CompilerInternalError> {
+        let clauses = self.compile_clauses(for_, clauses)?;
+        let x = self.expr(x)?;
+        Ok(ExprCompiled::compr(ComprCompiled::Set(
+            Box::new(x),
+            clauses,
+        )))
+    }
+
     /// Peel the final if's from clauses, and return them (in the order they started), plus the next for you get to
     fn compile_ifs(
         &mut self,
@@ -136,6 +150,7 @@ pub(crate) enum ComprCompiled {
         Box<(IrSpanned<ExprCompiled>, IrSpanned<ExprCompiled>)>,
         ClausesCompiled,
     ),
+    Set(Box<IrSpanned<ExprCompiled>>, ClausesCompiled),
 }
 
 impl ComprCompiled {
@@ -143,6 +158,7 @@ impl ComprCompiled {
         match self {
             ComprCompiled::List(_, clauses) => clauses,
             ComprCompiled::Dict(_, clauses) => clauses,
+            ComprCompiled::Set(_, clauses) => clauses,
         }
     }
 
@@ -160,6 +176,10 @@ impl ComprCompiled {
                     clauses.optimize(ctx),
                 ))
             }
+            ComprCompiled::Set(ref x, ref clauses) => {
+                let clauses = clauses.optimize(ctx);
+                ExprCompiled::compr(ComprCompiled::Set(Box::new(x.optimize(ctx)), clauses))
+            }
         }
     }
 }
@@ -350,6 +350,10 @@ pub(crate) struct DefInfo {
     stmt_compile_context: StmtCompileContext,
     /// Function can be inlined.
     pub(crate) inline_def_body: Option<InlineDefBody>,
+    /// If false, calling this function while it is already on the call stack is an error.
+    pub(crate) enable_recursion: bool,
+    /// If false, `str.encode` fails when called from this function.
+    pub(crate) enable_bytes: bool,
     /// Globals captured during function or module creation.
     /// Only needed for debugger evaluation.
     pub(crate) globals: FrozenRef<'static, Globals>,
@@ -370,6 +374,8 @@ impl DefInfo {
             body_stmts: StmtsCompiled::empty(),
             stmt_compile_context: StmtCompileContext::default(),
             inline_def_body: None,
+            enable_recursion: true,
+            enable_bytes: true,
             globals: FrozenRef::new(Globals::empty()),
         });
         FrozenRef::new(&EMPTY)
@@ -380,6 +386,7 @@ impl DefInfo {
         local_names: FrozenRef<'static, [FrozenStringValue]>,
         parent: FrozenRef<'static, [CopySlotFromParent]>,
         globals: FrozenRef<'static, Globals>,
+        enable_bytes: bool,
     ) -> DefInfo {
         DefInfo {
             name: const_frozen_string!("<module>"),
@@ -394,6 +401,8 @@ impl DefInfo {
             body_stmts: StmtsCompiled::empty(),
             stmt_compile_context: StmtCompileContext::default(),
             inline_def_body: None,
+            enable_recursion: true,
+            enable_bytes,
             globals,
         }
     }
@@ -487,6 +496,9 @@ impl Compiler<'_, '_, '_, '_> {
         let inline_def_body = if has_types {
             // It is harder to inline if a function declares parameter types or return type.
             None
+        } else if !self.enable_recursion {
+            // Inlined calls do not appear on the call stack, which we check for recursion.
+            None
         } else {
             inline_def_body(&params, &body)
         };
@@ -514,6 +526,8 @@ impl Compiler<'_, '_, '_, '_> {
             ),
             body_stmts: body,
             inline_def_body,
+            enable_recursion: self.enable_recursion,
+            enable_bytes: self.enable_bytes,
             stmt_compile_context: self.compile_context(return_type.is_some()),
             globals: self.globals,
         });
@@ -787,6 +801,10 @@ where
     ) -> crate::Result<Value<'v>> {
         // println!("invoking {}", self.def.stmt.name.node);
 
+        if !self.def_info.enable_recursion {
+            eval.check_recursion(self.def_info)?;
+        }
+
         if !self.parameter_types.is_empty() {
             self.check_parameter_types(eval)?;
         }
@@ -137,6 +137,7 @@ impl IsSafeToInlineExpr {
             ExprCompiled::Dict(xs) => xs
                 .iter()
                 .all(|(x, y)| self.is_safe_to_inline_expr(x) && self.is_safe_to_inline_expr(y)),
+            ExprCompiled::Set(xs) => xs.iter().all(|x| self.is_safe_to_inline_expr(x)),
             ExprCompiled::If(c_t_f) => {
                 let (c, t, f) = &**c_t_f;
                 self.is_safe_to_inline_expr(c)
@@ -301,6 +302,16 @@ impl InlineDefCallSite<'_, '_, '_, '_, '_> {
                     node: ExprCompiled::Dict(xs),
                 }
             }
+            ExprCompiled::Set(xs) => {
+                let xs = xs
+                    .iter()
+                    .map(|x| self.inline(x))
+                    .collect::<Result<Vec<_>, CannotInline>>()?;
+                IrSpanned {
+                    span,
+                    node: ExprCompiled::Set(xs),
+                }
+            }
             ExprCompiled::Builtin2(op, l_r) => {
                 let (l, r) = &**l_r;
                 let l = self.inline(l)?;
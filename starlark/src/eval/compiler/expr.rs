@@ -62,6 +62,7 @@ use crate::eval::runtime::slots::LocalSlotId;
 use crate::eval::Arguments;
 use crate::eval::Evaluator;
 use crate::values::bool::StarlarkBool;
+use crate::values::bytes::StarlarkBytes;
 use crate::values::function::BoundMethodGen;
 use crate::values::function::FrozenBoundMethod;
 use crate::values::list::ListRef;
@@ -243,6 +244,7 @@ pub(crate) enum ExprCompiled {
     Tuple(Vec<IrSpanned<ExprCompiled>>),
     List(Vec<IrSpanned<ExprCompiled>>),
     Dict(Vec<(IrSpanned<ExprCompiled>, IrSpanned<ExprCompiled>)>),
+    Set(Vec<IrSpanned<ExprCompiled>>),
     /// Comprehension.
     Compr(ComprCompiled),
     If(
@@ -363,6 +365,7 @@ impl ExprCompiled {
             ExprCompiled::List(xs) => xs.is_empty(),
             ExprCompiled::Tuple(xs) => xs.is_empty(),
             ExprCompiled::Dict(xs) => xs.is_empty(),
+            ExprCompiled::Set(xs) => xs.is_empty(),
             ExprCompiled::Value(v) if v.is_builtin() => {
                 v.to_value().length().map_or(false, |l| l == 0)
             }
@@ -389,6 +392,8 @@ impl ExprCompiled {
             Self::Value(..) => true,
             Self::List(xs) | Self::Tuple(xs) => xs.iter().all(|x| x.is_pure_infallible()),
             Self::Dict(xs) => xs.is_empty(),
+            // Set elements must be hashable, so only the empty set is infallible.
+            Self::Set(xs) => xs.is_empty(),
             Self::Builtin1(Builtin1::Not | Builtin1::TypeIs(_), x) => x.is_pure_infallible(),
             Self::Seq(x_y) => {
                 let (x, y) = &**x_y;
@@ -419,6 +424,7 @@ impl ExprCompiled {
             }
             // TODO(nga): if keys are unique hashable constants, we can fold this to constant too.
             ExprCompiled::Dict(xs) if xs.is_empty() => Some(false),
+            ExprCompiled::Set(xs) if xs.is_empty() => Some(false),
             ExprCompiled::Builtin1(Builtin1::Not, x) => x.is_pure_infallible_to_bool().map(|x| !x),
             ExprCompiled::LogicalBinOp(op, x_y) => {
                 let (x, y) = &**x_y;
@@ -512,6 +518,7 @@ impl IrSpanned<ExprCompiled> {
             ExprCompiled::Dict(kvs) => {
                 ExprCompiled::Dict(kvs.map(|(k, v)| (k.optimize(ctx), v.optimize(ctx))))
             }
+            ExprCompiled::Set(xs) => ExprCompiled::Set(xs.map(|e| e.optimize(ctx))),
             ExprCompiled::Compr(compr) => compr.optimize(ctx),
             ExprCompiled::If(cond_t_f) => {
                 let (cond, t, f) = &**cond_t_f;
@@ -854,6 +861,13 @@ impl ExprCompiled {
                     ExprCompiled::Compr(ComprCompiled::Dict(Box::new((k, v)), clauses))
                 }
             }
+            ComprCompiled::Set(x, clauses) => {
+                if clauses.is_nop() {
+                    ExprCompiled::Set(Vec::new())
+                } else {
+                    ExprCompiled::Compr(ComprCompiled::Set(x, clauses))
+                }
+            }
         }
     }
 
@@ -1052,6 +1066,7 @@ impl AstLiteralCompile for AstLiteral {
             AstLiteral::Int(i) => heap.alloc(StarlarkInt::from(i.node.clone())),
             AstLiteral::Float(f) => heap.alloc(f.node),
             AstLiteral::String(x) => heap.alloc(x.node.as_str()),
+            AstLiteral::Bytes(x) => heap.alloc(StarlarkBytes::new(x.node.as_slice())),
             AstLiteral::Ellipsis => heap.alloc(Ellipsis),
         }
     }
@@ -1262,6 +1277,10 @@ impl<'v, 'a, 'e> Compiler<'v, 'a, 'e, '_> {
                     .collect::<Result<_, CompilerInternalError>>()?;
                 ExprCompiled::Dict(xs)
             }
+            ExprP::Set(exprs) => {
+                let xs = self.exprs(exprs)?;
+                ExprCompiled::Set(xs)
+            }
             ExprP::If(cond_then_expr_else_expr) => {
                 let (cond, then_expr, else_expr) = &**cond_then_expr_else_expr;
                 let cond = self.expr(cond)?;
@@ -1422,6 +1441,9 @@ impl<'v, 'a, 'e> Compiler<'v, 'a, 'e, '_> {
                 let (k, v) = &**k_v;
                 self.dict_comprehension(k, v, for_, clauses)?
             }
+            ExprP::SetComprehension(x, for_, clauses) => {
+                self.set_comprehension(x, for_, clauses)?
+            }
             ExprP::Literal(x) => {
                 let val = x.compile(self.eval.module_env.frozen_heap());
                 ExprCompiled::Value(val)
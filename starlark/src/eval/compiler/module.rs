@@ -39,6 +39,7 @@ use crate::typing::error::InternalError;
 use crate::typing::fill_types_for_lint::ModuleVarTypes;
 use crate::typing::mode::TypecheckMode;
 use crate::typing::typecheck::solve_bindings;
+use crate::typing::typecheck::SolvedBindings;
 use crate::typing::Ty;
 use crate::typing::TypingOracleCtx;
 use crate::values::FrozenRef;
@@ -175,6 +176,7 @@ impl<'v> Compiler<'v, '_, '_, '_> {
 
         let oracle = TypingOracleCtx {
             codemap: &self.codemap,
+            enable_bytes: self.enable_bytes,
         };
         let module_var_types = self.mk_module_var_types();
         for top in stmts.iter_mut() {
@@ -186,10 +188,11 @@ impl<'v> Compiler<'v, '_, '_, '_> {
                     &mut Vec::new(),
                 )
                 .map_err(InternalError::into_eval_exception)?;
-                let (errors, ..) = match solve_bindings(bindings, oracle, &module_var_types) {
-                    Ok(x) => x,
-                    Err(e) => return Err(e.into_eval_exception()),
-                };
+                let SolvedBindings { errors, .. } =
+                    match solve_bindings(bindings, oracle, &module_var_types) {
+                        Ok(x) => x,
+                        Err(e) => return Err(e.into_eval_exception()),
+                    };
 
                 if let Some(error) = errors.into_iter().next() {
                     return Err(error.into_eval_exception());
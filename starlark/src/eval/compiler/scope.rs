@@ -44,6 +44,7 @@ use starlark_syntax::syntax::ast::LoadArgP;
 use starlark_syntax::syntax::ast::Stmt;
 use starlark_syntax::syntax::ast::StmtP;
 use starlark_syntax::syntax::ast::Visibility;
+use starlark_syntax::syntax::ast::WhileP;
 use starlark_syntax::syntax::top_level_stmts::top_level_stmts_mut;
 use starlark_syntax::syntax::uniplate::VisitMut;
 
@@ -627,6 +628,9 @@ impl<'f> ModuleScopeBuilder<'f> {
                 let (k, v) = &mut **k_v;
                 self.resolve_idents_in_compr(&mut [k, v], first_for, clauses)
             }
+            ExprP::SetComprehension(expr, first_for, clauses) => {
+                self.resolve_idents_in_compr(&mut [expr], first_for, clauses)
+            }
             _ => expr.visit_expr_mut(|expr| self.resolve_idents_in_expr_impl(scope, expr)),
         }
     }
@@ -884,6 +888,9 @@ impl StmtCollectDefines for Stmt {
                 );
                 StmtP::collect_defines(body, InLoop::Yes, scope_data, frozen_heap, result, dialect);
             }
+            StmtP::While(WhileP { cond: _, body }) => {
+                StmtP::collect_defines(body, InLoop::Yes, scope_data, frozen_heap, result, dialect);
+            }
             StmtP::Def(DefP { name, .. }) => AssignIdent::collect_assign_ident(
                 name,
                 in_loop,
@@ -33,6 +33,7 @@ use starlark_syntax::syntax::ast::AssignTargetP;
 use starlark_syntax::syntax::ast::DefP;
 use starlark_syntax::syntax::ast::ForP;
 use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::ast::WhileP;
 use thiserror::Error;
 
 use crate::codemap::Span;
@@ -99,6 +100,7 @@ pub(crate) enum StmtCompiled {
             StmtsCompiled,
         )>,
     ),
+    While(Box<(IrSpanned<ExprCompiled>, StmtsCompiled)>),
     Break,
     Continue,
 }
@@ -168,6 +170,12 @@ impl IrSpanned<StmtCompiled> {
                 let body = body.optimize(ctx);
                 StmtsCompiled::for_stmt(span, var, over, body)
             }
+            StmtCompiled::While(cond_body) => {
+                let (cond, body) = &**cond_body;
+                let cond = cond.optimize(ctx);
+                let body = body.optimize(ctx);
+                StmtsCompiled::while_stmt(span, cond, body)
+            }
             s @ (StmtCompiled::PossibleGc | StmtCompiled::Break | StmtCompiled::Continue) => {
                 StmtsCompiled::one(IrSpanned {
                     span,
@@ -338,6 +346,37 @@ impl StmtsCompiled {
             node: StmtCompiled::For(Box::new((var, over, body))),
         })
     }
+
+    fn while_stmt(
+        span: FrameSpan,
+        cond: IrSpanned<ExprCompiled>,
+        body: StmtsCompiled,
+    ) -> StmtsCompiled {
+        let cond = ExprCompiledBool::new(cond);
+        match cond.node {
+            ExprCompiledBool::Const(false) => StmtsCompiled::empty(),
+            ExprCompiledBool::Const(true) => StmtsCompiled::one(IrSpanned {
+                span,
+                node: StmtCompiled::While(Box::new((
+                    IrSpanned {
+                        span: cond.span,
+                        node: ExprCompiled::Value(FrozenValue::new_bool(true)),
+                    },
+                    body,
+                ))),
+            }),
+            ExprCompiledBool::Expr(node) => StmtsCompiled::one(IrSpanned {
+                span,
+                node: StmtCompiled::While(Box::new((
+                    IrSpanned {
+                        span: cond.span,
+                        node,
+                    },
+                    body,
+                ))),
+            }),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -767,6 +806,11 @@ impl Compiler<'_, '_, '_, '_> {
                 let st = self.stmt(body, false)?;
                 Ok(StmtsCompiled::for_stmt(span, var, over, st))
             }
+            StmtP::While(WhileP { cond, body }) => {
+                let cond = self.expr(cond)?;
+                let st = self.stmt(body, false)?;
+                Ok(StmtsCompiled::while_stmt(span, cond, st))
+            }
             StmtP::Return(None) => Ok(StmtsCompiled::one(IrSpanned {
                 node: StmtCompiled::Return(IrSpanned {
                     span,
@@ -807,7 +851,9 @@ impl Compiler<'_, '_, '_, '_> {
                 let rhs = self.expr(rhs)?;
                 self.assign_modify(span.span.span(), lhs, rhs, *op)
             }
-            StmtP::Load(..) => unreachable!(),
+            // Loads are handled by the caller, and modules with error statements
+            // are rejected by `eval_module`.
+            StmtP::Load(..) | StmtP::Error => unreachable!(),
             StmtP::Pass => Ok(StmtsCompiled::empty()),
             StmtP::Break => Ok(StmtsCompiled::one(IrSpanned {
                 span,
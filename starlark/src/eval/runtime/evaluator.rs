@@ -19,6 +19,7 @@ use std::collections::HashSet;
 use std::mem;
 use std::mem::MaybeUninit;
 use std::path::Path;
+use std::ptr;
 
 use dupe::Dupe;
 use starlark_syntax::eval_exception::EvalException;
@@ -34,6 +35,9 @@ use crate::codemap::ResolvedFileSpan;
 use crate::collections::alloca::Alloca;
 use crate::collections::string_pool::StringPool;
 use crate::const_frozen_string;
+use crate::debug::recording::record_stmt;
+use crate::debug::recording::Recorder;
+use crate::debug::Recording;
 use crate::environment::slots::ModuleSlotId;
 use crate::environment::FrozenModuleData;
 use crate::environment::Module;
@@ -85,7 +89,7 @@ use crate::values::Value;
 use crate::values::ValueLike;
 
 #[derive(Error, Debug)]
-enum EvaluatorError {
+pub(crate) enum EvaluatorError {
     #[error("Profiling was not enabled")]
     ProfilingNotEnabled,
     #[error("Profile data already collected")]
@@ -98,12 +102,20 @@ enum EvaluatorError {
     TopFrameNotDef,
     #[error("Coverage not enabled")]
     CoverageNotEnabled,
+    #[error("Recording not enabled")]
+    RecordingNotEnabled,
+    #[error("Recording already enabled")]
+    RecordingAlreadyEnabled,
     #[error("Local variable `{0}` referenced before assignment")]
     LocalVariableReferencedBeforeAssignment(String),
     #[error("Max callstack size is already set")]
     CallstackSizeAlreadySet,
     #[error("Max callstack size cannot be zero")]
     ZeroCallstackSize,
+    #[error("Function `{0}` called recursively, which is not allowed in this dialect: {1}")]
+    Recursion(String, String),
+    #[error("Cannot evaluate a module with syntax errors")]
+    SyntaxError,
 }
 
 /// Number of bytes to allocate between GC's.
@@ -140,6 +152,8 @@ pub struct Evaluator<'v, 'a, 'e> {
     pub(crate) profile_or_instrumentation_mode: ProfileOrInstrumentationMode,
     // Used for line profiling
     stmt_profile: StmtProfile,
+    // Used for recording evaluations for replay debugging
+    pub(crate) recorder: Recorder,
     // Holds things that require hooking into evaluation.
     eval_instrumentation: EvaluationInstrumentation<'a, 'e>,
     // Total time spent in runtime typechecking.
@@ -223,6 +237,7 @@ impl<'v, 'a, 'e: 'a> Evaluator<'v, 'a, 'e> {
             profile_or_instrumentation_mode: ProfileOrInstrumentationMode::None,
             heap_profile: HeapProfile::new(),
             stmt_profile: StmtProfile::new(),
+            recorder: Recorder::new(),
             typecheck_profile: TypecheckProfile::default(),
             time_flame_profile: TimeFlameProfile::new(),
             eval_instrumentation: EvaluationInstrumentation::new(),
@@ -383,6 +398,28 @@ impl<'v, 'a, 'e: 'a> Evaluator<'v, 'a, 'e> {
         }
     }
 
+    /// Record the evaluation for replay debugging, allowing [`Evaluator::take_recording`] to be used.
+    ///
+    /// Before each statement the call stack and a snapshot of the local variables are recorded,
+    /// as well as the result of every native function call. This is expensive, and only meant
+    /// for debugging.
+    pub fn enable_recording(&mut self) -> anyhow::Result<()> {
+        if self.recorder.enabled() {
+            return Err(EvaluatorError::RecordingAlreadyEnabled.into());
+        }
+        self.recorder.enable();
+        self.before_stmt_fn(&|span, eval| record_stmt(span, eval));
+        Ok(())
+    }
+
+    /// Obtain the recording of the evaluation so far, stopping the recording.
+    /// Only valid if [`Evaluator::enable_recording`] was called.
+    pub fn take_recording(&mut self) -> anyhow::Result<Recording> {
+        self.recorder
+            .take()
+            .ok_or_else(|| EvaluatorError::RecordingNotEnabled.into())
+    }
+
     /// Enable interactive `breakpoint()`. When enabled, `breakpoint()`
     /// reads commands from stdin and write to stdout.
     /// When disabled (default), `breakpoint()` function results in error.
@@ -661,6 +698,49 @@ impl<'v, 'a, 'e: 'a> Evaluator<'v, 'a, 'e> {
         }
     }
 
+    /// Fail if the function on top of the call stack, whose info is `def_info`,
+    /// is also one of its callers.
+    pub(crate) fn check_recursion(
+        &self,
+        def_info: FrozenRef<'static, DefInfo>,
+    ) -> crate::Result<()> {
+        // Skip the top frame, which is the function being called,
+        // and the bottom frame, which is the module.
+        for n in 1..self.call_stack.count().saturating_sub(1) {
+            let caller = self.call_stack.top_nth_function(n)?;
+            let is_same_def = match self.func_to_def_info(caller) {
+                Ok(caller_info) => ptr::eq(caller_info.as_ref(), def_info.as_ref()),
+                Err(_) => false,
+            };
+            if is_same_def {
+                let cycle = (0..=n)
+                    .rev()
+                    .map(|i| {
+                        self.call_stack
+                            .top_nth_function(i)
+                            .map(|f| f.name_for_call_stack())
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                return Err(crate::Error::new_other(EvaluatorError::Recursion(
+                    def_info.name.as_str().to_owned(),
+                    cycle.join(" -> "),
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the function calling the native function on top of the call stack
+    /// was compiled with [`Dialect::enable_bytes`](crate::syntax::Dialect::enable_bytes).
+    pub(crate) fn caller_enables_bytes(&self) -> bool {
+        match self.call_stack.top_nth_function_opt(1) {
+            Some(func) => self
+                .func_to_def_info(func)
+                .map_or(true, |def_info| def_info.enable_bytes),
+            None => true,
+        }
+    }
+
     pub(crate) fn top_frame_def_info(&self) -> crate::Result<FrozenRef<DefInfo>> {
         let func = self.call_stack.top_nth_function(0)?;
         self.func_to_def_info(func)
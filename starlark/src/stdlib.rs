@@ -35,6 +35,7 @@ pub use extra::PrintHandler;
 
 use crate::stdlib::funcs::globals::register_globals;
 use crate::stdlib::internal::register_internal;
+use crate::values::bytes::globals::register_bytes;
 use crate::values::enumeration::globals::register_enum;
 use crate::values::record::globals::register_record;
 use crate::values::structs::structs::register_struct;
@@ -98,6 +99,8 @@ pub enum LibraryExtension {
     CallStack,
     /// Definitions to support the `set` type, the `set()` constructor.
     SetType,
+    /// Definitions to support the `bytes` type, the `bytes()` constructor.
+    BytesType,
     // Make sure if you add anything new, you add it to `all` below.
 }
 
@@ -124,9 +127,27 @@ impl LibraryExtension {
             Internal,
             CallStack,
             SetType,
+            BytesType,
         ]
     }
 
+    /// The extensions providing the builtins of a named dialect preset, where `name` is one
+    /// of [`Dialect::NAMES`](crate::syntax::Dialect::NAMES). Returns `None` for other names.
+    pub fn for_dialect_name(name: &str) -> Option<&'static [Self]> {
+        use LibraryExtension::*;
+        match name {
+            "standard" => Some(&[]),
+            "extended" => Some(Self::all()),
+            "bazel" => Some(&[StructType, Json, Print, SetType]),
+            "buck2" => Some(&[
+                StructType, RecordType, EnumType, Map, Filter, Partial, Debug, Print, Json, Typing,
+                CallStack,
+            ]),
+            "starlark-go" => Some(&[StructType, Json, Print, SetType, BytesType]),
+            _ => None,
+        }
+    }
+
     /// Add a specific extension to a [`GlobalsBuilder`].
     pub fn add(self, builder: &mut GlobalsBuilder) {
         use LibraryExtension::*;
@@ -136,6 +157,7 @@ impl LibraryExtension {
             RecordType => register_record(builder),
             EnumType => register_enum(builder),
             SetType => register_set(builder),
+            BytesType => register_bytes(builder),
             Map => extra::map(builder),
             Filter => extra::filter(builder),
             Partial => partial::partial(builder),
@@ -33,3 +33,4 @@ mod runtime;
 mod type_annot;
 mod uncategorized;
 pub(crate) mod util;
+mod while_loop;
@@ -26,3 +26,4 @@ mod for_stmt;
 pub(crate) mod golden;
 mod if_stmt;
 mod isinstance;
+mod while_stmt;
@@ -53,3 +53,8 @@ fn test_if_true_clause_on_freeze() {
         "def test(y): return [x for x in y if C]\nC = False\nC = True",
     );
 }
+
+#[test]
+fn test_set_compr() {
+    bc_golden_test("compr_set", "def test(y): return {x for x in y if x}");
+}
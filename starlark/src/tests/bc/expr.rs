@@ -64,3 +64,13 @@ fn test_call_maybe_known_method() {
 fn test_fstring() {
     bc_golden_test("expr_fstring", "def test(x): return f'test: {x}'");
 }
+
+#[test]
+fn test_set_of_consts() {
+    bc_golden_test("expr_set_of_consts", "def test(): return {1, 'a', (2, 3)}");
+}
+
+#[test]
+fn test_set_npop() {
+    bc_golden_test("expr_set_npop", "def test(x, y): return {x, 1, y}");
+}
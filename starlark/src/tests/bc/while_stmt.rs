@@ -0,0 +1,39 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::tests::bc::golden::bc_golden_test;
+
+#[test]
+fn test_while() {
+    bc_golden_test("while", "def test(x):\n  while x:\n    x = noop(x)");
+}
+
+#[test]
+fn test_while_true_break_continue() {
+    bc_golden_test(
+        "while_true_break_continue",
+        "def test(x):\n  while True:\n    if x: break\n    if noop(x): continue\n    noop(x)",
+    );
+}
+
+#[test]
+fn test_while_in_for() {
+    bc_golden_test(
+        "while_in_for",
+        "def test(x):\n  for i in x:\n    while i:\n      if noop(i): return i\n      i = noop(i)",
+    );
+}
@@ -16,6 +16,7 @@
  */
 
 use std::cell::Cell;
+use std::cell::RefCell;
 
 use crate::codemap::FileSpanRef;
 use crate::environment::Globals;
@@ -36,14 +37,31 @@ fn before_stmt() {
     let mut evaluator = Evaluator::new(&module);
     evaluator.before_stmt_fn(&before_stmt);
 
-    // For a top-level statement, we get an additional before_stmt call for the possible gc.
     let program = "\
-x = 1          # 0 + 1
-def f():       # 1 + 1
+x = 1          # 0
+def f():       # 1
   return x + 1 # 3
-f()            # 2 + 1
+f()            # 2
 ";
     let ast = AstModule::parse("a.star", program.to_owned(), &Dialect::AllOptionsInternal).unwrap();
     evaluator.eval_module(ast, &globals).unwrap();
-    assert_eq!(7, counter.get());
+    assert_eq!(4, counter.get());
+}
+
+#[test]
+fn before_stmt_statements_on_one_line() {
+    let module = Module::new();
+    let globals = Globals::new();
+    let spans = RefCell::new(Vec::new());
+    let before_stmt = |span: FileSpanRef, _eval: &mut Evaluator<'_, '_, '_>| {
+        spans.borrow_mut().push(span.to_string());
+    };
+
+    let mut evaluator = Evaluator::new(&module);
+    evaluator.before_stmt_fn(&before_stmt);
+
+    let program = "x = [0]\nx[0] += 1; x[0] += 1\n";
+    let ast = AstModule::parse("a.star", program.to_owned(), &Dialect::AllOptionsInternal).unwrap();
+    evaluator.eval_module(ast, &globals).unwrap();
+    assert_eq!(vec!["a.star:1:1-8", "a.star:2:1-10", "a.star:2:12-21"], *spans.borrow());
 }
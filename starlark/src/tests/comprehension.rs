@@ -66,6 +66,13 @@ fn test_dict() {
     check_comp(&["{x: 1 for x in [0,1,2]} == {0: 1, 1: 1, 2: 1}"]);
 }
 
+#[test]
+fn test_set() {
+    // Set comprehensions
+    check_comp(&["{x % 2 for x in [0,1,2,3]} == set([0, 1])"]);
+    check_comp(&["{y for x in [[1, 2], [2, 3]] for y in x if y > 1} == set([2, 3])"]);
+}
+
 #[test]
 fn test_nested() {
     // Nested comprehensions
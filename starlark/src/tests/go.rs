@@ -151,7 +151,7 @@ fn test_go() {
     );
     // Skip module.star, we don't support modules
     // Skip paths.star, a path support library, not tests
-    // Skip recursion.star, we don't support `while` loops, which is what this mostly tests
+    // Skip recursion.star, not vendored, it needs `Dialect::enable_while` and no `enable_recursion`
     // Skip set.star, we don't support set
     // Skip string.star, our String's are fundamentally different
     assert.conformance(&ignore_bad_lines(
@@ -29,8 +29,12 @@ use starlark_derive::starlark_module;
 use crate as starlark;
 use crate::assert;
 use crate::assert::Assert;
+use crate::environment::Globals;
 use crate::environment::GlobalsBuilder;
+use crate::environment::Module;
 use crate::eval::Evaluator;
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
 use crate::values::any::StarlarkAny;
 use crate::values::FrozenHeap;
 use crate::values::Heap;
@@ -233,3 +237,23 @@ fn test_display_debug() {
     assert_eq!(format!("{:?}", v), "FrozenValue(\"test\")");
     assert_eq!(format!("{:#?}", v), "FrozenValue(\n    \"test\",\n)");
 }
+
+#[test]
+fn test_eval_module_with_recovered_syntax_errors() {
+    let program = "x = 1 +\ny = 2\ndef f():\n    z = )\n";
+    let (module, errors) =
+        AstModule::parse_with_recovery("test.star", program.to_owned(), &Dialect::Standard);
+    assert_eq!(2, errors.len());
+    let module = module.unwrap();
+    let env = Module::new();
+    let mut eval = Evaluator::new(&env);
+    let err = eval
+        .eval_module(module, &Globals::standard())
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Cannot evaluate a module with syntax errors"),
+        "{err}"
+    );
+    assert!(err.contains("test.star:1:8"), "{err}");
+}
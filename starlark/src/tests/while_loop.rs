@@ -0,0 +1,206 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Tests for `while` loops and `Dialect::enable_recursion`.
+
+use crate::assert;
+use crate::assert::Assert;
+use crate::syntax::Dialect;
+
+#[test]
+fn test_while() {
+    assert::eq(
+        "55",
+        r#"
+def sum(n):
+    s = 0
+    while n > 0:
+        s += n
+        n -= 1
+    return s
+sum(10)
+"#,
+    );
+}
+
+#[test]
+fn test_while_break_continue() {
+    assert::eq(
+        "[1, 3, 5]",
+        r#"
+def odd(n):
+    r = []
+    i = 0
+    while True:
+        i += 1
+        if i > n:
+            break
+        if i % 2 == 0:
+            continue
+        r.append(i)
+    return r
+odd(6)
+"#,
+    );
+}
+
+#[test]
+fn test_while_false() {
+    assert::eq(
+        "1",
+        r#"
+def f():
+    x = 1
+    while False:
+        x = 2
+    return x
+f()
+"#,
+    );
+}
+
+#[test]
+fn test_while_nested_for() {
+    assert::eq(
+        "[(0, 'a'), (0, 'b'), (1, 'a')]",
+        r#"
+def f():
+    r = []
+    i = 0
+    while i < 3:
+        for x in ["a", "b", "c"]:
+            if x == "c":
+                break
+            if i == 1 and x == "b":
+                return r
+            r.append((i, x))
+        i += 1
+    return r
+f()
+"#,
+    );
+}
+
+#[test]
+fn test_return_from_while_in_for() {
+    assert::eq(
+        "(2, 3)",
+        r#"
+def f():
+    for x in [1, 2, 3]:
+        y = 0
+        while y < x:
+            y += 1
+            if x == 2 and y == 2:
+                for z in [3]:
+                    return (x, z)
+    return None
+f()
+"#,
+    );
+}
+
+#[test]
+fn test_while_disabled() {
+    let mut a = Assert::new();
+    a.dialect(&Dialect::Extended);
+    a.fail(
+        r#"
+def f():
+    while True:
+        pass
+"#,
+        "`while` is not allowed in this dialect",
+    );
+}
+
+#[test]
+fn test_while_top_level() {
+    let mut a = Assert::new();
+    a.dialect_set(|d| d.enable_top_level_stmt = false);
+    a.fail(
+        "while True:\n    pass\n",
+        "`while` cannot be used outside `def` in this dialect",
+    );
+}
+
+#[test]
+fn test_break_outside_loop() {
+    let mut a = Assert::new();
+    a.fail(
+        "def f():\n    break\n",
+        "`break` cannot be used outside of a loop",
+    );
+    a.fail(
+        "def f():\n    continue\n",
+        "`continue` cannot be used outside of a loop",
+    );
+    a.dialect(&Dialect::Extended);
+    a.fail(
+        "def f():\n    break\n",
+        "`break` cannot be used outside of a `for` loop",
+    );
+}
+
+#[test]
+fn test_recursion_allowed() {
+    assert::eq(
+        "120",
+        r#"
+def fact(n):
+    return 1 if n <= 1 else n * fact(n - 1)
+fact(5)
+"#,
+    );
+}
+
+#[test]
+fn test_recursion_disabled() {
+    let mut a = Assert::new();
+    a.dialect_set(|d| d.enable_recursion = false);
+    a.fail(
+        r#"
+def fact(n):
+    return 1 if n <= 1 else n * fact(n - 1)
+fact(5)
+"#,
+        "Function `fact` called recursively, which is not allowed in this dialect: fact -> fact",
+    );
+    a.fail(
+        r#"
+def f(n):
+    return g(n)
+def g(n):
+    return f(n - 1) if n > 0 else 0
+def h():
+    return f(1)
+h()
+"#,
+        "called recursively, which is not allowed in this dialect: f -> g -> f",
+    );
+    // Calling the same function several times is not recursion.
+    a.eq(
+        "3",
+        r#"
+def one():
+    return 1
+def three():
+    return one() + one() + one()
+three()
+"#,
+    );
+}
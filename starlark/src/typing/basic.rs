@@ -20,7 +20,9 @@ use std::fmt::Display;
 
 use allocative::Allocative;
 use dupe::Dupe;
+use starlark_map::small_map::SmallMap;
 
+use crate::docs::DocMember;
 use crate::typing::arc_ty::ArcTy;
 use crate::typing::callable::TyCallable;
 use crate::typing::custom::TyCustom;
@@ -30,7 +32,10 @@ use crate::typing::tuple::TyTuple;
 use crate::typing::ty::TypeRenderConfig;
 use crate::typing::Ty;
 use crate::typing::TyFunction;
+use crate::values::dict::value::MutableDict;
+use crate::values::list::value::List;
 use crate::values::none::NoneType;
+use crate::values::set::value::MutableSet;
 use crate::values::string::str_type::StarlarkStr;
 use crate::values::typing::any::TypingAny;
 use crate::values::StarlarkValue;
@@ -62,6 +67,22 @@ pub enum TyBasic {
 }
 
 impl TyBasic {
+    /// The attributes known to be available on values of this type.
+    pub(crate) fn attributes(&self) -> SmallMap<String, DocMember> {
+        match self {
+            TyBasic::StarlarkValue(x) => x.attributes(),
+            TyBasic::List(_) => TyStarlarkValue::new::<List>().attributes(),
+            TyBasic::Dict(..) => TyStarlarkValue::new::<MutableDict>().attributes(),
+            TyBasic::Set(_) => TyStarlarkValue::new::<MutableSet>().attributes(),
+            TyBasic::Custom(x) => x.0.attributes_dyn(),
+            TyBasic::Any
+            | TyBasic::Iter(_)
+            | TyBasic::Callable(_)
+            | TyBasic::Type
+            | TyBasic::Tuple(_) => SmallMap::new(),
+        }
+    }
+
     pub(crate) const fn none() -> TyBasic {
         TyBasic::starlark_value::<NoneType>()
     }
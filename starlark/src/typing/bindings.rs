@@ -98,6 +98,9 @@ pub(crate) struct Bindings<'a> {
     /// ```
     pub(crate) check: Vec<&'a CstExpr>,
     pub(crate) check_type: Vec<(Span, Option<&'a CstExpr>, Ty)>,
+    /// `return` statements, with the span of the statement, the returned expression
+    /// and the declared return type of the function.
+    pub(crate) returns: Vec<(Span, Option<&'a CstExpr>, Ty)>,
 }
 
 pub(crate) struct BindingsCollect<'a, 'b> {
@@ -326,7 +329,7 @@ impl<'a, 'b> BindingsCollect<'a, 'b> {
                 StmtP::Load(..) => {}
                 StmtP::Return(ret) => {
                     self.bindings
-                        .check_type
+                        .returns
                         .push((x.span, ret.as_ref(), return_type.clone()))
                 }
                 StmtP::Expression(x) => {
@@ -366,7 +369,8 @@ impl<'a, 'b> BindingsCollect<'a, 'b> {
             },
             Visit::Expr(x) => match &**x {
                 ExprP::ListComprehension(_, for1, clauses)
-                | ExprP::DictComprehension(_, for1, clauses) => {
+                | ExprP::DictComprehension(_, for1, clauses)
+                | ExprP::SetComprehension(_, for1, clauses) => {
                     fn get_for_clause(x: &ClauseP<CstPayload>) -> Option<&ForClauseP<CstPayload>> {
                         match x {
                             ClauseP::For(x) => Some(x),
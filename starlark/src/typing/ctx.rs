@@ -16,8 +16,10 @@
  */
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt::Debug;
 
+use dupe::Dupe;
 use starlark_map::unordered_map::UnorderedMap;
 use starlark_syntax::slice_vec_ext::SliceExt;
 use starlark_syntax::syntax::ast::AssignOp;
@@ -51,6 +53,7 @@ use crate::typing::oracle::traits::TypingBinOp;
 use crate::typing::oracle::traits::TypingUnOp;
 use crate::typing::ty::Approximation;
 use crate::typing::ty::Ty;
+use crate::values::bytes::StarlarkBytes;
 
 pub(crate) struct TypingContext<'a> {
     pub(crate) oracle: TypingOracleCtx<'a>,
@@ -60,6 +63,9 @@ pub(crate) struct TypingContext<'a> {
     pub(crate) approximoations: RefCell<Vec<Approximation>>,
     pub(crate) types: UnorderedMap<BindingId, Ty>,
     pub(crate) module_var_types: &'a ModuleVarTypes,
+    /// The types of the expressions whose attributes are accessed, keyed by their span.
+    /// Overwritten on each iteration, so they end up with the solved types.
+    pub(crate) attribute_receivers: RefCell<HashMap<Span, Ty>>,
 }
 
 impl TypingContext<'_> {
@@ -411,7 +417,13 @@ impl TypingContext<'_> {
         let span = x.span;
         match &**x {
             ExprP::Tuple(xs) => Ok(Ty::tuple(xs.try_map(|x| self.expression_type(x))?)),
-            ExprP::Dot(a, b) => Ok(self.expr_dot(&self.expression_type(a)?, b, b.span)),
+            ExprP::Dot(a, b) => {
+                let ty = self.expression_type(a)?;
+                self.attribute_receivers
+                    .borrow_mut()
+                    .insert(a.span, ty.dupe());
+                Ok(self.expr_dot(&ty, b, b.span))
+            }
             ExprP::Call(f, args) => self.expr_call(span, f, args),
             ExprP::Index(a_b) => self.expr_index(span, &a_b.0, &a_b.1),
             ExprP::Index2(a_i0_i1) => {
@@ -437,6 +449,7 @@ impl TypingContext<'_> {
                 AstLiteral::Int(_) => Ok(Ty::int()),
                 AstLiteral::Float(_) => Ok(Ty::float()),
                 AstLiteral::String(_) => Ok(Ty::string()),
+                AstLiteral::Bytes(_) => Ok(Ty::starlark_value::<StarlarkBytes>()),
                 AstLiteral::Ellipsis => Ok(Ty::any()),
             },
             ExprP::Not(x) => {
@@ -471,6 +484,10 @@ impl TypingContext<'_> {
                     .unzip();
                 Ok(Ty::dict(Ty::unions(ks), Ty::unions(vs)))
             }
+            ExprP::Set(xs) => {
+                let ts = xs.try_map(|x| self.expression_type(x))?;
+                Ok(Ty::set(Ty::unions(ts)))
+            }
             ExprP::ListComprehension(a, b, c) => {
                 self.check_comprehension(b, c)?;
                 Ok(Ty::list(self.expression_type(a)?))
@@ -482,6 +499,10 @@ impl TypingContext<'_> {
                     self.expression_type(&k_v.1)?,
                 ))
             }
+            ExprP::SetComprehension(a, b, c) => {
+                self.check_comprehension(b, c)?;
+                Ok(Ty::set(self.expression_type(a)?))
+            }
             ExprP::FString(_) => Ok(Ty::string()),
         }
     }
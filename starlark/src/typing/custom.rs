@@ -29,9 +29,11 @@ use allocative::Allocative;
 use cmp_any::OrdAny;
 use cmp_any::PartialEqAny;
 use dupe::Dupe;
+use starlark_map::small_map::SmallMap;
 use starlark_map::StarlarkHasher;
 
 use crate::codemap::Span;
+use crate::docs::DocMember;
 use crate::typing::call_args::TyCallArgs;
 use crate::typing::callable::TyCallable;
 use crate::typing::error::InternalError;
@@ -89,8 +91,18 @@ pub trait TyCustomImpl: Debug + Display + Hash + Ord + Allocative + Send + Sync
         Err(TypingNoContextOrInternalError::Typing)
     }
     fn attribute(&self, attr: &str) -> Result<Ty, TypingNoContextError>;
+    /// The attributes known to be available, with their documentation. Used for
+    /// completion, so it may leave out attributes that [`attribute`](Self::attribute)
+    /// accepts.
+    fn attributes(&self) -> SmallMap<String, DocMember> {
+        SmallMap::new()
+    }
     fn union2(x: Arc<Self>, other: Arc<Self>) -> Result<Arc<Self>, (Arc<Self>, Arc<Self>)> {
-        if x == other { Ok(x) } else { Err((x, other)) }
+        if x == other {
+            Ok(x)
+        } else {
+            Err((x, other))
+        }
     }
     fn intersects(x: &Self, y: &Self) -> bool {
         let _ignore = (x, y);
@@ -129,6 +141,7 @@ pub(crate) trait TyCustomDyn: Debug + Display + Allocative + Send + Sync + 'stat
         ctx: &TypingOracleCtx,
     ) -> Result<Ty, TypingNoContextOrInternalError>;
     fn attribute_dyn(&self, attr: &str) -> Result<Ty, TypingNoContextError>;
+    fn attributes_dyn(&self) -> SmallMap<String, DocMember>;
     fn bin_op_dyn(
         &self,
         bin_op: TypingBinOp,
@@ -201,6 +214,10 @@ impl<T: TyCustomImpl> TyCustomDyn for T {
         self.attribute(attr)
     }
 
+    fn attributes_dyn(&self) -> SmallMap<String, DocMember> {
+        self.attributes()
+    }
+
     fn iter_item_dyn(&self) -> Result<Ty, TypingNoContextError> {
         self.iter_item()
     }
@@ -33,6 +33,7 @@ use starlark_syntax::syntax::ast::ForP;
 use starlark_syntax::syntax::ast::LoadArgP;
 use starlark_syntax::syntax::ast::LoadP;
 use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::ast::WhileP;
 use starlark_syntax::syntax::def::DefParamKind;
 use starlark_syntax::syntax::def::DefParams;
 use starlark_syntax::syntax::def::DefRegularParamMode;
@@ -280,8 +281,10 @@ impl<'a, 'v> GlobalTypesBuilder<'a, 'v> {
             | ExprP::If(..)
             | ExprP::List(_)
             | ExprP::Dict(_)
+            | ExprP::Set(_)
             | ExprP::ListComprehension(_, _, _)
             | ExprP::DictComprehension(_, _, _)
+            | ExprP::SetComprehension(_, _, _)
             | ExprP::FString(_) => Ok(GlobalValue::any()),
         }
     }
@@ -435,8 +438,10 @@ impl<'a, 'v> GlobalTypesBuilder<'a, 'v> {
                 Ok(())
             }
             StmtP::For(for_stmt) => self.for_stmt_unset(for_stmt),
+            StmtP::While(WhileP { cond: _, body }) => self.eval_stmt_unset(body),
             StmtP::Def(def) => self.assign_unset_ident(&def.name),
             StmtP::Load(_) => Err(self.internal_error(stmt.span, "load")),
+            StmtP::Error => Ok(()),
         }
     }
 
@@ -506,8 +511,10 @@ impl<'a, 'v> GlobalTypesBuilder<'a, 'v> {
                 Ok(())
             }
             StmtP::For(for_stmt) => self.for_stmt_unset(for_stmt),
+            StmtP::While(WhileP { cond: _, body }) => self.eval_stmt_unset(body),
             StmtP::Def(def) => self.top_level_def(def),
             StmtP::Load(load) => self.load(load),
+            StmtP::Error => Ok(()),
         }
     }
 
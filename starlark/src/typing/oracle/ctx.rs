@@ -84,6 +84,9 @@ enum TypingOracleCtxError {
 #[derive(Clone, Copy, Dupe)]
 pub struct TypingOracleCtx<'a> {
     pub(crate) codemap: &'a CodeMap,
+    /// [`Dialect::enable_bytes`](crate::syntax::Dialect::enable_bytes), without which
+    /// `str.encode` is not available.
+    pub(crate) enable_bytes: bool,
 }
 
 impl<'a> TypingOracleCtx<'a> {
@@ -460,6 +463,9 @@ impl<'a> TypingOracleCtx<'a> {
     }
 
     fn expr_dot_basic(&self, array: &TyBasic, attr: &str) -> Result<Ty, TypingNoContextError> {
+        if !self.enable_bytes && attr == "encode" && *array == TyBasic::string() {
+            return Err(TypingNoContextError);
+        }
         match array {
             TyBasic::Any | TyBasic::Callable(_) | TyBasic::Iter(_) | TyBasic::Type => Ok(Ty::any()),
             TyBasic::StarlarkValue(s) => s.attr(attr),
@@ -26,8 +26,10 @@ use std::marker::PhantomData;
 
 use allocative::Allocative;
 use dupe::Dupe;
+use starlark_map::small_map::SmallMap;
 use starlark_syntax::codemap::Span;
 
+use crate::docs::DocMember;
 use crate::typing::error::TypingError;
 use crate::typing::error::TypingNoContextError;
 use crate::typing::ty::TypeRenderConfig;
@@ -265,6 +267,18 @@ impl TyStarlarkValue {
         Err(TypingNoContextError)
     }
 
+    /// The methods of this type, with their documentation.
+    pub(crate) fn attributes(self) -> SmallMap<String, DocMember> {
+        match (self.vtable.vtable.get_methods)() {
+            Some(methods) => {
+                methods
+                    .documentation(Ty::basic(TyBasic::StarlarkValue(self)))
+                    .members
+            }
+            None => SmallMap::new(),
+        }
+    }
+
     pub(crate) fn attr(self, name: &str) -> Result<Ty, TypingNoContextError> {
         if let Ok(ty) = self.attr_from_methods(name) {
             return Ok(ty);
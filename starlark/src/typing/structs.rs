@@ -22,8 +22,11 @@ use std::sync::Arc;
 
 use allocative::Allocative;
 use dupe::Dupe;
+use starlark_map::small_map::SmallMap;
 use starlark_map::sorted_map::SortedMap;
 
+use crate::docs::DocMember;
+use crate::docs::DocProperty;
 use crate::typing::custom::TyCustomImpl;
 use crate::typing::error::TypingNoContextError;
 use crate::typing::error::TypingNoContextOrInternalError;
@@ -89,6 +92,21 @@ impl TyCustomImpl for TyStruct {
         }
     }
 
+    fn attributes(&self) -> SmallMap<String, DocMember> {
+        self.fields
+            .iter()
+            .map(|(name, ty)| {
+                (
+                    name.as_str().to_owned(),
+                    DocMember::Property(DocProperty {
+                        docs: None,
+                        typ: ty.dupe(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
     fn union2(a: Arc<Self>, b: Arc<Self>) -> Result<Arc<Self>, (Arc<Self>, Arc<Self>)> {
         if a == b {
             // Fast path.
@@ -25,6 +25,8 @@ use starlark_syntax::golden_test_template::golden_test_template;
 
 use crate as starlark;
 use crate::assert::Assert;
+use crate::codemap::Pos;
+use crate::codemap::Span;
 use crate::environment::FrozenModule;
 use crate::environment::GlobalsBuilder;
 use crate::environment::Module;
@@ -474,3 +476,31 @@ def test() -> test:
 "#,
     );
 }
+
+#[test]
+fn test_attribute_receiver_types() {
+    let code = r#"
+def f(s: str, xs: list[int]):
+    p = struct(a = 1, b = "x")
+    s.upper()
+    xs.append(1)
+    p.a
+"#;
+    let globals = GlobalsBuilder::extended().build();
+    let ast = AstModule::parse("filename", code.to_owned(), &Dialect::AllOptionsInternal).unwrap();
+    let (errors, typemap, ..) = ast.typecheck(&globals, &HashMap::new());
+    assert!(errors.is_empty());
+
+    let attributes = |receiver: &str| {
+        let begin = code.find(&format!("    {receiver}.")).unwrap() + 4;
+        let span = Span::new(
+            Pos::new(begin as u32),
+            Pos::new((begin + receiver.len()) as u32),
+        );
+        let ty = typemap.attribute_receiver_type(span).unwrap();
+        ty.attributes().keys().cloned().collect::<Vec<_>>()
+    };
+    assert!(attributes("s").contains(&"upper".to_owned()));
+    assert!(attributes("xs").contains(&"append".to_owned()));
+    assert_eq!(vec!["a".to_owned(), "b".to_owned()], attributes("p"));
+}
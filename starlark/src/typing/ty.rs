@@ -26,12 +26,14 @@ use dupe::Dupe;
 use dupe::IterDupedExt;
 use either::Either;
 use starlark_derive::Trace;
+use starlark_map::small_map::SmallMap;
 use starlark_syntax::codemap::CodeMap;
 use starlark_syntax::codemap::Span;
 use starlark_syntax::codemap::Spanned;
 
 use crate as starlark;
 use crate::__derive_refs::components::NativeCallableComponents;
+use crate::docs::DocMember;
 use crate::eval::compiler::small_vec_1::SmallVec1;
 use crate::typing::arc_ty::ArcTy;
 use crate::typing::basic::TyBasic;
@@ -361,6 +363,22 @@ impl Ty {
         }
     }
 
+    /// The attributes known to be available on values of this type, with their
+    /// documentation: the fields of records and structs, and the methods of builtin
+    /// and native types. For a union, the attributes of any of the alternatives.
+    /// Empty for `typing.Any`, whose attributes are not known.
+    pub fn attributes(&self) -> SmallMap<String, DocMember> {
+        let mut res = SmallMap::new();
+        for basic in self.iter_union() {
+            for (name, member) in basic.attributes() {
+                if !res.contains_key(&name) {
+                    res.insert(name, member);
+                }
+            }
+        }
+        res
+    }
+
     /// Iterate over the types within a union, pretending the type is a singleton union if not a union.
     pub fn iter_union(&self) -> &[TyBasic] {
         &self.alternatives
@@ -446,6 +464,7 @@ impl Ty {
     ) -> bool {
         let oracle = TypingOracleCtx {
             codemap: CodeMap::empty_static(),
+            enable_bytes: true,
         };
         let Ok(ret) = oracle.validate_call(
             Span::default(),
@@ -486,6 +505,7 @@ impl Ty {
     pub(crate) fn check_intersects(&self, other: &Ty) -> crate::Result<bool> {
         let oracle = TypingOracleCtx {
             codemap: CodeMap::empty_static(),
+            enable_bytes: true,
         };
         match oracle.intersects(self, other) {
             Ok(ok) => Ok(ok),
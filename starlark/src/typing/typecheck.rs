@@ -34,6 +34,7 @@ use crate::codemap::Span;
 use crate::codemap::Spanned;
 use crate::environment::names::MutableNames;
 use crate::environment::Globals;
+use crate::eval::compiler::scope::payload::CstExpr;
 use crate::eval::compiler::scope::payload::CstStmt;
 use crate::eval::compiler::scope::scope_resolver_globals::ScopeResolverGlobals;
 use crate::eval::compiler::scope::BindingId;
@@ -55,12 +56,23 @@ use crate::typing::ty::Approximation;
 use crate::typing::ty::Ty;
 use crate::values::FrozenHeap;
 
+/// The result of [`solve_bindings`].
+pub(crate) struct SolvedBindings {
+    pub(crate) errors: Vec<TypingError>,
+    pub(crate) types: HashMap<BindingId, Ty>,
+    /// The types of the values returned by `return` statements, keyed by the statement span.
+    pub(crate) returns: Vec<(Span, Ty)>,
+    /// The types of the expressions whose attributes are accessed, keyed by their span.
+    pub(crate) attribute_receivers: HashMap<Span, Ty>,
+    pub(crate) approximations: Vec<Approximation>,
+}
+
 // Things which are None in the map have type void - they are never constructed
 pub(crate) fn solve_bindings(
     bindings: Bindings,
     oracle: TypingOracleCtx,
     module_var_types: &ModuleVarTypes,
-) -> Result<(Vec<TypingError>, HashMap<BindingId, Ty>, Vec<Approximation>), InternalError> {
+) -> Result<SolvedBindings, InternalError> {
     let mut types = bindings
         .expressions
         .keys()
@@ -77,6 +89,7 @@ pub(crate) fn solve_bindings(
         approximoations: RefCell::new(Vec::new()),
         types,
         module_var_types,
+        attribute_receivers: RefCell::new(HashMap::new()),
     };
     const ITERATIONS: usize = 100;
     for _iteration in 0..ITERATIONS {
@@ -107,7 +120,7 @@ pub(crate) fn solve_bindings(
     for x in &bindings.check {
         ctx.expression_type(x)?;
     }
-    for (span, e, require) in &bindings.check_type {
+    let check_type = |(span, e, require): &(Span, Option<&CstExpr>, Ty)| {
         let ty = match e {
             None => Ty::none(),
             Some(x) => ctx.expression_type(x)?,
@@ -119,12 +132,22 @@ pub(crate) fn solve_bindings(
             },
             require,
         )?;
+        Ok::<_, InternalError>(ty)
+    };
+    for x in &bindings.check_type {
+        check_type(x)?;
     }
-    Ok((
-        ctx.errors.into_inner(),
-        ctx.types.into_hash_map(),
-        ctx.approximoations.into_inner(),
-    ))
+    let mut returns = Vec::with_capacity(bindings.returns.len());
+    for x in &bindings.returns {
+        returns.push((x.0, check_type(x)?));
+    }
+    Ok(SolvedBindings {
+        errors: ctx.errors.into_inner(),
+        types: ctx.types.into_hash_map(),
+        returns,
+        attribute_receivers: ctx.attribute_receivers.into_inner(),
+        approximations: ctx.approximoations.into_inner(),
+    })
 }
 
 /// Structure containing all the inferred types.
@@ -132,6 +155,9 @@ pub(crate) fn solve_bindings(
 pub struct TypeMap {
     codemap: CodeMap,
     bindings: UnorderedMap<BindingId, (String, Span, Ty)>,
+    module_vars: HashMap<String, Ty>,
+    returns: HashMap<Span, Ty>,
+    attribute_receivers: HashMap<Span, Ty>,
 }
 
 impl Display for TypeMap {
@@ -154,6 +180,41 @@ impl Display for TypeMap {
 }
 
 impl TypeMap {
+    fn empty(codemap: CodeMap) -> TypeMap {
+        TypeMap {
+            codemap,
+            bindings: UnorderedMap::new(),
+            module_vars: HashMap::new(),
+            returns: HashMap::new(),
+            attribute_receivers: HashMap::new(),
+        }
+    }
+
+    /// The inferred types of the variables in function bodies, including parameters,
+    /// with the span where each variable is first bound. Unordered.
+    pub fn bindings(&self) -> impl Iterator<Item = (&str, Span, &Ty)> {
+        self.bindings
+            .entries_unordered()
+            .map(|(_, (name, span, ty))| (name.as_str(), *span, ty))
+    }
+
+    /// The inferred type of a top-level variable of the module, public or private.
+    pub fn module_var(&self, name: &str) -> Option<&Ty> {
+        self.module_vars.get(name)
+    }
+
+    /// The inferred type of the value returned by the `return` statement at `span`.
+    /// A `return` without a value has type `None`.
+    pub fn return_type(&self, span: Span) -> Option<&Ty> {
+        self.returns.get(&span)
+    }
+
+    /// The inferred type of the expression at `span` whose attribute is accessed,
+    /// like `x` in `x.y`. Only known for expressions in function bodies.
+    pub fn attribute_receiver_type(&self, span: Span) -> Option<&Ty> {
+        self.attribute_receivers.get(&span)
+    }
+
     #[cfg(test)]
     pub(crate) fn find_bindings_by_name<'a>(&'a self, name: &str) -> Vec<&'a Ty> {
         self.bindings
@@ -161,7 +222,11 @@ impl TypeMap {
             .into_iter()
             .filter_map(
                 |(_binding_id, (n, _span, ty))| {
-                    if name == n { Some(ty) } else { None }
+                    if name == n {
+                        Some(ty)
+                    } else {
+                        None
+                    }
                 },
             )
             .collect()
@@ -192,7 +257,7 @@ impl AstModuleTypecheck for AstModule {
         globals: &Globals,
         loads: &HashMap<String, Interface>,
     ) -> (Vec<crate::Error>, TypeMap, Interface, Vec<Approximation>) {
-        let (codemap, statement, _dialect, _) = self.into_parts();
+        let (codemap, statement, dialect, _) = self.into_parts();
         let names = MutableNames::new();
         let frozen_heap = FrozenHeap::new();
         let (
@@ -217,7 +282,10 @@ impl AstModuleTypecheck for AstModule {
         // We don't really need to properly unpack top-level statements,
         // but make it safe against future changes.
         let mut cst: Vec<&mut CstStmt> = top_level_stmts_mut(&mut cst);
-        let oracle = TypingOracleCtx { codemap: &codemap };
+        let oracle = TypingOracleCtx {
+            codemap: &codemap,
+            enable_bytes: dialect.enable_bytes,
+        };
 
         let mut approximations = Vec::new();
         let (fill_types_errors, module_var_types) = match fill_types_for_lint_typechecker(
@@ -230,10 +298,7 @@ impl AstModuleTypecheck for AstModule {
             Err(e) => {
                 return (
                     vec![InternalError::into_error(e)],
-                    TypeMap {
-                        codemap,
-                        bindings: UnorderedMap::new(),
-                    },
+                    TypeMap::empty(codemap),
                     Interface::default(),
                     Vec::new(),
                 );
@@ -241,6 +306,8 @@ impl AstModuleTypecheck for AstModule {
         };
 
         let mut typemap = UnorderedMap::new();
+        let mut returns = HashMap::new();
+        let mut attribute_receivers = HashMap::new();
         let mut all_solve_errors = Vec::new();
 
         for top in cst.iter_mut() {
@@ -255,33 +322,34 @@ impl AstModuleTypecheck for AstModule {
                     Err(e) => {
                         return (
                             vec![InternalError::into_error(e)],
-                            TypeMap {
-                                codemap,
-                                bindings: UnorderedMap::new(),
-                            },
+                            TypeMap::empty(codemap),
+                            Interface::default(),
+                            Vec::new(),
+                        );
+                    }
+                };
+                let SolvedBindings {
+                    errors: solve_errors,
+                    types,
+                    returns: solve_returns,
+                    attribute_receivers: solve_attribute_receivers,
+                    approximations: solve_approximations,
+                } = match solve_bindings(bindings.bindings, oracle, &module_var_types) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        return (
+                            vec![e.into_error()],
+                            TypeMap::empty(codemap),
                             Interface::default(),
                             Vec::new(),
                         );
                     }
                 };
-                let (solve_errors, types, solve_approximations) =
-                    match solve_bindings(bindings.bindings, oracle, &module_var_types) {
-                        Ok(x) => x,
-                        Err(e) => {
-                            return (
-                                vec![e.into_error()],
-                                TypeMap {
-                                    codemap,
-                                    bindings: UnorderedMap::new(),
-                                },
-                                Interface::default(),
-                                Vec::new(),
-                            );
-                        }
-                    };
 
                 all_solve_errors.extend(solve_errors);
                 approximations.extend(solve_approximations);
+                returns.extend(solve_returns);
+                attribute_receivers.extend(solve_attribute_receivers);
 
                 for (id, ty) in &types {
                     let binding = scope_data.get_binding(*id);
@@ -295,11 +363,6 @@ impl AstModuleTypecheck for AstModule {
             }
         }
 
-        let typemap = TypeMap {
-            bindings: typemap,
-            codemap: codemap.dupe(),
-        };
-
         let errors = [scope_errors, fill_types_errors, all_solve_errors]
             .into_iter()
             .flatten()
@@ -307,18 +370,28 @@ impl AstModuleTypecheck for AstModule {
             .collect();
 
         let mut res = HashMap::new();
+        let mut module_vars = HashMap::new();
         for (name, module_slot_id, vis) in names.all_names_slots_and_visibilities() {
+            let ty = module_var_types
+                .types
+                .get(&module_slot_id)
+                .cloned()
+                .unwrap_or_else(Ty::any);
             if vis == Visibility::Public {
-                let ty = module_var_types
-                    .types
-                    .get(&module_slot_id)
-                    .cloned()
-                    .unwrap_or_else(Ty::any);
-                res.insert(name.as_str().to_owned(), ty);
+                res.insert(name.as_str().to_owned(), ty.dupe());
             }
+            module_vars.insert(name.as_str().to_owned(), ty);
         }
         let interface = Interface::new(res);
 
+        let typemap = TypeMap {
+            bindings: typemap,
+            codemap: codemap.dupe(),
+            module_vars,
+            returns,
+            attribute_receivers,
+        };
+
         (errors, typemap, interface, approximations)
     }
 }
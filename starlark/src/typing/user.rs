@@ -21,9 +21,12 @@ use std::hash::Hasher;
 
 use allocative::Allocative;
 use dupe::Dupe;
+use starlark_map::small_map::SmallMap;
 use starlark_map::sorted_map::SortedMap;
 use starlark_syntax::codemap::Span;
 
+use crate::docs::DocMember;
+use crate::docs::DocProperty;
 use crate::typing::call_args::TyCallArgs;
 use crate::typing::callable::TyCallable;
 use crate::typing::custom::TyCustomImpl;
@@ -40,13 +43,17 @@ use crate::values::typing::type_compiled::type_matcher_factory::TypeMatcherFacto
 
 #[derive(Debug, thiserror::Error)]
 enum TyUserError {
-    #[error("Type `{0}` specifies custom callable, but underlying `StarlarkValue` is not callable")]
+    #[error(
+        "Type `{0}` specifies custom callable, but underlying `StarlarkValue` is not callable"
+    )]
     CallableNotCallable(String),
     #[error(
         "Type `{0}` specifies custom indexable, but underlying `StarlarkValue` is not indexable"
     )]
     IndexableNotIndexable(String),
-    #[error("Type `{0}` specifies custom iterable, but underlying `StarlarkValue` is not iterable")]
+    #[error(
+        "Type `{0}` specifies custom iterable, but underlying `StarlarkValue` is not iterable"
+    )]
     IterableNotIterable(String),
 }
 
@@ -224,6 +231,22 @@ impl TyCustomImpl for TyUser {
         }
     }
 
+    fn attributes(&self) -> SmallMap<String, DocMember> {
+        let mut res = self.base.attributes();
+        for (name, ty) in &self.fields.known {
+            if !res.contains_key(name) {
+                res.insert(
+                    name.clone(),
+                    DocMember::Property(DocProperty {
+                        docs: None,
+                        typ: ty.dupe(),
+                    }),
+                );
+            }
+        }
+        res
+    }
+
     fn index(
         &self,
         item: &TyBasic,
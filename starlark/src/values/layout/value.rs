@@ -73,6 +73,7 @@ use crate::typing::Ty;
 use crate::typing::TyCallable;
 use crate::util::ArcStr;
 use crate::values::bool::value::VALUE_FALSE_TRUE;
+use crate::values::bytes::StarlarkBytes;
 use crate::values::demand::request_value_impl;
 use crate::values::dict::value::VALUE_EMPTY_FROZEN_DICT;
 use crate::values::dict::FrozenDictRef;
@@ -1322,6 +1323,8 @@ pub trait ValueLike<'v>:
     fn collect_str(self, collector: &mut String) {
         if let Some(s) = self.to_value().unpack_str() {
             collector.push_str(s);
+        } else if let Some(b) = self.downcast_ref::<StarlarkBytes>() {
+            collector.push_str(&String::from_utf8_lossy(b.as_bytes()));
         } else {
             self.collect_repr(collector);
         }
@@ -0,0 +1,347 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Display;
+use std::hash::Hash;
+
+use allocative::Allocative;
+use serde::Serialize;
+use serde::Serializer;
+use starlark_derive::starlark_value;
+use starlark_syntax::syntax::ast::BytesLiteral;
+
+use crate as starlark;
+use crate::any::ProvidesStaticType;
+use crate::collections::StarlarkHasher;
+use crate::environment::Methods;
+use crate::environment::MethodsStatic;
+use crate::starlark_simple_value;
+use crate::typing::Ty;
+use crate::typing::TyBasic;
+use crate::typing::TypingBinOp;
+use crate::values::bytes::methods::bytes_methods;
+use crate::values::index::apply_slice;
+use crate::values::index::convert_index;
+use crate::values::Heap;
+use crate::values::StarlarkValue;
+use crate::values::UnpackValue;
+use crate::values::Value;
+use crate::values::ValueError;
+
+/// Representation of the `bytes` type: an immutable sequence of bytes.
+#[derive(Clone, Debug, PartialEq, Eq, ProvidesStaticType, Allocative)]
+pub struct StarlarkBytes {
+    bytes: Box<[u8]>,
+}
+
+impl Display for StarlarkBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&BytesLiteral(self.as_bytes()), f)
+    }
+}
+
+starlark_simple_value!(StarlarkBytes);
+
+impl StarlarkBytes {
+    /// The result of calling `type()` on bytes.
+    pub const TYPE: &'static str = "bytes";
+
+    /// Create a new [`StarlarkBytes`].
+    pub fn new(bytes: impl Into<Box<[u8]>>) -> StarlarkBytes {
+        StarlarkBytes {
+            bytes: bytes.into(),
+        }
+    }
+
+    /// The underlying bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn ty() -> TyBasic {
+        TyBasic::starlark_value::<StarlarkBytes>()
+    }
+}
+
+#[starlark_value(type = StarlarkBytes::TYPE)]
+impl<'v> StarlarkValue<'v> for StarlarkBytes {
+    fn get_methods() -> Option<&'static Methods> {
+        static RES: MethodsStatic = MethodsStatic::new();
+        RES.methods(bytes_methods)
+    }
+
+    fn to_bool(&self) -> bool {
+        !self.bytes.is_empty()
+    }
+
+    fn length(&self) -> crate::Result<i32> {
+        Ok(self.bytes.len() as i32)
+    }
+
+    fn at(&self, index: Value, heap: &'v Heap) -> crate::Result<Value<'v>> {
+        let i = convert_index(index, self.bytes.len() as i32)? as usize;
+        Ok(heap.alloc(self.bytes[i] as i32))
+    }
+
+    fn slice(
+        &self,
+        start: Option<Value>,
+        stop: Option<Value>,
+        stride: Option<Value>,
+        heap: &'v Heap,
+    ) -> crate::Result<Value<'v>> {
+        let res = apply_slice(self.as_bytes(), start, stop, stride)?;
+        Ok(heap.alloc(StarlarkBytes::new(res)))
+    }
+
+    fn equals(&self, other: Value<'v>) -> crate::Result<bool> {
+        match StarlarkBytes::from_value(other) {
+            None => Ok(false),
+            Some(other) => Ok(self.bytes == other.bytes),
+        }
+    }
+
+    fn compare(&self, other: Value<'v>) -> crate::Result<Ordering> {
+        match StarlarkBytes::from_value(other) {
+            None => ValueError::unsupported_with(self, "cmp()", other),
+            Some(other) => Ok(self.bytes.cmp(&other.bytes)),
+        }
+    }
+
+    fn write_hash(&self, hasher: &mut StarlarkHasher) -> crate::Result<()> {
+        self.bytes.hash(hasher);
+        Ok(())
+    }
+
+    fn is_in(&self, other: Value<'v>) -> crate::Result<bool> {
+        if let Some(needle) = StarlarkBytes::from_value(other) {
+            let needle = needle.as_bytes();
+            Ok(needle.is_empty() || self.bytes.windows(needle.len()).any(|w| w == needle))
+        } else if let Some(b) = i32::unpack_value(other)? {
+            match u8::try_from(b) {
+                Ok(b) => Ok(self.bytes.contains(&b)),
+                Err(_) => Err(crate::Error::new_value(BytesError::ByteOutOfRange(b))),
+            }
+        } else {
+            ValueError::unsupported_with(self, "in", other)
+        }
+    }
+
+    fn add(&self, other: Value<'v>, heap: &'v Heap) -> Option<crate::Result<Value<'v>>> {
+        let other = StarlarkBytes::from_value(other)?;
+        let mut result = Vec::with_capacity(self.bytes.len() + other.bytes.len());
+        result.extend_from_slice(self.as_bytes());
+        result.extend_from_slice(other.as_bytes());
+        Some(Ok(heap.alloc(StarlarkBytes::new(result))))
+    }
+
+    fn mul(&self, other: Value, heap: &'v Heap) -> Option<crate::Result<Value<'v>>> {
+        let l = match i32::unpack_value(other) {
+            Ok(Some(l)) => l,
+            Ok(None) => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        let count = usize::try_from(l).unwrap_or(0);
+        match self.bytes.len().checked_mul(count) {
+            Some(len) if len <= i32::MAX as usize => {}
+            _ => {
+                return Some(Err(crate::Error::new_value(BytesError::RepeatTooLarge(
+                    self.bytes.len(),
+                    l,
+                ))));
+            }
+        }
+        let repeated = self.bytes.repeat(count);
+        Some(Ok(heap.alloc(StarlarkBytes::new(repeated))))
+    }
+
+    fn rmul(&self, lhs: Value<'v>, heap: &'v Heap) -> Option<crate::Result<Value<'v>>> {
+        self.mul(lhs, heap)
+    }
+
+    fn bin_op_ty(op: TypingBinOp, rhs: &TyBasic) -> Option<Ty> {
+        match op {
+            TypingBinOp::Add if *rhs == TyBasic::Any || *rhs == Self::ty() => {
+                Some(Ty::basic(Self::ty()))
+            }
+            TypingBinOp::Mul if *rhs == TyBasic::Any || *rhs == TyBasic::int() => {
+                Some(Ty::basic(Self::ty()))
+            }
+            TypingBinOp::In | TypingBinOp::Less => Some(Ty::bool()),
+            _ => None,
+        }
+    }
+
+    fn rbin_op_ty(lhs: &TyBasic, op: TypingBinOp) -> Option<Ty> {
+        match op {
+            TypingBinOp::Mul if *lhs == TyBasic::Any || *lhs == TyBasic::int() => {
+                Some(Ty::basic(Self::ty()))
+            }
+            _ => None,
+        }
+    }
+
+    fn get_type_starlark_repr() -> Ty {
+        Ty::starlark_value::<StarlarkBytes>()
+    }
+}
+
+/// Bytes serialize as a sequence of their values, so `json.encode(b"hi")` is `[104,105]`.
+impl Serialize for StarlarkBytes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_bytes(self.as_bytes())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum BytesError {
+    #[error("Byte value {0} is out of range, must be in 0..=255")]
+    ByteOutOfRange(i32),
+    #[error("Repeating {0} bytes {1} times is too large")]
+    RepeatTooLarge(usize, i32),
+    #[error("Bytes are not valid UTF-8: {0}")]
+    InvalidUtf8(#[source] std::str::Utf8Error),
+    #[error("must enable bytes to use `str.encode`")]
+    NotEnabled,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert;
+    use crate::assert::Assert;
+    use crate::syntax::Dialect;
+
+    #[test]
+    fn test_literal() {
+        assert::all_true(
+            r#"
+type(b"x") == "bytes"
+len(b"a\x00\xffz") == 4
+b"\101é" == b"A\xc3\xa9"
+rb"\x41" == b"\\x41"
+repr(b"a\"\n\xff") == 'b"a\\"\\n\\xff"'
+str(b"hi") == "hi"
+"%s" % b"hi" == "hi"
+"{}".format(b"hi") == "hi"
+str([b"hi"]) == '[b"hi"]'
+"#,
+        );
+        // Invalid UTF-8 is replaced by U+FFFD.
+        assert::eq("'a\\ufffdb'", "str(b'a\\xffb')");
+    }
+
+    #[test]
+    fn test_sequence() {
+        assert::all_true(
+            r#"
+b"abc"[0] == 97
+b"abc"[-1] == 99
+b"abcdef"[1:3] == b"bc"
+b"abcdef"[::2] == b"ace"
+b"ab" + b"cd" == b"abcd"
+b"ab" * 2 == b"abab"
+2 * b"ab" == b"abab"
+b"bc" in b"abcd"
+98 in b"abc"
+not (b"x" in b"abc")
+b"a" < b"b"
+not b""
+"#,
+        );
+        assert::fail("b'abc'[3]", "out of bound");
+        assert::fail("256 in b'abc'", "out of range");
+        assert::fail("b'ab' * 2147483647", "too large");
+        assert::fail("[x for x in b'abc']", "not supported");
+    }
+
+    #[test]
+    fn test_hash() {
+        assert::eq(
+            "{b\"a\": 3, b\"b\": 2}",
+            "d = {b'a': 1, b'b': 2}\nd[b'a' + b''] = 3\nd",
+        );
+        assert::is_true("{b'a': 1}[b'a'] == 1");
+    }
+
+    #[test]
+    fn test_methods() {
+        assert::all_true(
+            r#"
+b"Hi".elems() == [72, 105]
+b"h\xc3\xa9".decode() == "hé"
+"hé".encode() == b"h\xc3\xa9"
+bytes("hé") == "hé".encode()
+bytes([0, 255]) == b"\x00\xff"
+"#,
+        );
+        assert::fail("b'\\xff'.decode()", "not valid UTF-8");
+        assert::fail("bytes([256])", "out of range");
+    }
+
+    #[test]
+    fn test_json() {
+        assert::eq("'[104,105]'", "json.encode(b'hi')");
+        assert::eq("'{\"a\":[]}'", "json.encode({'a': b''})");
+    }
+
+    #[test]
+    fn test_typecheck() {
+        let a = Assert::new();
+        a.pass(
+            r#"
+def f(x: bytes) -> bytes:
+    return x + b"!"
+f(b"x")
+"#,
+        );
+        a.fail(
+            r#"
+def f(x: bytes) -> str:
+    return x
+f(b"x")
+"#,
+            "Value `b\"x\"` of type `bytes` does not match the type annotation `str`",
+        );
+    }
+
+    #[test]
+    fn test_disabled() {
+        let mut a = Assert::new();
+        a.dialect(&Dialect::Standard);
+        a.fail("b'x'", "must enable bytes to use bytes literals");
+        a.fail("'x'.encode()", "must enable bytes to use `str.encode`");
+        a.fail(
+            "def f(x):\n    return x.encode()\nf('x')",
+            "must enable bytes to use `str.encode`",
+        );
+
+        let mut a = Assert::new();
+        a.dialect(&Dialect {
+            enable_bytes: false,
+            ..Dialect::AllOptionsInternal
+        });
+        a.fail(
+            "# @starlark-rust: typecheck\ndef f(x: str):\n    return x.encode()",
+            "The attribute `encode` is not available on the type `str`",
+        );
+    }
+}
@@ -0,0 +1,77 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use starlark_derive::starlark_module;
+
+use crate as starlark;
+use crate::environment::GlobalsBuilder;
+use crate::values::bytes::bytes_type::BytesError;
+use crate::values::bytes::StarlarkBytes;
+use crate::values::type_repr::StarlarkTypeRepr;
+use crate::values::typing::StarlarkIter;
+use crate::values::Heap;
+use crate::values::UnpackValue;
+use crate::values::ValueOfUnchecked;
+
+#[derive(StarlarkTypeRepr, UnpackValue)]
+enum BytesSource<'v> {
+    Bytes(&'v StarlarkBytes),
+    Str(&'v str),
+    Iter(ValueOfUnchecked<'v, StarlarkIter<i32>>),
+}
+
+#[starlark_module]
+pub(crate) fn register_bytes(globals: &mut GlobalsBuilder) {
+    /// [bytes](
+    /// https://github.com/google/starlark-go/blob/master/doc/spec.md#bytes
+    /// ): construct a bytes value.
+    ///
+    /// `bytes(x)` accepts a string, which is encoded as UTF-8, an existing
+    /// bytes value, which is returned unchanged, or an iterable of integers
+    /// in the range 0 to 255.
+    ///
+    /// ```
+    /// # starlark::assert::all_true(r#"
+    /// bytes("hé") == b"h\xc3\xa9"
+    /// bytes([104, 105]) == b"hi"
+    /// bytes(b"hi") == b"hi"
+    /// # "#);
+    /// ```
+    #[starlark(as_type = StarlarkBytes, speculative_exec_safe)]
+    fn bytes<'v>(
+        #[starlark(require = pos)] x: BytesSource<'v>,
+        heap: &'v Heap,
+    ) -> starlark::Result<StarlarkBytes> {
+        match x {
+            BytesSource::Bytes(b) => Ok(b.clone()),
+            BytesSource::Str(s) => Ok(StarlarkBytes::new(s.as_bytes())),
+            BytesSource::Iter(xs) => {
+                let mut res = Vec::new();
+                for x in xs.get().iterate(heap)? {
+                    let x = i32::unpack_param(x)?;
+                    match u8::try_from(x) {
+                        Ok(b) => res.push(b),
+                        Err(_) => {
+                            return Err(starlark::Error::new_value(BytesError::ByteOutOfRange(x)))
+                        }
+                    }
+                }
+                Ok(StarlarkBytes::new(res))
+            }
+        }
+    }
+}
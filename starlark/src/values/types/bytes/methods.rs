@@ -0,0 +1,62 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Methods for the `bytes` type.
+
+use starlark_derive::starlark_module;
+
+use crate as starlark;
+use crate::environment::MethodsBuilder;
+use crate::values::bytes::bytes_type::BytesError;
+use crate::values::bytes::StarlarkBytes;
+
+#[starlark_module]
+pub(crate) fn bytes_methods(builder: &mut MethodsBuilder) {
+    /// [bytes.elems](
+    /// https://github.com/google/starlark-go/blob/master/doc/spec.md#bytes·elems
+    /// ): returns the byte values of the bytes.
+    ///
+    /// `b.elems()` returns a list of integers in the range 0 to 255, one for
+    /// each byte of `b`.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// b"Hi\xff".elems() == [72, 105, 255]
+    /// # "#);
+    /// ```
+    #[starlark(speculative_exec_safe)]
+    fn elems(this: &StarlarkBytes) -> anyhow::Result<Vec<i32>> {
+        Ok(this.as_bytes().iter().map(|b| *b as i32).collect())
+    }
+
+    /// `bytes.decode`: decodes the bytes as UTF-8 into a string.
+    ///
+    /// Fails if the bytes are not valid UTF-8.
+    ///
+    /// ```
+    /// # starlark::assert::is_true(r#"
+    /// b"h\xc3\xa9llo".decode() == "héllo"
+    /// # "#);
+    /// ```
+    #[starlark(speculative_exec_safe)]
+    fn decode(this: &StarlarkBytes) -> anyhow::Result<String> {
+        match std::str::from_utf8(this.as_bytes()) {
+            Ok(s) => Ok(s.to_owned()),
+            Err(e) => Err(BytesError::InvalidUtf8(e).into()),
+        }
+    }
+}
@@ -166,7 +166,9 @@ impl<'v> StarlarkValue<'v> for NativeFunction {
         args: &Arguments<'v, '_>,
         eval: &mut Evaluator<'v, '_, '_>,
     ) -> crate::Result<Value<'v>> {
-        self.function.invoke(eval, args).map_err(Into::into)
+        let res = self.function.invoke(eval, args);
+        eval.recorder.native_call(&self.name, &res);
+        res
     }
 
     fn get_attr(&self, attribute: &str, heap: &'v Heap) -> Option<Value<'v>> {
@@ -356,9 +358,11 @@ where
         args: &Arguments<'v, '_>,
         eval: &mut Evaluator<'v, '_, '_>,
     ) -> crate::Result<Value<'v>> {
-        self.method
-            .function
-            .invoke(eval, self.this.to_value(), args)
+        let this = self.this.to_value();
+        let res = self.method.function.invoke(eval, this, args);
+        eval.recorder
+            .native_method_call(this, &self.method.name, &res);
+        res
     }
 
     fn documentation(&self) -> DocItem {
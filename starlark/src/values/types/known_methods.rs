@@ -56,7 +56,10 @@ impl KnownMethod {
         args: &Arguments<'v, '_>,
         eval: &mut Evaluator<'v, '_, '_>,
     ) -> crate::Result<Value<'v>> {
-        self.imp.invoke(eval, this, args)
+        let res = self.imp.invoke(eval, this, args);
+        eval.recorder
+            .native_method_call(this, &self.method.name, &res);
+        res
     }
 }
 
@@ -21,9 +21,12 @@ use std::fmt::Formatter;
 
 use allocative::Allocative;
 use dupe::Dupe;
+use starlark_map::small_map::SmallMap;
 use starlark_map::sorted_map::SortedMap;
 
 use crate::codemap::Span;
+use crate::docs::DocMember;
+use crate::docs::DocProperty;
 use crate::typing::call_args::TyCallArgs;
 use crate::typing::callable::TyCallable;
 use crate::typing::custom::TyCustomImpl;
@@ -105,6 +108,21 @@ impl TyCustomImpl for TyNamespace {
         }
     }
 
+    fn attributes(&self) -> SmallMap<String, DocMember> {
+        self.fields
+            .iter()
+            .map(|(name, ty)| {
+                (
+                    name.as_str().to_owned(),
+                    DocMember::Property(DocProperty {
+                        docs: None,
+                        typ: ty.dupe(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
     fn matcher<T: TypeMatcherAlloc>(&self, factory: T) -> T::Result {
         #[derive(Allocative, Eq, PartialEq, Hash, Debug, Clone, Copy, Dupe)]
         struct NamespaceMatcher;
@@ -17,6 +17,7 @@
 
 use std::cell::Ref;
 use std::cell::RefCell;
+use std::cell::RefMut;
 use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Display;
@@ -99,6 +100,12 @@ impl<'v> SetData<'v> {
     pub fn remove_hashed(&mut self, value: Hashed<&Value<'v>>) -> bool {
         self.content.shift_remove_hashed(value)
     }
+
+    /// Downcast the value to a mutable set without checking its type.
+    pub(crate) unsafe fn from_value_unchecked_mut(x: Value<'v>) -> RefMut<'v, Self> {
+        let set = &x.downcast_ref_unchecked::<MutableSet<'v>>().0;
+        set.borrow_mut()
+    }
 }
 
 #[derive(Clone, Default, Debug, ProvidesStaticType, Allocative)]
@@ -362,6 +369,8 @@ impl<'v, T: SetLike<'v>> Display for SetGen<T> {
 #[cfg(test)]
 mod tests {
     use crate::assert;
+    use crate::assert::Assert;
+    use crate::syntax::Dialect;
 
     #[test]
     fn test_bit_or() {
@@ -469,4 +478,70 @@ mod tests {
             "Operation `-` not supported for types `set` and `list`",
         );
     }
+
+    #[test]
+    fn test_literal() {
+        assert::eq("{1, 2, 3}", "set([1, 2, 3])");
+        assert::eq("{3, 1, 3, 2,}", "set([3, 1, 2])");
+        assert::eq("x = 2\n{x, 1, x}", "set([2, 1])");
+        assert::eq("'set'", "type({1})");
+        assert::is_true("{(1, 2)} == set([(1, 2)])");
+    }
+
+    #[test]
+    fn test_literal_is_mutable() {
+        assert::eq(
+            "set([1, 2])",
+            r#"
+def f():
+    s = {1}
+    s.add(2)
+    return s
+f()
+f()
+"#,
+        );
+    }
+
+    #[test]
+    fn test_literal_unhashable() {
+        assert::fail("{[]}", "not hashable");
+        assert::fail("x = []\n{1, x}", "not hashable");
+    }
+
+    #[test]
+    fn test_comprehension() {
+        assert::eq("{x % 3 for x in range(7)}", "set([0, 1, 2])");
+        assert::eq("{x for x in []}", "set()");
+        assert::fail("{[x] for x in [1]}", "not hashable");
+    }
+
+    #[test]
+    fn test_literal_typecheck() {
+        assert::pass(
+            r#"
+def f() -> set[int]:
+    return {1, 2}
+def g(xs: list[str]) -> set[str]:
+    return {x for x in xs}
+"#,
+        );
+        assert::fail(
+            r#"
+def f() -> set[str]:
+    return {1, 2}
+"#,
+            "Expected type `set[str]` but got `set[int]`",
+        );
+    }
+
+    #[test]
+    fn test_literal_disabled() {
+        let mut a = Assert::new();
+        a.dialect(&Dialect::Standard);
+        a.fail("{1, 2}", "must enable set literals");
+        a.fail("{x for x in [1]}", "must enable set literals");
+        // Dict literals are unaffected.
+        a.eq("{1: 2}", "dict([(1, 2)])");
+    }
 }
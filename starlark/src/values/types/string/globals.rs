@@ -121,6 +121,8 @@ pub(crate) fn register_str(globals: &mut GlobalsBuilder) {
     /// ): formats its argument as a string.
     ///
     /// If x is a string, the result is x (without quotation).
+    /// If x is bytes, the result is x decoded as UTF-8, with invalid
+    /// sequences replaced by U+FFFD.
     /// All other strings, such as elements of a list of strings, are
     /// double-quoted.
     ///
@@ -141,7 +143,7 @@ pub(crate) fn register_str(globals: &mut GlobalsBuilder) {
             Ok(a)
         } else {
             let mut s = eval.string_pool.alloc();
-            a.collect_repr(&mut s);
+            a.collect_str(&mut s);
             let r = eval.heap().alloc_str(&s);
             eval.string_pool.release(s);
             Ok(r)
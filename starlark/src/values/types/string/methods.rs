@@ -28,6 +28,8 @@ use crate as starlark;
 use crate::environment::MethodsBuilder;
 use crate::eval::Arguments;
 use crate::eval::Evaluator;
+use crate::values::bytes::BytesError;
+use crate::values::bytes::StarlarkBytes;
 use crate::values::list::AllocList;
 use crate::values::list::UnpackList;
 use crate::values::none::NoneOr;
@@ -217,6 +219,24 @@ pub(crate) fn string_methods(builder: &mut MethodsBuilder) {
         }
     }
 
+    /// `string.encode`: encodes the string as UTF-8 bytes.
+    ///
+    /// Only available when [`Dialect::enable_bytes`](crate::syntax::Dialect::enable_bytes) is set.
+    ///
+    /// ```
+    /// # starlark::assert::all_true(r#"
+    /// "héllo".encode() == b"h\xc3\xa9llo"
+    /// "".encode() == b""
+    /// # "#);
+    /// ```
+    #[starlark(speculative_exec_safe)]
+    fn encode<'v>(this: &str, eval: &mut Evaluator<'v, '_, '_>) -> anyhow::Result<StarlarkBytes> {
+        if !eval.caller_enables_bytes() {
+            return Err(BytesError::NotEnabled.into());
+        }
+        Ok(StarlarkBytes::new(this.as_bytes()))
+    }
+
     /// [string.endswith](
     /// https://github.com/bazelbuild/starlark/blob/master/spec.md#string·endswith
     /// ): determine if a string ends with a given suffix.
@@ -89,7 +89,11 @@ impl UnboundValue {
             self.to_frozen_value().to_value(),
             Some(span),
             |eval| match self {
-                UnboundValue::Method(_, m) => m.invoke(eval, this, args),
+                UnboundValue::Method(method, m) => {
+                    let res = m.invoke(eval, this, args);
+                    eval.recorder.native_method_call(this, &method.name, &res);
+                    res
+                }
                 UnboundValue::Attr(_, a) => {
                     NativeAttribute::invoke_method_impl(&**a, this, args, eval)
                 }
@@ -51,13 +51,13 @@ use starlark::syntax::Dialect;
 use starlark::StarlarkResultExt;
 use starlark_lsp::completion::StringCompletionResult;
 use starlark_lsp::completion::StringCompletionType;
-use starlark_lsp::error::eval_message_to_lsp_diagnostic;
 use starlark_lsp::server::LspContext;
 use starlark_lsp::server::LspEvalResult;
 use starlark_lsp::server::LspUrl;
 use starlark_lsp::server::StringLiteralResult;
 
 use self::label::Label;
+use crate::eval::parse_file_with_recovery;
 use crate::eval::ContextMode;
 use crate::eval::EvalResult;
 
@@ -352,18 +352,6 @@ impl BazelContext {
             .into_iter()
             .map(EvalMessage::from)
     }
-    pub(crate) fn file_with_contents(
-        &self,
-        filename: &str,
-        content: String,
-    ) -> EvalResult<impl Iterator<Item = EvalMessage>> {
-        Self::err(
-            filename,
-            AstModule::parse(filename, content, &self.dialect)
-                .map(|module| self.go(filename, module))
-                .map_err(Into::into),
-        )
-    }
 
     fn get_repository_for_path<'a>(&'a self, path: &'a Path) -> Option<(Cow<'a, str>, &'a Path)> {
         self.external_output_base
@@ -619,17 +607,13 @@ impl BazelContext {
 
 impl LspContext for BazelContext {
     fn parse_file_with_contents(&self, uri: &LspUrl, content: String) -> LspEvalResult {
-        match uri {
-            LspUrl::File(uri) => {
-                let EvalResult { messages, ast } =
-                    self.file_with_contents(&uri.to_string_lossy(), content);
-                LspEvalResult {
-                    diagnostics: messages.map(eval_message_to_lsp_diagnostic).collect(),
-                    ast,
-                }
-            }
-            _ => LspEvalResult::default(),
-        }
+        parse_file_with_recovery(
+            uri,
+            content,
+            &self.dialect,
+            |file, ast| self.go(file, ast),
+            |_file, ast| self.check(ast),
+        )
     }
 
     fn resolve_load(
@@ -767,6 +751,10 @@ impl LspContext for BazelContext {
         DocModule::default()
     }
 
+    fn get_dialect(&self, _uri: &LspUrl) -> Dialect {
+        self.dialect.clone()
+    }
+
     fn get_url_for_global_symbol(
         &self,
         _current_file: &LspUrl,
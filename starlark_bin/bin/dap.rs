@@ -28,10 +28,13 @@ use serde_json::Map;
 use serde_json::Value;
 use starlark::debug::dap_capabilities;
 use starlark::debug::prepare_dap_adapter;
+use starlark::debug::prepare_replay_adapter;
 use starlark::debug::resolve_breakpoints;
 use starlark::debug::DapAdapter;
 use starlark::debug::DapAdapterClient;
 use starlark::debug::DapAdapterEvalHook;
+use starlark::debug::Recording;
+use starlark::debug::StepKind;
 use starlark::environment::Globals;
 use starlark::environment::Module;
 use starlark::eval::Evaluator;
@@ -41,12 +44,23 @@ use starlark::StarlarkResultExt;
 
 mod library;
 
+#[derive(Debug)]
+enum Program {
+    /// Evaluate a file, optionally recording the evaluation to a trace file.
+    Eval {
+        path: String,
+        record: Option<String>,
+    },
+    /// Replay a recorded evaluation.
+    Replay,
+}
+
 #[derive(Debug)]
 struct Backend {
-    adapter: Arc<dyn DapAdapter>,
+    adapter: Mutex<Arc<dyn DapAdapter>>,
     eval_wrapper: Mutex<Option<Box<dyn DapAdapterEvalHook>>>,
     client: Client,
-    file: Mutex<Option<String>>,
+    program: Mutex<Option<Program>>,
     dialect: Dialect,
     globals: Globals,
 }
@@ -66,11 +80,16 @@ impl DapAdapterClient for Client {
 }
 
 impl Backend {
-    fn execute(&self, path: &str) {
+    fn adapter(&self) -> Arc<dyn DapAdapter> {
+        self.adapter.lock().unwrap().dupe()
+    }
+
+    fn execute(&self, path: &str, record: Option<&str>) {
         let client = self.client.dupe();
         let client2 = self.client.dupe();
         let wrapper = self.eval_wrapper.lock().unwrap().take().unwrap();
         let path = PathBuf::from(path);
+        let record = record.map(PathBuf::from);
         let dialect = self.dialect.clone();
         let globals = self.globals.dupe();
 
@@ -80,11 +99,18 @@ impl Backend {
             let module = Module::new();
             let mut eval = Evaluator::new(&module);
             wrapper.add_dap_hooks(&mut eval);
+            if record.is_some() {
+                eval.enable_recording()?;
+            }
 
             // No way to pass back success/failure to the caller
             client.log(&format!("EVALUATION START: {}", path.display()));
-            let v = eval.eval_module(ast, &globals).into_anyhow_result()?;
-            let s = v.to_string();
+            let res = eval.eval_module(ast, &globals).into_anyhow_result();
+            if let Some(record) = &record {
+                // Write the recording even if the evaluation failed, that's when it's most useful.
+                eval.take_recording()?.write(record)?;
+            }
+            let s = res?.to_string();
             client.log(&format!("EVALUATION FINISHED: {}", path.display()));
             Ok(s)
         };
@@ -130,7 +156,7 @@ impl DebugServer for Backend {
     ) -> anyhow::Result<SetBreakpointsResponseBody> {
         let source = x.source.path.as_ref().unwrap();
         let resolved = resolve_breakpoints(&x, &*self.get_ast(source)?)?;
-        self.adapter.set_breakpoints(source, &resolved)?;
+        self.adapter().set_breakpoints(source, &resolved)?;
         Ok(resolved.to_response())
     }
 
@@ -140,17 +166,40 @@ impl DebugServer for Backend {
     }
 
     fn launch(&self, _: LaunchRequestArguments, args: Map<String, Value>) -> anyhow::Result<()> {
-        // Expecting program of type string
-        match args.get("program") {
-            Some(Value::String(path)) => {
-                *self.file.lock().unwrap() = Some(path.to_owned());
-                Ok(())
+        // Expecting either a program of type string, optionally with a file to record to,
+        // or a previously recorded trace to replay.
+        let string_arg = |name| match args.get(name) {
+            Some(Value::String(v)) => Some(v.to_owned()),
+            _ => None,
+        };
+        let program = match (string_arg("program"), string_arg("trace")) {
+            (Some(path), None) => Program::Eval {
+                path,
+                record: string_arg("record"),
+            },
+            (None, Some(trace)) => {
+                let recording = Recording::read(Path::new(&trace))?;
+                let adapter = prepare_replay_adapter(recording, Box::new(self.client.dupe()))?;
+                *self.adapter.lock().unwrap() = Arc::new(adapter);
+                // Stepping back is only possible through a recording, and we only know
+                // we are replaying one once launched.
+                self.client.event_capabilities(CapabilitiesEventBody {
+                    capabilities: Capabilities {
+                        supports_step_back: Some(true),
+                        ..Capabilities::default()
+                    },
+                });
+                Program::Replay
             }
-            _ => Err(anyhow::anyhow!(
-                "Couldn't find a program to launch, got args {:?}",
-                args
-            )),
-        }
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Couldn't find a program to launch or a trace to replay, got args {:?}",
+                    args
+                ));
+            }
+        };
+        *self.program.lock().unwrap() = Some(program);
+        Ok(())
     }
 
     fn threads(&self) -> anyhow::Result<ThreadsResponseBody> {
@@ -163,18 +212,21 @@ impl DebugServer for Backend {
     }
 
     fn configuration_done(&self) -> anyhow::Result<()> {
-        if let Some(path) = self.file.lock().unwrap().as_ref() {
-            self.execute(path);
+        match self.program.lock().unwrap().as_ref() {
+            Some(Program::Eval { path, record }) => self.execute(path, record.as_deref()),
+            // Stop on the first statement, so the user can decide which way to go.
+            Some(Program::Replay) => self.adapter().step(StepKind::Into)?,
+            None => {}
         }
         Ok(())
     }
 
     fn stack_trace(&self, v: StackTraceArguments) -> anyhow::Result<StackTraceResponseBody> {
-        self.adapter.stack_trace(v)
+        self.adapter().stack_trace(v)
     }
 
     fn scopes(&self, _: ScopesArguments) -> anyhow::Result<ScopesResponseBody> {
-        let scopes_info = self.adapter.scopes()?;
+        let scopes_info = self.adapter().scopes()?;
         Ok(ScopesResponseBody {
             scopes: vec![Scope {
                 name: "Locals".to_owned(),
@@ -192,7 +244,7 @@ impl DebugServer for Backend {
     }
 
     fn variables(&self, _: VariablesArguments) -> anyhow::Result<VariablesResponseBody> {
-        let vars_info = self.adapter.variables()?;
+        let vars_info = self.adapter().variables()?;
         Ok(VariablesResponseBody {
             variables: vars_info
                 .locals
@@ -203,7 +255,7 @@ impl DebugServer for Backend {
     }
 
     fn evaluate(&self, x: EvaluateArguments) -> anyhow::Result<EvaluateResponseBody> {
-        let expr_result = self.adapter.evaluate(&x.expression)?;
+        let expr_result = self.adapter().evaluate(&x.expression)?;
 
         Ok(EvaluateResponseBody {
             indexed_variables: None,
@@ -216,19 +268,39 @@ impl DebugServer for Backend {
     }
 
     fn continue_(&self, _: ContinueArguments) -> anyhow::Result<ContinueResponseBody> {
-        self.adapter.continue_()?;
+        self.adapter().continue_()?;
         Ok(ContinueResponseBody::default())
     }
+
+    fn next(&self, _: NextArguments) -> anyhow::Result<()> {
+        self.adapter().step(StepKind::Over)
+    }
+
+    fn step_in(&self, _: StepInArguments) -> anyhow::Result<()> {
+        self.adapter().step(StepKind::Into)
+    }
+
+    fn step_out(&self, _: StepOutArguments) -> anyhow::Result<()> {
+        self.adapter().step(StepKind::Out)
+    }
+
+    fn step_back(&self, _: StepBackArguments) -> anyhow::Result<()> {
+        self.adapter().step_back()
+    }
+
+    fn reverse_continue(&self, _: ReverseContinueArguments) -> anyhow::Result<()> {
+        self.adapter().reverse_continue()
+    }
 }
 
 pub(crate) fn server(dialect: Dialect, globals: Globals) {
     DapService::run(|client| {
         let (adapter, wrapper) = prepare_dap_adapter(Box::new(client.dupe()));
         Backend {
-            adapter: Arc::new(adapter),
+            adapter: Mutex::new(Arc::new(adapter)),
             eval_wrapper: Mutex::new(Some(Box::new(wrapper))),
             client,
-            file: Default::default(),
+            program: Default::default(),
             dialect,
             globals,
         }
@@ -77,6 +77,15 @@ impl Client {
         })
     }
 
+    pub(crate) fn event_capabilities(&self, body: CapabilitiesEventBody) {
+        self.event(CapabilitiesEvent {
+            type_: "event".to_owned(),
+            seq: 0,
+            event: "capabilities".to_owned(),
+            body,
+        })
+    }
+
     pub(crate) fn event_output(&self, body: OutputEventBody) {
         self.event(OutputEvent {
             type_: "event".to_owned(),
@@ -23,6 +23,7 @@ use std::iter;
 use std::path::Path;
 use std::path::PathBuf;
 
+use dupe::Dupe;
 use itertools::Either;
 use lsp_types::Url;
 use starlark::analysis::AstModuleLint;
@@ -81,6 +82,46 @@ pub(crate) struct EvalResult<T: Iterator<Item = EvalMessage>> {
     pub ast: Option<AstModule>,
 }
 
+/// Parse a file for [`LspContext::parse_file_with_contents`], continuing after syntax errors.
+/// A module without syntax errors is evaluated with `go`. Otherwise whatever could be parsed
+/// is linted with `check`, so the rest of the file still gets diagnostics.
+pub(crate) fn parse_file_with_recovery<I, J>(
+    uri: &LspUrl,
+    content: String,
+    dialect: &Dialect,
+    go: impl FnOnce(&str, AstModule) -> EvalResult<I>,
+    check: impl FnOnce(&str, &AstModule) -> J,
+) -> LspEvalResult
+where
+    I: Iterator<Item = EvalMessage>,
+    J: Iterator<Item = EvalMessage>,
+{
+    match uri {
+        LspUrl::File(uri) => {
+            let filename = uri.to_string_lossy();
+            let (ast, errors) = AstModule::parse_with_recovery(&filename, content, dialect);
+            let mut diagnostics: Vec<_> = errors
+                .iter()
+                .map(|e| eval_message_to_lsp_diagnostic(EvalMessage::from_error(uri, e)))
+                .collect();
+            let ast = match ast {
+                Some(ast) if errors.is_empty() => {
+                    let EvalResult { messages, ast } = go(&filename, ast);
+                    diagnostics.extend(messages.map(eval_message_to_lsp_diagnostic));
+                    ast
+                }
+                Some(ast) => {
+                    diagnostics.extend(check(&filename, &ast).map(eval_message_to_lsp_diagnostic));
+                    Some(ast)
+                }
+                None => None,
+            };
+            LspEvalResult { diagnostics, ast }
+        }
+        _ => LspEvalResult::default(),
+    }
+}
+
 /// Errors when [`LspContext::resolve_load()`] cannot resolve a given path.
 #[derive(thiserror::Error, Debug)]
 enum ResolveLoadError {
@@ -282,17 +323,13 @@ impl Context {
 
 impl LspContext for Context {
     fn parse_file_with_contents(&self, uri: &LspUrl, content: String) -> LspEvalResult {
-        match uri {
-            LspUrl::File(uri) => {
-                let EvalResult { messages, ast } =
-                    self.file_with_contents(&uri.to_string_lossy(), content);
-                LspEvalResult {
-                    diagnostics: messages.map(eval_message_to_lsp_diagnostic).collect(),
-                    ast,
-                }
-            }
-            _ => LspEvalResult::default(),
-        }
+        parse_file_with_recovery(
+            uri,
+            content,
+            &self.dialect,
+            |file, ast| self.go(file, ast),
+            |file, ast| self.check(file, ast),
+        )
     }
 
     fn resolve_load(
@@ -368,4 +405,14 @@ impl LspContext for Context {
     fn get_environment(&self, _uri: &LspUrl) -> DocModule {
         DocModule::default()
     }
+
+    fn get_globals(&self, _uri: &LspUrl) -> Option<Globals> {
+        // Symbols from the prelude are not part of the globals, so the typechecker
+        // would report every use of them as an error.
+        self.prelude.is_empty().then(|| self.globals.dupe())
+    }
+
+    fn get_dialect(&self, _uri: &LspUrl) -> Dialect {
+        self.dialect.clone()
+    }
 }
@@ -22,9 +22,11 @@
 use std::ffi::OsStr;
 use std::fmt;
 use std::fmt::Display;
+use std::fs;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use anyhow::Context as _;
 use clap::builder::StringValueParser;
 use clap::builder::TypedValueParser;
 use clap::Parser;
@@ -39,7 +41,10 @@ use starlark::environment::Globals;
 use starlark::errors::EvalMessage;
 use starlark::errors::EvalSeverity;
 use starlark::read_line::ReadLine;
+use starlark::syntax::AstModule;
 use starlark::syntax::Dialect;
+use starlark_syntax::syntax::format::format_source;
+use starlark_syntax::syntax::json::JsonModule;
 use suppression::GlobLintSuppression;
 use walkdir::WalkDir;
 
@@ -59,6 +64,8 @@ struct Args {
         conflicts_with_all = &[
             "dap",
             "check",
+            "format",
+            "dump_ast_json",
             "json",
             "docs",
             "evaluate",
@@ -74,6 +81,8 @@ struct Args {
         conflicts_with_all = &[
             "lsp",
             "check",
+            "format",
+            "dump_ast_json",
             "json",
             "docs",
             "extension",
@@ -91,6 +100,23 @@ struct Args {
     )]
     check: bool,
 
+    #[arg(
+        long = "format",
+        help = "Format the files in place. With `--check`, report the files that are not \
+formatted instead.",
+        requires = "files",
+        conflicts_with_all = &["lsp", "dap", "dump_ast_json", "json", "docs", "evaluate"],
+    )]
+    format: bool,
+
+    #[arg(
+        long = "dump-ast-json",
+        help = "Print the AST of each file as a line of JSON, without evaluating it.",
+        requires = "files",
+        conflicts_with_all = &["lsp", "dap", "check", "format", "json", "docs", "evaluate"],
+    )]
+    dump_ast_json: bool,
+
     #[arg(
         long = "json",
         help = "Show output as JSON lines.",
@@ -127,7 +153,8 @@ struct Args {
 
     #[arg(
         long = "dialect",
-        help = "Dialect to use for features and globals.",
+        help = "Dialect to use for features and globals, either `standard`, `extended` \
+or a preset matching another implementation: `bazel`, `buck2` or `starlark-go`.",
         default_value = "extended"
     )]
     dialect: ArgsDialect,
@@ -167,6 +194,21 @@ enum ArgsDoc {
 enum ArgsDialect {
     Standard,
     Extended,
+    Bazel,
+    Buck2,
+    StarlarkGo,
+}
+
+impl ArgsDialect {
+    /// The syntax options and globals of the named preset, see [`Dialect::from_name`].
+    fn preset(self) -> (Dialect, Globals) {
+        let value = self.to_possible_value().expect("no dialect is skipped");
+        let name = value.get_name();
+        (
+            Dialect::from_name(name).expect("every dialect argument is a preset name"),
+            Globals::for_dialect_name(name).expect("every dialect argument is a preset name"),
+        )
+    }
 }
 
 // Treat directories as things to recursively walk for .<extension> files,
@@ -249,6 +291,65 @@ fn drain(
     Ok(())
 }
 
+/// Format files in place, or if `check` is set, report the files that are not formatted.
+/// Files that do not parse are reported, and the others are still formatted.
+fn format_files(
+    files: impl Iterator<Item = PathBuf>,
+    dialect: &Dialect,
+    check: bool,
+) -> anyhow::Result<()> {
+    let mut unformatted = 0;
+    let mut unparsed = 0;
+    for file in files {
+        let content = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read `{}`", file.display()))?;
+        let formatted = match format_source(&file.to_string_lossy(), content.clone(), dialect) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                println!("{}", e);
+                unparsed += 1;
+                continue;
+            }
+        };
+        if formatted == content {
+            continue;
+        }
+        if check {
+            println!("{} is not formatted", file.display());
+            unformatted += 1;
+        } else {
+            fs::write(&file, formatted)
+                .with_context(|| format!("Failed to write `{}`", file.display()))?;
+        }
+    }
+    match (unparsed, unformatted) {
+        (0, 0) => Ok(()),
+        (0, _) => Err(anyhow::anyhow!("{} files are not formatted", unformatted)),
+        (_, 0) => Err(anyhow::anyhow!("{} files could not be parsed", unparsed)),
+        _ => Err(anyhow::anyhow!(
+            "{} files could not be parsed, {} files are not formatted",
+            unparsed,
+            unformatted
+        )),
+    }
+}
+
+/// Print the AST of each file as a line of JSON.
+fn dump_ast_json(files: impl Iterator<Item = PathBuf>, dialect: &Dialect) -> anyhow::Result<()> {
+    for file in files {
+        let content = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read `{}`", file.display()))?;
+        let ast = AstModule::parse(&file.to_string_lossy(), content, dialect)
+            .map_err(|e| e.into_anyhow())?;
+        println!(
+            "{}",
+            serde_json::to_string(&JsonModule::new(&ast))
+                .map_err(|e| anyhow::anyhow!("Failed to serialize AST to JSON: {e}"))?
+        );
+    }
+    Ok(())
+}
+
 fn interactive(ctx: &Context) -> anyhow::Result<()> {
     let mut rl = ReadLine::new("STARLARK_RUST_HISTFILE")?;
     loop {
@@ -279,10 +380,7 @@ fn main() -> anyhow::Result<()> {
     let args = argfile::expand_args(argfile::parse_fromfile, argfile::PREFIX)?;
     let args: Args = Args::parse_from(args);
 
-    let (dialect, globals) = match args.dialect {
-        ArgsDialect::Standard => (Dialect::Standard, Globals::standard()),
-        ArgsDialect::Extended => (Dialect::Extended, Globals::extended_internal()),
-    };
+    let (dialect, globals) = args.dialect.preset();
 
     if args.dap {
         dap::server(dialect, globals);
@@ -296,6 +394,14 @@ fn main() -> anyhow::Result<()> {
         let prelude = expand_dirs(ext, args.prelude).collect::<Vec<_>>();
         let print_non_none = !args.evaluate.is_empty() || is_interactive;
 
+        if args.format {
+            return format_files(expand_dirs(ext, args.files), &dialect, args.check);
+        }
+
+        if args.dump_ast_json {
+            return dump_ast_json(expand_dirs(ext, args.files), &dialect);
+        }
+
         // TODO: Remove this when extracting the Bazel binary to its own
         // repository, after the LspContext interface stabilizes.
         if args.bazel {
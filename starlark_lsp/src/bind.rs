@@ -38,6 +38,7 @@ use starlark_syntax::syntax::ast::ForP;
 use starlark_syntax::syntax::ast::IdentP;
 use starlark_syntax::syntax::ast::LambdaP;
 use starlark_syntax::syntax::ast::Stmt;
+use starlark_syntax::syntax::ast::WhileP;
 use starlark_syntax::syntax::module::AstModuleFields;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -221,6 +222,9 @@ fn expr(x: &AstExpr, res: &mut Vec<Bind>) {
             expr(&x.0, res);
             expr(&x.1, res)
         }),
+        Expr::SetComprehension(x, for_, clauses) => {
+            comprehension(for_, clauses, res, |res| expr(x, res))
+        }
 
         // Uninteresting - just recurse
         _ => x.visit_expr(|x| expr(x, res)),
@@ -255,7 +259,7 @@ fn stmt(x: &AstStmt, res: &mut Vec<Bind>) {
             }
         }
         Stmt::Break | Stmt::Continue | Stmt::Return(None) => flow(res),
-        Stmt::Pass => {}
+        Stmt::Pass | Stmt::Error => {}
         Stmt::Return(Some(x)) => {
             expr(x, res);
             flow(res)
@@ -322,6 +326,12 @@ fn stmt(x: &AstStmt, res: &mut Vec<Bind>) {
             stmt(body, res);
             flow(res)
         }
+        Stmt::While(WhileP { cond, body }) => {
+            expr(cond, res);
+            flow(res);
+            stmt(body, res);
+            flow(res)
+        }
         Stmt::Load(load) => {
             for x in &load.args {
                 res.push(Bind::Set(
@@ -0,0 +1,302 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Call hierarchy: the functions that call a `def`, and the functions it calls,
+//! following `load()` statements between files.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use lsp_types::CallHierarchyIncomingCall;
+use lsp_types::CallHierarchyIncomingCallsParams;
+use lsp_types::CallHierarchyItem;
+use lsp_types::CallHierarchyOutgoingCall;
+use lsp_types::CallHierarchyOutgoingCallsParams;
+use lsp_types::CallHierarchyPrepareParams;
+use lsp_types::InitializeParams;
+use lsp_types::Range;
+use lsp_types::SymbolKind;
+use starlark::analysis::find_call_name::AstModuleFindCallName;
+use starlark::codemap::ResolvedSpan;
+use starlark::codemap::Span;
+use starlark_syntax::syntax::ast::AstStmt;
+use starlark_syntax::syntax::ast::Stmt;
+use starlark_syntax::syntax::module::AstModuleFields;
+
+use crate::bind::Assigner;
+use crate::definition::LspModule;
+use crate::references::Binding;
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+/// The module a call hierarchy item is in, and the binding of its function, which is
+/// `None` for the item of the top level of a module.
+type ResolvedItem = (LspUrl, Arc<LspModule>, Option<Binding>);
+
+/// Find the span of the `def` statement whose name is at `name`.
+fn def_span(stmt: &AstStmt, name: Span) -> Option<Span> {
+    if let Stmt::Def(def) = &stmt.node {
+        if def.name.span == name {
+            return Some(stmt.span);
+        }
+    }
+    let mut res = None;
+    stmt.visit_stmt(|x| {
+        if res.is_none() {
+            res = def_span(x, name);
+        }
+    });
+    res
+}
+
+impl LspModule {
+    /// The call hierarchy item for the function whose `def` has its name at `name`.
+    fn function_item(&self, uri: &LspUrl, name: Span) -> anyhow::Result<Option<CallHierarchyItem>> {
+        let Some(span) = def_span(self.ast.statement(), name) else {
+            return Ok(None);
+        };
+        let codemap = self.ast.codemap();
+        // The body of a `def` includes the blank lines after it.
+        let text = codemap.source_span(span);
+        let span = Span::new(span.begin(), span.begin() + text.trim_end().len() as u32);
+        Ok(Some(CallHierarchyItem {
+            name: codemap.source_span(name).to_owned(),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: uri.try_into()?,
+            range: codemap.resolve_span(span).into(),
+            selection_range: codemap.resolve_span(name).into(),
+            data: None,
+        }))
+    }
+
+    /// The call hierarchy item for the top level of the module, which makes the calls
+    /// that are not inside any `def`.
+    fn module_item(&self, uri: &LspUrl) -> anyhow::Result<CallHierarchyItem> {
+        let codemap = self.ast.codemap();
+        let start = Range::from(codemap.resolve_span(Span::new(
+            codemap.full_span().begin(),
+            codemap.full_span().begin(),
+        )));
+        Ok(CallHierarchyItem {
+            name: uri
+                .path()
+                .file_name()
+                .map_or_else(|| uri.to_string(), |x| x.to_string_lossy().into_owned()),
+            kind: SymbolKind::FILE,
+            tags: None,
+            detail: None,
+            uri: uri.try_into()?,
+            range: codemap.resolve_span(codemap.full_span()).into(),
+            selection_range: start,
+            data: None,
+        })
+    }
+}
+
+/// The calls to or from one item, in the order they were first found.
+fn group_calls(calls: Vec<(CallHierarchyItem, Range)>) -> Vec<(CallHierarchyItem, Vec<Range>)> {
+    let mut res: Vec<(CallHierarchyItem, Vec<Range>)> = Vec::new();
+    for (item, range) in calls {
+        match res
+            .iter_mut()
+            .find(|(x, _)| x.uri == item.uri && x.selection_range == item.selection_range)
+        {
+            Some((_, ranges)) => ranges.push(range),
+            None => res.push((item, vec![range])),
+        }
+    }
+    res
+}
+
+impl<T: LspContext> Backend<T> {
+    /// Find the module, and the `def` in it, that defines the function bound by
+    /// `binding`, following `load()` statements.
+    fn function_definition(
+        &self,
+        uri: &LspUrl,
+        module: &LspModule,
+        binding: &Binding,
+        workspace_root: Option<&Path>,
+    ) -> anyhow::Result<Option<CallHierarchyItem>> {
+        match &binding.assigner {
+            Assigner::Load { path, name } => {
+                let Ok(load_uri) = self.resolve_load_path(path, uri, workspace_root) else {
+                    return Ok(None);
+                };
+                let Some(load_module) = self.get_ast_or_load_from_disk(&load_uri)? else {
+                    return Ok(None);
+                };
+                match load_module.find_top_level_binding(&name.node) {
+                    Some(binding) => load_module.function_item(&load_uri, binding.span),
+                    None => Ok(None),
+                }
+            }
+            Assigner::Assign => module.function_item(uri, binding.span),
+            Assigner::Argument => Ok(None),
+        }
+    }
+
+    /// The module and binding that a call hierarchy item sent by the client refers to.
+    fn resolve_call_hierarchy_item(
+        &self,
+        item: &CallHierarchyItem,
+    ) -> anyhow::Result<Option<ResolvedItem>> {
+        let uri: LspUrl = item.uri.clone().try_into()?;
+        let Some(module) = self.get_ast_or_load_from_disk(&uri)? else {
+            return Ok(None);
+        };
+        if item.kind == SymbolKind::FILE {
+            return Ok(Some((uri, module, None)));
+        }
+        let start = item.selection_range.start;
+        match module.find_binding_at_location(start.line, start.character) {
+            Some(binding) => Ok(Some((uri, module, Some(binding)))),
+            None => Ok(None),
+        }
+    }
+
+    /// Find the function at the cursor, which is either the name of a `def` or a call.
+    pub(crate) fn prepare_call_hierarchy_items(
+        &self,
+        params: CallHierarchyPrepareParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<Option<Vec<CallHierarchyItem>>> {
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .try_into()?;
+        let position = params.text_document_position_params.position;
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+        let Some(module) = self.get_ast(&uri) else {
+            return Ok(None);
+        };
+        let Some(binding) = module.find_binding_at_location(position.line, position.character)
+        else {
+            return Ok(None);
+        };
+        Ok(self
+            .function_definition(&uri, &module, &binding, workspace_root.as_deref())?
+            .map(|item| vec![item]))
+    }
+
+    /// Find the calls to a function, in its own module and in the modules that load it,
+    /// grouped by the function or module that makes them.
+    pub(crate) fn find_incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<Option<Vec<CallHierarchyIncomingCall>>> {
+        let Some((uri, module, Some(binding))) = self.resolve_call_hierarchy_item(&params.item)?
+        else {
+            return Ok(None);
+        };
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+        let references =
+            match self.exported_symbol_for_binding(&binding, &uri, workspace_root.as_deref()) {
+                Some((module_uri, name)) => self.find_exported_symbol_references(
+                    &module_uri,
+                    &name,
+                    workspace_root.as_deref(),
+                    false,
+                )?,
+                None => Self::binding_references(&uri, &module, &binding, false),
+            };
+
+        let mut calls = Vec::new();
+        let mut seen = HashSet::new();
+        for (doc_uri, _) in &references {
+            if !seen.insert(doc_uri) {
+                continue;
+            }
+            let Some(doc) = self.get_ast_or_load_from_disk(doc_uri)? else {
+                continue;
+            };
+            let spans: HashSet<ResolvedSpan> = references
+                .iter()
+                .filter(|(x, _)| x == doc_uri)
+                .map(|(_, span)| *span)
+                .collect();
+            let codemap = doc.ast.codemap();
+            for call in doc.ast.find_function_calls() {
+                let span = codemap.resolve_span(call.span);
+                if !spans.contains(&span) {
+                    continue;
+                }
+                let from = match &call.caller {
+                    Some(caller) => doc.function_item(doc_uri, caller.span)?,
+                    None => Some(doc.module_item(doc_uri)?),
+                };
+                if let Some(from) = from {
+                    calls.push((from, Range::from(span)));
+                }
+            }
+        }
+        Ok(Some(
+            group_calls(calls)
+                .into_iter()
+                .map(|(from, from_ranges)| CallHierarchyIncomingCall { from, from_ranges })
+                .collect(),
+        ))
+    }
+
+    /// Find the functions called by a function, or by the top level of a module,
+    /// grouped by the function that is called. Calls to builtins are not included.
+    pub(crate) fn find_outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+        let Some((uri, module, binding)) = self.resolve_call_hierarchy_item(&params.item)? else {
+            return Ok(None);
+        };
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+        let codemap = module.ast.codemap();
+
+        let mut calls = Vec::new();
+        for call in module.ast.find_function_calls() {
+            let caller = call.caller.as_ref().map(|caller| caller.span);
+            if caller != binding.as_ref().map(|binding| binding.span) {
+                continue;
+            }
+            let span = codemap.resolve_span(call.span);
+            let Some(callee) =
+                module.find_binding_at_location(span.begin.line as u32, span.begin.column as u32)
+            else {
+                continue;
+            };
+            if let Some(to) =
+                self.function_definition(&uri, &module, &callee, workspace_root.as_deref())?
+            {
+                calls.push((to, Range::from(span)));
+            }
+        }
+        Ok(Some(
+            group_calls(calls)
+                .into_iter()
+                .map(|(to, from_ranges)| CallHierarchyOutgoingCall { to, from_ranges })
+                .collect(),
+        ))
+    }
+}
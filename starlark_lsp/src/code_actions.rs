@@ -0,0 +1,398 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Quick fixes for the problems reported by the linter.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use lsp_types::CodeAction;
+use lsp_types::CodeActionKind;
+use lsp_types::CodeActionOrCommand;
+use lsp_types::CodeActionParams;
+use lsp_types::Diagnostic;
+use lsp_types::InitializeParams;
+use lsp_types::NumberOrString;
+use lsp_types::Range;
+use lsp_types::TextEdit;
+use lsp_types::Url;
+use lsp_types::WorkspaceEdit;
+use starlark::analysis::unused_load_removals;
+use starlark::codemap::CodeMap;
+use starlark::codemap::Span;
+use starlark_syntax::syntax::ast::ArgumentP;
+use starlark_syntax::syntax::ast::AstStmt;
+use starlark_syntax::syntax::ast::Expr;
+use starlark_syntax::syntax::ast::Stmt;
+use starlark_syntax::syntax::module::AstModuleFields;
+
+use crate::bind::scope;
+use crate::bind::Assigner;
+use crate::bind::Bind;
+use crate::bind::Scope;
+use crate::definition::Definition;
+use crate::definition::IdentifierDefinition;
+use crate::definition::LspModule;
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+use crate::signature::visit_exprs;
+
+/// Expand `span` to the whole lines it is on if there is nothing else on those lines,
+/// so that deleting it does not leave a blank line behind.
+fn deletion_span(codemap: &CodeMap, span: Span) -> Span {
+    let first_line = codemap.line_span(codemap.find_line(span.begin()));
+    let last_line = codemap.find_line(span.end());
+    let line_end = codemap.line_span_trim_newline(last_line).end();
+    let before = codemap.source_span(Span::new(first_line.begin(), span.begin()));
+    let after = codemap.source_span(Span::new(span.end(), line_end.max(span.end())));
+    if before.trim().is_empty() && after.trim().is_empty() {
+        Span::new(first_line.begin(), codemap.line_span(last_line).end())
+    } else {
+        span
+    }
+}
+
+/// Find the statement at `range`, and whether it is the only statement in its block.
+fn find_statement(
+    codemap: &CodeMap,
+    stmt: &AstStmt,
+    range: Range,
+    alone: bool,
+) -> Option<(Span, bool)> {
+    if let Stmt::Statements(stmts) = &stmt.node {
+        let alone = alone && stmts.len() == 1;
+        return stmts
+            .iter()
+            .find_map(|x| find_statement(codemap, x, range, alone));
+    }
+    if Range::from(codemap.resolve_span(stmt.span)) == range {
+        return Some((stmt.span, alone));
+    }
+    let mut res = None;
+    stmt.visit_stmt(|x| {
+        if res.is_none() {
+            res = find_statement(codemap, x, range, true);
+        }
+    });
+    res
+}
+
+/// Find the variable assigned at `range`.
+fn find_assigned<'a>(codemap: &CodeMap, scope: &'a Scope, range: Range) -> Option<&'a str> {
+    scope.inner.iter().find_map(|bind| match bind {
+        Bind::Set(Assigner::Assign, ident)
+            if Range::from(codemap.resolve_span(ident.span)) == range =>
+        {
+            Some(ident.ident.as_str())
+        }
+        Bind::Scope(inner) => find_assigned(codemap, inner, range),
+        _ => None,
+    })
+}
+
+impl LspModule {
+    /// The edit that renames the unused variable assigned at `range` to start with
+    /// `_`, along with the current name of the variable.
+    pub(crate) fn prefix_unused_assign(&self, range: Range) -> Option<(String, TextEdit)> {
+        let scope = scope(&self.ast);
+        let name = find_assigned(self.ast.codemap(), &scope, range)?;
+        if name.starts_with('_') {
+            return None;
+        }
+        Some((
+            name.to_owned(),
+            TextEdit::new(Range::new(range.start, range.start), "_".to_owned()),
+        ))
+    }
+
+    /// The edit that removes the redundant `return` or `continue` statement at `range`.
+    /// If it is the only statement in its block, it is replaced with `pass` instead.
+    pub(crate) fn remove_redundant_statement(&self, range: Range) -> Option<TextEdit> {
+        let codemap = self.ast.codemap();
+        let (span, alone) = find_statement(codemap, self.ast.statement(), range, true)?;
+        Some(if alone {
+            TextEdit::new(range, "pass".to_owned())
+        } else {
+            TextEdit::new(
+                codemap.resolve_span(deletion_span(codemap, span)).into(),
+                String::new(),
+            )
+        })
+    }
+
+    /// The edit that rewrites the `dict(**x)` call at `range` as `dict(x)`.
+    pub(crate) fn rewrite_dict_copy(&self, range: Range) -> Option<TextEdit> {
+        let codemap = self.ast.codemap();
+        let mut res = None;
+        self.ast.statement().visit_expr(|expr| {
+            visit_exprs(expr, &mut |x| {
+                if res.is_some() || Range::from(codemap.resolve_span(x.span)) != range {
+                    return;
+                }
+                if let Expr::Call(_, args) = &x.node {
+                    if let [arg] = args.args.as_slice() {
+                        if let ArgumentP::KwArgs(arg) = &arg.node {
+                            res = Some(TextEdit::new(
+                                range,
+                                format!("dict({})", codemap.source_span(arg.span)),
+                            ));
+                        }
+                    }
+                }
+            })
+        });
+        res
+    }
+
+    /// The edits that remove each of the unused loads: either a whole `load`
+    /// statement, or one of its arguments.
+    pub(crate) fn unused_load_removals(&self) -> Vec<TextEdit> {
+        let codemap = self.ast.codemap();
+        let Ok(spans) = unused_load_removals(codemap.filename(), codemap.source()) else {
+            return Vec::new();
+        };
+        spans
+            .into_iter()
+            .map(|span| {
+                TextEdit::new(
+                    codemap.resolve_span(deletion_span(codemap, span)).into(),
+                    String::new(),
+                )
+            })
+            .collect()
+    }
+}
+
+fn quick_fix(
+    uri: &Url,
+    title: String,
+    diagnostics: Vec<Diagnostic>,
+    edits: Vec<TextEdit>,
+) -> CodeActionOrCommand {
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title,
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(diagnostics),
+        edit: Some(WorkspaceEdit::new(HashMap::from([(uri.clone(), edits)]))),
+        ..CodeAction::default()
+    })
+}
+
+fn contains(outer: Range, inner: Range) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+impl<T: LspContext> Backend<T> {
+    /// Find the quick fixes for the diagnostics in the code action request.
+    pub(crate) fn find_code_actions(
+        &self,
+        params: CodeActionParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<Vec<CodeActionOrCommand>> {
+        let url = params.text_document.uri;
+        let uri = url.clone().try_into()?;
+        let Some(module) = self.get_ast(&uri) else {
+            return Ok(Vec::new());
+        };
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+
+        let mut load_removals = None;
+        let mut unused_loads = Vec::new();
+        let mut actions = Vec::new();
+        for diagnostic in params.context.diagnostics {
+            let Some(NumberOrString::String(code)) = &diagnostic.code else {
+                continue;
+            };
+            let range = diagnostic.range;
+            let fixes: Vec<(String, TextEdit)> = match code.as_str() {
+                "unused-load" => {
+                    let removals =
+                        load_removals.get_or_insert_with(|| module.unused_load_removals());
+                    unused_loads.push(diagnostic.clone());
+                    removals
+                        .iter()
+                        .filter(|edit| contains(edit.range, range))
+                        .map(|edit| ("Remove unused load".to_owned(), edit.clone()))
+                        .collect()
+                }
+                "unused-assign" => module
+                    .prefix_unused_assign(range)
+                    .map(|(name, edit)| (format!("Prefix `{}` with `_`", name), edit))
+                    .into_iter()
+                    .collect(),
+                "redundant-return" | "redundant-continue" => module
+                    .remove_redundant_statement(range)
+                    .map(|edit| {
+                        let keyword = code.trim_start_matches("redundant-");
+                        (format!("Remove redundant `{}`", keyword), edit)
+                    })
+                    .into_iter()
+                    .collect(),
+                "dict-without-star-star" => module
+                    .rewrite_dict_copy(range)
+                    .map(|edit| (format!("Replace with `{}`", edit.new_text), edit))
+                    .into_iter()
+                    .collect(),
+                "using-undefined" => {
+                    self.missing_load_fixes(&module, &uri, range, workspace_root.as_deref())
+                }
+                _ => Vec::new(),
+            };
+            for (title, edit) in fixes {
+                actions.push(quick_fix(&url, title, vec![diagnostic.clone()], vec![edit]));
+            }
+        }
+
+        if let Some(removals) = load_removals {
+            if removals.len() > 1 {
+                actions.push(quick_fix(
+                    &url,
+                    "Remove all unused loads".to_owned(),
+                    unused_loads,
+                    removals,
+                ));
+            }
+        }
+        Ok(actions)
+    }
+
+    /// Edits that add a `load()` of the undefined symbol at `range`, one for each open
+    /// module that exports a symbol with that name.
+    fn missing_load_fixes(
+        &self,
+        module: &LspModule,
+        uri: &LspUrl,
+        range: Range,
+        workspace_root: Option<&Path>,
+    ) -> Vec<(String, TextEdit)> {
+        let name = match module.find_definition_at_location(range.start.line, range.start.character)
+        {
+            Definition::Identifier(IdentifierDefinition::Unresolved { name, .. }) => name,
+            _ => return Vec::new(),
+        };
+        let (last_load, loads) = module.find_loads();
+
+        let mut fixes = Vec::new();
+        for (doc_uri, doc) in self.parsed_modules() {
+            if &doc_uri == uri || doc.find_exported_symbol(&name).is_none() {
+                continue;
+            }
+            let Ok(load_path) = self.context.render_as_load(&doc_uri, uri, workspace_root) else {
+                continue;
+            };
+            let edit = Self::get_load_text_edit(
+                &load_path,
+                &name,
+                module,
+                last_load,
+                loads.get(&load_path),
+            );
+            fixes.push((format!("Load `{}` from `{}`", name, load_path), edit));
+        }
+        fixes.sort_by(|a, b| a.0.cmp(&b.0));
+        fixes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use textwrap::dedent;
+
+    use super::*;
+    use crate::definition::helpers::FixtureWithRanges;
+
+    fn range(parsed: &FixtureWithRanges, name: &str) -> Range {
+        parsed.resolved_span(name).into()
+    }
+
+    #[test]
+    fn fixes_lints() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            def f(x):
+                <y>y</y> = 1
+                for _ in x:
+                    <continue>continue</continue>
+                <return>return</return>
+            def g():
+                <only>return</only>
+            z = <dict>dict(**{"a": 1})</dict>
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = parsed.module().unwrap();
+        let y = range(&parsed, "y");
+
+        assert_eq!(
+            Some((
+                "y".to_owned(),
+                TextEdit::new(Range::new(y.start, y.start), "_".to_owned())
+            )),
+            module.prefix_unused_assign(y)
+        );
+        assert_eq!(
+            Some(TextEdit::new(range(&parsed, "continue"), "pass".to_owned())),
+            module.remove_redundant_statement(range(&parsed, "continue"))
+        );
+        let ret = range(&parsed, "return");
+        assert_eq!(
+            Some(TextEdit::new(
+                Range::new(
+                    lsp_types::Position::new(ret.start.line, 0),
+                    lsp_types::Position::new(ret.start.line + 1, 0)
+                ),
+                String::new()
+            )),
+            module.remove_redundant_statement(ret)
+        );
+        assert_eq!(
+            Some(TextEdit::new(range(&parsed, "only"), "pass".to_owned())),
+            module.remove_redundant_statement(range(&parsed, "only"))
+        );
+        assert_eq!(
+            Some(TextEdit::new(
+                range(&parsed, "dict"),
+                r#"dict({"a": 1})"#.to_owned()
+            )),
+            module.rewrite_dict_copy(range(&parsed, "dict"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn removes_unused_loads() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            load("foo.star", "a", <b>"b",</b> "c")
+            <unused>load("bar.star", "d")
+            </unused>print(a, c)
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = parsed.module().unwrap();
+        assert_eq!(
+            vec![
+                TextEdit::new(range(&parsed, "b"), String::new()),
+                TextEdit::new(range(&parsed, "unused"), String::new()),
+            ],
+            module.unused_load_removals()
+        );
+        Ok(())
+    }
+}
@@ -28,14 +28,18 @@ use lsp_types::MarkupContent;
 use lsp_types::MarkupKind;
 use lsp_types::Range;
 use lsp_types::TextEdit;
+use starlark::codemap::Pos;
 use starlark::codemap::ResolvedSpan;
 use starlark::docs::markdown::render_doc_item_no_link;
 use starlark::docs::markdown::render_doc_param;
 use starlark::docs::DocItem;
 use starlark::docs::DocMember;
 use starlark_syntax::codemap::ResolvedPos;
-use starlark_syntax::syntax::ast::StmtP;
+use starlark_syntax::syntax::ast::AstExpr;
+use starlark_syntax::syntax::ast::AstNoPayload;
+use starlark_syntax::syntax::ast::Expr;
 use starlark_syntax::syntax::module::AstModuleFields;
+use starlark_syntax::syntax::uniplate::Visit;
 
 use crate::definition::Definition;
 use crate::definition::DottedDefinition;
@@ -70,7 +74,110 @@ pub struct StringCompletionResult {
     pub kind: CompletionItemKind,
 }
 
+/// If the cursor is after a `.` and the start of an attribute name, the offset in
+/// `text` where the attribute name starts, and its end, which may be after the cursor.
+fn attribute_name_at(text: &str, line: u32, character: u32) -> Option<(usize, usize)> {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(line as usize)
+        .map(str::len)
+        .sum();
+    let line_text = text[line_start..].split('\n').next()?;
+    let cursor = line_text
+        .char_indices()
+        .nth(character as usize)
+        .map_or(line_text.len(), |(i, _)| i);
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    let start = line_text[..cursor].trim_end_matches(is_ident_char).len();
+    if !line_text[..start].ends_with('.') {
+        return None;
+    }
+    let end = line_text.len() - line_text[cursor..].trim_start_matches(is_ident_char).len();
+    Some((line_start + start, line_start + end))
+}
+
+/// Find the expression whose attribute is accessed by the name starting at `pos`.
+fn find_attribute_receiver(node: Visit<'_, AstNoPayload>, pos: Pos) -> Option<&AstExpr> {
+    if let Visit::Expr(expr) = &node {
+        if let Expr::Dot(receiver, name) = &expr.node {
+            if name.span.begin() == pos {
+                return Some(receiver);
+            }
+        }
+    }
+    let mut res = None;
+    node.visit_children(|x| {
+        if res.is_none() {
+            res = find_attribute_receiver(x, pos);
+        }
+    });
+    res
+}
+
 impl<T: LspContext> Backend<T> {
+    /// Completion options for the attribute after a `.`, from the type of the value
+    /// before it inferred by the typechecker: the fields of records and structs, and
+    /// the methods of builtin and native types.
+    ///
+    /// Returns `None` if the cursor is not after a `.` that accesses an attribute, or the
+    /// context does not provide globals to typecheck with. An attribute whose type is not
+    /// known has no completion options, since names in scope are never valid there.
+    pub(crate) fn attribute_completion_options(
+        &self,
+        document_uri: &LspUrl,
+        line: u32,
+        character: u32,
+        workspace_root: Option<&Path>,
+    ) -> Option<Vec<CompletionItem>> {
+        let mut text = self.documents.read().unwrap().get(document_uri)?.text();
+        let (start, end) = attribute_name_at(&text, line, character)?;
+        // Without a name after the `.`, the document does not parse.
+        if start == end {
+            text.insert(start, '_');
+        }
+        let ast = self
+            .context
+            .parse_file_with_contents(document_uri, text)
+            .ast?;
+        let module = LspModule::new(ast);
+        let receiver =
+            find_attribute_receiver(Visit::Stmt(module.ast.statement()), Pos::new(start as u32))?;
+        let (_, types) = self.typecheck(document_uri, &module, workspace_root)?;
+
+        let ty = types
+            .attribute_receiver_type(receiver.span)
+            .or_else(|| match &receiver.node {
+                // Only the bodies of functions are typechecked, so at the top level only
+                // the types of variables are known.
+                Expr::Identifier(ident) => types.module_var(&ident.node.ident),
+                _ => None,
+            });
+        let Some(ty) = ty else {
+            return Some(Vec::new());
+        };
+        Some(
+            ty.attributes()
+                .into_iter()
+                .map(|(name, member)| CompletionItem {
+                    kind: Some(match &member {
+                        DocMember::Function(_) => CompletionItemKind::METHOD,
+                        DocMember::Property(_) => CompletionItemKind::PROPERTY,
+                    }),
+                    detail: match &member {
+                        DocMember::Property(property) => Some(property.typ.to_string()),
+                        DocMember::Function(_) => None,
+                    },
+                    documentation: Some(Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: render_doc_item_no_link(&name, &DocItem::Member(member)),
+                    })),
+                    label: name,
+                    ..Default::default()
+                })
+                .collect(),
+        )
+    }
+
     pub(crate) fn default_completion_options(
         &self,
         document_uri: &LspUrl,
@@ -124,18 +231,9 @@ impl<T: LspContext> Backend<T> {
         .collect();
 
         // Discover exported symbols from other documents
-        let docs = self.last_valid_parse.read().unwrap();
-        if docs.len() > 1 {
+        if self.parsed_modules().len() > 1 {
             // Find the position of the last load in the current file.
-            let mut last_load = None;
-            let mut loads = HashMap::new();
-            document.ast.statement().visit_stmt(|node| {
-                if let StmtP::Load(load) = &node.node {
-                    last_load = Some(node.span);
-                    loads.insert(load.module.node.clone(), (load.args.clone(), node.span));
-                }
-            });
-            let last_load = last_load.map(|span| document.ast.codemap().resolve_span(span));
+            let (last_load, loads) = document.find_loads();
 
             symbols.extend(
                 self.get_all_exported_symbols(
@@ -15,6 +15,7 @@
  * limitations under the License.
  */
 
+use std::collections::HashMap;
 use std::iter;
 
 use starlark::codemap::CodeMap;
@@ -226,6 +227,9 @@ pub(crate) struct LspModule {
     pub(crate) ast: AstModule,
 }
 
+/// The arguments and location of a `load` statement.
+pub(crate) type LoadStatement = (Vec<LoadArgP<AstNoPayload>>, Span);
+
 impl LspModule {
     pub(crate) fn new(ast: AstModule) -> Self {
         Self { ast }
@@ -444,6 +448,21 @@ impl LspModule {
         self.ast.loaded_symbols()
     }
 
+    /// Find the location of the last `load` statement, and the arguments and location
+    /// of the `load` statement for each module path.
+    pub(crate) fn find_loads(&self) -> (Option<ResolvedSpan>, HashMap<String, LoadStatement>) {
+        let mut last_load = None;
+        let mut loads = HashMap::new();
+        self.ast.statement().visit_stmt(|node| {
+            if let StmtP::Load(load) = &node.node {
+                last_load = Some(node.span);
+                loads.insert(load.module.node.clone(), (load.args.clone(), node.span));
+            }
+        });
+        let last_load = last_load.map(|span| self.ast.codemap().resolve_span(span));
+        (last_load, loads)
+    }
+
     /// Attempt to find an exported symbol with the given name.
     pub(crate) fn find_exported_symbol(&self, name: &str) -> Option<Symbol> {
         self.ast
@@ -33,43 +33,58 @@ use starlark_syntax::syntax::ast::StmtP;
 use starlark_syntax::syntax::def::DefParams;
 
 /// Given the AST node for a `def` statement, return a `DocFunction` if the
-/// `def` statement has a docstring as its first statement.
+/// `def` statement has a docstring as its first statement and valid parameters.
 pub(crate) fn get_doc_item_for_def<P: AstPayload>(
     def: &DefP<P>,
     codemap: &CodeMap,
 ) -> Option<DocFunction> {
-    if let Some(doc_string) = peek_docstring(&def.body) {
-        // TODO(nga): do not unwrap.
-        let def = DefParams::unpack(&def.params, codemap).unwrap();
+    let doc_string = peek_docstring(&def.body)?;
+    doc_function_for_def(def, codemap, Some(doc_string))
+}
 
-        let dp = |i: usize| -> DocParam {
-            let param = &def.params[i];
-            DocParam {
-                name: param.ident.ident.clone(),
-                docs: None,
-                typ: Ty::any(),
-                default_value: None,
-            }
-        };
+/// Given the AST node for a `def` statement, return a `DocFunction` describing
+/// its parameters, whether or not it has a docstring.
+///
+/// Returns `None` if the parameters are invalid, which can happen for modules
+/// parsed with error recovery.
+pub(crate) fn get_signature_for_def<P: AstPayload>(
+    def: &DefP<P>,
+    codemap: &CodeMap,
+) -> Option<DocFunction> {
+    doc_function_for_def(def, codemap, peek_docstring(&def.body))
+}
 
-        let doc_params = DocParams {
-            pos_only: def.indices.pos_only().map(dp).collect(),
-            pos_or_named: def.indices.pos_or_named().map(dp).collect(),
-            args: def.indices.args.map(|a| a as usize).map(dp),
-            named_only: def.indices.named_only(def.params.len()).map(dp).collect(),
-            kwargs: def.indices.kwargs.map(|a| a as usize).map(dp),
-        };
-        let doc_function = DocFunction::from_docstring(
-            DocStringKind::Starlark,
-            doc_params,
-            // TODO: Figure out how to get a `Ty` from the `def.return_type`.
-            Ty::any(),
-            Some(doc_string),
-        );
-        Some(doc_function)
-    } else {
-        None
-    }
+fn doc_function_for_def<P: AstPayload>(
+    def: &DefP<P>,
+    codemap: &CodeMap,
+    doc_string: Option<&str>,
+) -> Option<DocFunction> {
+    let def = DefParams::unpack(&def.params, codemap).ok()?;
+
+    let dp = |i: usize| -> DocParam {
+        let param = &def.params[i];
+        DocParam {
+            name: param.ident.ident.clone(),
+            docs: None,
+            typ: Ty::any(),
+            default_value: None,
+        }
+    };
+
+    let doc_params = DocParams {
+        pos_only: def.indices.pos_only().map(dp).collect(),
+        pos_or_named: def.indices.pos_or_named().map(dp).collect(),
+        args: def.indices.args.map(|a| a as usize).map(dp),
+        named_only: def.indices.named_only(def.params.len()).map(dp).collect(),
+        kwargs: def.indices.kwargs.map(|a| a as usize).map(dp),
+    };
+    Some(DocFunction::from_docstring(
+        DocStringKind::Starlark,
+        doc_params,
+        // TODO: Figure out how to get a `Ty` from the `def.return_type`.
+        Ty::any(),
+        doc_string,
+    ))
 }
 
 pub(crate) fn get_doc_item_for_assign<P: AstPayload>(
@@ -85,7 +100,7 @@ pub(crate) fn get_doc_item_for_assign<P: AstPayload>(
     })
 }
 
-fn peek_docstring<P: AstPayload>(stmt: &AstStmtP<P>) -> Option<&str> {
+pub(crate) fn peek_docstring<P: AstPayload>(stmt: &AstStmtP<P>) -> Option<&str> {
     match &stmt.node {
         StmtP::Statements(stmts) => stmts.first().and_then(peek_docstring),
         StmtP::Expression(expr) => match &expr.node {
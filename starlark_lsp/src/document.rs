@@ -0,0 +1,135 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The text of open documents, kept up to date with incremental changes.
+
+use lsp_types::Position;
+use lsp_types::TextDocumentContentChangeEvent;
+use ropey::Rope;
+
+/// The contents of an open document.
+#[derive(Debug)]
+pub(crate) struct OpenDocument {
+    text: Rope,
+    /// The version of the document from the last change sent by the client.
+    pub(crate) version: i64,
+}
+
+impl OpenDocument {
+    pub(crate) fn new(text: &str, version: i64) -> Self {
+        Self {
+            text: Rope::from_str(text),
+            version,
+        }
+    }
+
+    /// The full text of the document.
+    pub(crate) fn text(&self) -> String {
+        self.text.to_string()
+    }
+
+    /// Apply a change sent by the client. A change without a range replaces the
+    /// whole document.
+    pub(crate) fn apply_change(&mut self, change: TextDocumentContentChangeEvent) {
+        match change.range {
+            None => self.text = Rope::from_str(&change.text),
+            Some(range) => {
+                let start = self.char_index(range.start);
+                let end = self.char_index(range.end).max(start);
+                self.text.remove(start..end);
+                self.text.insert(start, &change.text);
+            }
+        }
+    }
+
+    /// Convert a position, whose `character` counts UTF-16 code units as required by
+    /// the LSP spec, to an index into the characters of the document. Positions past
+    /// the end of a line or of the document are clamped.
+    fn char_index(&self, position: Position) -> usize {
+        let line = position.line as usize;
+        if line >= self.text.len_lines() {
+            return self.text.len_chars();
+        }
+        let line_start = self.text.line_to_char(line);
+        let content = self.text.line(line);
+        let mut line_end = line_start + content.len_chars();
+        // Exclude the line terminator.
+        for c in ['\n', '\r'] {
+            if line_end > line_start && self.text.char(line_end - 1) == c {
+                line_end -= 1;
+            }
+        }
+        let line_start_utf16 = self.text.char_to_utf16_cu(line_start);
+        let line_end_utf16 = self.text.char_to_utf16_cu(line_end);
+        let offset = (line_start_utf16 + position.character as usize).min(line_end_utf16);
+        self.text.utf16_cu_to_char(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Range;
+
+    use super::*;
+
+    fn change(
+        range: Option<((u32, u32), (u32, u32))>,
+        text: &str,
+    ) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: range.map(|(start, end)| {
+                Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+            }),
+            range_length: None,
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn applies_incremental_changes() {
+        let mut document = OpenDocument::new("x = 1\ny = 2\n", 1);
+        document.apply_change(change(Some(((0, 4), (0, 5))), "10"));
+        assert_eq!("x = 10\ny = 2\n", document.text());
+        document.apply_change(change(Some(((1, 0), (1, 0))), "z = 3\n"));
+        assert_eq!("x = 10\nz = 3\ny = 2\n", document.text());
+        document.apply_change(change(Some(((0, 6), (2, 0))), "\n"));
+        assert_eq!("x = 10\ny = 2\n", document.text());
+        document.apply_change(change(None, "w = 4\n"));
+        assert_eq!("w = 4\n", document.text());
+    }
+
+    #[test]
+    fn counts_utf16_code_units() {
+        // `😀` is two UTF-16 code units, but a single character.
+        let mut document = OpenDocument::new("s = \"😀\"\n", 1);
+        document.apply_change(change(Some(((0, 7), (0, 7))), "!"));
+        assert_eq!("s = \"😀!\"\n", document.text());
+        // Positions past the end of a line are clamped to the end of the line.
+        document.apply_change(change(Some(((0, 100), (0, 100))), " # c"));
+        assert_eq!("s = \"😀!\" # c\n", document.text());
+    }
+
+    #[test]
+    fn only_splits_lines_like_lsp() {
+        // LSP lines end at `\n`, `\r\n` or `\r` only, not at Unicode line separators.
+        let mut document = OpenDocument::new("s = \"a\u{2028}b\"\nt = 1\n", 1);
+        document.apply_change(change(Some(((1, 4), (1, 5))), "2"));
+        assert_eq!("s = \"a\u{2028}b\"\nt = 2\n", document.text());
+        document.apply_change(change(Some(((0, 7), (0, 7))), "!"));
+        assert_eq!("s = \"a\u{2028}!b\"\nt = 2\n", document.text());
+    }
+}
@@ -70,6 +70,15 @@ impl From<SymbolKind> for CompletionItemKind {
     }
 }
 
+impl From<SymbolKind> for lsp_types::SymbolKind {
+    fn from(value: SymbolKind) -> Self {
+        match value {
+            SymbolKind::Any => lsp_types::SymbolKind::VARIABLE,
+            SymbolKind::Function { .. } => lsp_types::SymbolKind::FUNCTION,
+        }
+    }
+}
+
 /// A symbol. Returned from [`AstModule::exported_symbols`].
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) struct Symbol {
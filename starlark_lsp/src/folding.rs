@@ -0,0 +1,287 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Folding ranges for blocks, multi-line brackets and comments, and selection ranges
+//! that expand along the AST.
+
+use lsp_types::FoldingRange;
+use lsp_types::FoldingRangeKind;
+use lsp_types::Position;
+use lsp_types::Range;
+use lsp_types::SelectionRange;
+use starlark::codemap::CodeMap;
+use starlark::codemap::Pos;
+use starlark::codemap::Span;
+use starlark_syntax::syntax::ast::AstNoPayload;
+use starlark_syntax::syntax::ast::Expr;
+use starlark_syntax::syntax::ast::Stmt;
+use starlark_syntax::syntax::module::AstModuleFields;
+use starlark_syntax::syntax::uniplate::Visit;
+
+use crate::definition::LspModule;
+
+/// The zero based line of the last non-whitespace character in `span`. The span of a
+/// block includes the blank lines after it.
+fn end_line(codemap: &CodeMap, span: Span) -> usize {
+    let text = codemap.source_span(span);
+    let len = text.trim_end().len() as u32;
+    codemap.find_line(span.begin() + len.max(1) - 1)
+}
+
+struct Folder<'a> {
+    codemap: &'a CodeMap,
+    ranges: Vec<FoldingRange>,
+}
+
+impl Folder<'_> {
+    /// Fold the lines from `begin` to the end of `end`, if there is more than one.
+    fn fold(&mut self, begin: Pos, end: Span, kind: Option<FoldingRangeKind>) {
+        let start_line = self.codemap.find_line(begin);
+        let end_line = end_line(self.codemap, end);
+        if end_line > start_line {
+            self.ranges.push(FoldingRange {
+                start_line: start_line as u32,
+                end_line: end_line as u32,
+                kind,
+                ..FoldingRange::default()
+            });
+        }
+    }
+
+    fn visit(&mut self, node: Visit<AstNoPayload>) {
+        match node {
+            Visit::Stmt(stmt) => match &stmt.node {
+                Stmt::Def(def) => self.fold(stmt.span.begin(), def.body.span, None),
+                Stmt::For(for_) => self.fold(stmt.span.begin(), for_.body.span, None),
+                Stmt::While(while_) => self.fold(stmt.span.begin(), while_.body.span, None),
+                Stmt::If(_, then_block) => self.fold(stmt.span.begin(), then_block.span, None),
+                Stmt::IfElse(_, then_block_else_block) => {
+                    let (then_block, else_block) = &**then_block_else_block;
+                    self.fold(stmt.span.begin(), then_block.span, None);
+                    // An `elif` is a nested `if` statement, which is folded when it is
+                    // visited. Otherwise the `else:` is on the line before the block.
+                    if !matches!(else_block.node, Stmt::If(..) | Stmt::IfElse(..)) {
+                        let else_line = self.codemap.find_line(else_block.span.begin());
+                        if else_line > end_line(self.codemap, then_block.span) + 1 {
+                            let else_span = self.codemap.line_span(else_line - 1);
+                            self.fold(else_span.begin(), else_block.span, None);
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Visit::Expr(expr) => match &expr.node {
+                Expr::Call(..)
+                | Expr::List(_)
+                | Expr::Dict(_)
+                | Expr::Set(_)
+                | Expr::Tuple(_)
+                | Expr::ListComprehension(..)
+                | Expr::DictComprehension(..)
+                | Expr::SetComprehension(..) => self.fold(expr.span.begin(), expr.span, None),
+                _ => {}
+            },
+        }
+        node.visit_children(|x| self.visit(x));
+    }
+
+    /// Fold runs of two or more lines that only contain a comment.
+    fn comments(&mut self) {
+        let mut run: Option<(usize, usize)> = None;
+        let lines = self.codemap.source().lines().map(|line| line.trim_start());
+        for (i, line) in lines.chain(std::iter::once("")).enumerate() {
+            if line.starts_with('#') {
+                run = Some((run.map_or(i, |(start, _)| start), i));
+            } else if let Some((start, end)) = run.take() {
+                if end > start {
+                    self.ranges.push(FoldingRange {
+                        start_line: start as u32,
+                        end_line: end as u32,
+                        kind: Some(FoldingRangeKind::Comment),
+                        ..FoldingRange::default()
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// The spans of the AST nodes that contain `pos`, from the outermost to the innermost.
+fn enclosing_spans(node: Visit<AstNoPayload>, pos: Pos, res: &mut Vec<Span>) {
+    let span = match &node {
+        Visit::Stmt(stmt) => stmt.span,
+        Visit::Expr(expr) => expr.span,
+    };
+    if !span.contains(pos) {
+        return;
+    }
+    if res.last() != Some(&span) {
+        res.push(span);
+    }
+    if let Visit::Stmt(stmt) = &node {
+        if let Stmt::Def(def) = &stmt.node {
+            if def.name.span.contains(pos) {
+                res.push(def.name.span);
+                return;
+            }
+        }
+    }
+    let mut found = false;
+    node.visit_children(|x| {
+        if !found {
+            let len = res.len();
+            enclosing_spans(x, pos, res);
+            found = res.len() > len;
+        }
+    });
+}
+
+impl LspModule {
+    /// The ranges of lines that can be folded: the bodies of `def`, `if` and `for`
+    /// statements, calls and collection literals that span multiple lines, and
+    /// blocks of comments. The result is sorted by the first line.
+    pub(crate) fn folding_ranges(&self) -> Vec<FoldingRange> {
+        let mut folder = Folder {
+            codemap: self.ast.codemap(),
+            ranges: Vec::new(),
+        };
+        folder.visit(Visit::Stmt(self.ast.statement()));
+        folder.comments();
+        let mut ranges = folder.ranges;
+        ranges.sort_by_key(|range| (range.start_line, std::cmp::Reverse(range.end_line)));
+        ranges.dedup_by_key(|range| (range.start_line, range.end_line));
+        ranges
+    }
+
+    /// The selection range at `position`: the innermost AST node containing it, with
+    /// each enclosing node as a parent. Returns `None` if the position is outside the
+    /// module.
+    pub(crate) fn selection_range(&self, position: Position) -> Option<SelectionRange> {
+        let codemap = self.ast.codemap();
+        let line_span = codemap.line_span_opt(position.line as usize)?;
+        let pos = std::cmp::min(line_span.begin() + position.character, line_span.end());
+        let mut spans = Vec::new();
+        enclosing_spans(Visit::Stmt(self.ast.statement()), pos, &mut spans);
+        if spans.is_empty() {
+            // Whitespace outside any statement still selects the whole module.
+            spans.push(codemap.full_span());
+        }
+        spans.into_iter().fold(None, |parent, span| {
+            Some(SelectionRange {
+                range: Range::from(codemap.resolve_span(span)),
+                parent: parent.map(Box::new),
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use textwrap::dedent;
+
+    use super::*;
+    use crate::definition::helpers::FixtureWithRanges;
+
+    fn lines(ranges: &[FoldingRange]) -> Vec<(u32, u32, Option<FoldingRangeKind>)> {
+        ranges
+            .iter()
+            .map(|range| (range.start_line, range.end_line, range.kind.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn folds_blocks_brackets_and_comments() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            # A comment
+            # over two lines.
+            def f(x):
+                if x:
+                    return [
+                        1,
+                        2,
+                    ]
+                else:
+                    pass
+                    return None
+
+            y = f(
+                1,
+            )
+            z = {"a": 1}
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = parsed.module().unwrap();
+        assert_eq!(
+            vec![
+                (1, 2, Some(FoldingRangeKind::Comment)),
+                (3, 11, None),
+                (4, 8, None),
+                (5, 8, None),
+                (9, 11, None),
+                (13, 15, None),
+            ],
+            lines(&module.folding_ranges())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn selects_enclosing_nodes() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            def f(x):
+                return <call>g(<arg>x</arg> + 1)</call>
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = parsed.module().unwrap();
+        let arg = parsed.resolved_span("arg");
+        let call = parsed.resolved_span("call");
+
+        let mut ranges = Vec::new();
+        let mut selection = module.selection_range(Position::new(
+            arg.begin.line as u32,
+            arg.begin.column as u32,
+        ));
+        while let Some(x) = selection {
+            ranges.push(x.range);
+            selection = x.parent.map(|x| *x);
+        }
+        let program = parsed.program();
+        let lines: Vec<&str> = program.lines().collect();
+        let texts: Vec<String> = ranges
+            .iter()
+            .map(|range| {
+                let start = range.start.character as usize;
+                let end = range.end.character as usize;
+                if range.start.line == range.end.line {
+                    lines[range.start.line as usize][start..end].to_owned()
+                } else {
+                    format!("{}..", &lines[range.start.line as usize][start..])
+                }
+            })
+            .collect();
+        assert_eq!(
+            vec!["x", "x + 1", "g(x + 1)", "return g(x + 1)", "def f(x):.."],
+            texts
+        );
+        assert_eq!(Range::from(call), ranges[2]);
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Formatting of whole documents, and of the top level statements in a range.
+
+use lsp_types::DocumentFormattingParams;
+use lsp_types::DocumentRangeFormattingParams;
+use lsp_types::Position;
+use lsp_types::Range;
+use lsp_types::TextEdit;
+use starlark::codemap::CodeMap;
+use starlark::codemap::Pos;
+use starlark::codemap::Span;
+use starlark_syntax::syntax::cst::CstModule;
+use starlark_syntax::syntax::format::format;
+use starlark_syntax::syntax::format::format_range;
+
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+/// The position in `codemap` of an LSP position, clamped to the end of its line.
+fn pos(codemap: &CodeMap, position: Position) -> Pos {
+    match codemap.line_span_opt(position.line as usize) {
+        Some(line) => std::cmp::min(line.begin() + position.character, line.end()),
+        None => codemap.full_span().end(),
+    }
+}
+
+/// An edit that replaces `span` with `text`, or no edit if that changes nothing.
+fn edits(codemap: &CodeMap, span: Span, text: String) -> Vec<TextEdit> {
+    if codemap.source_span(span) == text {
+        return Vec::new();
+    }
+    vec![TextEdit {
+        range: Range::from(codemap.resolve_span(span)),
+        new_text: text,
+    }]
+}
+
+impl<T: LspContext> Backend<T> {
+    /// Parse an open document for formatting. Returns `None` if it is not open or
+    /// does not parse, since only valid code can be formatted.
+    fn parse_for_formatting(&self, uri: &LspUrl) -> Option<CstModule> {
+        let text = self.documents.read().unwrap().get(uri)?.text();
+        CstModule::parse(&uri.to_string(), text, &self.context.get_dialect(uri)).ok()
+    }
+
+    pub(crate) fn format_document(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> anyhow::Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri.try_into()?;
+        let Some(module) = self.parse_for_formatting(&uri) else {
+            return Ok(None);
+        };
+        let codemap = module.codemap();
+        Ok(Some(edits(codemap, codemap.full_span(), format(&module))))
+    }
+
+    pub(crate) fn format_document_range(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> anyhow::Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri.try_into()?;
+        let Some(module) = self.parse_for_formatting(&uri) else {
+            return Ok(None);
+        };
+        let codemap = module.codemap();
+        let span = Span::new(
+            pos(codemap, params.range.start),
+            pos(codemap, params.range.end),
+        );
+        Ok(Some(match format_range(&module, span) {
+            Some((span, text)) => edits(codemap, span, text),
+            None => Vec::new(),
+        }))
+    }
+}
@@ -0,0 +1,309 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Background indexing of the files in the workspace, so that the symbols they export
+//! are known before the files are opened.
+//!
+//! A thread finds and reads the files, and the main loop parses them when it has no
+//! messages from the client to handle.
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+use crossbeam_channel::Receiver;
+use lsp_server::Message;
+use lsp_server::Request;
+use lsp_server::RequestId;
+use lsp_types::notification::Progress;
+use lsp_types::request::WorkDoneProgressCreate;
+use lsp_types::InitializeParams;
+use lsp_types::MessageType;
+use lsp_types::NumberOrString;
+use lsp_types::ProgressParams;
+use lsp_types::ProgressParamsValue;
+use lsp_types::ProgressToken;
+use lsp_types::WorkDoneProgress;
+use lsp_types::WorkDoneProgressBegin;
+use lsp_types::WorkDoneProgressCreateParams;
+use lsp_types::WorkDoneProgressEnd;
+use lsp_types::WorkDoneProgressReport;
+
+use crate::definition::LspModule;
+use crate::server::new_notification;
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+/// The token used for the progress notifications sent while indexing.
+const PROGRESS_TOKEN: &str = "starlark/index";
+
+/// What the indexing thread sends to the main loop.
+pub(crate) enum IndexEvent {
+    /// The number of files that were found to index.
+    Found(usize),
+    /// The contents of a file to index.
+    File(LspUrl, String),
+}
+
+/// The state of the indexing of the workspace.
+pub(crate) struct Indexer {
+    /// The events sent by the indexing thread. Disconnected once every file is sent.
+    pub(crate) events: Receiver<IndexEvent>,
+    /// The progress token, if the client supports progress notifications.
+    token: Option<ProgressToken>,
+    total: usize,
+    indexed: usize,
+}
+
+/// Find the files under `dir` whose extension is one of `extensions`. Hidden
+/// directories, like `.git`, and symbolic links to directories are skipped.
+fn find_files(dir: &Path, extensions: &[String], res: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.filter_map(Result::ok).collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                find_files(&path, extensions, res);
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| extensions.iter().any(|x| ext == x.as_str()))
+        {
+            res.push(path);
+        }
+    }
+}
+
+/// Start a thread that finds the files to index under `roots`, and sends their contents.
+fn spawn_indexer(roots: Vec<PathBuf>, extensions: Vec<String>) -> Receiver<IndexEvent> {
+    // The thread only reads ahead a little of the main loop, which parses the files.
+    let (sender, receiver) = crossbeam_channel::bounded(16);
+    thread::spawn(move || {
+        let mut files = Vec::new();
+        for root in &roots {
+            find_files(root, &extensions, &mut files);
+        }
+        if sender.send(IndexEvent::Found(files.len())).is_err() {
+            return;
+        }
+        for file in files {
+            let contents = fs::read_to_string(&file).unwrap_or_default();
+            if sender
+                .send(IndexEvent::File(LspUrl::File(file), contents))
+                .is_err()
+            {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+impl<T: LspContext> Backend<T> {
+    /// Start indexing the workspace folders, if any extensions to index are configured.
+    pub(crate) fn start_indexing(&self, initialize_params: &InitializeParams) -> Option<Indexer> {
+        if self.settings.index_extensions.is_empty() {
+            return None;
+        }
+        #[allow(deprecated)] // `root_uri` is used by clients without workspace folders.
+        let roots: Vec<PathBuf> = match &initialize_params.workspace_folders {
+            Some(folders) => folders
+                .iter()
+                .filter_map(|folder| folder.uri.to_file_path().ok())
+                .collect(),
+            None => initialize_params
+                .root_uri
+                .iter()
+                .filter_map(|uri| uri.to_file_path().ok())
+                .collect(),
+        };
+        if roots.is_empty() {
+            return None;
+        }
+
+        let supports_progress = initialize_params
+            .capabilities
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false);
+        let token = supports_progress.then(|| {
+            let token = NumberOrString::String(PROGRESS_TOKEN.to_owned());
+            self.connection
+                .sender
+                .send(Message::Request(Request::new(
+                    RequestId::from(PROGRESS_TOKEN.to_owned()),
+                    <WorkDoneProgressCreate as lsp_types::request::Request>::METHOD.to_owned(),
+                    WorkDoneProgressCreateParams {
+                        token: token.clone(),
+                    },
+                )))
+                .unwrap();
+            token
+        });
+
+        Some(Indexer {
+            events: spawn_indexer(roots, self.settings.index_extensions.clone()),
+            token,
+            total: 0,
+            indexed: 0,
+        })
+    }
+
+    fn send_progress(&self, indexer: &Indexer, progress: WorkDoneProgress) {
+        if let Some(token) = &indexer.token {
+            self.send_notification(new_notification::<Progress>(ProgressParams {
+                token: token.clone(),
+                value: ProgressParamsValue::WorkDone(progress),
+            }));
+        }
+    }
+
+    /// Handle an event from the indexing thread.
+    pub(crate) fn handle_index_event(&self, indexer: &mut Indexer, event: IndexEvent) {
+        match event {
+            IndexEvent::Found(total) => {
+                indexer.total = total;
+                self.send_progress(
+                    indexer,
+                    WorkDoneProgress::Begin(WorkDoneProgressBegin {
+                        title: "Indexing".to_owned(),
+                        cancellable: Some(false),
+                        message: Some(format!("0/{total} files")),
+                        percentage: Some(0),
+                    }),
+                );
+            }
+            IndexEvent::File(uri, contents) => {
+                self.index_file(uri, contents);
+                let old_percentage = indexer.indexed * 100 / indexer.total.max(1);
+                indexer.indexed += 1;
+                let percentage = indexer.indexed * 100 / indexer.total.max(1);
+                if percentage != old_percentage {
+                    self.send_progress(
+                        indexer,
+                        WorkDoneProgress::Report(WorkDoneProgressReport {
+                            cancellable: Some(false),
+                            message: Some(format!("{}/{} files", indexer.indexed, indexer.total)),
+                            percentage: Some(percentage as u32),
+                        }),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Report that every file in the workspace has been indexed.
+    pub(crate) fn finish_indexing(&self, indexer: &Indexer) {
+        self.send_progress(
+            indexer,
+            WorkDoneProgress::End(WorkDoneProgressEnd { message: None }),
+        );
+        self.log_message(
+            MessageType::INFO,
+            &format!("Indexed {} files in the workspace", indexer.indexed),
+        );
+    }
+
+    /// Parse a file and add it to the workspace index. Files that do not parse are
+    /// left out of the index.
+    fn index_file(&self, uri: LspUrl, contents: String) {
+        let eval_result = self.context.parse_file_with_contents(&uri, contents);
+        if let Some(ast) = eval_result.ast {
+            self.workspace_index
+                .write()
+                .unwrap()
+                .insert(uri, Arc::new(LspModule::new(ast)));
+        }
+    }
+
+    /// Update the index of a file after it is closed, since its contents on disk may
+    /// have changed while it was open.
+    pub(crate) fn reindex_file(&self, uri: &LspUrl) -> anyhow::Result<()> {
+        if !self.workspace_index.read().unwrap().contains_key(uri) {
+            return Ok(());
+        }
+        let module = self
+            .context
+            .parse_file(uri)?
+            .and_then(|eval_result| eval_result.ast);
+        let mut workspace_index = self.workspace_index.write().unwrap();
+        match module {
+            Some(ast) => workspace_index.insert(uri.clone(), Arc::new(LspModule::new(ast))),
+            None => workspace_index.remove(uri),
+        };
+        Ok(())
+    }
+
+    /// Every module the server has parsed: the open files, and the files in the
+    /// workspace index that are not open. Sorted by URL.
+    pub(crate) fn parsed_modules(&self) -> Vec<(LspUrl, Arc<LspModule>)> {
+        let last_valid_parse = self.last_valid_parse.read().unwrap();
+        let workspace_index = self.workspace_index.read().unwrap();
+        let mut modules: Vec<(LspUrl, Arc<LspModule>)> = last_valid_parse
+            .iter()
+            .chain(
+                workspace_index
+                    .iter()
+                    .filter(|(uri, _)| !last_valid_parse.contains_key(*uri)),
+            )
+            .map(|(uri, module)| (uri.clone(), module.clone()))
+            .collect();
+        modules.sort_by_key(|(uri, _)| uri.to_string());
+        modules
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_files_with_extensions() -> anyhow::Result<()> {
+        let root =
+            std::env::temp_dir().join(format!("starlark_lsp_find_files_{}", std::process::id()));
+        let _ignored = fs::remove_dir_all(&root);
+        for file in ["a.star", "b.bzl", "c.txt", "sub/d.star", ".hidden/e.star"] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, "")?;
+        }
+
+        let mut files = Vec::new();
+        find_files(&root, &["star".to_owned(), "bzl".to_owned()], &mut files);
+        fs::remove_dir_all(&root)?;
+        assert_eq!(
+            vec![
+                root.join("a.star"),
+                root.join("b.bzl"),
+                root.join("sub").join("d.star"),
+            ],
+            files
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Inlay hints for the types inferred by the typechecker, and for the names of
+//! the parameters that positional arguments are bound to.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use lsp_types::InlayHint;
+use lsp_types::InlayHintKind;
+use lsp_types::InlayHintLabel;
+use lsp_types::Position;
+use lsp_types::Range;
+use starlark::codemap::CodeMap;
+use starlark::codemap::Pos;
+use starlark::codemap::ResolvedSpan;
+use starlark::codemap::Span;
+use starlark::docs::DocFunction;
+use starlark::typing::Ty;
+use starlark::typing::TypeMap;
+use starlark_syntax::syntax::ast::ArgumentP;
+use starlark_syntax::syntax::ast::AssignP;
+use starlark_syntax::syntax::ast::AstStmt;
+use starlark_syntax::syntax::ast::Expr;
+use starlark_syntax::syntax::ast::Stmt;
+use starlark_syntax::syntax::module::AstModuleFields;
+
+use crate::definition::LspModule;
+use crate::signature::visit_exprs;
+
+/// A call to a named function with positional arguments.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct PositionalArguments {
+    /// The location of the function name in the call.
+    pub(crate) callee: ResolvedSpan,
+    /// The location of each positional argument, and the argument itself if it is a
+    /// plain identifier. Arguments after a `*args` argument are not included, since
+    /// it is not known which parameters they are bound to.
+    pub(crate) args: Vec<(ResolvedSpan, Option<String>)>,
+}
+
+fn position(codemap: &CodeMap, pos: Pos) -> Position {
+    Range::from(codemap.resolve_span(Span::new(pos, pos))).start
+}
+
+fn type_hint(codemap: &CodeMap, pos: Pos, label: String) -> InlayHint {
+    InlayHint {
+        position: position(codemap, pos),
+        label: InlayHintLabel::String(label),
+        kind: Some(InlayHintKind::TYPE),
+        text_edits: None,
+        tooltip: None,
+        padding_left: None,
+        padding_right: None,
+        data: None,
+    }
+}
+
+/// Whether a type is worth showing, i.e. it says something about the value.
+fn is_informative(ty: &Ty) -> bool {
+    *ty != Ty::any() && *ty != Ty::never()
+}
+
+/// Whether running `stmt` always ends in a `return`, as opposed to falling off the
+/// end of a function and returning `None`.
+fn always_returns(stmt: &AstStmt) -> bool {
+    match &stmt.node {
+        Stmt::Return(_) => true,
+        Stmt::Statements(stmts) => stmts.last().is_some_and(always_returns),
+        Stmt::IfElse(_, branches) => always_returns(&branches.0) && always_returns(&branches.1),
+        _ => false,
+    }
+}
+
+/// The spans of the `return` statements of a function, not including those of nested functions.
+fn return_spans(stmt: &AstStmt, res: &mut Vec<Span>) {
+    match &stmt.node {
+        Stmt::Return(_) => res.push(stmt.span),
+        Stmt::Def(_) => {}
+        _ => stmt.visit_stmt(|x| return_spans(x, res)),
+    }
+}
+
+/// The position just after the closing parenthesis of the parameters of a `def`,
+/// found by skipping past the last parameter.
+fn after_params(codemap: &CodeMap, signature: Span) -> Option<Pos> {
+    let source = codemap.source();
+    let rest = source.get(signature.end().get() as usize..)?;
+    let offset = rest.find(|c: char| !c.is_whitespace() && c != ',' && c != '(')?;
+    if rest[offset..].starts_with(')') {
+        Some(signature.end() + (offset + 1) as u32)
+    } else {
+        None
+    }
+}
+
+impl LspModule {
+    /// Hints for the inferred types of variables assigned without a type annotation,
+    /// and the inferred return types of functions without a return type annotation.
+    ///
+    /// Only types that carry some information are shown, not `typing.Any`. Top-level
+    /// code is not typechecked, so top-level variables only get a hint when their type
+    /// is known without typechecking, e.g. when they are assigned a function.
+    pub(crate) fn type_hints(&self, types: &TypeMap) -> Vec<InlayHint> {
+        let codemap = self.ast.codemap();
+        let locals: HashMap<Span, &Ty> = types.bindings().map(|(_, span, ty)| (span, ty)).collect();
+        let mut seen_module_vars = HashSet::new();
+        let mut res = Vec::new();
+
+        fn walk<'a>(stmt: &'a AstStmt, in_def: bool, f: &mut impl FnMut(&'a AstStmt, bool)) {
+            f(stmt, in_def);
+            let in_def = in_def || matches!(stmt.node, Stmt::Def(_));
+            stmt.visit_stmt(|x| walk(x, in_def, f));
+        }
+
+        walk(
+            self.ast.statement(),
+            false,
+            &mut |stmt, in_def| match &stmt.node {
+                Stmt::Assign(AssignP { lhs, ty: None, .. }) => lhs.visit_lvalue(|ident| {
+                    let ty = if in_def {
+                        locals.get(&ident.span).copied()
+                    } else if seen_module_vars.insert(ident.ident.as_str()) {
+                        types.module_var(&ident.ident)
+                    } else {
+                        None
+                    };
+                    if let Some(ty) = ty.filter(|ty| is_informative(ty)) {
+                        res.push(type_hint(codemap, ident.span.end(), format!(": {}", ty)));
+                    }
+                }),
+                Stmt::Def(def) if def.return_type.is_none() => {
+                    let mut spans = Vec::new();
+                    return_spans(&def.body, &mut spans);
+                    let mut returned: Vec<Ty> = spans
+                        .iter()
+                        .filter_map(|span| types.return_type(*span).cloned())
+                        .collect();
+                    if !always_returns(&def.body) {
+                        returned.push(Ty::none());
+                    }
+                    let ty = Ty::unions(returned);
+                    if let (true, Some(pos)) = (
+                        is_informative(&ty),
+                        after_params(codemap, def.signature_span()),
+                    ) {
+                        res.push(type_hint(codemap, pos, format!(" -> {}", ty)));
+                    }
+                }
+                _ => {}
+            },
+        );
+        res
+    }
+
+    /// Find all the calls to named functions that have positional arguments.
+    pub(crate) fn find_positional_arguments(&self) -> Vec<PositionalArguments> {
+        let codemap = self.ast.codemap();
+        let mut res = Vec::new();
+        self.ast.statement().visit_expr(|expr| {
+            visit_exprs(expr, &mut |x| {
+                if let Expr::Call(callee, args) = &x.node {
+                    if let Expr::Identifier(_) = &callee.node {
+                        let args: Vec<_> = args
+                            .args
+                            .iter()
+                            .take_while(|arg| !matches!(arg.node, ArgumentP::Args(_)))
+                            .filter_map(|arg| match &arg.node {
+                                ArgumentP::Positional(e) => Some((
+                                    codemap.resolve_span(e.span),
+                                    match &e.node {
+                                        Expr::Identifier(ident) => Some(ident.node.ident.clone()),
+                                        _ => None,
+                                    },
+                                )),
+                                _ => None,
+                            })
+                            .collect();
+                        if !args.is_empty() {
+                            res.push(PositionalArguments {
+                                callee: codemap.resolve_span(callee.span),
+                                args,
+                            });
+                        }
+                    }
+                }
+            })
+        });
+        res
+    }
+}
+
+/// Hints for the names of the parameters that the positional arguments of a call to
+/// `function` are bound to. Arguments that are just a variable with the same name as
+/// the parameter do not get a hint.
+pub(crate) fn parameter_name_hints(
+    function: &DocFunction,
+    call: &PositionalArguments,
+) -> Vec<InlayHint> {
+    let params = function
+        .params
+        .pos_only
+        .iter()
+        .chain(&function.params.pos_or_named);
+    params
+        .zip(&call.args)
+        .filter(|(param, (_, ident))| ident.as_deref() != Some(param.name.as_str()))
+        .map(|(param, (span, _))| InlayHint {
+            position: Range::from(*span).start,
+            label: InlayHintLabel::String(format!("{}:", param.name)),
+            kind: Some(InlayHintKind::PARAMETER),
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: Some(true),
+            data: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use starlark::environment::Globals;
+    use starlark::typing::AstModuleTypecheck;
+    use textwrap::dedent;
+
+    use super::*;
+    use crate::definition::helpers::FixtureWithRanges;
+
+    fn labels(hints: &[InlayHint]) -> Vec<(Position, String)> {
+        hints
+            .iter()
+            .map(|hint| match &hint.label {
+                InlayHintLabel::String(s) => (hint.position, s.clone()),
+                InlayHintLabel::LabelParts(_) => panic!("Unexpected label parts"),
+            })
+            .collect()
+    }
+
+    fn end(parsed: &FixtureWithRanges, name: &str) -> Position {
+        Range::from(parsed.resolved_span(name)).end
+    }
+
+    #[test]
+    fn type_hints() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            x = 1
+            y: str = "y"
+            def f(a<f>)</f>:
+                <b>b</b> = [a]
+                c, <d>d</d> = a, "d"
+                if a:
+                    return len(b)
+            def g(<g>)</g>:
+                return
+            def h(a: int) -> int:
+                return a
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = parsed.module().unwrap();
+        let (_, types, ..) = module
+            .ast
+            .clone()
+            .typecheck(&Globals::standard(), &HashMap::new());
+
+        let mut hints = labels(&module.type_hints(&types));
+        hints.sort();
+        assert_eq!(
+            vec![
+                (end(&parsed, "f"), " -> None | int".to_owned()),
+                (end(&parsed, "b"), ": list".to_owned()),
+                (end(&parsed, "d"), ": str".to_owned()),
+                (end(&parsed, "g"), " -> None".to_owned()),
+            ],
+            hints
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parameter_name_hints_for_positional_arguments() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            def f(a, b, c = 1):
+                return a + b + c
+            b = 1
+            f(<a>1</a>, b, c = 2)
+            f(*[])
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = parsed.module().unwrap();
+        let calls = module.find_positional_arguments();
+        assert_eq!(1, calls.len());
+
+        let function = module
+            .find_def_signature(module.find_exported_symbol_span("f").unwrap())
+            .unwrap();
+        let hints = labels(&parameter_name_hints(&function, &calls[0]));
+        assert_eq!(
+            vec![(
+                Range::from(parsed.resolved_span("a")).start,
+                "a:".to_owned()
+            )],
+            hints
+        );
+        Ok(())
+    }
+}
@@ -22,14 +22,25 @@
 #[allow(clippy::needless_lifetimes)]
 #[allow(clippy::type_complexity)]
 mod bind;
+mod call_hierarchy;
+mod code_actions;
 pub mod completion;
 mod definition;
 pub(crate) mod docs;
+mod document;
 pub mod error;
 mod exported;
+mod folding;
+mod formatting;
+mod index;
+mod inlay_hints;
 pub(crate) mod inspect;
 pub(crate) mod loaded;
+mod references;
+mod semantic_tokens;
 pub mod server;
+mod signature;
 mod symbols;
 #[cfg(all(test, not(windows)))]
 mod test;
+mod typecheck;
@@ -0,0 +1,344 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Finding the uses of a variable within a module, based on the scopes from [`crate::bind`].
+
+use starlark::codemap::Pos;
+use starlark::codemap::Span;
+use starlark_syntax::syntax::ast::AstString;
+use starlark_syntax::syntax::module::AstModuleFields;
+
+use crate::bind::scope;
+use crate::bind::Assigner;
+use crate::bind::Bind;
+use crate::bind::Scope;
+use crate::definition::LspModule;
+
+/// A variable binding: the place where a name is first assigned in the scope that owns it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct Binding {
+    /// The name of the variable.
+    pub(crate) name: String,
+    /// The location of the binding, as recorded in [`Scope::bound`].
+    pub(crate) span: Span,
+    /// How the variable was bound.
+    pub(crate) assigner: Assigner,
+    /// Whether the variable is bound at the top level of the module.
+    pub(crate) top_level: bool,
+}
+
+/// Whether a reference to a variable reads or assigns it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum Access {
+    Write,
+    Read,
+}
+
+/// The span of the contents of a string literal, without its quotes.
+///
+/// Used for load statements like `load("foo.star", "bar")`, where the symbol `bar`
+/// is bound by the string itself.
+pub(crate) fn string_contents_span(s: &AstString) -> Span {
+    if s.span.end() > s.span.begin() + 1 {
+        Span::new(s.span.begin() + 1, s.span.end() - 1)
+    } else {
+        s.span
+    }
+}
+
+/// The span to report for a use of a variable that is bound by `Bind::Set`.
+pub(crate) fn set_span(assigner: &Assigner, local: Span) -> Span {
+    match assigner {
+        Assigner::Load { name, .. } if name.span == local => string_contents_span(name),
+        _ => local,
+    }
+}
+
+/// Find the binding for `name`, looking in the innermost scope first.
+pub(crate) fn resolve(scopes: &[&Scope], name: &str) -> Option<Binding> {
+    scopes.iter().enumerate().rev().find_map(|(depth, scope)| {
+        scope.bound.get(name).map(|(assigner, span)| Binding {
+            name: name.to_owned(),
+            span: *span,
+            assigner: assigner.clone(),
+            top_level: depth == 0,
+        })
+    })
+}
+
+fn binding_at<'a>(scopes: &mut Vec<&'a Scope>, scope: &'a Scope, pos: Pos) -> Option<Binding> {
+    scopes.push(scope);
+    let mut res = None;
+    for bind in &scope.inner {
+        res = match bind {
+            Bind::Set(assigner, local) => {
+                let in_load_name =
+                    matches!(assigner, Assigner::Load { name, .. } if name.span.contains(pos));
+                if local.span.contains(pos) || in_load_name {
+                    resolve(scopes, &local.ident)
+                } else {
+                    None
+                }
+            }
+            Bind::Get(x) if x.span.contains(pos) => resolve(scopes, &x.node.ident),
+            Bind::GetDotted(x) if x.variable.span.contains(pos) => {
+                resolve(scopes, &x.variable.node.ident)
+            }
+            Bind::Scope(inner) => binding_at(scopes, inner, pos),
+            Bind::Get(_) | Bind::GetDotted(_) | Bind::Flow => None,
+        };
+        if res.is_some() {
+            break;
+        }
+    }
+    scopes.pop();
+    res
+}
+
+fn references_in_scope<'a>(
+    scopes: &mut Vec<&'a Scope>,
+    scope: &'a Scope,
+    binding: &Binding,
+    res: &mut Vec<(Span, Access)>,
+) {
+    scopes.push(scope);
+    let refers_to_binding =
+        |scopes: &[&Scope]| resolve(scopes, &binding.name).map(|b| b.span) == Some(binding.span);
+    for bind in &scope.inner {
+        match bind {
+            Bind::Set(assigner, local)
+                if local.ident == binding.name && refers_to_binding(scopes) =>
+            {
+                res.push((set_span(assigner, local.span), Access::Write))
+            }
+            Bind::Get(x) if x.node.ident == binding.name && refers_to_binding(scopes) => {
+                res.push((x.span, Access::Read))
+            }
+            Bind::GetDotted(x)
+                if x.variable.node.ident == binding.name && refers_to_binding(scopes) =>
+            {
+                res.push((x.variable.span, Access::Read))
+            }
+            Bind::Scope(inner) => references_in_scope(scopes, inner, binding, res),
+            Bind::Set(..) | Bind::Get(_) | Bind::GetDotted(_) | Bind::Flow => {}
+        }
+    }
+    scopes.pop();
+}
+
+fn name_used_where_visible<'a>(
+    scopes: &mut Vec<&'a Scope>,
+    scope: &'a Scope,
+    binding: &Binding,
+    name: &str,
+) -> bool {
+    scopes.push(scope);
+    let visible = resolve(scopes, &binding.name).map(|b| b.span) == Some(binding.span);
+    let res = scope.inner.iter().any(|bind| match bind {
+        Bind::Set(_, x) => visible && x.ident == name,
+        Bind::Get(x) => visible && x.node.ident == name,
+        Bind::GetDotted(x) => visible && x.variable.node.ident == name,
+        Bind::Scope(inner) => name_used_where_visible(scopes, inner, binding, name),
+        Bind::Flow => false,
+    });
+    scopes.pop();
+    res
+}
+
+impl LspModule {
+    /// Find the binding of the variable at the given location. The location can be either
+    /// a use of the variable, or a place it is assigned.
+    ///
+    /// `line` and `col` are zero based. Returns `None` if there is no variable at the
+    /// location, or it is not bound in this module (e.g. it is a global).
+    pub(crate) fn find_binding_at_location(&self, line: u32, col: u32) -> Option<Binding> {
+        let line_span = self.ast.codemap().line_span_opt(line as usize)?;
+        let pos = std::cmp::min(line_span.begin() + col, line_span.end());
+        binding_at(&mut Vec::new(), &scope(&self.ast), pos)
+    }
+
+    /// Find the binding of a variable with the given name at the top level of the module.
+    pub(crate) fn find_top_level_binding(&self, name: &str) -> Option<Binding> {
+        resolve(&[&scope(&self.ast)], name)
+    }
+
+    /// Get the bindings created by the top level `load()` statements of this module.
+    pub(crate) fn find_load_bindings(&self) -> Vec<Binding> {
+        let scope = scope(&self.ast);
+        scope
+            .inner
+            .iter()
+            .filter_map(|bind| match bind {
+                Bind::Set(assigner @ Assigner::Load { .. }, local) => Some(Binding {
+                    name: local.ident.clone(),
+                    span: local.span,
+                    assigner: assigner.clone(),
+                    top_level: true,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether renaming `binding` to `new_name` could change the meaning of the module,
+    /// because `new_name` is already bound or used somewhere `binding` is visible.
+    pub(crate) fn rename_collides(&self, binding: &Binding, new_name: &str) -> bool {
+        name_used_where_visible(&mut Vec::new(), &scope(&self.ast), binding, new_name)
+    }
+
+    /// Find all the places in this module that refer to `binding`, including
+    /// the places it is assigned to. The result is in source order.
+    pub(crate) fn find_references(&self, binding: &Binding) -> Vec<Span> {
+        self.find_accesses(binding)
+            .into_iter()
+            .map(|(span, _)| span)
+            .collect()
+    }
+
+    /// Like [`LspModule::find_references`], but also says whether each reference
+    /// reads or assigns the variable.
+    pub(crate) fn find_accesses(&self, binding: &Binding) -> Vec<(Span, Access)> {
+        let mut res = Vec::new();
+        references_in_scope(&mut Vec::new(), &scope(&self.ast), binding, &mut res);
+        // A variable that is both read and assigned by `x += 1` is reported once,
+        // as an assignment.
+        res.sort_by_key(|(span, access)| (span.begin(), *access));
+        res.dedup_by_key(|(span, _)| *span);
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use textwrap::dedent;
+
+    use super::*;
+    use crate::definition::helpers::FixtureWithRanges;
+
+    fn references_at(parsed: &FixtureWithRanges, name: &str) -> Vec<String> {
+        let module = parsed.module().unwrap();
+        let binding = module
+            .find_binding_at_location(parsed.begin_line(name), parsed.begin_column(name))
+            .unwrap();
+        module
+            .find_references(&binding)
+            .into_iter()
+            .map(|span| module.ast.codemap().resolve_span(span).to_string())
+            .collect()
+    }
+
+    fn spans(parsed: &FixtureWithRanges, names: &[&str]) -> Vec<String> {
+        names
+            .iter()
+            .map(|name| parsed.resolved_span(name).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn finds_references_respecting_scopes() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            <x1>x</x1> = 1
+            def f(<x2>x</x2>):
+                return <x3>x</x3> + 1
+            def g():
+                return [<x4>x</x4> for <x5>x</x5> in [<x6>x</x6>]]
+            <x7>x</x7> += f(<x8>x</x8>)
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+
+        let globals = spans(&parsed, &["x1", "x6", "x7", "x8"]);
+        assert_eq!(globals, references_at(&parsed, "x1"));
+        assert_eq!(globals, references_at(&parsed, "x6"));
+        assert_eq!(spans(&parsed, &["x2", "x3"]), references_at(&parsed, "x3"));
+        assert_eq!(spans(&parsed, &["x4", "x5"]), references_at(&parsed, "x4"));
+
+        let module = parsed.module().unwrap();
+        let binding = module
+            .find_binding_at_location(parsed.begin_line("x1"), parsed.begin_column("x1"))
+            .unwrap();
+        let accesses: Vec<Access> = module
+            .find_accesses(&binding)
+            .into_iter()
+            .map(|(_, access)| access)
+            .collect();
+        assert_eq!(
+            vec![Access::Write, Access::Read, Access::Write, Access::Read],
+            accesses
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn finds_references_to_loaded_symbols() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            load("foo.star", "<a1>a</a1>", <b1>b</b1> = "c")
+            <a2>a</a2>(<b2>b</b2>.field)
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+
+        assert_eq!(spans(&parsed, &["a1", "a2"]), references_at(&parsed, "a2"));
+        assert_eq!(spans(&parsed, &["b1", "b2"]), references_at(&parsed, "b1"));
+
+        let module = parsed.module().unwrap();
+        let loads = module.find_load_bindings();
+        assert_eq!(
+            vec!["a", "b"],
+            loads.iter().map(|b| b.name.as_str()).collect::<Vec<_>>()
+        );
+        assert!(loads.iter().all(|b| b.top_level));
+        Ok(())
+    }
+
+    #[test]
+    fn detects_rename_collisions() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            <x>x</x> = 1
+            def f(<y>y</y>):
+                z = 2
+                return y + z
+            def g(z):
+                return x + z + w
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = parsed.module().unwrap();
+        let binding_at = |name| {
+            module
+                .find_binding_at_location(parsed.begin_line(name), parsed.begin_column(name))
+                .unwrap()
+        };
+
+        let x = binding_at("x");
+        assert!(module.rename_collides(&x, "f"));
+        // `z` is a local in `g`, which uses `x`.
+        assert!(module.rename_collides(&x, "z"));
+        // `w` is a global used in `g`.
+        assert!(module.rename_collides(&x, "w"));
+        assert!(!module.rename_collides(&x, "v"));
+
+        let y = binding_at("y");
+        assert!(module.rename_collides(&y, "z"));
+        assert!(!module.rename_collides(&y, "x"));
+        assert!(!module.rename_collides(&y, "w"));
+        Ok(())
+    }
+}
@@ -0,0 +1,384 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Semantic highlighting of identifiers, based on the scopes from [`crate::bind`].
+
+use std::collections::HashMap;
+
+use lsp_types::Range;
+use lsp_types::SemanticToken;
+use lsp_types::SemanticTokenModifier;
+use lsp_types::SemanticTokenType;
+use lsp_types::SemanticTokensLegend;
+use starlark::codemap::ResolvedSpan;
+use starlark::codemap::Span;
+use starlark::docs::DocItem;
+use starlark::docs::DocMember;
+use starlark::docs::DocModule;
+use starlark_syntax::syntax::ast::AstStmt;
+use starlark_syntax::syntax::ast::Stmt;
+use starlark_syntax::syntax::module::AstModuleFields;
+
+use crate::bind::scope;
+use crate::bind::Assigner;
+use crate::bind::Bind;
+use crate::bind::Scope;
+use crate::definition::LspModule;
+use crate::docs::peek_docstring;
+use crate::references::resolve;
+use crate::references::set_span;
+use crate::references::Binding;
+
+/// The token types, in the order of their indices in the legend.
+const TOKEN_TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::FUNCTION,
+];
+
+const VARIABLE: u32 = 0;
+const PARAMETER: u32 = 1;
+const FUNCTION: u32 = 2;
+
+/// The token modifiers, in the order of their bits in the legend.
+const TOKEN_MODIFIERS: &[SemanticTokenModifier] = &[
+    SemanticTokenModifier::DEFINITION,
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::DEPRECATED,
+    SemanticTokenModifier::DEFAULT_LIBRARY,
+    SemanticTokenModifier::new("global"),
+    SemanticTokenModifier::new("loaded"),
+];
+
+/// The place a variable is bound or assigned.
+const DEFINITION: u32 = 1 << 0;
+/// Globals and loaded symbols, which are frozen.
+const READONLY: u32 = 1 << 1;
+/// Symbols whose docstring starts with "Deprecated".
+const DEPRECATED: u32 = 1 << 2;
+/// Globals provided by the interpreter.
+const DEFAULT_LIBRARY: u32 = 1 << 3;
+/// Variables bound at the top level of the module.
+const GLOBAL: u32 = 1 << 4;
+/// Variables bound by a `load()` statement.
+const LOADED: u32 = 1 << 5;
+
+/// The legend for the token types and modifiers returned by [`LspModule::semantic_tokens`].
+pub(crate) fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: TOKEN_MODIFIERS.to_vec(),
+    }
+}
+
+/// A classified identifier, before it is encoded relative to the previous one.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Token {
+    span: ResolvedSpan,
+    token_type: u32,
+    modifiers: u32,
+}
+
+fn is_deprecated(doc: Option<&str>) -> bool {
+    doc.is_some_and(|doc| doc.trim_start().to_lowercase().starts_with("deprecated"))
+}
+
+/// Find the names of the functions defined by `def`, and whether they are deprecated.
+fn defs(stmt: &AstStmt, res: &mut HashMap<Span, bool>) {
+    if let Stmt::Def(def) = &stmt.node {
+        res.insert(def.name.span, is_deprecated(peek_docstring(&def.body)));
+    }
+    stmt.visit_stmt(|x| defs(x, res));
+}
+
+struct Classifier<'a> {
+    module: &'a LspModule,
+    globals: &'a DocModule,
+    defs: HashMap<Span, bool>,
+    tokens: Vec<Token>,
+}
+
+impl Classifier<'_> {
+    fn push(&mut self, span: Span, token_type: u32, modifiers: u32) {
+        self.tokens.push(Token {
+            span: self.module.ast.codemap().resolve_span(span),
+            token_type,
+            modifiers,
+        });
+    }
+
+    fn binding(&mut self, span: Span, binding: &Binding, modifiers: u32) {
+        let mut modifiers = modifiers;
+        if binding.top_level {
+            modifiers |= GLOBAL;
+        }
+        let token_type = match &binding.assigner {
+            Assigner::Argument => PARAMETER,
+            Assigner::Load { .. } => {
+                modifiers |= LOADED | READONLY;
+                VARIABLE
+            }
+            Assigner::Assign => match self.defs.get(&binding.span) {
+                Some(deprecated) => {
+                    if *deprecated {
+                        modifiers |= DEPRECATED;
+                    }
+                    FUNCTION
+                }
+                None => VARIABLE,
+            },
+        };
+        self.push(span, token_type, modifiers);
+    }
+
+    fn global(&mut self, span: Span, name: &str) {
+        let Some(item) = self.globals.members.get(name) else {
+            return;
+        };
+        let mut modifiers = DEFAULT_LIBRARY | READONLY;
+        if is_deprecated(item.get_doc_summary()) {
+            modifiers |= DEPRECATED;
+        }
+        let token_type = match item {
+            DocItem::Member(DocMember::Function(_)) => FUNCTION,
+            _ => VARIABLE,
+        };
+        self.push(span, token_type, modifiers);
+    }
+
+    fn get(&mut self, scopes: &[&Scope], span: Span, name: &str) {
+        match resolve(scopes, name) {
+            Some(binding) => self.binding(span, &binding, 0),
+            None => self.global(span, name),
+        }
+    }
+
+    fn scope<'a>(&mut self, scopes: &mut Vec<&'a Scope>, scope: &'a Scope) {
+        scopes.push(scope);
+        for bind in &scope.inner {
+            match bind {
+                Bind::Set(assigner, local) => {
+                    if let Some(binding) = resolve(scopes, &local.ident) {
+                        self.binding(set_span(assigner, local.span), &binding, DEFINITION);
+                    }
+                }
+                Bind::Get(x) => self.get(scopes, x.span, &x.node.ident),
+                Bind::GetDotted(x) => self.get(scopes, x.variable.span, &x.variable.node.ident),
+                Bind::Scope(inner) => self.scope(scopes, inner),
+                Bind::Flow => {}
+            }
+        }
+        scopes.pop();
+    }
+}
+
+impl LspModule {
+    /// Classify the identifiers in the module as variables, parameters or functions,
+    /// encoded as semantic tokens. `globals` are the symbols provided by the interpreter.
+    /// If `range` is given, only the tokens inside it are returned.
+    pub(crate) fn semantic_tokens(
+        &self,
+        globals: &DocModule,
+        range: Option<Range>,
+    ) -> Vec<SemanticToken> {
+        let mut defs_map = HashMap::new();
+        defs(self.ast.statement(), &mut defs_map);
+        let mut classifier = Classifier {
+            module: self,
+            globals,
+            defs: defs_map,
+            tokens: Vec::new(),
+        };
+        classifier.scope(&mut Vec::new(), &scope(&self.ast));
+
+        let mut tokens = classifier.tokens;
+        if let Some(range) = range {
+            tokens.retain(|token| {
+                let token = Range::from(token.span);
+                range.start <= token.start && token.end <= range.end
+            });
+        }
+        tokens.sort_by_key(|token| (token.span.begin.line, token.span.begin.column));
+        // `x += 1` both reads and assigns `x`, keep the first.
+        tokens.dedup_by_key(|token| token.span);
+
+        let mut line = 0;
+        let mut column = 0;
+        tokens
+            .into_iter()
+            .map(|token| {
+                let begin = token.span.begin;
+                let delta_line = (begin.line - line) as u32;
+                let delta_start = if delta_line == 0 {
+                    begin.column - column
+                } else {
+                    begin.column
+                } as u32;
+                line = begin.line;
+                column = begin.column;
+                SemanticToken {
+                    delta_line,
+                    delta_start,
+                    length: (token.span.end.column - begin.column) as u32,
+                    token_type: token.token_type,
+                    token_modifiers_bitset: token.modifiers,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starlark::docs::DocFunction;
+    use starlark::docs::DocProperty;
+    use starlark::docs::DocString;
+    use starlark::docs::DocStringKind;
+    use starlark::typing::Ty;
+    use textwrap::dedent;
+
+    use super::*;
+    use crate::definition::helpers::FixtureWithRanges;
+
+    /// Decode the tokens into the source text, type and modifiers of each token.
+    fn decode(source: &str, tokens: &[SemanticToken]) -> Vec<(String, String, Vec<String>)> {
+        let lines: Vec<&str> = source.lines().collect();
+        let mut line = 0;
+        let mut column = 0;
+        tokens
+            .iter()
+            .map(|token| {
+                if token.delta_line != 0 {
+                    column = 0;
+                }
+                line += token.delta_line as usize;
+                column += token.delta_start as usize;
+                let text = lines[line]
+                    .chars()
+                    .skip(column)
+                    .take(token.length as usize)
+                    .collect();
+                let modifiers = TOKEN_MODIFIERS
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| token.token_modifiers_bitset & (1 << i) != 0)
+                    .map(|(_, m)| m.as_str().to_owned())
+                    .collect();
+                (
+                    text,
+                    TOKEN_TYPES[token.token_type as usize].as_str().to_owned(),
+                    modifiers,
+                )
+            })
+            .collect()
+    }
+
+    fn token(text: &str, token_type: &str, modifiers: &[&str]) -> (String, String, Vec<String>) {
+        (
+            text.to_owned(),
+            token_type.to_owned(),
+            modifiers.iter().map(|m| (*m).to_owned()).collect(),
+        )
+    }
+
+    fn globals() -> DocModule {
+        let mut globals = DocModule::default();
+        globals.members.insert(
+            "len".to_owned(),
+            DocItem::Member(DocMember::Function(DocFunction::default())),
+        );
+        globals.members.insert(
+            "OLD".to_owned(),
+            DocItem::Member(DocMember::Property(DocProperty {
+                docs: DocString::from_docstring(DocStringKind::Starlark, "Deprecated: use NEW."),
+                typ: Ty::any(),
+            })),
+        );
+        globals
+    }
+
+    #[test]
+    fn classifies_identifiers() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            load("foo.star", "bar", baz = "qux")
+            def f(x):
+                """Deprecated: use g."""
+                y = x + len(bar)
+                y += OLD
+                return y
+            z = [f(w) for w in baz]
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = parsed.module().unwrap();
+        let tokens = decode(&parsed.program(), &module.semantic_tokens(&globals(), None));
+        assert_eq!(
+            vec![
+                token(
+                    "bar",
+                    "variable",
+                    &["definition", "readonly", "global", "loaded"]
+                ),
+                token(
+                    "baz",
+                    "variable",
+                    &["definition", "readonly", "global", "loaded"]
+                ),
+                token("f", "function", &["definition", "deprecated", "global"]),
+                token("x", "parameter", &["definition"]),
+                token("y", "variable", &["definition"]),
+                token("x", "parameter", &[]),
+                token("len", "function", &["readonly", "defaultLibrary"]),
+                token("bar", "variable", &["readonly", "global", "loaded"]),
+                token("y", "variable", &[]),
+                token(
+                    "OLD",
+                    "variable",
+                    &["readonly", "deprecated", "defaultLibrary"]
+                ),
+                token("y", "variable", &[]),
+                token("z", "variable", &["definition", "global"]),
+                token("f", "function", &["deprecated", "global"]),
+                token("w", "variable", &[]),
+                token("w", "variable", &["definition"]),
+                token("baz", "variable", &["readonly", "global", "loaded"]),
+            ],
+            tokens
+        );
+
+        let range = Range::new(
+            lsp_types::Position::new(7, 0),
+            lsp_types::Position::new(8, 0),
+        );
+        let tokens = decode(
+            &parsed.program(),
+            &module.semantic_tokens(&globals(), Some(range)),
+        );
+        assert_eq!(
+            vec![
+                token("z", "variable", &["definition", "global"]),
+                token("f", "function", &["deprecated", "global"]),
+                token("w", "variable", &[]),
+                token("w", "variable", &["definition"]),
+                token("baz", "variable", &["readonly", "global", "loaded"]),
+            ],
+            tokens
+        );
+        Ok(())
+    }
+}
@@ -23,7 +23,10 @@ use std::fmt::Debug;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
 
 use derivative::Derivative;
 use derive_more::Display;
@@ -42,9 +45,33 @@ use lsp_types::notification::DidCloseTextDocument;
 use lsp_types::notification::DidOpenTextDocument;
 use lsp_types::notification::LogMessage;
 use lsp_types::notification::PublishDiagnostics;
+use lsp_types::request::CallHierarchyIncomingCalls;
+use lsp_types::request::CallHierarchyOutgoingCalls;
+use lsp_types::request::CallHierarchyPrepare;
+use lsp_types::request::CodeActionRequest;
 use lsp_types::request::Completion;
+use lsp_types::request::DocumentHighlightRequest;
+use lsp_types::request::DocumentSymbolRequest;
+use lsp_types::request::FoldingRangeRequest;
+use lsp_types::request::Formatting;
 use lsp_types::request::GotoDefinition;
 use lsp_types::request::HoverRequest;
+use lsp_types::request::InlayHintRequest;
+use lsp_types::request::PrepareRenameRequest;
+use lsp_types::request::RangeFormatting;
+use lsp_types::request::References;
+use lsp_types::request::Rename;
+use lsp_types::request::SelectionRangeRequest;
+use lsp_types::request::SemanticTokensFullRequest;
+use lsp_types::request::SemanticTokensRangeRequest;
+use lsp_types::request::SignatureHelpRequest;
+use lsp_types::request::WorkspaceSymbolRequest;
+use lsp_types::CallHierarchyIncomingCallsParams;
+use lsp_types::CallHierarchyOutgoingCallsParams;
+use lsp_types::CallHierarchyPrepareParams;
+use lsp_types::CallHierarchyServerCapability;
+use lsp_types::CodeActionParams;
+use lsp_types::CodeActionProviderCapability;
 use lsp_types::CompletionItem;
 use lsp_types::CompletionItemKind;
 use lsp_types::CompletionOptions;
@@ -55,7 +82,17 @@ use lsp_types::Diagnostic;
 use lsp_types::DidChangeTextDocumentParams;
 use lsp_types::DidCloseTextDocumentParams;
 use lsp_types::DidOpenTextDocumentParams;
+use lsp_types::DocumentFormattingParams;
+use lsp_types::DocumentHighlight;
+use lsp_types::DocumentHighlightKind;
+use lsp_types::DocumentHighlightParams;
+use lsp_types::DocumentRangeFormattingParams;
+use lsp_types::DocumentSymbolParams;
+use lsp_types::DocumentSymbolResponse;
 use lsp_types::Documentation;
+use lsp_types::FoldingRange;
+use lsp_types::FoldingRangeParams;
+use lsp_types::FoldingRangeProviderCapability;
 use lsp_types::GotoDefinitionParams;
 use lsp_types::GotoDefinitionResponse;
 use lsp_types::Hover;
@@ -63,7 +100,10 @@ use lsp_types::HoverContents;
 use lsp_types::HoverParams;
 use lsp_types::HoverProviderCapability;
 use lsp_types::InitializeParams;
+use lsp_types::InlayHint;
+use lsp_types::InlayHintParams;
 use lsp_types::LanguageString;
+use lsp_types::Location;
 use lsp_types::LocationLink;
 use lsp_types::LogMessageParams;
 use lsp_types::MarkedString;
@@ -72,15 +112,38 @@ use lsp_types::MarkupKind;
 use lsp_types::MessageType;
 use lsp_types::OneOf;
 use lsp_types::Position;
+use lsp_types::PrepareRenameResponse;
 use lsp_types::PublishDiagnosticsParams;
 use lsp_types::Range;
+use lsp_types::ReferenceParams;
+use lsp_types::RenameOptions;
+use lsp_types::RenameParams;
+use lsp_types::SelectionRange;
+use lsp_types::SelectionRangeParams;
+use lsp_types::SelectionRangeProviderCapability;
+use lsp_types::SemanticTokens;
+use lsp_types::SemanticTokensFullOptions;
+use lsp_types::SemanticTokensOptions;
+use lsp_types::SemanticTokensParams;
+use lsp_types::SemanticTokensRangeParams;
+use lsp_types::SemanticTokensRangeResult;
+use lsp_types::SemanticTokensResult;
+use lsp_types::SemanticTokensServerCapabilities;
 use lsp_types::ServerCapabilities;
+use lsp_types::SignatureHelp;
+use lsp_types::SignatureHelpOptions;
+use lsp_types::SignatureHelpParams;
+use lsp_types::SymbolInformation;
+use lsp_types::TextDocumentPositionParams;
 use lsp_types::TextDocumentSyncCapability;
 use lsp_types::TextDocumentSyncKind;
 use lsp_types::TextEdit;
 use lsp_types::Url;
 use lsp_types::WorkDoneProgressOptions;
+use lsp_types::WorkspaceEdit;
 use lsp_types::WorkspaceFolder;
+use lsp_types::WorkspaceSymbolParams;
+use lsp_types::WorkspaceSymbolResponse;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde::Deserializer;
@@ -90,24 +153,37 @@ use starlark::codemap::ResolvedSpan;
 use starlark::codemap::Span;
 use starlark::docs::markdown::render_doc_item_no_link;
 use starlark::docs::markdown::render_doc_param;
+use starlark::docs::DocFunction;
 use starlark::docs::DocItem;
 use starlark::docs::DocMember;
 use starlark::docs::DocModule;
+use starlark::environment::Globals;
 use starlark::syntax::AstModule;
+use starlark::syntax::Dialect;
 use starlark_syntax::codemap::ResolvedPos;
+use starlark_syntax::lexer::lex_exactly_one_identifier;
 use starlark_syntax::syntax::ast::AstPayload;
 use starlark_syntax::syntax::ast::LoadArgP;
 use starlark_syntax::syntax::module::AstModuleFields;
 
+use crate::bind::Assigner;
 use crate::completion::StringCompletionResult;
 use crate::completion::StringCompletionType;
 use crate::definition::Definition;
 use crate::definition::DottedDefinition;
 use crate::definition::IdentifierDefinition;
 use crate::definition::LspModule;
+use crate::document::OpenDocument;
+use crate::inlay_hints::parameter_name_hints;
 use crate::inspect::AstModuleInspect;
 use crate::inspect::AutocompleteType;
+use crate::references::string_contents_span;
+use crate::references::Access;
+use crate::references::Binding;
+use crate::semantic_tokens::legend;
+use crate::signature::signature_information;
 use crate::symbols::find_symbols_at_location;
+use crate::symbols::get_document_symbols;
 
 /// The request to get the file contents for a starlark: URI
 struct StarlarkFileContentsRequest {}
@@ -273,16 +349,29 @@ pub struct LspEvalResult {
 
 /// Settings that the LspContext can provide to change what capabilities the server enables
 /// or disables.
-#[derive(Dupe, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct LspServerSettings {
     /// Whether goto definition should work.
     pub enable_goto_definition: bool,
+    /// Whether open files should be typechecked, with type errors reported as
+    /// diagnostics. Only files for which [`LspContext::get_globals`] returns
+    /// globals are typechecked.
+    #[serde(default)]
+    pub enable_typecheck: bool,
+    /// The extensions, without the leading `.`, of the files in the workspace folders
+    /// to index in the background at startup, e.g. `["star", "bzl"]`. The symbols
+    /// exported by indexed files are offered by completion before the files are opened.
+    /// Nothing is indexed if this is empty.
+    #[serde(default)]
+    pub index_extensions: Vec<String>,
 }
 
 impl Default for LspServerSettings {
     fn default() -> Self {
         Self {
             enable_goto_definition: true,
+            enable_typecheck: false,
+            index_extensions: Vec::new(),
         }
     }
 }
@@ -344,6 +433,22 @@ pub trait LspContext {
     /// Get the preloaded environment for a particular file.
     fn get_environment(&self, uri: &LspUrl) -> DocModule;
 
+    /// Get the globals used to typecheck a particular file, if it should be typechecked.
+    ///
+    /// This is only called when typechecking is enabled in [`LspServerSettings`]. The
+    /// globals should include everything that is in scope for the file, otherwise
+    /// uses of missing symbols are reported as errors.
+    fn get_globals(&self, uri: &LspUrl) -> Option<Globals> {
+        let _unused = uri;
+        None
+    }
+
+    /// Get the dialect to parse a file with when formatting it.
+    fn get_dialect(&self, uri: &LspUrl) -> Dialect {
+        let _unused = uri;
+        Dialect::Standard
+    }
+
     /// Get the LSPUrl for a global symbol if possible.
     ///
     /// The current file is provided in case different files have different global symbols
@@ -377,6 +482,23 @@ enum ResolveLoadError {
     WrongScheme(String, LspUrl),
 }
 
+/// Errors when renaming a symbol.
+#[derive(thiserror::Error, Debug)]
+enum RenameError {
+    /// There was no symbol that can be renamed at the requested location.
+    #[error("No symbol that can be renamed was found at this location")]
+    NotFound,
+    /// The new name is not a valid identifier.
+    #[error("`{}` is not a valid identifier", .0)]
+    InvalidName(String),
+    /// The new name is already bound or used where the symbol is visible.
+    #[error("Cannot rename `{}` to `{}`, as `{}` is already used in `{}`", .0, .1, .1, .2)]
+    Collision(String, String, LspUrl),
+    /// The symbol is loaded by other files, but the new name would make it private.
+    #[error("Cannot rename `{}` to `{}`, as it is loaded by `{}`", .0, .1, .2)]
+    WouldBecomePrivate(String, String, LspUrl),
+}
+
 /// Errors when loading contents of a starlark program.
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum LoadContentsError {
@@ -385,12 +507,39 @@ pub(crate) enum LoadContentsError {
     WrongScheme(String, LspUrl),
 }
 
+/// How long to wait after a document stops changing before it is parsed and
+/// diagnostics are published. Requests about a changed document parse it straight
+/// away, without publishing diagnostics.
+const VALIDATION_DELAY: Duration = Duration::from_millis(200);
+
+/// The symbol that a rename request applies to. See [`Backend::find_rename_target`].
+struct RenameTarget {
+    /// The binding at the cursor.
+    binding: Binding,
+    /// If the rename should also apply to other files, the module that defines the
+    /// symbol and the name it is exported as.
+    exported: Option<(LspUrl, String)>,
+    /// The range at the cursor that would be replaced.
+    range: ResolvedSpan,
+}
+
 pub(crate) struct Backend<T: LspContext> {
-    connection: Connection,
+    pub(crate) connection: Connection,
     pub(crate) context: T,
+    pub(crate) settings: LspServerSettings,
     /// The `AstModule` from the last time that a file was opened / changed and parsed successfully.
     /// Entries are evicted when the file is closed.
     pub(crate) last_valid_parse: RwLock<HashMap<LspUrl, Arc<LspModule>>>,
+    /// The files in the workspace that were parsed by [`crate::index`], whether or not they
+    /// are open. Entries for open files may be out of date, see [`Backend::parsed_modules`].
+    pub(crate) workspace_index: RwLock<HashMap<LspUrl, Arc<LspModule>>>,
+    /// The text of each open document, with the changes sent by the client applied.
+    pub(crate) documents: RwLock<HashMap<LspUrl, OpenDocument>>,
+    /// Documents that have changed since their diagnostics were published, and when
+    /// they should be parsed to publish them.
+    pending_validation: Mutex<HashMap<LspUrl, Instant>>,
+    /// Documents that have changed since they were last parsed.
+    stale_asts: Mutex<HashSet<LspUrl>>,
 }
 
 /// The logic implementations of stuff
@@ -404,15 +553,54 @@ impl<T: LspContext> Backend<T> {
             })
         });
         ServerCapabilities {
-            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                TextDocumentSyncKind::INCREMENTAL,
+            )),
             definition_provider,
-            completion_provider: Some(CompletionOptions::default()),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec![".".to_owned()]),
+                ..CompletionOptions::default()
+            }),
             hover_provider: Some(HoverProviderCapability::Simple(true)),
+            signature_help_provider: Some(SignatureHelpOptions {
+                trigger_characters: Some(vec!["(".to_owned(), ",".to_owned()]),
+                retrigger_characters: None,
+                work_done_progress_options: WorkDoneProgressOptions {
+                    work_done_progress: None,
+                },
+            }),
+            references_provider: Some(OneOf::Left(true)),
+            call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+            document_highlight_provider: Some(OneOf::Left(true)),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            workspace_symbol_provider: Some(OneOf::Left(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            inlay_hint_provider: Some(OneOf::Left(true)),
+            folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+            selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            document_range_formatting_provider: Some(OneOf::Left(true)),
+            rename_provider: Some(OneOf::Right(RenameOptions {
+                prepare_provider: Some(true),
+                work_done_progress_options: WorkDoneProgressOptions {
+                    work_done_progress: None,
+                },
+            })),
+            semantic_tokens_provider: Some(
+                SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+                    legend: legend(),
+                    range: Some(true),
+                    full: Some(SemanticTokensFullOptions::Bool(true)),
+                    work_done_progress_options: WorkDoneProgressOptions {
+                        work_done_progress: None,
+                    },
+                }),
+            ),
             ..ServerCapabilities::default()
         }
     }
 
-    fn get_ast(&self, uri: &LspUrl) -> Option<Arc<LspModule>> {
+    pub(crate) fn get_ast(&self, uri: &LspUrl) -> Option<Arc<LspModule>> {
         let last_valid_parse = self.last_valid_parse.read().unwrap();
         last_valid_parse.get(uri).duped()
     }
@@ -421,7 +609,8 @@ impl<T: LspContext> Backend<T> {
         &self,
         uri: &LspUrl,
     ) -> anyhow::Result<Option<Arc<LspModule>>> {
-        let module = match self.get_ast(uri) {
+        let indexed = || self.workspace_index.read().unwrap().get(uri).duped();
+        let module = match self.get_ast(uri).or_else(indexed) {
             Some(result) => Some(result),
             None => self
                 .context
@@ -431,41 +620,169 @@ impl<T: LspContext> Backend<T> {
         Ok(module)
     }
 
-    fn validate(&self, uri: Url, version: Option<i64>, text: String) -> anyhow::Result<()> {
+    fn validate(
+        &self,
+        uri: Url,
+        version: Option<i64>,
+        text: String,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<()> {
         let uri = uri.try_into()?;
-        let eval_result = self.context.parse_file_with_contents(&uri, text);
+        self.stale_asts.lock().unwrap().remove(&uri);
+        let mut eval_result = self.context.parse_file_with_contents(&uri, text);
         if let Some(ast) = eval_result.ast {
             let module = Arc::new(LspModule::new(ast));
-            let mut last_valid_parse = self.last_valid_parse.write().unwrap();
-            last_valid_parse.insert(uri.clone(), module);
+            {
+                let mut last_valid_parse = self.last_valid_parse.write().unwrap();
+                last_valid_parse.insert(uri.clone(), module.dupe());
+            }
+            if self.settings.enable_typecheck {
+                let workspace_root =
+                    Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+                eval_result.diagnostics.extend(self.typecheck_diagnostics(
+                    &uri,
+                    &module,
+                    workspace_root.as_deref(),
+                ));
+            }
         }
         self.publish_diagnostics(uri.try_into()?, eval_result.diagnostics, version);
         Ok(())
     }
 
-    fn did_open(&self, params: DidOpenTextDocumentParams) -> anyhow::Result<()> {
+    fn did_open(
+        &self,
+        params: DidOpenTextDocumentParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<()> {
+        let uri = params.text_document.uri.clone().try_into()?;
+        let version = params.text_document.version as i64;
+        self.documents
+            .write()
+            .unwrap()
+            .insert(uri, OpenDocument::new(&params.text_document.text, version));
         self.validate(
             params.text_document.uri,
-            Some(params.text_document.version as i64),
+            Some(version),
             params.text_document.text,
+            initialize_params,
         )
     }
 
+    /// Apply the changes to the document, and schedule it to be parsed once it
+    /// stops changing. Only the text is updated incrementally: parsing always
+    /// starts over from the whole document.
     fn did_change(&self, params: DidChangeTextDocumentParams) -> anyhow::Result<()> {
-        // We asked for Sync full, so can just grab all the text from params
-        let change = params.content_changes.into_iter().next().unwrap();
-        self.validate(
-            params.text_document.uri,
-            Some(params.text_document.version as i64),
-            change.text,
-        )
+        let uri: LspUrl = params.text_document.uri.try_into()?;
+        {
+            let mut documents = self.documents.write().unwrap();
+            let mut changes = params.content_changes;
+            if !documents.contains_key(&uri) {
+                // Edits to a document we never saw opened can only be applied from a
+                // change which replaces the whole text.
+                match changes.iter().rposition(|change| change.range.is_none()) {
+                    Some(i) => {
+                        changes.drain(..i);
+                    }
+                    None => {
+                        drop(documents);
+                        self.log_message(
+                            MessageType::WARNING,
+                            &format!("Ignoring incremental changes to unopened file {}", uri),
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+            let document = documents
+                .entry(uri.clone())
+                .or_insert_with(|| OpenDocument::new("", 0));
+            for change in changes {
+                document.apply_change(change);
+            }
+            document.version = params.text_document.version as i64;
+        }
+        self.stale_asts.lock().unwrap().insert(uri.clone());
+        self.pending_validation
+            .lock()
+            .unwrap()
+            .insert(uri, Instant::now() + VALIDATION_DELAY);
+        Ok(())
+    }
+
+    /// Parse the documents that have stopped changing and publish their diagnostics.
+    fn validate_pending(&self, initialize_params: &InitializeParams) {
+        let now = Instant::now();
+        let ready: Vec<LspUrl> = {
+            let mut pending = self.pending_validation.lock().unwrap();
+            let ready = pending
+                .iter()
+                .filter(|(_, deadline)| **deadline <= now)
+                .map(|(uri, _)| uri.clone())
+                .collect::<Vec<_>>();
+            for uri in &ready {
+                pending.remove(uri);
+            }
+            ready
+        };
+        for uri in ready {
+            let document = self
+                .documents
+                .read()
+                .unwrap()
+                .get(&uri)
+                .map(|document| (document.text(), document.version));
+            if let Some((text, version)) = document {
+                let res = Url::try_from(&uri)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|url| self.validate(url, Some(version), text, initialize_params));
+                if let Err(e) = res {
+                    self.log_message(
+                        MessageType::ERROR,
+                        &format!("Failed to validate {}: {:#}", uri, e),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Parse a document that has changed since it was last parsed, so requests about
+    /// it see its latest text. Diagnostics are left to [`Backend::validate_pending`].
+    fn refresh_ast(&self, uri: &LspUrl) {
+        if !self.stale_asts.lock().unwrap().remove(uri) {
+            return;
+        }
+        let Some(text) = self.documents.read().unwrap().get(uri).map(|x| x.text()) else {
+            return;
+        };
+        if let Some(ast) = self.context.parse_file_with_contents(uri, text).ast {
+            self.last_valid_parse
+                .write()
+                .unwrap()
+                .insert(uri.clone(), Arc::new(LspModule::new(ast)));
+        }
+    }
+
+    /// When the next changed document should be parsed, if any.
+    fn next_validation(&self) -> Option<Instant> {
+        self.pending_validation
+            .lock()
+            .unwrap()
+            .values()
+            .min()
+            .copied()
     }
 
     fn did_close(&self, params: DidCloseTextDocumentParams) -> anyhow::Result<()> {
+        let uri = params.text_document.uri.clone().try_into()?;
         {
             let mut last_valid_parse = self.last_valid_parse.write().unwrap();
-            last_valid_parse.remove(&params.text_document.uri.clone().try_into()?);
+            last_valid_parse.remove(&uri);
         }
+        self.documents.write().unwrap().remove(&uri);
+        self.pending_validation.lock().unwrap().remove(&uri);
+        self.stale_asts.lock().unwrap().remove(&uri);
+        self.reindex_file(&uri)?;
         self.publish_diagnostics(params.text_document.uri, Vec::new(), None);
         Ok(())
     }
@@ -506,6 +823,172 @@ impl<T: LspContext> Backend<T> {
         self.send_response(new_response(id, self.hover_info(params, initialize_params)));
     }
 
+    /// Offers the signature of the function being called at the current cursor.
+    fn signature_help(
+        &self,
+        id: RequestId,
+        params: SignatureHelpParams,
+        initialize_params: &InitializeParams,
+    ) {
+        self.send_response(new_response(
+            id,
+            self.find_signature_help(params, initialize_params),
+        ));
+    }
+
+    /// Offers hints for inferred types and the names of positional arguments.
+    fn inlay_hints(
+        &self,
+        id: RequestId,
+        params: InlayHintParams,
+        initialize_params: &InitializeParams,
+    ) {
+        self.send_response(new_response(
+            id,
+            self.find_inlay_hints(params, initialize_params),
+        ));
+    }
+
+    /// Offers quick fixes for the diagnostics in the requested range.
+    fn code_actions(
+        &self,
+        id: RequestId,
+        params: CodeActionParams,
+        initialize_params: &InitializeParams,
+    ) {
+        self.send_response(new_response(
+            id,
+            self.find_code_actions(params, initialize_params),
+        ));
+    }
+
+    /// Offers semantic highlighting for the identifiers in a document.
+    fn semantic_tokens_full(&self, id: RequestId, params: SemanticTokensParams) {
+        let tokens = self.find_semantic_tokens(params.text_document.uri, None);
+        self.send_response(new_response(
+            id,
+            tokens.map(|x| x.map(SemanticTokensResult::Tokens)),
+        ));
+    }
+
+    /// Offers semantic highlighting for the identifiers in part of a document.
+    fn semantic_tokens_range(&self, id: RequestId, params: SemanticTokensRangeParams) {
+        let tokens = self.find_semantic_tokens(params.text_document.uri, Some(params.range));
+        self.send_response(new_response(
+            id,
+            tokens.map(|x| x.map(SemanticTokensRangeResult::Tokens)),
+        ));
+    }
+
+    /// Finds all the references to the symbol at the current cursor.
+    fn references(
+        &self,
+        id: RequestId,
+        params: ReferenceParams,
+        initialize_params: &InitializeParams,
+    ) {
+        self.send_response(new_response(
+            id,
+            self.find_references(params, initialize_params),
+        ));
+    }
+
+    /// Finds the function at the current cursor, to show the calls to and from it.
+    fn prepare_call_hierarchy(
+        &self,
+        id: RequestId,
+        params: CallHierarchyPrepareParams,
+        initialize_params: &InitializeParams,
+    ) {
+        self.send_response(new_response(
+            id,
+            self.prepare_call_hierarchy_items(params, initialize_params),
+        ));
+    }
+
+    /// Finds the calls to a function, including from files that load it.
+    fn incoming_calls(
+        &self,
+        id: RequestId,
+        params: CallHierarchyIncomingCallsParams,
+        initialize_params: &InitializeParams,
+    ) {
+        self.send_response(new_response(
+            id,
+            self.find_incoming_calls(params, initialize_params),
+        ));
+    }
+
+    /// Finds the functions called by a function.
+    fn outgoing_calls(
+        &self,
+        id: RequestId,
+        params: CallHierarchyOutgoingCallsParams,
+        initialize_params: &InitializeParams,
+    ) {
+        self.send_response(new_response(
+            id,
+            self.find_outgoing_calls(params, initialize_params),
+        ));
+    }
+
+    /// Highlights the places the symbol at the current cursor is read or assigned
+    /// in the current document.
+    fn document_highlight(&self, id: RequestId, params: DocumentHighlightParams) {
+        self.send_response(new_response(id, self.find_document_highlights(params)));
+    }
+
+    /// Offers the ranges of lines in a document that can be folded.
+    fn folding_ranges(&self, id: RequestId, params: FoldingRangeParams) {
+        self.send_response(new_response(id, self.find_folding_ranges(params)));
+    }
+
+    /// Formats a whole document.
+    fn formatting(&self, id: RequestId, params: DocumentFormattingParams) {
+        self.send_response(new_response(id, self.format_document(params)));
+    }
+
+    /// Formats the top level statements that overlap a range of a document.
+    fn range_formatting(&self, id: RequestId, params: DocumentRangeFormattingParams) {
+        self.send_response(new_response(id, self.format_document_range(params)));
+    }
+
+    /// Offers ranges to expand the selection to, around each of the given positions.
+    fn selection_ranges(&self, id: RequestId, params: SelectionRangeParams) {
+        self.send_response(new_response(id, self.find_selection_ranges(params)));
+    }
+
+    /// Checks that the symbol at the current cursor can be renamed.
+    fn prepare_rename(
+        &self,
+        id: RequestId,
+        params: TextDocumentPositionParams,
+        initialize_params: &InitializeParams,
+    ) {
+        self.send_response(new_response(
+            id,
+            self.prepare_rename_range(params, initialize_params),
+        ));
+    }
+
+    /// Renames the symbol at the current cursor, including in files that load it.
+    fn rename(&self, id: RequestId, params: RenameParams, initialize_params: &InitializeParams) {
+        self.send_response(new_response(
+            id,
+            self.rename_edits(params, initialize_params),
+        ));
+    }
+
+    /// Offers an outline of the symbols defined in a document.
+    fn document_symbols(&self, id: RequestId, params: DocumentSymbolParams) {
+        self.send_response(new_response(id, self.get_document_symbols(params)));
+    }
+
+    /// Searches the symbols exported by all the files the server has parsed.
+    fn workspace_symbols(&self, id: RequestId, params: WorkspaceSymbolParams) {
+        self.send_response(new_response(id, self.find_workspace_symbols(params)));
+    }
+
     /// Get the file contents of a starlark: URI.
     fn get_starlark_file_contents(&self, id: RequestId, params: StarlarkFileContentsParams) {
         let response: anyhow::Result<_> = match params.uri {
@@ -720,22 +1203,586 @@ impl<T: LspContext> Backend<T> {
         Ok(GotoDefinitionResponse::Link(response))
     }
 
-    fn completion_options(
+    /// Find the places that refer to the variable at the cursor.
+    ///
+    /// Variables that are local to a file are only looked for in that file. Top level
+    /// symbols that other files can load, and symbols loaded from another file, are also
+    /// looked for in the `load()` statements of every file the server has parsed.
+    /// Find the signature of the function being called at the cursor. The function can
+    /// be defined in the same file, loaded from another file, or a global.
+    ///
+    /// NOTE: This uses the last valid parse of a file, so argument positions may be
+    /// slightly off if the file has changed since.
+    fn find_signature_help(
         &self,
-        params: CompletionParams,
+        params: SignatureHelpParams,
         initialize_params: &InitializeParams,
-    ) -> anyhow::Result<CompletionResponse> {
+    ) -> anyhow::Result<Option<SignatureHelp>> {
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .try_into()?;
+        let position = params.text_document_position_params.position;
+        let Some(module) = self.get_ast(&uri) else {
+            return Ok(None);
+        };
+        let Some(call) = module.find_call_at_location(position.line, position.character) else {
+            return Ok(None);
+        };
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+
+        let function =
+            self.resolve_function_signature(&module, &uri, call.callee, workspace_root.as_deref())?;
+
+        Ok(function.map(|function| {
+            let signature = signature_information(&call.name, &function, &call.active);
+            SignatureHelp {
+                active_parameter: signature.active_parameter,
+                signatures: vec![signature],
+                active_signature: Some(0),
+            }
+        }))
+    }
+
+    /// Find the signature of the function named at `callee`, whether it is defined in
+    /// this module, loaded from another one, or a global.
+    fn resolve_function_signature(
+        &self,
+        module: &LspModule,
+        uri: &LspUrl,
+        callee: ResolvedSpan,
+        workspace_root: Option<&Path>,
+    ) -> anyhow::Result<Option<DocFunction>> {
+        Ok(
+            match module
+                .find_definition_at_location(callee.begin.line as u32, callee.begin.column as u32)
+            {
+                Definition::Identifier(IdentifierDefinition::Location { destination, .. }) => {
+                    module.find_def_signature(destination)
+                }
+                Definition::Identifier(IdentifierDefinition::LoadedLocation {
+                    path, name, ..
+                }) => {
+                    let load_uri = self.resolve_load_path(&path, uri, workspace_root)?;
+                    self.get_ast_or_load_from_disk(&load_uri)?
+                        .and_then(|loaded| {
+                            let span = loaded.find_exported_symbol_span(&name)?;
+                            loaded.find_def_signature(span)
+                        })
+                }
+                Definition::Identifier(IdentifierDefinition::Unresolved { name, .. }) => self
+                    .context
+                    .get_environment(uri)
+                    .members
+                    .into_iter()
+                    .find_map(|(member, item)| match item {
+                        DocItem::Member(DocMember::Function(function)) if member == name => {
+                            Some(function)
+                        }
+                        _ => None,
+                    }),
+                _ => None,
+            },
+        )
+    }
+
+    fn find_inlay_hints(
+        &self,
+        params: InlayHintParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<Vec<InlayHint>> {
+        let uri = params.text_document.uri.try_into()?;
+        let Some(module) = self.get_ast(&uri) else {
+            return Ok(Vec::new());
+        };
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+
+        let mut hints = Vec::new();
+        if self.settings.enable_typecheck {
+            if let Some((_, types)) = self.typecheck(&uri, &module, workspace_root.as_deref()) {
+                hints.extend(module.type_hints(&types));
+            }
+        }
+        for call in module.find_positional_arguments() {
+            // A callee that cannot be resolved just gets no hints.
+            if let Ok(Some(function)) = self.resolve_function_signature(
+                &module,
+                &uri,
+                call.callee,
+                workspace_root.as_deref(),
+            ) {
+                hints.extend(parameter_name_hints(&function, &call));
+            }
+        }
+
+        let range = params.range;
+        hints.retain(|hint| range.start <= hint.position && hint.position <= range.end);
+        hints.sort_by_key(|hint| hint.position);
+        Ok(hints)
+    }
+
+    fn find_semantic_tokens(
+        &self,
+        uri: Url,
+        range: Option<Range>,
+    ) -> anyhow::Result<Option<SemanticTokens>> {
+        let uri = uri.try_into()?;
+        let Some(module) = self.get_ast(&uri) else {
+            return Ok(None);
+        };
+        let globals = self.context.get_environment(&uri);
+        Ok(Some(SemanticTokens {
+            result_id: None,
+            data: module.semantic_tokens(&globals, range),
+        }))
+    }
+
+    fn find_document_highlights(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> anyhow::Result<Option<Vec<DocumentHighlight>>> {
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .try_into()?;
+        let position = params.text_document_position_params.position;
+        let Some(module) = self.get_ast(&uri) else {
+            return Ok(None);
+        };
+        let Some(binding) = module.find_binding_at_location(position.line, position.character)
+        else {
+            return Ok(None);
+        };
+        let highlights = module
+            .find_accesses(&binding)
+            .into_iter()
+            .map(|(span, access)| DocumentHighlight {
+                range: module.ast.codemap().resolve_span(span).into(),
+                kind: Some(match access {
+                    Access::Read => DocumentHighlightKind::READ,
+                    Access::Write => DocumentHighlightKind::WRITE,
+                }),
+            })
+            .collect();
+        Ok(Some(highlights))
+    }
+
+    fn find_folding_ranges(
+        &self,
+        params: FoldingRangeParams,
+    ) -> anyhow::Result<Option<Vec<FoldingRange>>> {
+        let uri = params.text_document.uri.try_into()?;
+        Ok(self.get_ast(&uri).map(|module| module.folding_ranges()))
+    }
+
+    fn find_selection_ranges(
+        &self,
+        params: SelectionRangeParams,
+    ) -> anyhow::Result<Option<Vec<SelectionRange>>> {
+        let uri = params.text_document.uri.try_into()?;
+        let Some(module) = self.get_ast(&uri) else {
+            return Ok(None);
+        };
+        // The response must have an entry for every position, so a position outside
+        // the module selects an empty range at itself.
+        let ranges = params
+            .positions
+            .into_iter()
+            .map(|position| {
+                module
+                    .selection_range(position)
+                    .unwrap_or_else(|| SelectionRange {
+                        range: Range::new(position, position),
+                        parent: None,
+                    })
+            })
+            .collect();
+        Ok(Some(ranges))
+    }
+
+    fn find_references(
+        &self,
+        params: ReferenceParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<Vec<Location>> {
         let uri = params.text_document_position.text_document.uri.try_into()?;
         let line = params.text_document_position.position.line;
         let character = params.text_document_position.position.character;
+        let include_declaration = params.context.include_declaration;
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
 
-        let symbols: Option<Vec<_>> = match self.get_ast(&uri) {
-            Some(document) => {
-                // Figure out what kind of position we are in, to determine the best type of
-                // autocomplete.
-                let autocomplete_type = document.ast.get_auto_complete_type(line, character);
-                let workspace_root =
-                    Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+        let Some(ast) = self.get_ast(&uri) else {
+            return Ok(Vec::new());
+        };
+        let Some(binding) = ast.find_binding_at_location(line, character) else {
+            return Ok(Vec::new());
+        };
+
+        let exported = self.exported_symbol_for_binding(&binding, &uri, workspace_root.as_deref());
+        let references = match exported {
+            Some((module_uri, name)) => self.find_exported_symbol_references(
+                &module_uri,
+                &name,
+                workspace_root.as_deref(),
+                include_declaration,
+            )?,
+            None => Self::binding_references(&uri, &ast, &binding, include_declaration),
+        };
+
+        references
+            .into_iter()
+            .map(|(uri, span)| Ok(Location::new(uri.try_into()?, span.into())))
+            .collect()
+    }
+
+    /// If `binding` is a symbol that can be loaded by other files, or was itself loaded
+    /// from another file, get the module that defines it and the name it is exported as.
+    pub(crate) fn exported_symbol_for_binding(
+        &self,
+        binding: &Binding,
+        uri: &LspUrl,
+        workspace_root: Option<&Path>,
+    ) -> Option<(LspUrl, String)> {
+        match &binding.assigner {
+            Assigner::Load { path, name } => self
+                .resolve_load_path(path, uri, workspace_root)
+                .ok()
+                .map(|load_uri| (load_uri, name.node.clone())),
+            _ if binding.top_level && !binding.name.starts_with('_') => {
+                Some((uri.clone(), binding.name.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// The bindings created by `load()` statements in parsed files other than `module_uri`
+    /// that load the symbol `name` from `module_uri`.
+    fn find_bindings_loading_symbol(
+        &self,
+        module_uri: &LspUrl,
+        name: &str,
+        workspace_root: Option<&Path>,
+    ) -> Vec<(LspUrl, Arc<LspModule>, Binding)> {
+        let documents: Vec<(LspUrl, Arc<LspModule>)> = self
+            .parsed_modules()
+            .into_iter()
+            .filter(|(doc_uri, _)| doc_uri != module_uri)
+            .collect();
+
+        let mut result = Vec::new();
+        for (doc_uri, doc) in documents {
+            for binding in doc.find_load_bindings() {
+                let Assigner::Load {
+                    path,
+                    name: loaded_name,
+                } = &binding.assigner
+                else {
+                    continue;
+                };
+                if loaded_name.node != name {
+                    continue;
+                }
+                match self.resolve_load_path(path, &doc_uri, workspace_root) {
+                    Ok(load_uri) if &load_uri == module_uri => {
+                        result.push((doc_uri.clone(), doc.dupe(), binding))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        result
+    }
+
+    /// The places in a single module that refer to `binding`.
+    pub(crate) fn binding_references(
+        uri: &LspUrl,
+        module: &LspModule,
+        binding: &Binding,
+        include_declaration: bool,
+    ) -> Vec<(LspUrl, ResolvedSpan)> {
+        module
+            .find_references(binding)
+            .into_iter()
+            .filter(|span| include_declaration || !binding.span.contains(span.begin()))
+            .map(|span| (uri.clone(), module.ast.codemap().resolve_span(span)))
+            .collect()
+    }
+
+    /// Find the references to the top level symbol `name` in `module_uri`, both in that
+    /// module, and in all the parsed modules that load it.
+    pub(crate) fn find_exported_symbol_references(
+        &self,
+        module_uri: &LspUrl,
+        name: &str,
+        workspace_root: Option<&Path>,
+        include_declaration: bool,
+    ) -> anyhow::Result<Vec<(LspUrl, ResolvedSpan)>> {
+        let mut result = Vec::new();
+        if let Some(module) = self.get_ast_or_load_from_disk(module_uri)? {
+            if let Some(binding) = module.find_top_level_binding(name) {
+                result.extend(Self::binding_references(
+                    module_uri,
+                    &module,
+                    &binding,
+                    include_declaration,
+                ));
+            }
+        }
+
+        for (doc_uri, doc, binding) in
+            self.find_bindings_loading_symbol(module_uri, name, workspace_root)
+        {
+            // If the symbol is loaded under a different name, the string naming it
+            // is not one of the references to the local binding.
+            if let Assigner::Load {
+                name: loaded_name, ..
+            } = &binding.assigner
+            {
+                if loaded_name.span != binding.span {
+                    let span = string_contents_span(loaded_name);
+                    result.push((doc_uri.clone(), doc.ast.codemap().resolve_span(span)));
+                }
+            }
+            result.extend(Self::binding_references(&doc_uri, &doc, &binding, true));
+        }
+        Ok(result)
+    }
+
+    /// Find the symbol to rename at the cursor.
+    ///
+    /// A symbol that is loaded under another name (e.g. `load("foo.star", bar = "baz")`) is
+    /// only renamed locally, unless the cursor is on the name it was loaded as (`"baz"`).
+    fn find_rename_target(
+        &self,
+        ast: &LspModule,
+        uri: &LspUrl,
+        position: Position,
+        workspace_root: Option<&Path>,
+    ) -> Option<RenameTarget> {
+        let binding = ast.find_binding_at_location(position.line, position.character)?;
+        let pos = ResolvedPos {
+            line: position.line as usize,
+            column: position.character as usize,
+        };
+        let exported = self.exported_symbol_for_binding(&binding, uri, workspace_root);
+        if let Assigner::Load { name, .. } = &binding.assigner {
+            if name.span != binding.span {
+                let name_span = ast.ast.codemap().resolve_span(string_contents_span(name));
+                return if name_span.contains(pos) {
+                    Some(RenameTarget {
+                        binding,
+                        exported,
+                        range: name_span,
+                    })
+                } else {
+                    let range = Self::reference_range_at(ast, &binding, pos)?;
+                    Some(RenameTarget {
+                        binding,
+                        exported: None,
+                        range,
+                    })
+                };
+            }
+        }
+        let range = Self::reference_range_at(ast, &binding, pos)?;
+        Some(RenameTarget {
+            binding,
+            exported,
+            range,
+        })
+    }
+
+    /// The range of the reference to `binding` at `pos`.
+    fn reference_range_at(
+        ast: &LspModule,
+        binding: &Binding,
+        pos: ResolvedPos,
+    ) -> Option<ResolvedSpan> {
+        ast.find_references(binding)
+            .into_iter()
+            .map(|span| ast.ast.codemap().resolve_span(span))
+            .find(|span| span.contains(pos))
+    }
+
+    fn prepare_rename_range(
+        &self,
+        params: TextDocumentPositionParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<Option<PrepareRenameResponse>> {
+        let uri = params.text_document.uri.try_into()?;
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+        Ok(self.get_ast(&uri).and_then(|ast| {
+            self.find_rename_target(&ast, &uri, params.position, workspace_root.as_deref())
+                .map(|target| PrepareRenameResponse::Range(target.range.into()))
+        }))
+    }
+
+    /// Compute the edits to rename the symbol at the cursor.
+    ///
+    /// Renames the definition and all the references to it. If the symbol can be loaded
+    /// by other files, the `load()` statements in every file the server has parsed are
+    /// updated too, along with the references to the symbol in those files if they
+    /// loaded it under its original name.
+    fn rename_edits(
+        &self,
+        params: RenameParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<WorkspaceEdit> {
+        let uri = params.text_document_position.text_document.uri.try_into()?;
+        let new_name = params.new_name;
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+        if lex_exactly_one_identifier(&new_name).as_deref() != Some(new_name.as_str()) {
+            return Err(RenameError::InvalidName(new_name).into());
+        }
+
+        let ast = self.get_ast(&uri).ok_or(RenameError::NotFound)?;
+        let RenameTarget {
+            binding, exported, ..
+        } = self
+            .find_rename_target(
+                &ast,
+                &uri,
+                params.text_document_position.position,
+                workspace_root.as_deref(),
+            )
+            .ok_or(RenameError::NotFound)?;
+
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        let rename_binding = |changes: &mut HashMap<Url, Vec<TextEdit>>,
+                              uri: &LspUrl,
+                              module: &LspModule,
+                              binding: &Binding|
+         -> anyhow::Result<()> {
+            if module.rename_collides(binding, &new_name) {
+                return Err(RenameError::Collision(
+                    binding.name.clone(),
+                    new_name.clone(),
+                    uri.clone(),
+                )
+                .into());
+            }
+            let edits = changes.entry(uri.try_into()?).or_default();
+            for (_, span) in Self::binding_references(uri, module, binding, true) {
+                edits.push(TextEdit::new(span.into(), new_name.clone()));
+            }
+            Ok(())
+        };
+
+        match exported {
+            None => rename_binding(&mut changes, &uri, &ast, &binding)?,
+            Some((module_uri, name)) => {
+                if let Some(module) = self.get_ast_or_load_from_disk(&module_uri)? {
+                    if let Some(binding) = module.find_top_level_binding(&name) {
+                        rename_binding(&mut changes, &module_uri, &module, &binding)?;
+                    }
+                }
+                for (doc_uri, doc, binding) in
+                    self.find_bindings_loading_symbol(&module_uri, &name, workspace_root.as_deref())
+                {
+                    if new_name.starts_with('_') {
+                        return Err(RenameError::WouldBecomePrivate(name, new_name, doc_uri).into());
+                    }
+                    match &binding.assigner {
+                        Assigner::Load {
+                            name: loaded_name, ..
+                        } if loaded_name.span != binding.span => {
+                            let span = doc
+                                .ast
+                                .codemap()
+                                .resolve_span(string_contents_span(loaded_name));
+                            changes
+                                .entry((&doc_uri).try_into()?)
+                                .or_default()
+                                .push(TextEdit::new(span.into(), new_name.clone()));
+                        }
+                        _ => rename_binding(&mut changes, &doc_uri, &doc, &binding)?,
+                    }
+                }
+            }
+        }
+        Ok(WorkspaceEdit::new(changes))
+    }
+
+    fn get_document_symbols(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> anyhow::Result<DocumentSymbolResponse> {
+        let uri = params.text_document.uri.try_into()?;
+        let symbols = match self.get_ast(&uri) {
+            Some(document) => {
+                get_document_symbols(document.ast.codemap(), document.ast.statement())
+            }
+            None => Vec::new(),
+        };
+        Ok(DocumentSymbolResponse::Nested(symbols))
+    }
+
+    /// Find the exported symbols whose names contain the characters of the query,
+    /// in order, ignoring case.
+    fn find_workspace_symbols(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> anyhow::Result<WorkspaceSymbolResponse> {
+        let query = params.query.to_lowercase();
+        let matches_query = |name: &str| {
+            let mut name = name.chars().flat_map(char::to_lowercase);
+            query.chars().all(|q| name.any(|c| c == q))
+        };
+
+        let mut result = Vec::new();
+        for (doc_uri, doc) in self.parsed_modules() {
+            let url: Url = doc_uri.try_into()?;
+            for symbol in doc.get_exported_symbols() {
+                if !matches_query(&symbol.name) {
+                    continue;
+                }
+                #[allow(deprecated)] // `deprecated` is a required field.
+                result.push(SymbolInformation {
+                    location: Location::new(url.clone(), symbol.span.resolve_span().into()),
+                    name: symbol.name,
+                    kind: symbol.kind.into(),
+                    tags: None,
+                    deprecated: None,
+                    container_name: None,
+                });
+            }
+        }
+        result.sort_by(|a, b| {
+            (&a.name, a.location.uri.as_str()).cmp(&(&b.name, b.location.uri.as_str()))
+        });
+        Ok(WorkspaceSymbolResponse::Flat(result))
+    }
+
+    fn completion_options(
+        &self,
+        params: CompletionParams,
+        initialize_params: &InitializeParams,
+    ) -> anyhow::Result<CompletionResponse> {
+        let uri = params.text_document_position.text_document.uri.try_into()?;
+        let line = params.text_document_position.position.line;
+        let character = params.text_document_position.position.character;
+
+        let workspace_root =
+            Self::get_workspace_root(initialize_params.workspace_folders.as_ref(), &uri);
+        // After a `.`, the document usually does not parse, so that is handled separately.
+        if let Some(symbols) =
+            self.attribute_completion_options(&uri, line, character, workspace_root.as_deref())
+        {
+            return Ok(CompletionResponse::Array(symbols));
+        }
+
+        let symbols: Option<Vec<_>> = match self.get_ast(&uri) {
+            Some(document) => {
+                // Figure out what kind of position we are in, to determine the best type of
+                // autocomplete.
+                let autocomplete_type = document.ast.get_auto_complete_type(line, character);
 
                 match &autocomplete_type {
                     None | Some(AutocompleteType::None) => None,
@@ -826,7 +1873,7 @@ impl<T: LspContext> Backend<T> {
         let mut seen = HashSet::new();
         let mut result = Vec::new();
 
-        let all_documents = self.last_valid_parse.read().unwrap();
+        let all_documents = self.parsed_modules();
 
         for (doc_uri, doc) in all_documents
             .iter()
@@ -1166,7 +2213,7 @@ impl<T: LspContext> Backend<T> {
         })
     }
 
-    fn get_workspace_root(
+    pub(crate) fn get_workspace_root(
         workspace_roots: Option<&Vec<WorkspaceFolder>>,
         target: &LspUrl,
     ) -> Option<PathBuf> {
@@ -1184,7 +2231,7 @@ impl<T: LspContext> Backend<T> {
 
 /// The library style pieces
 impl<T: LspContext> Backend<T> {
-    fn send_notification(&self, x: Notification) {
+    pub(crate) fn send_notification(&self, x: Notification) {
         self.connection
             .sender
             .send(Message::Notification(x))
@@ -1195,7 +2242,7 @@ impl<T: LspContext> Backend<T> {
         self.connection.sender.send(Message::Response(x)).unwrap()
     }
 
-    fn log_message(&self, typ: MessageType, message: &str) {
+    pub(crate) fn log_message(&self, typ: MessageType, message: &str) {
         self.send_notification(new_notification::<LogMessage>(LogMessageParams {
             typ,
             message: message.to_owned(),
@@ -1210,11 +2257,45 @@ impl<T: LspContext> Backend<T> {
 
     fn main_loop(&self, initialize_params: InitializeParams) -> anyhow::Result<()> {
         self.log_message(MessageType::INFO, "Starlark server initialised");
-        for msg in &self.connection.receiver {
+        let mut indexer = self.start_indexing(&initialize_params);
+        loop {
+            let timer = match self.next_validation() {
+                Some(deadline) => crossbeam_channel::at(deadline),
+                None => crossbeam_channel::never(),
+            };
+            let index_events = match &indexer {
+                Some(indexer) => indexer.events.clone(),
+                None => crossbeam_channel::never(),
+            };
+            // Messages from the client come first, and files are only indexed when
+            // there is nothing else to do.
+            let msg = crossbeam_channel::select_biased! {
+                recv(self.connection.receiver) -> msg => match msg {
+                    Ok(msg) => msg,
+                    Err(_) => break,
+                },
+                recv(timer) -> _ => {
+                    self.validate_pending(&initialize_params);
+                    continue;
+                },
+                recv(index_events) -> event => {
+                    match (event, &mut indexer) {
+                        (Ok(event), Some(indexer)) => self.handle_index_event(indexer, event),
+                        (_, indexer) => {
+                            if let Some(finished) = indexer.take() {
+                                self.finish_indexing(&finished);
+                            }
+                        }
+                    }
+                    continue;
+                },
+            };
             match msg {
                 Message::Request(req) => {
-                    // TODO(nmj): Also implement DocumentSymbols so that some logic can
-                    //            be handled client side.
+                    // Requests should see the latest version of the document they are about.
+                    if let Some(uri) = request_document(&req) {
+                        self.refresh_ast(&uri);
+                    }
                     if let Some(params) = as_request::<GotoDefinition>(&req) {
                         self.goto_definition(req.id, params, &initialize_params);
                     } else if let Some(params) = as_request::<StarlarkFileContentsRequest>(&req) {
@@ -1223,6 +2304,42 @@ impl<T: LspContext> Backend<T> {
                         self.completion(req.id, params, &initialize_params);
                     } else if let Some(params) = as_request::<HoverRequest>(&req) {
                         self.hover(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<SignatureHelpRequest>(&req) {
+                        self.signature_help(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<CodeActionRequest>(&req) {
+                        self.code_actions(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<InlayHintRequest>(&req) {
+                        self.inlay_hints(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<SemanticTokensFullRequest>(&req) {
+                        self.semantic_tokens_full(req.id, params);
+                    } else if let Some(params) = as_request::<SemanticTokensRangeRequest>(&req) {
+                        self.semantic_tokens_range(req.id, params);
+                    } else if let Some(params) = as_request::<References>(&req) {
+                        self.references(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<CallHierarchyPrepare>(&req) {
+                        self.prepare_call_hierarchy(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<CallHierarchyIncomingCalls>(&req) {
+                        self.incoming_calls(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<CallHierarchyOutgoingCalls>(&req) {
+                        self.outgoing_calls(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<DocumentHighlightRequest>(&req) {
+                        self.document_highlight(req.id, params);
+                    } else if let Some(params) = as_request::<FoldingRangeRequest>(&req) {
+                        self.folding_ranges(req.id, params);
+                    } else if let Some(params) = as_request::<SelectionRangeRequest>(&req) {
+                        self.selection_ranges(req.id, params);
+                    } else if let Some(params) = as_request::<Formatting>(&req) {
+                        self.formatting(req.id, params);
+                    } else if let Some(params) = as_request::<RangeFormatting>(&req) {
+                        self.range_formatting(req.id, params);
+                    } else if let Some(params) = as_request::<PrepareRenameRequest>(&req) {
+                        self.prepare_rename(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<Rename>(&req) {
+                        self.rename(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<DocumentSymbolRequest>(&req) {
+                        self.document_symbols(req.id, params);
+                    } else if let Some(params) = as_request::<WorkspaceSymbolRequest>(&req) {
+                        self.workspace_symbols(req.id, params);
                     } else if self.connection.handle_shutdown(&req)? {
                         return Ok(());
                     }
@@ -1230,7 +2347,7 @@ impl<T: LspContext> Backend<T> {
                 }
                 Message::Notification(x) => {
                     if let Some(params) = as_notification::<DidOpenTextDocument>(&x) {
-                        self.did_open(params)?;
+                        self.did_open(params, &initialize_params)?;
                     } else if let Some(params) = as_notification::<DidChangeTextDocument>(&x) {
                         self.did_change(params)?;
                     } else if let Some(params) = as_notification::<DidCloseTextDocument>(&x) {
@@ -1269,12 +2386,12 @@ pub fn server_with_connection<T: LspContext>(
     let (init_request_id, init_value) = connection.initialize_start()?;
 
     let initialization_params: InitializeParams = serde_json::from_value(init_value)?;
-    let server_settings = initialization_params
+    let server_settings: LspServerSettings = initialization_params
         .initialization_options
         .as_ref()
         .and_then(|opts| serde_json::from_value(opts.clone()).ok())
         .unwrap_or_default();
-    let capabilities_payload = Backend::<T>::server_capabilities(server_settings);
+    let capabilities_payload = Backend::<T>::server_capabilities(server_settings.clone());
     let server_capabilities = serde_json::to_value(capabilities_payload).unwrap();
 
     let initialize_data = serde_json::json!({
@@ -1285,13 +2402,24 @@ pub fn server_with_connection<T: LspContext>(
     Backend {
         connection,
         context,
+        settings: server_settings,
         last_valid_parse: RwLock::default(),
+        workspace_index: RwLock::default(),
+        documents: RwLock::default(),
+        pending_validation: Mutex::default(),
+        stale_asts: Mutex::default(),
     }
     .main_loop(initialization_params)?;
 
     Ok(())
 }
 
+/// The document a request is about, for the requests that name one.
+fn request_document(req: &Request) -> Option<LspUrl> {
+    let uri = req.params.get("textDocument")?.get("uri")?;
+    serde_json::from_value(uri.clone()).ok()
+}
+
 fn as_notification<T>(x: &Notification) -> Option<T::Params>
 where
     T: lsp_types::notification::Notification,
@@ -1365,21 +2493,91 @@ where
 //            some paths. Revisit later.
 #[cfg(all(test, not(windows)))]
 mod tests {
+    use std::fs;
     use std::path::Path;
     use std::path::PathBuf;
 
     use anyhow::Context;
     use lsp_server::Request;
     use lsp_server::RequestId;
+    use lsp_types::notification::LogMessage;
+    use lsp_types::notification::PublishDiagnostics;
+    use lsp_types::request::CallHierarchyIncomingCalls;
+    use lsp_types::request::CallHierarchyOutgoingCalls;
+    use lsp_types::request::CallHierarchyPrepare;
+    use lsp_types::request::CodeActionRequest;
+    use lsp_types::request::Completion;
+    use lsp_types::request::DocumentHighlightRequest;
+    use lsp_types::request::DocumentSymbolRequest;
+    use lsp_types::request::FoldingRangeRequest;
+    use lsp_types::request::Formatting;
     use lsp_types::request::GotoDefinition;
+    use lsp_types::request::InlayHintRequest;
+    use lsp_types::request::PrepareRenameRequest;
+    use lsp_types::request::RangeFormatting;
+    use lsp_types::request::References;
+    use lsp_types::request::Rename;
+    use lsp_types::request::SelectionRangeRequest;
+    use lsp_types::request::SemanticTokensFullRequest;
+    use lsp_types::request::SemanticTokensRangeRequest;
+    use lsp_types::request::SignatureHelpRequest;
+    use lsp_types::request::WorkspaceSymbolRequest;
+    use lsp_types::CallHierarchyIncomingCall;
+    use lsp_types::CallHierarchyIncomingCallsParams;
+    use lsp_types::CallHierarchyItem;
+    use lsp_types::CallHierarchyOutgoingCall;
+    use lsp_types::CallHierarchyOutgoingCallsParams;
+    use lsp_types::CallHierarchyPrepareParams;
+    use lsp_types::CodeActionContext;
+    use lsp_types::CodeActionOrCommand;
+    use lsp_types::CodeActionParams;
+    use lsp_types::CompletionItem;
+    use lsp_types::CompletionItemKind;
+    use lsp_types::CompletionParams;
+    use lsp_types::CompletionResponse;
+    use lsp_types::Diagnostic;
+    use lsp_types::DiagnosticSeverity;
+    use lsp_types::DocumentFormattingParams;
+    use lsp_types::DocumentHighlight;
+    use lsp_types::DocumentHighlightKind;
+    use lsp_types::DocumentHighlightParams;
+    use lsp_types::DocumentRangeFormattingParams;
+    use lsp_types::DocumentSymbolParams;
+    use lsp_types::DocumentSymbolResponse;
+    use lsp_types::FoldingRange;
+    use lsp_types::FoldingRangeParams;
+    use lsp_types::FormattingOptions;
     use lsp_types::GotoDefinitionParams;
     use lsp_types::GotoDefinitionResponse;
+    use lsp_types::InlayHint;
+    use lsp_types::InlayHintLabel;
+    use lsp_types::InlayHintParams;
+    use lsp_types::Location;
     use lsp_types::LocationLink;
+    use lsp_types::NumberOrString;
     use lsp_types::Position;
+    use lsp_types::PrepareRenameResponse;
     use lsp_types::Range;
+    use lsp_types::ReferenceContext;
+    use lsp_types::ReferenceParams;
+    use lsp_types::RenameParams;
+    use lsp_types::SelectionRange;
+    use lsp_types::SelectionRangeParams;
+    use lsp_types::SemanticTokensParams;
+    use lsp_types::SemanticTokensRangeParams;
+    use lsp_types::SemanticTokensRangeResult;
+    use lsp_types::SemanticTokensResult;
+    use lsp_types::SignatureHelp;
+    use lsp_types::SignatureHelpParams;
+    use lsp_types::SymbolKind;
     use lsp_types::TextDocumentIdentifier;
     use lsp_types::TextDocumentPositionParams;
+    use lsp_types::TextEdit;
     use lsp_types::Url;
+    use lsp_types::WorkspaceEdit;
+    use lsp_types::WorkspaceFolder;
+    use lsp_types::WorkspaceSymbolParams;
+    use lsp_types::WorkspaceSymbolResponse;
     use starlark::codemap::ResolvedSpan;
     use starlark::wasm::is_wasm;
     use textwrap::dedent;
@@ -2220,6 +3418,60 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn reports_type_errors_when_enabled() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let lib_uri = temp_file_uri("lib.star");
+        let bar_uri = temp_file_uri("bar.star");
+
+        let lib_contents = dedent(
+            r#"
+            def double(x: int) -> int:
+                return x * 2
+            "#,
+        )
+        .trim()
+        .to_owned();
+        // Only the bodies of functions are typechecked.
+        let bar_contents = |arg: &str| {
+            format!(
+                "load(\"{}\", \"double\")\ndef f():\n    return double({})\n",
+                lib_uri.path(),
+                arg
+            )
+        };
+
+        for enable_typecheck in [false, true] {
+            let mut server = TestServer::new_with_settings(Some(LspServerSettings {
+                enable_typecheck,
+                ..LspServerSettings::default()
+            }))?;
+            server.open_file(lib_uri.clone(), lib_contents.clone())?;
+            server.open_file(bar_uri.clone(), bar_contents("1"))?;
+
+            server.change_file(bar_uri.clone(), bar_contents("\"a\""))?;
+            let diagnostics = server.get_notification::<PublishDiagnostics>()?;
+            assert_eq!(bar_uri, diagnostics.uri);
+            if enable_typecheck {
+                assert_eq!(1, diagnostics.diagnostics.len());
+                let diagnostic = &diagnostics.diagnostics[0];
+                assert_eq!(Some(DiagnosticSeverity::ERROR), diagnostic.severity);
+                assert_eq!(2, diagnostic.range.start.line);
+                assert!(
+                    diagnostic.message.contains("int"),
+                    "Unexpected message: {}",
+                    diagnostic.message
+                );
+            } else {
+                assert!(diagnostics.diagnostics.is_empty());
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn disables_goto_definition() -> anyhow::Result<()> {
         if is_wasm() {
@@ -2228,6 +3480,7 @@ mod tests {
 
         let server = TestServer::new_with_settings(Some(LspServerSettings {
             enable_goto_definition: false,
+            ..LspServerSettings::default()
         }))?;
 
         let goto_definition_disabled = server
@@ -2241,6 +3494,7 @@ mod tests {
 
         let server = TestServer::new_with_settings(Some(LspServerSettings {
             enable_goto_definition: true,
+            ..LspServerSettings::default()
         }))?;
 
         let goto_definition_enabled = server
@@ -2493,4 +3747,1290 @@ mod tests {
         }
         Ok(())
     }
+
+    fn references_request(
+        server: &mut TestServer,
+        uri: Url,
+        line: u32,
+        character: u32,
+        include_declaration: bool,
+    ) -> Request {
+        server.new_request::<References>(ReferenceParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: Position { line, character },
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: ReferenceContext {
+                include_declaration,
+            },
+        })
+    }
+
+    #[test]
+    fn finds_local_references() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("file.star");
+        let contents = dedent(
+            r#"
+            def f(<x1>x</x1>):
+                return <x2>x</x2> + 1
+            def g(x):
+                return x
+            f(<x3>x</x3> = 1)
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let fixture = FixtureWithRanges::from_fixture(uri.path(), &contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), fixture.program())?;
+
+        let request = references_request(
+            &mut server,
+            uri.clone(),
+            fixture.begin_line("x2"),
+            fixture.begin_column("x2"),
+            true,
+        );
+        let request_id = server.send_request(request)?;
+        let response = server.get_response::<Vec<Location>>(request_id)?;
+        let expected = vec![
+            Location::new(uri.clone(), fixture.resolved_span("x1").into()),
+            Location::new(uri.clone(), fixture.resolved_span("x2").into()),
+        ];
+        assert_eq!(expected, response);
+
+        let request = references_request(
+            &mut server,
+            uri.clone(),
+            fixture.begin_line("x1"),
+            fixture.begin_column("x1"),
+            false,
+        );
+        let request_id = server.send_request(request)?;
+        let response = server.get_response::<Vec<Location>>(request_id)?;
+        assert_eq!(expected[1..], response);
+
+        // A named argument is not a variable.
+        let request = references_request(
+            &mut server,
+            uri,
+            fixture.begin_line("x3"),
+            fixture.begin_column("x3"),
+            true,
+        );
+        let request_id = server.send_request(request)?;
+        let response = server.get_response::<Vec<Location>>(request_id)?;
+        assert!(response.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn offers_call_hierarchy_across_loads() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let lib_uri = temp_file_uri("lib.star");
+        let main_uri = temp_file_uri("main.star");
+
+        let lib_contents = dedent(
+            r#"
+            def <helper>helper</helper>():
+                pass
+            def <macro>macro</macro>():
+                <call1>helper</call1>()
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let main_contents = dedent(
+            r#"
+            load("{load}", "macro")
+            def <rule>rule</rule>():
+                <call2>macro</call2>()
+            <call3>macro</call3>()
+            "#,
+        )
+        .replace("{load}", lib_uri.path())
+        .trim()
+        .to_owned();
+
+        let lib = FixtureWithRanges::from_fixture(lib_uri.path(), &lib_contents)?;
+        let main = FixtureWithRanges::from_fixture(main_uri.path(), &main_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(lib_uri.clone(), lib.program())?;
+        server.open_file(main_uri.clone(), main.program())?;
+
+        let call2 = main.resolved_span("call2");
+        let request = server.new_request::<CallHierarchyPrepare>(CallHierarchyPrepareParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier {
+                    uri: main_uri.clone(),
+                },
+                position: Position::new(call2.begin.line as u32, call2.begin.column as u32),
+            },
+            work_done_progress_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let items = server.get_response::<Vec<CallHierarchyItem>>(request_id)?;
+        assert_eq!(1, items.len());
+        let item = items[0].clone();
+        assert_eq!("macro", item.name);
+        assert_eq!(lib_uri, item.uri);
+        assert_eq!(
+            Range::from(lib.resolved_span("macro")),
+            item.selection_range
+        );
+
+        let request =
+            server.new_request::<CallHierarchyIncomingCalls>(CallHierarchyIncomingCallsParams {
+                item: item.clone(),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            });
+        let request_id = server.send_request(request)?;
+        let calls = server.get_response::<Vec<CallHierarchyIncomingCall>>(request_id)?;
+        let calls: Vec<(String, Url, Vec<Range>)> = calls
+            .into_iter()
+            .map(|call| (call.from.name, call.from.uri, call.from_ranges))
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    "rule".to_owned(),
+                    main_uri.clone(),
+                    vec![Range::from(call2)]
+                ),
+                (
+                    "main.star".to_owned(),
+                    main_uri.clone(),
+                    vec![Range::from(main.resolved_span("call3"))]
+                ),
+            ],
+            calls
+        );
+
+        let request =
+            server.new_request::<CallHierarchyOutgoingCalls>(CallHierarchyOutgoingCallsParams {
+                item,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            });
+        let request_id = server.send_request(request)?;
+        let calls = server.get_response::<Vec<CallHierarchyOutgoingCall>>(request_id)?;
+        assert_eq!(1, calls.len());
+        assert_eq!("helper", calls[0].to.name);
+        assert_eq!(
+            Range::from(lib.resolved_span("helper")),
+            calls[0].to.selection_range
+        );
+        assert_eq!(
+            vec![Range::from(lib.resolved_span("call1"))],
+            calls[0].from_ranges
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn indexes_workspace_for_completion() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let root = std::env::temp_dir().join(format!("starlark_lsp_index_{}", std::process::id()));
+        let _ignored = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("lib"))?;
+        fs::write(
+            root.join("lib").join("defs.star"),
+            "def indexed_macro():\n    pass\n",
+        )?;
+        let main_uri = Url::from_file_path(root.join("main.star")).unwrap();
+
+        let mut server = TestServer::new_with_workspace(
+            Some(LspServerSettings {
+                index_extensions: vec!["star".to_owned()],
+                ..LspServerSettings::default()
+            }),
+            Some(vec![WorkspaceFolder {
+                uri: Url::from_directory_path(&root).unwrap(),
+                name: "root".to_owned(),
+            }]),
+        )?;
+        loop {
+            let message = server.get_notification::<LogMessage>()?.message;
+            if message.starts_with("Indexed") {
+                assert_eq!("Indexed 1 files in the workspace", message);
+                break;
+            }
+        }
+        server.open_file(main_uri.clone(), "ind()\n".to_owned())?;
+
+        let request = server.new_request::<Completion>(CompletionParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: main_uri },
+                position: Position::new(0, 3),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        });
+        let request_id = server.send_request(request)?;
+        let response = server.get_response::<CompletionResponse>(request_id)?;
+        fs::remove_dir_all(&root)?;
+        let CompletionResponse::Array(items) = response else {
+            panic!("Expected an array of completions, got {:?}", response);
+        };
+        let item = items
+            .iter()
+            .find(|item| item.label == "indexed_macro")
+            .expect("the symbol exported by the unopened file is offered");
+        let edits = item.additional_text_edits.as_ref().unwrap();
+        assert_eq!(1, edits.len());
+        assert!(edits[0].new_text.starts_with("load(\""));
+        assert!(edits[0]
+            .new_text
+            .contains("defs.star\", \"indexed_macro\")"));
+        Ok(())
+    }
+
+    #[test]
+    fn completes_attributes_from_types() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("file.star");
+        let mut server = TestServer::new()?;
+        server.open_file(
+            uri.clone(),
+            "def f(s: str, xs: list[int], n: int):\n    return s.up(xs, n)\n".to_owned(),
+        )?;
+
+        let complete = |server: &mut TestServer,
+                        line: u32,
+                        character: u32|
+         -> anyhow::Result<Vec<CompletionItem>> {
+            let request = server.new_request::<Completion>(CompletionParams {
+                text_document_position: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier { uri: uri.clone() },
+                    position: Position::new(line, character),
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: None,
+            });
+            let request_id = server.send_request(request)?;
+            match server.get_response::<CompletionResponse>(request_id)? {
+                CompletionResponse::Array(items) => Ok(items),
+                response => panic!("Expected an array of completions, got {:?}", response),
+            }
+        };
+
+        let items = complete(&mut server, 1, 14)?;
+        let upper = items
+            .iter()
+            .find(|item| item.label == "upper")
+            .expect("the methods of `str` are offered");
+        assert_eq!(Some(CompletionItemKind::METHOD), upper.kind);
+        assert!(upper.documentation.is_some());
+        assert!(items.iter().all(|item| item.label != "f"));
+
+        // The document does not parse with nothing after the `.`.
+        server.change_file(
+            uri.clone(),
+            "def f(s: str, xs: list[int], n: int):\n    xs.\n".to_owned(),
+        )?;
+        let items = complete(&mut server, 1, 7)?;
+        assert!(items.iter().any(|item| item.label == "append"));
+        assert!(items.iter().all(|item| item.label != "upper"));
+
+        // An `int` has no attributes, and names in scope are not valid after a `.`.
+        server.change_file(
+            uri.clone(),
+            "def f(s: str, xs: list[int], n: int):\n    n.\n".to_owned(),
+        )?;
+        assert_eq!(Vec::<CompletionItem>::new(), complete(&mut server, 1, 6)?);
+        Ok(())
+    }
+
+    #[test]
+    fn finds_references_across_loads() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let lib_uri = temp_file_uri("lib.star");
+        let bar_uri = temp_file_uri("bar.star");
+        let baz_uri = temp_file_uri("baz.star");
+
+        let lib_contents = dedent(
+            r#"
+            def <lib1>foo</lib1>():
+                pass
+            <lib2>foo</lib2>()
+            def unrelated(foo):
+                return foo
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let bar_contents = dedent(
+            r#"
+            load("{load}", "<bar1>foo</bar1>")
+            <bar2>foo</bar2>()
+            "#,
+        )
+        .replace("{load}", lib_uri.path())
+        .trim()
+        .to_owned();
+        let baz_contents = dedent(
+            r#"
+            load("{load}", <baz2>renamed</baz2> = "<baz1>foo</baz1>")
+            <baz3>renamed</baz3>.attr
+            "#,
+        )
+        .replace("{load}", lib_uri.path())
+        .trim()
+        .to_owned();
+
+        let lib = FixtureWithRanges::from_fixture(lib_uri.path(), &lib_contents)?;
+        let bar = FixtureWithRanges::from_fixture(bar_uri.path(), &bar_contents)?;
+        let baz = FixtureWithRanges::from_fixture(baz_uri.path(), &baz_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(lib_uri.clone(), lib.program())?;
+        server.open_file(bar_uri.clone(), bar.program())?;
+        server.open_file(baz_uri.clone(), baz.program())?;
+
+        let expected = vec![
+            Location::new(lib_uri.clone(), lib.resolved_span("lib1").into()),
+            Location::new(lib_uri.clone(), lib.resolved_span("lib2").into()),
+            Location::new(bar_uri.clone(), bar.resolved_span("bar1").into()),
+            Location::new(bar_uri.clone(), bar.resolved_span("bar2").into()),
+            Location::new(baz_uri.clone(), baz.resolved_span("baz1").into()),
+            Location::new(baz_uri.clone(), baz.resolved_span("baz2").into()),
+            Location::new(baz_uri.clone(), baz.resolved_span("baz3").into()),
+        ];
+
+        let cases = [
+            (&lib, &lib_uri, "lib1"),
+            (&lib, &lib_uri, "lib2"),
+            (&bar, &bar_uri, "bar1"),
+            (&bar, &bar_uri, "bar2"),
+            (&baz, &baz_uri, "baz1"),
+            (&baz, &baz_uri, "baz3"),
+        ];
+        for (fixture, uri, id) in cases {
+            let request = references_request(
+                &mut server,
+                uri.clone(),
+                fixture.begin_line(id),
+                fixture.begin_column(id),
+                true,
+            );
+            let request_id = server.send_request(request)?;
+            let response = server.get_response::<Vec<Location>>(request_id)?;
+            assert_eq!(expected, response, "Incorrect response for case `{}`", id);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn offers_signature_help() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let lib_uri = temp_file_uri("lib.star");
+        let bar_uri = temp_file_uri("bar.star");
+
+        let lib_contents = dedent(
+            r#"
+            def foo(a, *args, b, **kwargs):
+                return [a, args, b, kwargs]
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let bar_contents = dedent(
+            r#"
+            load("{load}", "foo")
+            def local(x, y = 1):
+                return x + y
+            foo(1, 2, b = <foo_b>3</foo_b>)
+            foo(1, <foo_args>2</foo_args>, c = <foo_kwargs>3</foo_kwargs>)
+            local(1, <local_y>2</local_y>)
+            native_function1(<native>)</native>
+            "#,
+        )
+        .replace("{load}", lib_uri.path())
+        .trim()
+        .to_owned();
+
+        let bar = FixtureWithRanges::from_fixture(bar_uri.path(), &bar_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(lib_uri, lib_contents)?;
+        server.open_file(bar_uri.clone(), bar.program())?;
+
+        let cases = [
+            ("foo_b", "foo(a, *args, b, **kwargs)", Some(2)),
+            ("foo_args", "foo(a, *args, b, **kwargs)", Some(1)),
+            ("foo_kwargs", "foo(a, *args, b, **kwargs)", Some(3)),
+            ("local_y", "local(x, y)", Some(1)),
+            ("native", "native_function1()", None),
+        ];
+        for (id, label, active_parameter) in cases {
+            let request = server.new_request::<SignatureHelpRequest>(SignatureHelpParams {
+                context: None,
+                text_document_position_params: TextDocumentPositionParams {
+                    text_document: TextDocumentIdentifier {
+                        uri: bar_uri.clone(),
+                    },
+                    position: Position {
+                        line: bar.begin_line(id),
+                        character: bar.begin_column(id),
+                    },
+                },
+                work_done_progress_params: Default::default(),
+            });
+            let request_id = server.send_request(request)?;
+            let response = server.get_response::<SignatureHelp>(request_id)?;
+            assert_eq!(
+                1,
+                response.signatures.len(),
+                "Incorrect response for `{}`",
+                id
+            );
+            assert_eq!(
+                label, response.signatures[0].label,
+                "Incorrect label for `{}`",
+                id
+            );
+            assert_eq!(
+                active_parameter, response.active_parameter,
+                "Incorrect active parameter for `{}`",
+                id
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn offers_inlay_hints() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let lib_uri = temp_file_uri("lib.star");
+        let bar_uri = temp_file_uri("bar.star");
+
+        let lib_contents =
+            "def scale(value: int, factor: int) -> int:\n    return value * factor\n".to_owned();
+        let bar_contents = dedent(
+            r#"
+            load("{load}", "scale")
+            def f(<f>)</f>:
+                <x>x</x> = "x"
+                return scale(<value>1</value>, <factor>len(x)</factor>)
+            "#,
+        )
+        .replace("{load}", lib_uri.path())
+        .trim()
+        .to_owned();
+        let bar = FixtureWithRanges::from_fixture(bar_uri.path(), &bar_contents)?;
+        let end = |id: &str| Range::from(bar.resolved_span(id)).end;
+        let start = |id: &str| Range::from(bar.resolved_span(id)).start;
+
+        for enable_typecheck in [false, true] {
+            let mut server = TestServer::new_with_settings(Some(LspServerSettings {
+                enable_typecheck,
+                ..LspServerSettings::default()
+            }))?;
+            server.open_file(lib_uri.clone(), lib_contents.clone())?;
+            server.open_file(bar_uri.clone(), bar.program())?;
+
+            let request = server.new_request::<InlayHintRequest>(InlayHintParams {
+                work_done_progress_params: Default::default(),
+                text_document: TextDocumentIdentifier {
+                    uri: bar_uri.clone(),
+                },
+                range: Range::new(Position::new(0, 0), Position::new(10, 0)),
+            });
+            let request_id = server.send_request(request)?;
+            let response = server.get_response::<Vec<InlayHint>>(request_id)?;
+            let hints: Vec<_> = response
+                .iter()
+                .map(|hint| match &hint.label {
+                    InlayHintLabel::String(s) => (hint.position, s.as_str()),
+                    InlayHintLabel::LabelParts(_) => panic!("Unexpected label parts"),
+                })
+                .collect();
+
+            let mut expected = vec![(start("value"), "value:"), (start("factor"), "factor:")];
+            if enable_typecheck {
+                expected.insert(0, (end("f"), " -> int"));
+                expected.insert(1, (end("x"), ": str"));
+            }
+            assert_eq!(expected, hints);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn offers_code_actions() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let lib_uri = temp_file_uri("lib.star");
+        let bar_uri = temp_file_uri("bar.star");
+
+        let bar_contents = dedent(
+            r#"
+            <unused>load(":other.star", "unused")
+            </unused>x = <dict>dict(**{"a": 1})</dict>
+            print(<helper>helper</helper>(x))
+            "#,
+        )
+        .trim_start()
+        .to_owned();
+        let bar = FixtureWithRanges::from_fixture(bar_uri.path(), &bar_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(lib_uri, "def helper(x):\n    return x\n".to_owned())?;
+        server.open_file(bar_uri.clone(), String::new())?;
+        server.change_file(bar_uri.clone(), bar.program())?;
+        let mut diagnostics = server.get_notification::<PublishDiagnostics>()?.diagnostics;
+        // The test context lints without globals, so it does not report undefined variables.
+        diagnostics.push(Diagnostic {
+            code: Some(NumberOrString::String("using-undefined".to_owned())),
+            ..Diagnostic::new_simple(bar.resolved_span("helper").into(), String::new())
+        });
+
+        let request = server.new_request::<CodeActionRequest>(CodeActionParams {
+            text_document: TextDocumentIdentifier {
+                uri: bar_uri.clone(),
+            },
+            range: Range::new(Position::new(0, 0), Position::new(3, 0)),
+            context: CodeActionContext {
+                diagnostics,
+                only: None,
+                trigger_kind: None,
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let response = server.get_response::<Vec<CodeActionOrCommand>>(request_id)?;
+        let mut actions: Vec<_> = response
+            .into_iter()
+            .map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => {
+                    let mut changes = action.edit.unwrap().changes.unwrap();
+                    (action.title, changes.remove(&bar_uri).unwrap())
+                }
+                CodeActionOrCommand::Command(_) => panic!("Unexpected command"),
+            })
+            .collect();
+        actions.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let unused = bar.resolved_span("unused");
+        let expected = vec![
+            (
+                "Load `helper` from `:lib.star`".to_owned(),
+                vec![TextEdit::new(
+                    Range::new(Position::new(0, 29), Position::new(0, 29)),
+                    "\nload(\":lib.star\", \"helper\")".to_owned(),
+                )],
+            ),
+            (
+                "Remove unused load".to_owned(),
+                vec![TextEdit::new(unused.into(), String::new())],
+            ),
+            (
+                "Replace with `dict({\"a\": 1})`".to_owned(),
+                vec![TextEdit::new(
+                    bar.resolved_span("dict").into(),
+                    "dict({\"a\": 1})".to_owned(),
+                )],
+            ),
+        ];
+        assert_eq!(expected, actions);
+        Ok(())
+    }
+
+    #[test]
+    fn offers_semantic_tokens() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("foo.star");
+        let contents = "def f(x):\n    return native_function1(x)\n".to_owned();
+
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), contents)?;
+
+        let request = server.new_request::<SemanticTokensFullRequest>(SemanticTokensParams {
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+        });
+        let request_id = server.send_request(request)?;
+        let response = server.get_response::<SemanticTokensResult>(request_id)?;
+        let SemanticTokensResult::Tokens(tokens) = response else {
+            panic!("Expected full tokens, got {:?}", response);
+        };
+        // `f`, `x`, `native_function1` and `x`.
+        assert_eq!(4, tokens.data.len());
+        // `native_function1` is a builtin function.
+        assert_eq!((1, 11, 16, 2), {
+            let t = &tokens.data[2];
+            (t.delta_line, t.delta_start, t.length, t.token_type)
+        });
+
+        let request = server.new_request::<SemanticTokensRangeRequest>(SemanticTokensRangeParams {
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            text_document: TextDocumentIdentifier { uri },
+            range: Range::new(Position::new(1, 0), Position::new(2, 0)),
+        });
+        let request_id = server.send_request(request)?;
+        let response = server.get_response::<SemanticTokensRangeResult>(request_id)?;
+        let SemanticTokensRangeResult::Tokens(tokens) = response else {
+            panic!("Expected tokens, got {:?}", response);
+        };
+        assert_eq!(2, tokens.data.len());
+        Ok(())
+    }
+
+    #[test]
+    fn offers_folding_selection_and_highlights() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("foo.star");
+        let contents = "def f(x):\n    y = [\n        x,\n    ]\n    y += [x]\n    return y\n";
+
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), contents.to_owned())?;
+
+        let request = server.new_request::<FoldingRangeRequest>(FoldingRangeParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let ranges = server.get_response::<Vec<FoldingRange>>(request_id)?;
+        assert_eq!(
+            vec![(0, 5), (1, 3)],
+            ranges
+                .iter()
+                .map(|range| (range.start_line, range.end_line))
+                .collect::<Vec<_>>()
+        );
+
+        let request = server.new_request::<SelectionRangeRequest>(SelectionRangeParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            positions: vec![Position::new(2, 8)],
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let selections = server.get_response::<Vec<SelectionRange>>(request_id)?;
+        assert_eq!(1, selections.len());
+        assert_eq!(
+            Range::new(Position::new(2, 8), Position::new(2, 9)),
+            selections[0].range
+        );
+        let parent = selections[0].parent.as_ref().unwrap();
+        assert_eq!(
+            Range::new(Position::new(1, 8), Position::new(3, 5)),
+            parent.range
+        );
+
+        let request = server.new_request::<DocumentHighlightRequest>(DocumentHighlightParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: Position::new(5, 11),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let highlights = server.get_response::<Vec<DocumentHighlight>>(request_id)?;
+        assert_eq!(
+            vec![
+                (1, DocumentHighlightKind::WRITE),
+                (4, DocumentHighlightKind::WRITE),
+                (5, DocumentHighlightKind::READ),
+            ],
+            highlights
+                .iter()
+                .map(|highlight| (highlight.range.start.line, highlight.kind.unwrap()))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn formats_documents_and_ranges() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("foo.star");
+        let contents = "x=[1,2]\ny  =  'a'\n";
+
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), contents.to_owned())?;
+
+        let request = server.new_request::<Formatting>(DocumentFormattingParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            options: FormattingOptions::default(),
+            work_done_progress_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let edits = server.get_response::<Vec<TextEdit>>(request_id)?;
+        assert_eq!(
+            vec![TextEdit {
+                range: Range::new(Position::new(0, 0), Position::new(2, 0)),
+                new_text: "x = [1, 2]\ny = \"a\"\n".to_owned(),
+            }],
+            edits
+        );
+
+        let request = server.new_request::<RangeFormatting>(DocumentRangeFormattingParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            range: Range::new(Position::new(1, 0), Position::new(1, 1)),
+            options: FormattingOptions::default(),
+            work_done_progress_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let edits = server.get_response::<Vec<TextEdit>>(request_id)?;
+        assert_eq!(
+            vec![TextEdit {
+                range: Range::new(Position::new(1, 0), Position::new(2, 0)),
+                new_text: "y = \"a\"\n".to_owned(),
+            }],
+            edits
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn applies_incremental_changes() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("foo.star");
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), "x = 1\n".to_owned())?;
+
+        let range = |start: (u32, u32), end: (u32, u32)| {
+            Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+        };
+        server.edit_file(uri.clone(), range((0, 4), (0, 5)), "[]")?;
+        server.edit_file(uri.clone(), range((1, 0), (1, 0)), "y = )\n")?;
+        // Only the final version is parsed, once the edits stop.
+        let diagnostics = server.get_notification::<PublishDiagnostics>()?;
+        assert_eq!(1, diagnostics.diagnostics.len());
+        assert_eq!(1, diagnostics.diagnostics[0].range.start.line);
+
+        server.edit_file(uri.clone(), range((1, 4), (1, 5)), "x")?;
+        // Requests see the latest text without waiting.
+        let request = server.new_request::<DocumentSymbolRequest>(DocumentSymbolParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let symbols = match server.get_response::<DocumentSymbolResponse>(request_id)? {
+            DocumentSymbolResponse::Nested(symbols) => symbols,
+            response => panic!("Expected nested symbols, got {:?}", response),
+        };
+        assert_eq!(
+            vec!["x", "y"],
+            symbols.iter().map(|s| s.name.as_str()).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn requests_do_not_publish_pending_diagnostics() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("foo.star");
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), "x = 1\n".to_owned())?;
+
+        let range = Range::new(Position::new(1, 0), Position::new(1, 0));
+        server.edit_file(uri.clone(), range, "y = 2\n")?;
+        let request = server.new_request::<DocumentSymbolRequest>(DocumentSymbolParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let symbols = match server.get_response::<DocumentSymbolResponse>(request_id)? {
+            DocumentSymbolResponse::Nested(symbols) => symbols,
+            response => panic!("Expected nested symbols, got {:?}", response),
+        };
+        assert_eq!(
+            vec!["x", "y"],
+            symbols.iter().map(|s| s.name.as_str()).collect::<Vec<_>>()
+        );
+        // The diagnostics wait until the document stops changing.
+        assert!(!server.has_notification::<PublishDiagnostics>());
+        let diagnostics = server.get_notification::<PublishDiagnostics>()?;
+        assert_eq!(uri, diagnostics.uri);
+        assert!(diagnostics.diagnostics.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn ignores_incremental_changes_to_unopened_files() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("foo.star");
+        let mut server = TestServer::new()?;
+        // Drain the message sent on startup.
+        server.get_notification::<LogMessage>()?;
+
+        let range = Range::new(Position::new(0, 0), Position::new(0, 0));
+        server.edit_file(uri.clone(), range, "y = )\n")?;
+        let message = server.get_notification::<LogMessage>()?.message;
+        assert!(
+            message.starts_with("Ignoring incremental changes to unopened file"),
+            "{}",
+            message
+        );
+
+        // A change of the whole text is enough to know the contents.
+        server.change_file(uri, "y = )\n".to_owned())?;
+        let diagnostics = server.get_notification::<PublishDiagnostics>()?;
+        assert_eq!(1, diagnostics.diagnostics.len());
+        Ok(())
+    }
+
+    fn rename_request(
+        server: &mut TestServer,
+        uri: Url,
+        line: u32,
+        character: u32,
+        new_name: &str,
+    ) -> Request {
+        server.new_request::<Rename>(RenameParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: Position { line, character },
+            },
+            new_name: new_name.to_owned(),
+            work_done_progress_params: Default::default(),
+        })
+    }
+
+    /// The files and ranges that a rename changes, in a deterministic order.
+    fn renamed_ranges(edit: WorkspaceEdit) -> Vec<(Url, Range)> {
+        let mut ranges: Vec<_> = edit
+            .changes
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|(uri, edits)| edits.into_iter().map(move |edit| (uri.clone(), edit)))
+            .map(|(uri, TextEdit { range, .. })| (uri, range))
+            .collect();
+        ranges.sort_by_key(|(uri, range)| (uri.to_string(), range.start, range.end));
+        ranges
+    }
+
+    #[test]
+    fn renames_symbols_across_loads() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let lib_uri = temp_file_uri("lib.star");
+        let bar_uri = temp_file_uri("bar.star");
+        let baz_uri = temp_file_uri("baz.star");
+
+        let lib_contents = dedent(
+            r#"
+            def <lib1>foo</lib1>():
+                pass
+            <lib2>foo</lib2>()
+            def unrelated(foo):
+                return foo
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let bar_contents = dedent(
+            r#"
+            load("{load}", "<bar1>foo</bar1>")
+            <bar2>foo</bar2>()
+            "#,
+        )
+        .replace("{load}", lib_uri.path())
+        .trim()
+        .to_owned();
+        let baz_contents = dedent(
+            r#"
+            load("{load}", <baz2>renamed</baz2> = "<baz1>foo</baz1>")
+            <baz3>renamed</baz3>.attr
+            "#,
+        )
+        .replace("{load}", lib_uri.path())
+        .trim()
+        .to_owned();
+
+        let lib = FixtureWithRanges::from_fixture(lib_uri.path(), &lib_contents)?;
+        let bar = FixtureWithRanges::from_fixture(bar_uri.path(), &bar_contents)?;
+        let baz = FixtureWithRanges::from_fixture(baz_uri.path(), &baz_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(lib_uri.clone(), lib.program())?;
+        server.open_file(bar_uri.clone(), bar.program())?;
+        server.open_file(baz_uri.clone(), baz.program())?;
+
+        let request = rename_request(
+            &mut server,
+            bar_uri.clone(),
+            bar.begin_line("bar2"),
+            bar.begin_column("bar2"),
+            "new_foo",
+        );
+        let request_id = server.send_request(request)?;
+        let edit = server.get_response::<WorkspaceEdit>(request_id)?;
+        assert!(edit
+            .changes
+            .iter()
+            .flat_map(|changes| changes.values().flatten())
+            .all(|edit| edit.new_text == "new_foo"));
+        let expected = vec![
+            (bar_uri.clone(), bar.resolved_span("bar1").into()),
+            (bar_uri.clone(), bar.resolved_span("bar2").into()),
+            (baz_uri.clone(), baz.resolved_span("baz1").into()),
+            (lib_uri.clone(), lib.resolved_span("lib1").into()),
+            (lib_uri.clone(), lib.resolved_span("lib2").into()),
+        ];
+        assert_eq!(expected, renamed_ranges(edit));
+
+        // Renaming the local name of an aliased load only changes the current file.
+        let request = rename_request(
+            &mut server,
+            baz_uri.clone(),
+            baz.begin_line("baz3"),
+            baz.begin_column("baz3"),
+            "other",
+        );
+        let request_id = server.send_request(request)?;
+        let edit = server.get_response::<WorkspaceEdit>(request_id)?;
+        let expected = vec![
+            (baz_uri.clone(), baz.resolved_span("baz2").into()),
+            (baz_uri.clone(), baz.resolved_span("baz3").into()),
+        ];
+        assert_eq!(expected, renamed_ranges(edit));
+
+        let prepare = server.new_request::<PrepareRenameRequest>(TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier {
+                uri: baz_uri.clone(),
+            },
+            position: Position {
+                line: baz.begin_line("baz1"),
+                character: baz.begin_column("baz1"),
+            },
+        });
+        let request_id = server.send_request(prepare)?;
+        let response = server.get_response::<PrepareRenameResponse>(request_id)?;
+        assert_eq!(
+            PrepareRenameResponse::Range(baz.resolved_span("baz1").into()),
+            response
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn refuses_invalid_renames() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let lib_uri = temp_file_uri("lib.star");
+        let bar_uri = temp_file_uri("bar.star");
+
+        let lib_contents = dedent(
+            r#"
+            def <foo>foo</foo>(x):
+                return x
+            def bar():
+                pass
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let bar_contents = format!("load(\"{}\", \"foo\")\nfoo(1)\n", lib_uri.path());
+        let lib = FixtureWithRanges::from_fixture(lib_uri.path(), &lib_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(lib_uri.clone(), lib.program())?;
+        server.open_file(bar_uri, bar_contents)?;
+
+        for new_name in ["bar", "not", "1foo", "_foo"] {
+            let request = rename_request(
+                &mut server,
+                lib_uri.clone(),
+                lib.begin_line("foo"),
+                lib.begin_column("foo"),
+                new_name,
+            );
+            let request_id = server.send_request(request)?;
+            assert!(
+                server.get_response::<WorkspaceEdit>(request_id).is_err(),
+                "Expected renaming to `{}` to fail",
+                new_name
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn finds_document_and_workspace_symbols() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let foo_uri = temp_file_uri("foo.star");
+        let bar_uri = temp_file_uri("bar.star");
+
+        let foo_contents = dedent(
+            r#"
+            def <build_rule>build_rule</build_rule>(<name>name</name>):
+                def <impl>impl</impl>():
+                    return name
+                return impl
+            <rules>RULES</rules> = [build_rule]
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let bar_contents = dedent(
+            r#"
+            def <build_bar>build_bar</build_bar>():
+                pass
+            def _build_private():
+                pass
+            _build_private()
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let foo = FixtureWithRanges::from_fixture(foo_uri.path(), &foo_contents)?;
+        let bar = FixtureWithRanges::from_fixture(bar_uri.path(), &bar_contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(foo_uri.clone(), foo.program())?;
+        server.open_file(bar_uri.clone(), bar.program())?;
+
+        let request = server.new_request::<DocumentSymbolRequest>(DocumentSymbolParams {
+            text_document: TextDocumentIdentifier {
+                uri: foo_uri.clone(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let symbols = match server.get_response::<DocumentSymbolResponse>(request_id)? {
+            DocumentSymbolResponse::Nested(symbols) => symbols,
+            response => {
+                return Err(anyhow::anyhow!(
+                    "Expected nested symbols, got {:?}",
+                    response
+                ))
+            }
+        };
+        assert_eq!(2, symbols.len());
+        assert_eq!("build_rule", symbols[0].name);
+        assert_eq!(
+            Range::from(foo.resolved_span("build_rule")),
+            symbols[0].selection_range
+        );
+        let children = symbols[0].children.as_deref().unwrap_or_default();
+        assert_eq!(
+            vec![
+                ("name", Range::from(foo.resolved_span("name"))),
+                ("impl", Range::from(foo.resolved_span("impl"))),
+            ],
+            children
+                .iter()
+                .map(|s| (s.name.as_str(), s.selection_range))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("RULES", symbols[1].name);
+        assert_eq!(SymbolKind::VARIABLE, symbols[1].kind);
+        assert_eq!(
+            Range::from(foo.resolved_span("rules")),
+            symbols[1].selection_range
+        );
+
+        let request = server.new_request::<WorkspaceSymbolRequest>(WorkspaceSymbolParams {
+            query: "bld".to_owned(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let symbols = match server.get_response::<WorkspaceSymbolResponse>(request_id)? {
+            WorkspaceSymbolResponse::Flat(symbols) => symbols,
+            response => return Err(anyhow::anyhow!("Expected flat symbols, got {:?}", response)),
+        };
+        assert_eq!(
+            vec![
+                (
+                    "build_bar".to_owned(),
+                    Location::new(bar_uri, bar.resolved_span("build_bar").into())
+                ),
+                (
+                    "build_rule".to_owned(),
+                    Location::new(foo_uri, foo.resolved_span("build_rule").into())
+                ),
+            ],
+            symbols
+                .into_iter()
+                .map(|s| (s.name, s.location))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reports_all_syntax_errors_and_keeps_partial_ast() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("file.star");
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), "x = 1\n".to_owned())?;
+
+        let contents = "x = 1 +\ndef f():\n    return ]\nf()\n";
+        server.change_file(uri.clone(), contents.to_owned())?;
+        let diagnostics = server.get_notification::<PublishDiagnostics>()?;
+        assert_eq!(uri, diagnostics.uri);
+        assert_eq!(
+            vec![0, 2],
+            diagnostics
+                .diagnostics
+                .iter()
+                .map(|d| d.range.start.line)
+                .collect::<Vec<_>>()
+        );
+
+        // The statements without errors are still available.
+        let goto_definition = goto_definition_request(&mut server, uri.clone(), 3, 0);
+        let request_id = server.send_request(goto_definition)?;
+        let response = goto_definition_response_location(&mut server, request_id)?;
+        assert_eq!(expected_location_link(uri, 3, 0, 1, 1, 4, 5), response);
+        Ok(())
+    }
+
+    #[test]
+    fn skips_recovered_defs_with_invalid_params() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("file.star");
+        let contents = dedent(
+            r#"
+            def f(a = 1, b):
+                """Docs for f."""
+                return a
+            f(<call>)</call>
+            "#,
+        )
+        .trim()
+        .to_owned();
+        let file = FixtureWithRanges::from_fixture(uri.path(), &contents)?;
+
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), "x = 1\n".to_owned())?;
+        server.change_file(uri.clone(), file.program())?;
+        let diagnostics = server.get_notification::<PublishDiagnostics>()?;
+        assert!(diagnostics
+            .diagnostics
+            .iter()
+            .any(|d| d.message == "positional parameter after non positional"));
+
+        let request = server.new_request::<SignatureHelpRequest>(SignatureHelpParams {
+            context: None,
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: uri.clone() },
+                position: Position {
+                    line: file.begin_line("call"),
+                    character: file.begin_column("call"),
+                },
+            },
+            work_done_progress_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let response = server.get_response::<Option<SignatureHelp>>(request_id)?;
+        if let Some(response) = response {
+            assert!(response.signatures.is_empty());
+        }
+
+        let request = server.new_request::<Completion>(CompletionParams {
+            text_document_position: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri: uri.clone() },
+                position: Position::new(file.begin_line("call"), 0),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        });
+        let request_id = server.send_request(request)?;
+        let CompletionResponse::Array(items) =
+            server.get_response::<CompletionResponse>(request_id)?
+        else {
+            panic!("Expected an array of completions");
+        };
+        let item = items.iter().find(|item| item.label == "f").unwrap();
+        assert_eq!(None, item.documentation);
+
+        let request = server.new_request::<DocumentSymbolRequest>(DocumentSymbolParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let request_id = server.send_request(request)?;
+        let symbols = match server.get_response::<DocumentSymbolResponse>(request_id)? {
+            DocumentSymbolResponse::Nested(symbols) => symbols,
+            response => {
+                return Err(anyhow::anyhow!(
+                    "Expected nested symbols, got {:?}",
+                    response
+                ))
+            }
+        };
+        assert_eq!(
+            vec!["f"],
+            symbols.iter().map(|s| s.name.as_str()).collect::<Vec<_>>()
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,379 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Signature help for the function call surrounding the cursor.
+
+use lsp_types::Documentation;
+use lsp_types::MarkupContent;
+use lsp_types::MarkupKind;
+use lsp_types::ParameterInformation;
+use lsp_types::ParameterLabel;
+use lsp_types::SignatureInformation;
+use starlark::codemap::Pos;
+use starlark::codemap::ResolvedSpan;
+use starlark::docs::DocFunction;
+use starlark::docs::DocParam;
+use starlark::docs::DocString;
+use starlark::typing::Ty;
+use starlark_syntax::syntax::ast::ArgumentP;
+use starlark_syntax::syntax::ast::AstArgument;
+use starlark_syntax::syntax::ast::AstExpr;
+use starlark_syntax::syntax::ast::AstStmt;
+use starlark_syntax::syntax::ast::Expr;
+use starlark_syntax::syntax::ast::Stmt;
+use starlark_syntax::syntax::module::AstModuleFields;
+
+use crate::definition::LspModule;
+use crate::docs::get_signature_for_def;
+
+/// The argument of a call that the cursor is in.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) enum ActiveArgument {
+    /// The n-th positional argument (zero based).
+    Positional(usize),
+    /// A named argument, e.g. `name = value`.
+    Named(String),
+    /// A `*args` argument.
+    Args,
+    /// A `**kwargs` argument.
+    Kwargs,
+}
+
+/// A function call surrounding the cursor.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct CallAtLocation {
+    /// The name of the function being called.
+    pub(crate) name: String,
+    /// The location of the function name in the call.
+    pub(crate) callee: ResolvedSpan,
+    /// The argument the cursor is in.
+    pub(crate) active: ActiveArgument,
+}
+
+pub(crate) fn visit_exprs<'a>(expr: &'a AstExpr, f: &mut impl FnMut(&'a AstExpr)) {
+    f(expr);
+    expr.visit_expr(|x| visit_exprs(x, f));
+}
+
+fn find_in_stmts(
+    stmt: &AstStmt,
+    f: &mut impl FnMut(&AstStmt) -> Option<DocFunction>,
+) -> Option<DocFunction> {
+    if let Some(res) = f(stmt) {
+        return Some(res);
+    }
+    let mut res = None;
+    stmt.visit_stmt(|x| {
+        if res.is_none() {
+            res = find_in_stmts(x, f);
+        }
+    });
+    res
+}
+
+fn active_argument(args: &[AstArgument], pos: Pos) -> ActiveArgument {
+    if let Some(arg) = args.iter().find(|arg| arg.span.contains(pos)) {
+        return match &arg.node {
+            ArgumentP::Positional(_) => ActiveArgument::Positional(
+                args.iter()
+                    .take_while(|x| x.span.begin() < arg.span.begin())
+                    .filter(|x| matches!(x.node, ArgumentP::Positional(_)))
+                    .count(),
+            ),
+            ArgumentP::Named(name, _) => ActiveArgument::Named(name.node.clone()),
+            ArgumentP::Args(_) => ActiveArgument::Args,
+            ArgumentP::KwArgs(_) => ActiveArgument::Kwargs,
+        };
+    }
+    // The cursor is in a gap between arguments, so it is about to type a new positional one.
+    ActiveArgument::Positional(
+        args.iter()
+            .filter(|x| x.span.end() <= pos && matches!(x.node, ArgumentP::Positional(_)))
+            .count(),
+    )
+}
+
+impl LspModule {
+    /// Find the innermost call to a named function whose argument list contains the
+    /// given location. `line` and `col` are zero based.
+    ///
+    /// NOTE: This uses the AST, so only works for files that parse. Calls to
+    /// attributes like `foo.bar()` are not handled.
+    pub(crate) fn find_call_at_location(&self, line: u32, col: u32) -> Option<CallAtLocation> {
+        let line_span = self.ast.codemap().line_span_opt(line as usize)?;
+        let pos = std::cmp::min(line_span.begin() + col, line_span.end());
+
+        // Calls are visited before their arguments, so the last call containing the
+        // cursor is the innermost one.
+        let mut res = None;
+        self.ast.statement().visit_expr(|expr| {
+            visit_exprs(expr, &mut |x| {
+                if let Expr::Call(callee, args) = &x.node {
+                    // The cursor must be between the parentheses.
+                    if callee.span.end() < pos && pos < x.span.end() {
+                        res = Some((callee, args));
+                    }
+                }
+            })
+        });
+
+        let (callee, args) = res?;
+        match &callee.node {
+            Expr::Identifier(ident) => Some(CallAtLocation {
+                name: ident.node.ident.clone(),
+                callee: self.ast.codemap().resolve_span(callee.span),
+                active: active_argument(&args.args, pos),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Find the signature of the `def` whose name is at `destination`, which is
+    /// usually the result of resolving a function name to its definition.
+    pub(crate) fn find_def_signature(&self, destination: ResolvedSpan) -> Option<DocFunction> {
+        let codemap = self.ast.codemap();
+        find_in_stmts(self.ast.statement(), &mut |stmt| match &stmt.node {
+            Stmt::Def(def) if codemap.resolve_span(def.name.span) == destination => {
+                get_signature_for_def(def, codemap)
+            }
+            _ => None,
+        })
+    }
+}
+
+fn render_doc_string(docs: &DocString) -> Documentation {
+    let value = match &docs.details {
+        Some(details) => format!("{}\n\n{}", docs.summary, details),
+        None => docs.summary.clone(),
+    };
+    Documentation::MarkupContent(MarkupContent {
+        kind: MarkupKind::Markdown,
+        value,
+    })
+}
+
+/// Build the signature of `function`, as it would be written in a `def`, highlighting
+/// the parameter that `active` would be bound to.
+pub(crate) fn signature_information(
+    name: &str,
+    function: &DocFunction,
+    active: &ActiveArgument,
+) -> SignatureInformation {
+    let params = &function.params;
+
+    // The parts of the signature, with `None` for the `/` and `*` markers.
+    let mut parts: Vec<(String, Option<&DocParam>)> = Vec::new();
+    fn param<'a>(prefix: &str, p: &'a DocParam) -> (String, Option<&'a DocParam>) {
+        (format!("{}{}", prefix, p.name), Some(p))
+    }
+    parts.extend(params.pos_only.iter().map(|p| param("", p)));
+    if !params.pos_only.is_empty() {
+        parts.push(("/".to_owned(), None));
+    }
+    parts.extend(params.pos_or_named.iter().map(|p| param("", p)));
+    match &params.args {
+        Some(p) => parts.push(param("*", p)),
+        None if !params.named_only.is_empty() => parts.push(("*".to_owned(), None)),
+        None => {}
+    }
+    parts.extend(params.named_only.iter().map(|p| param("", p)));
+    parts.extend(params.kwargs.iter().map(|p| param("**", p)));
+
+    let mut label = format!("{}(", name);
+    let mut parameters = Vec::new();
+    for (i, (text, param)) in parts.into_iter().enumerate() {
+        if i != 0 {
+            label.push_str(", ");
+        }
+        // Offsets are in UTF-16 code units, like all positions in LSP.
+        let begin = label.encode_utf16().count() as u32;
+        label.push_str(&text);
+        if let Some(param) = param {
+            if param.typ != Ty::any() {
+                label.push_str(&format!(": {}", param.typ));
+            }
+            if let Some(default_value) = &param.default_value {
+                label.push_str(&format!(" = {}", default_value));
+            }
+            parameters.push(ParameterInformation {
+                label: ParameterLabel::LabelOffsets([begin, label.encode_utf16().count() as u32]),
+                documentation: param.docs.as_ref().map(render_doc_string),
+            });
+        }
+    }
+    label.push(')');
+
+    // Indices into `parameters`, which are in the same order as the fields of `DocParams`.
+    let num_positional = params.pos_only.len() + params.pos_or_named.len();
+    let args_index = params.args.as_ref().map(|_| num_positional);
+    let named_only_start = num_positional + args_index.map_or(0, |_| 1);
+    let kwargs_index = params
+        .kwargs
+        .as_ref()
+        .map(|_| named_only_start + params.named_only.len());
+    let active_parameter = match active {
+        ActiveArgument::Positional(i) if *i < num_positional => Some(*i),
+        ActiveArgument::Positional(_) | ActiveArgument::Args => args_index,
+        ActiveArgument::Named(name) => params
+            .pos_or_named
+            .iter()
+            .position(|p| &p.name == name)
+            .map(|i| params.pos_only.len() + i)
+            .or_else(|| {
+                params
+                    .named_only
+                    .iter()
+                    .position(|p| &p.name == name)
+                    .map(|i| named_only_start + i)
+            })
+            .or(kwargs_index),
+        ActiveArgument::Kwargs => kwargs_index,
+    };
+
+    SignatureInformation {
+        label,
+        documentation: function.docs.as_ref().map(render_doc_string),
+        parameters: Some(parameters),
+        active_parameter: active_parameter.map(|i| i as u32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::ParameterLabel;
+    use starlark::docs::DocParams;
+    use starlark::docs::DocReturn;
+    use textwrap::dedent;
+
+    use super::*;
+    use crate::definition::helpers::FixtureWithRanges;
+
+    fn param(name: &str) -> DocParam {
+        DocParam {
+            name: name.to_owned(),
+            docs: None,
+            typ: Ty::any(),
+            default_value: None,
+        }
+    }
+
+    fn active_label(info: &SignatureInformation) -> Option<String> {
+        let i = info.active_parameter? as usize;
+        match &info.parameters.as_ref()?[i].label {
+            ParameterLabel::LabelOffsets([begin, end]) => Some(String::from_utf16_lossy(
+                &info.label.encode_utf16().collect::<Vec<_>>()[*begin as usize..*end as usize],
+            )),
+            ParameterLabel::Simple(s) => Some(s.clone()),
+        }
+    }
+
+    #[test]
+    fn finds_active_argument() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            f(<a>1</a>, g(<b>2</b>), <c>x</c> = 3, *<e>y</e>)
+            h(1, <d> </d>)
+            h(<f>)</f>
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = parsed.module().unwrap();
+        let call_at = |name| {
+            module
+                .find_call_at_location(parsed.begin_line(name), parsed.begin_column(name))
+                .map(|call| (call.name, call.active))
+        };
+
+        assert_eq!(
+            Some(("f".to_owned(), ActiveArgument::Positional(0))),
+            call_at("a")
+        );
+        assert_eq!(
+            Some(("g".to_owned(), ActiveArgument::Positional(0))),
+            call_at("b")
+        );
+        assert_eq!(
+            Some(("f".to_owned(), ActiveArgument::Named("x".to_owned()))),
+            call_at("c")
+        );
+        assert_eq!(
+            Some(("h".to_owned(), ActiveArgument::Positional(1))),
+            call_at("d")
+        );
+        assert_eq!(Some(("f".to_owned(), ActiveArgument::Args)), call_at("e"));
+        assert_eq!(
+            Some(("h".to_owned(), ActiveArgument::Positional(0))),
+            call_at("f")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn finds_def_signature() -> anyhow::Result<()> {
+        let contents = dedent(
+            r#"
+            def <f>f</f>(a, b = 1, *args, c, **kwargs):
+                pass
+            "#,
+        );
+        let parsed = FixtureWithRanges::from_fixture("foo.star", &contents)?;
+        let module = parsed.module().unwrap();
+        let function = module
+            .find_def_signature(parsed.resolved_span("f"))
+            .unwrap();
+        let info = signature_information("f", &function, &ActiveArgument::Positional(0));
+        assert_eq!("f(a, b, *args, c, **kwargs)", info.label);
+        Ok(())
+    }
+
+    #[test]
+    fn highlights_active_parameter() {
+        let function = DocFunction {
+            docs: None,
+            params: DocParams {
+                pos_only: vec![param("a")],
+                pos_or_named: vec![param("b")],
+                args: None,
+                named_only: vec![param("c")],
+                kwargs: Some(param("kwargs")),
+            },
+            ret: DocReturn {
+                docs: None,
+                typ: Ty::any(),
+            },
+        };
+        let active = |active| active_label(&signature_information("f", &function, &active));
+
+        assert_eq!(
+            "f(a, /, b, *, c, **kwargs)",
+            signature_information("f", &function, &ActiveArgument::Args).label
+        );
+        assert_eq!(Some("a".to_owned()), active(ActiveArgument::Positional(0)));
+        assert_eq!(Some("b".to_owned()), active(ActiveArgument::Positional(1)));
+        assert_eq!(None, active(ActiveArgument::Positional(2)));
+        assert_eq!(None, active(ActiveArgument::Args));
+        assert_eq!(
+            Some("c".to_owned()),
+            active(ActiveArgument::Named("c".to_owned()))
+        );
+        assert_eq!(
+            Some("**kwargs".to_owned()),
+            active(ActiveArgument::Named("a".to_owned()))
+        );
+        assert_eq!(Some("**kwargs".to_owned()), active(ActiveArgument::Kwargs));
+    }
+}
@@ -15,11 +15,14 @@
  * limitations under the License.
  */
 
-//! Find which symbols are in scope at a particular point.
+//! Find which symbols are in scope at a particular point, and list the symbols
+//! defined in a document.
 
 use std::collections::HashMap;
 
+use lsp_types::DocumentSymbol;
 use starlark::codemap::CodeMap;
+use starlark::codemap::Span;
 use starlark::docs::DocItem;
 use starlark::docs::DocMember;
 use starlark::docs::DocParam;
@@ -27,6 +30,7 @@ use starlark_syntax::codemap::ResolvedPos;
 use starlark_syntax::syntax::ast::AssignP;
 use starlark_syntax::syntax::ast::AstPayload;
 use starlark_syntax::syntax::ast::AstStmtP;
+use starlark_syntax::syntax::ast::DefP;
 use starlark_syntax::syntax::ast::ExprP;
 use starlark_syntax::syntax::ast::ForP;
 use starlark_syntax::syntax::ast::LoadArgP;
@@ -162,6 +166,97 @@ pub(crate) fn find_symbols_at_location<P: AstPayload>(
     symbols
 }
 
+/// Get the outline of a document: the top-level assignments and `def`s, with the
+/// parameters and nested `def`s of each `def` as its children.
+pub(crate) fn get_document_symbols<P: AstPayload>(
+    codemap: &CodeMap,
+    ast: &AstStmtP<P>,
+) -> Vec<DocumentSymbol> {
+    #[allow(deprecated)] // `deprecated` is a required field.
+    fn symbol(
+        codemap: &CodeMap,
+        name: String,
+        kind: lsp_types::SymbolKind,
+        span: Span,
+        selection_span: Span,
+        children: Option<Vec<DocumentSymbol>>,
+    ) -> DocumentSymbol {
+        DocumentSymbol {
+            name,
+            detail: None,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: codemap.resolve_span(span).into(),
+            selection_range: codemap.resolve_span(selection_span).into(),
+            children,
+        }
+    }
+
+    fn def_symbol<P: AstPayload>(codemap: &CodeMap, def: &DefP<P>, span: Span) -> DocumentSymbol {
+        let mut children: Vec<_> = def
+            .params
+            .iter()
+            .filter_map(|param| {
+                let (prefix, name) = match &param.node {
+                    ParameterP::Normal(name, ..) => ("", name),
+                    ParameterP::Args(name, _) => ("*", name),
+                    ParameterP::KwArgs(name, _) => ("**", name),
+                    ParameterP::Slash | ParameterP::NoArgs => return None,
+                };
+                Some(symbol(
+                    codemap,
+                    format!("{}{}", prefix, name.ident),
+                    lsp_types::SymbolKind::VARIABLE,
+                    param.span,
+                    name.span,
+                    None,
+                ))
+            })
+            .collect();
+        walk(codemap, &def.body, false, &mut children);
+        symbol(
+            codemap,
+            def.name.ident.clone(),
+            lsp_types::SymbolKind::FUNCTION,
+            span,
+            def.name.span,
+            Some(children),
+        )
+    }
+
+    /// Only `def`s are collected for nested scopes, assignments only at the top level.
+    fn walk<P: AstPayload>(
+        codemap: &CodeMap,
+        ast: &AstStmtP<P>,
+        top_level: bool,
+        symbols: &mut Vec<DocumentSymbol>,
+    ) {
+        match &ast.node {
+            StmtP::Def(def) => symbols.push(def_symbol(codemap, def, ast.span)),
+            StmtP::Assign(AssignP { lhs, ty: _, rhs }) if top_level => lhs.visit_lvalue(|x| {
+                let kind = match rhs.node {
+                    ExprP::Lambda(_) => lsp_types::SymbolKind::FUNCTION,
+                    _ => lsp_types::SymbolKind::VARIABLE,
+                };
+                symbols.push(symbol(
+                    codemap,
+                    x.ident.clone(),
+                    kind,
+                    ast.span,
+                    x.span,
+                    None,
+                ));
+            }),
+            stmt => stmt.visit_stmt(|x| walk(codemap, x, top_level, symbols)),
+        }
+    }
+
+    let mut symbols = Vec::new();
+    walk(codemap, ast, true, &mut symbols);
+    symbols
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -172,6 +267,7 @@ mod tests {
     use starlark_syntax::syntax::module::AstModuleFields;
 
     use super::find_symbols_at_location;
+    use super::get_document_symbols;
     use super::Symbol;
     use super::SymbolKind;
 
@@ -318,4 +414,62 @@ my_var = True
             ])
         );
     }
+
+    #[test]
+    fn document_symbols() {
+        let ast_module = AstModule::parse(
+            "t.star",
+            r#"load("foo.star", "exported_a")
+
+def method(param, *args, **kwargs):
+    x = 1
+    def nested():
+        pass
+
+if True:
+    my_var = True
+    callback = lambda: None
+        "#
+            .to_owned(),
+            &Dialect::AllOptionsInternal,
+        )
+        .unwrap();
+
+        fn outline(symbols: &[lsp_types::DocumentSymbol], depth: usize, res: &mut Vec<String>) {
+            for symbol in symbols {
+                res.push(format!(
+                    "{}{} {:?} {}:{}",
+                    "  ".repeat(depth),
+                    symbol.name,
+                    symbol.kind,
+                    symbol.selection_range.start.line,
+                    symbol.selection_range.start.character,
+                ));
+                outline(
+                    symbol.children.as_deref().unwrap_or_default(),
+                    depth + 1,
+                    res,
+                );
+            }
+        }
+
+        let mut res = Vec::new();
+        outline(
+            &get_document_symbols(ast_module.codemap(), ast_module.statement()),
+            0,
+            &mut res,
+        );
+        assert_eq!(
+            vec![
+                "method Function 2:4",
+                "  param Variable 2:11",
+                "  *args Variable 2:19",
+                "  **kwargs Variable 2:27",
+                "  nested Function 4:8",
+                "my_var Variable 8:4",
+                "callback Function 9:4",
+            ],
+            res
+        );
+    }
 }
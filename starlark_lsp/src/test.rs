@@ -47,11 +47,13 @@ use lsp_types::GotoCapability;
 use lsp_types::InitializeParams;
 use lsp_types::InitializeResult;
 use lsp_types::InitializedParams;
+use lsp_types::Range;
 use lsp_types::TextDocumentClientCapabilities;
 use lsp_types::TextDocumentContentChangeEvent;
 use lsp_types::TextDocumentItem;
 use lsp_types::Url;
 use lsp_types::VersionedTextDocumentIdentifier;
+use lsp_types::WorkspaceFolder;
 use maplit::hashmap;
 use serde::de::DeserializeOwned;
 use starlark::analysis::AstModuleLint;
@@ -61,6 +63,7 @@ use starlark::docs::DocFunction;
 use starlark::docs::DocItem;
 use starlark::docs::DocMember;
 use starlark::docs::DocModule;
+use starlark::environment::Globals;
 use starlark::errors::EvalMessage;
 use starlark::syntax::AstModule;
 use starlark::syntax::Dialect;
@@ -134,30 +137,22 @@ impl LspContext for TestServerContext {
     fn parse_file_with_contents(&self, uri: &LspUrl, content: String) -> LspEvalResult {
         match uri {
             LspUrl::File(path) | LspUrl::Starlark(path) => {
-                match AstModule::parse(
+                let (ast, errors) = AstModule::parse_with_recovery(
                     &path.to_string_lossy(),
                     content,
                     &Dialect::AllOptionsInternal,
-                ) {
-                    Ok(ast) => {
-                        let diagnostics = ast
-                            .lint(None)
-                            .into_map(|l| eval_message_to_lsp_diagnostic(EvalMessage::from(l)));
-                        LspEvalResult {
-                            diagnostics,
-                            ast: Some(ast),
-                        }
-                    }
-                    Err(e) => {
-                        let diagnostics = vec![eval_message_to_lsp_diagnostic(
-                            EvalMessage::from_error(path, &e),
-                        )];
-                        LspEvalResult {
-                            diagnostics,
-                            ast: None,
-                        }
-                    }
+                );
+                let mut diagnostics = errors.into_map(|e| {
+                    eval_message_to_lsp_diagnostic(EvalMessage::from_error(path, &e))
+                });
+                if let Some(ast) = &ast {
+                    diagnostics.extend(
+                        ast.lint(None)
+                            .into_iter()
+                            .map(|l| eval_message_to_lsp_diagnostic(EvalMessage::from(l))),
+                    );
                 }
+                LspEvalResult { diagnostics, ast }
             }
             _ => LspEvalResult::default(),
         }
@@ -290,6 +285,10 @@ impl LspContext for TestServerContext {
         Ok(self.builtin_symbols.get(symbol).cloned())
     }
 
+    fn get_dialect(&self, _uri: &LspUrl) -> Dialect {
+        Dialect::AllOptionsInternal
+    }
+
     fn get_environment(&self, _uri: &LspUrl) -> DocModule {
         DocModule {
             docs: None,
@@ -305,6 +304,10 @@ impl LspContext for TestServerContext {
                 .collect(),
         }
     }
+
+    fn get_globals(&self, _uri: &LspUrl) -> Option<Globals> {
+        Some(Globals::standard())
+    }
 }
 
 /// A server for use in testing that provides helpers for sending requests, correlating
@@ -412,6 +415,14 @@ impl TestServer {
     /// initialization payload and makes sure that when the server is dropped, the threads
     /// are attempted to be stopped.
     pub(crate) fn new_with_settings(settings: Option<LspServerSettings>) -> anyhow::Result<Self> {
+        Self::new_with_workspace(settings, None)
+    }
+
+    /// Create and start a new LSP server whose client has the given workspace folders open.
+    pub(crate) fn new_with_workspace(
+        settings: Option<LspServerSettings>,
+        workspace_folders: Option<Vec<WorkspaceFolder>>,
+    ) -> anyhow::Result<Self> {
         let (server_connection, client_connection) = Connection::memory();
 
         let builtin = Self::testing_builtins(&std::env::current_dir()?)?;
@@ -463,7 +474,7 @@ impl TestServer {
             initialize_response: None,
             builtin_docs,
         };
-        ret.initialize(settings)
+        ret.initialize(settings, workspace_folders)
     }
 
     /// Create and start a new LSP server. This sends the initialization messages, and makes
@@ -472,7 +483,11 @@ impl TestServer {
         Self::new_with_settings(None)
     }
 
-    fn initialize(mut self, settings: Option<LspServerSettings>) -> anyhow::Result<Self> {
+    fn initialize(
+        mut self,
+        settings: Option<LspServerSettings>,
+        workspace_folders: Option<Vec<WorkspaceFolder>>,
+    ) -> anyhow::Result<Self> {
         let capabilities = ClientCapabilities {
             text_document: Some(TextDocumentClientCapabilities {
                 definition: Some(GotoCapability {
@@ -494,7 +509,7 @@ impl TestServer {
             initialization_options,
             capabilities,
             trace: None,
-            workspace_folders: None,
+            workspace_folders,
             client_info: None,
             locale: None,
         };
@@ -582,6 +597,11 @@ impl TestServer {
         ))
     }
 
+    /// Whether a notification of type `T` was received and not processed yet.
+    pub fn has_notification<T: Notification>(&self) -> bool {
+        self.notifications.iter().any(|n| T::METHOD == n.method)
+    }
+
     /// Attempt to receive a message and either put it in the `responses` map if it's a
     /// response, or the notifications queue if it's a notification.
     ///
@@ -662,6 +682,24 @@ impl TestServer {
         Ok(())
     }
 
+    /// Send a notification saying that the text in `range` of a file was replaced with `text`.
+    pub fn edit_file(&mut self, uri: Url, range: Range, text: &str) -> anyhow::Result<()> {
+        let change_params = DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier {
+                uri,
+                version: self.next_document_version(),
+            },
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: Some(range),
+                range_length: None,
+                text: text.to_owned(),
+            }],
+        };
+        let change_notification = new_notification::<DidChangeTextDocument>(change_params);
+        self.send_notification(change_notification)?;
+        Ok(())
+    }
+
     /// Set the file contents that `get_load_contents()` will return. The path must be absolute.
     pub fn set_file_contents(&self, path: PathBuf, contents: String) -> anyhow::Result<()> {
         let path = get_path_from_uri(&format!("{}", path.display()));
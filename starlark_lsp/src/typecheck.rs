@@ -0,0 +1,117 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Running the static typechecker over open documents.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use dupe::Dupe;
+use lsp_types::Diagnostic;
+use starlark::analysis::EvalMessage;
+use starlark::typing::AstModuleTypecheck;
+use starlark::typing::Interface;
+use starlark::typing::TypeMap;
+
+use crate::definition::LspModule;
+use crate::error::eval_message_to_lsp_diagnostic;
+use crate::server::Backend;
+use crate::server::LspContext;
+use crate::server::LspUrl;
+
+impl<T: LspContext> Backend<T> {
+    /// Typecheck a module, returning the type errors and the inferred types. Returns
+    /// `None` if the context does not provide globals for the module.
+    ///
+    /// The types of loaded symbols come from typechecking the modules they are
+    /// loaded from. Modules that cannot be found or parsed are treated as having
+    /// no type information, rather than as errors, since those are reported elsewhere.
+    pub(crate) fn typecheck(
+        &self,
+        uri: &LspUrl,
+        module: &LspModule,
+        workspace_root: Option<&Path>,
+    ) -> Option<(Vec<starlark::Error>, TypeMap)> {
+        let globals = self.context.get_globals(uri)?;
+        let mut interfaces = HashMap::new();
+        // Guard against a module loading itself.
+        interfaces.insert(uri.clone(), None);
+        let loads = self.load_interfaces(uri, module, workspace_root, &mut interfaces);
+        let (errors, types, ..) = module.ast.clone().typecheck(&globals, &loads);
+        Some((errors, types))
+    }
+
+    /// Typecheck a module, returning the type errors as diagnostics.
+    pub(crate) fn typecheck_diagnostics(
+        &self,
+        uri: &LspUrl,
+        module: &LspModule,
+        workspace_root: Option<&Path>,
+    ) -> Vec<Diagnostic> {
+        let Some((errors, _)) = self.typecheck(uri, module, workspace_root) else {
+            return Vec::new();
+        };
+        errors
+            .iter()
+            .map(|e| eval_message_to_lsp_diagnostic(EvalMessage::from_error(uri.path(), e)))
+            .collect()
+    }
+
+    /// Get the interfaces of the modules loaded by `module`, keyed by the path in the
+    /// `load()` statement.
+    ///
+    /// `interfaces` caches the interface of every module seen so far, with `None` for
+    /// modules that could not be typechecked or are still being typechecked.
+    fn load_interfaces(
+        &self,
+        uri: &LspUrl,
+        module: &LspModule,
+        workspace_root: Option<&Path>,
+        interfaces: &mut HashMap<LspUrl, Option<Interface>>,
+    ) -> HashMap<String, Interface> {
+        module
+            .ast
+            .loads()
+            .into_iter()
+            .filter_map(|load| {
+                let load_uri = self
+                    .resolve_load_path(load.module_id, uri, workspace_root)
+                    .ok()?;
+                let interface = self.module_interface(&load_uri, workspace_root, interfaces)?;
+                Some((load.module_id.to_owned(), interface))
+            })
+            .collect()
+    }
+
+    fn module_interface(
+        &self,
+        uri: &LspUrl,
+        workspace_root: Option<&Path>,
+        interfaces: &mut HashMap<LspUrl, Option<Interface>>,
+    ) -> Option<Interface> {
+        if let Some(interface) = interfaces.get(uri) {
+            return interface.dupe();
+        }
+        interfaces.insert(uri.clone(), None);
+        let module = self.get_ast_or_load_from_disk(uri).ok()??;
+        let globals = self.context.get_globals(uri)?;
+        let loads = self.load_interfaces(uri, &module, workspace_root, interfaces);
+        let (_, _, interface, _) = module.ast.clone().typecheck(&globals, &loads);
+        interfaces.insert(uri.clone(), Some(interface.dupe()));
+        Some(interface)
+    }
+}
@@ -63,6 +63,19 @@ pub struct Dialect {
     ///
     /// [Starlark spec proposal](https://github.com/bazelbuild/starlark/issues/91).
     pub enable_f_strings: bool,
+    /// Are `while` loops allowed?
+    /// Disabled by default, as the Starlark standard requires all programs to terminate.
+    pub enable_while: bool,
+    /// Are functions allowed to call themselves, directly or through other functions?
+    /// Enabled by default. The Starlark standard forbids recursion, and when this
+    /// is disabled a recursive call fails at runtime with an error naming the cycle.
+    pub enable_recursion: bool,
+    /// Are `b"..."` bytes literals allowed?
+    /// Disabled by default.
+    pub enable_bytes: bool,
+    /// Are `{1, 2}` set literals and `{x for x in xs}` set comprehensions allowed?
+    /// Disabled by default.
+    pub enable_set_literals: bool,
     /// Like `#[non_exhaustive]`, but allows struct expression.
     ///
     /// [Explanation](https://github.com/rust-lang/rust-clippy/issues/6559).
@@ -92,6 +105,10 @@ impl Dialect {
         enable_load_reexport: true, // But they plan to change it
         enable_top_level_stmt: false,
         enable_f_strings: false,
+        enable_while: false,
+        enable_recursion: true,
+        enable_bytes: false,
+        enable_set_literals: false,
         _non_exhaustive: (),
     };
 
@@ -107,9 +124,89 @@ impl Dialect {
         enable_load_reexport: true,
         enable_top_level_stmt: true,
         enable_f_strings: false,
+        enable_while: false,
+        enable_recursion: true,
+        enable_bytes: false,
+        enable_set_literals: false,
+        _non_exhaustive: (),
+    };
+
+    /// The dialect accepted by [Bazel](https://bazel.build/rules/language) in `.bzl` files.
+    ///
+    /// Loaded symbols are not reexported, and neither recursion nor top-level
+    /// `for`/`if` statements are allowed.
+    pub const Bazel: Self = Self {
+        enable_def: true,
+        enable_lambda: true,
+        enable_load: true,
+        enable_keyword_only_arguments: true,
+        enable_positional_only_arguments: false,
+        enable_types: DialectTypes::Disable,
+        enable_load_reexport: false,
+        enable_top_level_stmt: false,
+        enable_f_strings: false,
+        enable_while: false,
+        enable_recursion: false,
+        enable_bytes: false,
+        enable_set_literals: false,
         _non_exhaustive: (),
     };
 
+    /// The dialect accepted by [Buck2](https://buck2.build/) in `.bzl` files,
+    /// with type annotations checked at runtime.
+    pub const Buck2: Self = Self {
+        enable_def: true,
+        enable_lambda: true,
+        enable_load: true,
+        enable_keyword_only_arguments: true,
+        enable_positional_only_arguments: true,
+        enable_types: DialectTypes::Enable,
+        enable_load_reexport: false,
+        enable_top_level_stmt: true,
+        enable_f_strings: false,
+        enable_while: false,
+        enable_recursion: true,
+        enable_bytes: false,
+        enable_set_literals: false,
+        _non_exhaustive: (),
+    };
+
+    /// The dialect accepted by [starlark-go](https://github.com/google/starlark-go)
+    /// with its default options, which include `b"..."` bytes literals.
+    pub const StarlarkGo: Self = Self {
+        enable_def: true,
+        enable_lambda: true,
+        enable_load: true,
+        enable_keyword_only_arguments: true,
+        enable_positional_only_arguments: false,
+        enable_types: DialectTypes::Disable,
+        enable_load_reexport: false,
+        enable_top_level_stmt: false,
+        enable_f_strings: false,
+        enable_while: false,
+        enable_recursion: false,
+        enable_bytes: true,
+        enable_set_literals: false,
+        _non_exhaustive: (),
+    };
+
+    /// The names accepted by [`Dialect::from_name`].
+    pub const NAMES: &'static [&'static str] =
+        &["standard", "extended", "bazel", "buck2", "starlark-go"];
+
+    /// Look up a dialect by name, e.g. `"bazel"`, returning `None` if the name
+    /// is not one of [`Dialect::NAMES`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Dialect::Standard),
+            "extended" => Some(Dialect::Extended),
+            "bazel" => Some(Dialect::Bazel),
+            "buck2" => Some(Dialect::Buck2),
+            "starlark-go" => Some(Dialect::StarlarkGo),
+            _ => None,
+        }
+    }
+
     /// Only for starlark-rust self tests.
     #[doc(hidden)]
     pub const AllOptionsInternal: Self = Self {
@@ -122,6 +219,10 @@ impl Dialect {
         enable_load_reexport: true,
         enable_top_level_stmt: true,
         enable_f_strings: true,
+        enable_while: true,
+        enable_recursion: true,
+        enable_bytes: true,
+        enable_set_literals: true,
         _non_exhaustive: (),
     };
 }
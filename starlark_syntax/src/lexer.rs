@@ -19,6 +19,8 @@ use std::char;
 use std::collections::VecDeque;
 use std::fmt;
 use std::fmt::Display;
+use std::iter::Peekable;
+use std::str::CharIndices;
 
 use logos::Logos;
 use num_bigint::BigInt;
@@ -32,6 +34,7 @@ use crate::cursors::CursorBytes;
 use crate::cursors::CursorChars;
 use crate::dialect::Dialect;
 use crate::eval_exception::EvalException;
+use crate::syntax::ast::BytesLiteral;
 
 #[derive(Error, Debug)]
 pub enum LexemeError {
@@ -429,6 +432,18 @@ impl<'a> Lexer<'a> {
         )
     }
 
+    /// Turn a bytes literal, which was lexed as a raw string, into bytes.
+    fn bytes(&self, lexeme: LexemeT<(String, usize)>, raw: bool) -> Lexeme {
+        let (start, (content, _offset), end) = lexeme?;
+        if raw {
+            return Ok((start, Token::Bytes(content.into_bytes()), end));
+        }
+        match unescape_bytes(&content) {
+            Ok(bytes) => Ok((start, Token::Bytes(bytes), end)),
+            Err(bad) => self.err_span(LexemeError::InvalidEscapeSequence(bad), start, end),
+        }
+    }
+
     fn int(&self, s: &str, radix: u32) -> Lexeme {
         let span = self.lexer.span();
         match TokenInt::from_str_radix(s, radix) {
@@ -512,6 +527,19 @@ impl<'a> Lexer<'a> {
                         Token::String(_) => {
                             unreachable!("The lexer does not produce String")
                         }
+                        Token::RawBytesDoubleQuote => {
+                            let raw = self.lexer.span().len() == 3;
+                            self.parse_double_quoted_string(true)
+                                .map(|lex| self.bytes(lex, raw))
+                        }
+                        Token::RawBytesSingleQuote => {
+                            let raw = self.lexer.span().len() == 3;
+                            self.parse_single_quoted_string(true)
+                                .map(|lex| self.bytes(lex, raw))
+                        }
+                        Token::Bytes(_) => {
+                            unreachable!("The lexer does not produce Bytes")
+                        }
                         Token::RawFStringDoubleQuote => {
                             let span_len = self.lexer.span().len();
                             let raw = span_len == 3;
@@ -544,7 +572,9 @@ impl<'a> Lexer<'a> {
                             self.wrap(token)
                         }
                         Token::ClosingCurly | Token::ClosingRound | Token::ClosingSquare => {
-                            self.parens -= 1;
+                            // An unmatched closing bracket is a syntax error, but must not
+                            // hide the newlines that follow from the parser's error recovery.
+                            self.parens = std::cmp::max(self.parens - 1, 0);
                             self.wrap(token)
                         }
                         _ => self.wrap(token),
@@ -589,6 +619,84 @@ impl<'a> Lexer<'a> {
     }
 }
 
+/// Process the escapes in the contents of a bytes literal.
+/// Unlike in strings, `\x` and octal escapes denote a single byte.
+/// On failure, return the invalid escape sequence.
+fn unescape_bytes(s: &str) -> Result<Vec<u8>, String> {
+    // Consume between `min` and `max` digits and treat them as an int in base `radix`.
+    fn digits(it: &mut Peekable<CharIndices>, min: usize, max: usize, radix: u32) -> Option<u32> {
+        let mut value = 0u32;
+        let mut count = 0;
+        while count < max {
+            match it.peek().and_then(|(_, c)| c.to_digit(radix)) {
+                Some(v) => {
+                    it.next();
+                    count += 1;
+                    value = value * radix + v;
+                }
+                None => break,
+            }
+        }
+        if count >= min {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    // We have seen a `\`, parse what comes next.
+    fn escape(it: &mut Peekable<CharIndices>, res: &mut Vec<u8>) -> Option<()> {
+        let (_, c) = it.next()?;
+        match c {
+            'n' => res.push(b'\n'),
+            'r' => res.push(b'\r'),
+            't' => res.push(b'\t'),
+            'a' => res.push(0x07),
+            'b' => res.push(0x08),
+            'f' => res.push(0x0C),
+            'v' => res.push(0x0B),
+            '\n' => {}
+            'x' => res.push(digits(it, 2, 2, 16)? as u8),
+            '0'..='7' => {
+                let mut value = c.to_digit(8)?;
+                for _ in 0..2 {
+                    match it.peek().and_then(|(_, c)| c.to_digit(8)) {
+                        Some(v) => {
+                            it.next();
+                            value = value * 8 + v;
+                        }
+                        None => break,
+                    }
+                }
+                res.push(u8::try_from(value).ok()?);
+            }
+            'u' | 'U' => {
+                let n = if c == 'u' { 4 } else { 8 };
+                let c = char::from_u32(digits(it, n, n, 16)?)?;
+                res.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+            '"' | '\'' | '\\' => res.push(c as u8),
+            _ => {
+                res.push(b'\\');
+                res.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            }
+        }
+        Some(())
+    }
+
+    let mut res = Vec::with_capacity(s.len());
+    let mut it = s.char_indices().peekable();
+    while let Some((start, c)) = it.next() {
+        if c != '\\' {
+            res.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        } else if escape(&mut it, &mut res).is_none() {
+            let end = it.peek().map_or(s.len(), |(i, _)| *i);
+            return Err(s[start + 1..end].to_owned());
+        }
+    }
+    Ok(res)
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, derive_more::Display)]
 pub enum TokenInt {
     I32(i32),
@@ -658,6 +766,17 @@ pub enum Token {
     #[token("fr\"")]
     RawFStringDoubleQuote,
 
+    /// The start of a single-quoted bytes literal.
+    #[token("b'")]
+    #[token("br'")]
+    #[token("rb'")]
+    RawBytesSingleQuote,
+    /// The start of a double-quoted bytes literal.
+    #[token("b\"")]
+    #[token("br\"")]
+    #[token("rb\"")]
+    RawBytesDoubleQuote,
+
     #[regex(
         "as|\
         assert|\
@@ -674,7 +793,6 @@ pub enum Token {
         nonlocal|\
         raise|\
         try|\
-        while|\
         with|\
         yield"
     )]
@@ -704,6 +822,8 @@ pub enum Token {
     String(String), // A string literal
     /// The raw text of a f-string
     FString(TokenFString),
+    /// A bytes literal, enabled with [`Dialect::enable_bytes`](crate::dialect::Dialect::enable_bytes).
+    Bytes(Vec<u8>),
 
     // Keywords
     #[token("and")]
@@ -720,6 +840,8 @@ pub enum Token {
     Else,
     #[token("for")]
     For,
+    #[token("while")]
+    While,
     #[token("if")]
     If,
     #[token("in")]
@@ -842,6 +964,7 @@ impl Token {
                 // Reuse the StarlarkValue implementation since it's close to hand.
                 serde_json::to_string(x).unwrap()
             }
+            Token::Bytes(x) => BytesLiteral(x).to_string(),
             Token::FString(x) => {
                 let mut buff = Vec::new();
                 write!(&mut buff, "f").unwrap();
@@ -876,6 +999,7 @@ impl Display for Token {
             Token::Load => write!(f, "keyword 'load'"),
             Token::Break => write!(f, "keyword 'break'"),
             Token::For => write!(f, "keyword 'for'"),
+            Token::While => write!(f, "keyword 'while'"),
             Token::Not => write!(f, "keyword 'not'"),
             Token::Continue => write!(f, "keyword 'continue'"),
             Token::If => write!(f, "keyword 'if'"),
@@ -943,6 +1067,9 @@ impl Display for Token {
             Token::RawFStringDoubleQuote => write!(f, "starting f'"),
             Token::RawFStringSingleQuote => write!(f, "starting f\""),
             Token::FString(s) => write!(f, "f-string {:?}", &s.content),
+            Token::RawBytesSingleQuote => write!(f, "starting b'"),
+            Token::RawBytesDoubleQuote => write!(f, "starting b\""),
+            Token::Bytes(b) => write!(f, "bytes literal {}", BytesLiteral(b)),
             Token::Comment(c) => write!(f, "comment '{}'", c),
             Token::Tabs => Ok(()),
         }
@@ -191,7 +191,7 @@ fn test_number_collated_with_keywords_or_identifier() {
 fn test_reserved() {
     lexer_fail_golden_test(
         "reserved",
-        &"as import is class nonlocal del raise except try finally while from with global yield"
+        &"as import is class nonlocal del raise except try finally from with global yield"
             .split_whitespace()
             .collect::<Vec<&str>>(),
     );
@@ -273,6 +273,18 @@ fn test_string_escape() {
     );
 }
 
+#[test]
+fn test_bytes_lit() {
+    lexer_golden_test(
+        "bytes_lit",
+        r#"
+b"abc" b'\x00\xff' b"\101\n" b"\u00e9" b"é"
+br"\x41" rb'\n' b = 1
+"#,
+    );
+    lexer_fail_golden_test("bytes_lit", &["b'\\400'", "b'\\xZZ'"]);
+}
+
 #[test]
 fn test_simple_example() {
     lexer_golden_test(
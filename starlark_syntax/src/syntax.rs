@@ -25,10 +25,14 @@ pub use crate::dialect::DialectTypes;
 
 pub mod ast;
 pub mod call;
+pub mod codemod;
+pub mod cst;
 pub mod def;
+pub mod format;
 #[cfg(test)]
 mod grammar_tests;
 pub mod grammar_util;
+pub mod json;
 mod lint_suppressions;
 pub mod module;
 pub mod parser;
@@ -39,6 +43,7 @@ mod testcases;
 pub mod top_level_stmts;
 pub mod type_expr;
 pub mod uniplate;
+pub mod unparse;
 pub mod validate;
 
 #[allow(clippy::all)]
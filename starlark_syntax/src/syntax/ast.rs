@@ -21,6 +21,7 @@ use std::fmt;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
+use std::fmt::Write;
 
 use allocative::Allocative;
 use dupe::Dupe;
@@ -87,6 +88,7 @@ pub type AstAssignIdent = AstAssignIdentP<AstNoPayload>;
 pub type AstIdent = AstIdentP<AstNoPayload>;
 pub type AstArgument = AstArgumentP<AstNoPayload>;
 pub type AstString = Spanned<String>;
+pub type AstBytes = Spanned<Vec<u8>>;
 pub type AstParameter = AstParameterP<AstNoPayload>;
 pub type AstInt = Spanned<TokenInt>;
 pub type AstFloat = Spanned<f64>;
@@ -147,6 +149,7 @@ pub enum AstLiteral {
     Int(AstInt),
     Float(AstFloat),
     String(AstString),
+    Bytes(AstBytes),
     Ellipsis,
 }
 
@@ -199,12 +202,14 @@ pub enum ExprP<P: AstPayload> {
     If(Box<(AstExprP<P>, AstExprP<P>, AstExprP<P>)>), // Order: condition, v1, v2 <=> v1 if condition else v2
     List(Vec<AstExprP<P>>),
     Dict(Vec<(AstExprP<P>, AstExprP<P>)>),
+    Set(Vec<AstExprP<P>>),
     ListComprehension(Box<AstExprP<P>>, Box<ForClauseP<P>>, Vec<ClauseP<P>>),
     DictComprehension(
         Box<(AstExprP<P>, AstExprP<P>)>,
         Box<ForClauseP<P>>,
         Vec<ClauseP<P>>,
     ),
+    SetComprehension(Box<AstExprP<P>>, Box<ForClauseP<P>>, Vec<ClauseP<P>>),
     FString(AstFStringP<P>),
 }
 
@@ -372,6 +377,13 @@ pub struct ForP<P: AstPayload> {
     pub body: Box<AstStmtP<P>>,
 }
 
+/// `while` loop, only allowed when [`Dialect::enable_while`](crate::dialect::Dialect::enable_while).
+#[derive(Debug, Clone)]
+pub struct WhileP<P: AstPayload> {
+    pub cond: AstExprP<P>,
+    pub body: Box<AstStmtP<P>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct FStringP<P: AstPayload> {
     /// A format string containing a `{}` marker for each expression to interpolate.
@@ -393,8 +405,13 @@ pub enum StmtP<P: AstPayload> {
     If(AstExprP<P>, Box<AstStmtP<P>>),
     IfElse(AstExprP<P>, Box<(AstStmtP<P>, AstStmtP<P>)>),
     For(ForP<P>),
+    While(WhileP<P>),
     Def(DefP<P>),
     Load(LoadP<P>),
+    /// The source from a syntax error to the end of its line, which the parser skipped.
+    /// Only produced when parsing with
+    /// [`AstModule::parse_with_recovery`](crate::syntax::AstModule::parse_with_recovery).
+    Error,
 }
 
 impl<P: AstPayload> ArgumentP<P> {
@@ -490,7 +507,7 @@ where
     Ok(())
 }
 
-fn fmt_string_literal(f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+pub(crate) fn fmt_string_literal(f: &mut Formatter<'_>, s: &str) -> fmt::Result {
     f.write_str("\"")?;
     for c in s.chars() {
         match c {
@@ -506,12 +523,34 @@ fn fmt_string_literal(f: &mut Formatter<'_>, s: &str) -> fmt::Result {
     f.write_str("\"")
 }
 
+/// Displays as a bytes literal, `b"..."`, escaping everything but printable ASCII.
+pub struct BytesLiteral<'a>(pub &'a [u8]);
+
+impl Display for BytesLiteral<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("b\"")?;
+        for &b in self.0 {
+            match b {
+                b'\n' => f.write_str("\\n")?,
+                b'\t' => f.write_str("\\t")?,
+                b'\r' => f.write_str("\\r")?,
+                b'"' => f.write_str("\\\"")?,
+                b'\\' => f.write_str("\\\\")?,
+                b' '..=b'~' => f.write_char(b as char)?,
+                b => write!(f, "\\x{:02x}", b)?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
 impl Display for AstLiteral {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             AstLiteral::Int(i) => write!(f, "{}", &i.node),
             AstLiteral::Float(n) => write!(f, "{}", &n.node),
             AstLiteral::String(s) => fmt_string_literal(f, &s.node),
+            AstLiteral::Bytes(b) => write!(f, "{}", BytesLiteral(&b.node)),
             AstLiteral::Ellipsis => f.write_str("..."),
         }
     }
@@ -590,6 +629,11 @@ impl Display for Expr {
                 comma_separated_fmt(f, v, |x, f| write!(f, "{}: {}", x.0.node, x.1.node), false)?;
                 f.write_str("}")
             }
+            Expr::Set(v) => {
+                f.write_str("{")?;
+                comma_separated_fmt(f, v, |x, f| write!(f, "{}", x.node), false)?;
+                f.write_str("}")
+            }
             Expr::ListComprehension(e, for_, c) => {
                 write!(f, "[{}", e.node)?;
                 write!(f, "{}", for_)?;
@@ -607,6 +651,14 @@ impl Display for Expr {
                 }
                 f.write_str("}}")
             }
+            Expr::SetComprehension(e, for_, c) => {
+                write!(f, "{{{}", e.node)?;
+                write!(f, "{}", for_)?;
+                for x in c {
+                    write!(f, "{}", x)?;
+                }
+                f.write_str("}")
+            }
             Expr::Literal(x) => write!(f, "{}", x),
             Expr::FString(x) => {
                 // Write out the desugared form.
@@ -738,6 +790,10 @@ impl Stmt {
                 writeln!(f, "{}for {} in {}:", tab, var.node, over.node)?;
                 body.node.fmt_with_tab(f, tab + "  ")
             }
+            Stmt::While(WhileP { cond, body }) => {
+                writeln!(f, "{}while {}:", tab, cond.node)?;
+                body.node.fmt_with_tab(f, tab + "  ")
+            }
             Stmt::Def(DefP {
                 name,
                 params,
@@ -768,6 +824,7 @@ impl Stmt {
                 )?;
                 f.write_str(")\n")
             }
+            Stmt::Error => writeln!(f, "{}<error>", tab),
         }
     }
 }
@@ -0,0 +1,508 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Codemods: rewrite source code by matching the AST and editing the text at the
+//! spans of the matched nodes.
+//!
+//! Editing the text rather than printing a modified AST keeps everything the
+//! codemod did not touch (comments, layout, quoting) exactly as written.
+//!
+//! ```
+//! use starlark_syntax::syntax::codemod::CallPattern;
+//! use starlark_syntax::syntax::codemod::Codemod;
+//! use starlark_syntax::syntax::AstModule;
+//! use starlark_syntax::syntax::Dialect;
+//!
+//! let source = "foo(name = 'x', bar = 1)\nfoo(name = 'y')\n";
+//! let module = AstModule::parse("BUILD", source.to_owned(), &Dialect::Standard).unwrap();
+//! let mut codemod = Codemod::new(&module);
+//! for call in codemod.find_calls(&CallPattern::new("foo").with_kwarg("bar")) {
+//!     codemod.rename_kwarg(&call, "bar", "baz");
+//! }
+//! assert_eq!(
+//!     "foo(name = 'x', baz = 1)\nfoo(name = 'y')\n",
+//!     codemod.apply().unwrap()
+//! );
+//! ```
+
+use crate::codemap::CodeMap;
+use crate::codemap::Pos;
+use crate::codemap::Span;
+use crate::codemap::Spanned;
+use crate::error::Error;
+use crate::error::ErrorKind;
+use crate::syntax::ast::ArgumentP;
+use crate::syntax::ast::AstArgument;
+use crate::syntax::ast::AstExpr;
+use crate::syntax::ast::ExprP;
+use crate::syntax::AstModule;
+
+#[derive(Debug, thiserror::Error)]
+enum CodemodError {
+    #[error("Overlapping edits: cannot replace `{0}` with `{1}` and `{2}` with `{3}`")]
+    Overlap(String, String, String, String),
+}
+
+/// A replacement of the source text in `span` by `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Edit {
+    /// Replace the text in `span`.
+    pub fn replace(span: Span, replacement: impl Into<String>) -> Edit {
+        Edit {
+            span,
+            replacement: replacement.into(),
+        }
+    }
+
+    /// Insert text at `pos`.
+    pub fn insert(pos: Pos, text: impl Into<String>) -> Edit {
+        Edit::replace(Span::new(pos, pos), text)
+    }
+
+    /// Delete the text in `span`.
+    pub fn delete(span: Span) -> Edit {
+        Edit::replace(span, "")
+    }
+}
+
+/// Apply `edits` to the source of `codemap`.
+///
+/// The edits may be given in any order. Identical edits are applied once, and
+/// insertions at the same position are applied in the order given. Edits whose
+/// spans overlap are an error, as there is no way to apply both.
+pub fn apply_edits(codemap: &CodeMap, mut edits: Vec<Edit>) -> crate::Result<String> {
+    // Stable, so insertions at the same position keep their order.
+    edits.sort_by_key(|e| (e.span.begin(), e.span.end()));
+    edits.dedup();
+
+    let source = codemap.source();
+    let mut res = String::with_capacity(source.len());
+    let mut last: Option<&Edit> = None;
+    for edit in &edits {
+        if let Some(last) = last {
+            if edit.span.begin() < last.span.end() {
+                return Err(Error::new_spanned(
+                    ErrorKind::Other(anyhow::Error::new(CodemodError::Overlap(
+                        codemap.source_span(last.span).to_owned(),
+                        last.replacement.clone(),
+                        codemap.source_span(edit.span).to_owned(),
+                        edit.replacement.clone(),
+                    ))),
+                    edit.span,
+                    codemap,
+                ));
+            }
+        }
+        let copied = last.map_or(Pos::new(0), |e| e.span.end());
+        res.push_str(&source[copied.get() as usize..edit.span.begin().get() as usize]);
+        res.push_str(&edit.replacement);
+        last = Some(edit);
+    }
+    let copied = last.map_or(Pos::new(0), |e| e.span.end());
+    res.push_str(&source[copied.get() as usize..]);
+    Ok(res)
+}
+
+/// A pattern matching calls of a function, see [`Codemod::find_calls`].
+#[derive(Debug, Clone)]
+pub struct CallPattern {
+    function: String,
+    kwargs: Vec<String>,
+}
+
+impl CallPattern {
+    /// Match calls of `function`, which is a name like `foo`, or a dotted path
+    /// like `native.foo`.
+    pub fn new(function: &str) -> CallPattern {
+        CallPattern {
+            function: function.to_owned(),
+            kwargs: Vec::new(),
+        }
+    }
+
+    /// Only match calls which pass the keyword argument `name`.
+    pub fn with_kwarg(mut self, name: &str) -> CallPattern {
+        self.kwargs.push(name.to_owned());
+        self
+    }
+
+    /// Match an expression against this pattern.
+    pub fn matches<'a>(&self, expr: &'a AstExpr) -> Option<CallMatch<'a>> {
+        let ExprP::Call(function, args) = &expr.node else {
+            return None;
+        };
+        if !dotted_name_is(function, &self.function) {
+            return None;
+        }
+        let call = CallMatch {
+            call: expr,
+            function,
+            args: &args.args,
+        };
+        if self.kwargs.iter().all(|k| call.kwarg(k).is_some()) {
+            Some(call)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether `expr` is the identifier or dotted path `name`, e.g. `native.foo`.
+fn dotted_name_is(expr: &AstExpr, name: &str) -> bool {
+    match &expr.node {
+        ExprP::Identifier(ident) => ident.node.ident == name,
+        ExprP::Dot(object, attribute) => match name.rsplit_once('.') {
+            Some((prefix, last)) => last == attribute.node && dotted_name_is(object, prefix),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// A call matched by a [`CallPattern`].
+#[derive(Debug, Clone, Copy)]
+pub struct CallMatch<'a> {
+    /// The whole call expression.
+    pub call: &'a AstExpr,
+    /// The function being called.
+    pub function: &'a AstExpr,
+    /// The arguments of the call.
+    pub args: &'a [AstArgument],
+}
+
+impl<'a> CallMatch<'a> {
+    /// The argument passing the keyword `name`.
+    pub fn kwarg_argument(&self, name: &str) -> Option<&'a AstArgument> {
+        self.args
+            .iter()
+            .find(|x| matches!(&x.node, ArgumentP::Named(n, _) if n.node == name))
+    }
+
+    /// The value passed for the keyword argument `name`.
+    pub fn kwarg(&self, name: &str) -> Option<&'a AstExpr> {
+        match &self.kwarg_argument(name)?.node {
+            ArgumentP::Named(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The value of the `index`th positional argument.
+    pub fn positional(&self, index: usize) -> Option<&'a AstExpr> {
+        self.args
+            .iter()
+            .filter_map(|x| match &x.node {
+                ArgumentP::Positional(value) => Some(value),
+                _ => None,
+            })
+            .nth(index)
+    }
+
+    /// Position of the closing parenthesis of the call.
+    fn close_paren(&self) -> Pos {
+        self.call.span.end() - 1
+    }
+}
+
+/// A set of edits to the source of a module, built up by matching its AST.
+pub struct Codemod<'a> {
+    module: &'a AstModule,
+    edits: Vec<Edit>,
+}
+
+impl<'a> Codemod<'a> {
+    pub fn new(module: &'a AstModule) -> Codemod<'a> {
+        Codemod {
+            module,
+            edits: Vec::new(),
+        }
+    }
+
+    /// The module being edited.
+    pub fn module(&self) -> &'a AstModule {
+        self.module
+    }
+
+    /// The original source text of `span`.
+    pub fn source(&self, span: Span) -> &'a str {
+        self.module.codemap.source_span(span)
+    }
+
+    /// Visit every expression in the module, in source order, outer expressions
+    /// before the expressions they contain.
+    pub fn visit_expr(&self, mut f: impl FnMut(&'a AstExpr)) {
+        fn go<'a>(x: &'a AstExpr, f: &mut impl FnMut(&'a AstExpr)) {
+            f(x);
+            x.visit_expr(|x| go(x, f));
+        }
+        self.module.statement.visit_expr(|x| go(x, &mut f));
+    }
+
+    /// All the calls matching `pattern`, in source order.
+    pub fn find_calls(&self, pattern: &CallPattern) -> Vec<CallMatch<'a>> {
+        let mut res = Vec::new();
+        self.visit_expr(|x| res.extend(pattern.matches(x)));
+        res
+    }
+
+    /// The edits made so far.
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// Add an edit. Edits are checked for overlap when they are applied.
+    pub fn edit(&mut self, edit: Edit) {
+        self.edits.push(edit);
+    }
+
+    /// Add the edits of another codemod over the same module.
+    pub fn merge(&mut self, other: Codemod<'a>) {
+        self.edits.extend(other.edits);
+    }
+
+    pub fn replace(&mut self, span: Span, replacement: impl Into<String>) {
+        self.edit(Edit::replace(span, replacement));
+    }
+
+    pub fn insert(&mut self, pos: Pos, text: impl Into<String>) {
+        self.edit(Edit::insert(pos, text));
+    }
+
+    pub fn delete(&mut self, span: Span) {
+        self.edit(Edit::delete(span));
+    }
+
+    /// Rename the keyword argument `name` of `call` to `new_name`, if it is passed.
+    pub fn rename_kwarg(&mut self, call: &CallMatch<'a>, name: &str, new_name: &str) {
+        if let Some(Spanned {
+            node: ArgumentP::Named(n, _),
+            ..
+        }) = call.kwarg_argument(name)
+        {
+            self.replace(n.span, new_name);
+        }
+    }
+
+    /// Set the keyword argument `name` of `call` to the source text `value`,
+    /// replacing the existing value or adding the argument at the end.
+    pub fn set_kwarg(&mut self, call: &CallMatch<'a>, name: &str, value: &str) {
+        if let Some(existing) = call.kwarg(name) {
+            self.replace(existing.span, value);
+            return;
+        }
+        let Some(last) = call.args.last() else {
+            self.insert(call.close_paren(), format!("{name} = {value}"));
+            return;
+        };
+        let after = self.source(Span::new(last.span.end(), call.close_paren()));
+        match after.find(',') {
+            Some(comma) if after.contains('\n') => {
+                // One argument per line: add a line in the same style.
+                let codemap = &self.module.codemap;
+                let line = codemap.source_line(codemap.resolve_span(last.span).begin.line);
+                let indent = &line[..line.len() - line.trim_start().len()];
+                self.insert(
+                    last.span.end() + comma as u32 + 1,
+                    format!("\n{indent}{name} = {value},"),
+                );
+            }
+            Some(comma) => self.insert(
+                last.span.end() + comma as u32 + 1,
+                format!(" {name} = {value},"),
+            ),
+            None => self.insert(last.span.end(), format!(", {name} = {value}")),
+        }
+    }
+
+    /// Remove `arg` from `call`, along with the comma separating it from its neighbours.
+    pub fn remove_argument(&mut self, call: &CallMatch<'a>, arg: &AstArgument) {
+        let Some(i) = call.args.iter().position(|x| x.span == arg.span) else {
+            return;
+        };
+        let span = if let Some(next) = call.args.get(i + 1) {
+            Span::new(arg.span.begin(), next.span.begin())
+        } else if let Some(prev) = i.checked_sub(1).map(|i| &call.args[i]) {
+            Span::new(prev.span.end(), arg.span.end())
+        } else {
+            Span::new(arg.span.begin(), call.close_paren())
+        };
+        self.delete(span);
+    }
+
+    /// Remove the keyword argument `name` from `call`, if it is passed.
+    pub fn remove_kwarg(&mut self, call: &CallMatch<'a>, name: &str) {
+        if let Some(arg) = call.kwarg_argument(name) {
+            self.remove_argument(call, arg);
+        }
+    }
+
+    /// Apply the edits to the source of the module.
+    pub fn apply(self) -> crate::Result<String> {
+        apply_edits(&self.module.codemap, self.edits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::Dialect;
+
+    fn parse(source: &str) -> AstModule {
+        AstModule::parse("BUILD", source.to_owned(), &Dialect::Standard).unwrap()
+    }
+
+    /// Apply `f` to every call matching `pattern` in `source`.
+    fn codemod(
+        source: &str,
+        pattern: &CallPattern,
+        mut f: impl for<'a> FnMut(&mut Codemod<'a>, &CallMatch<'a>),
+    ) -> String {
+        let module = parse(source);
+        let mut codemod = Codemod::new(&module);
+        for call in codemod.find_calls(pattern) {
+            f(&mut codemod, &call);
+        }
+        codemod.apply().unwrap()
+    }
+
+    #[test]
+    fn test_find_calls() {
+        let module = parse(
+            r#"
+foo(bar = 1)
+foo(baz = 2)
+native.foo(bar = 3)
+x.native.foo(bar = 4)
+def f():
+    return [foo(bar = foo(bar = 5))]
+"#,
+        );
+        let codemod = Codemod::new(&module);
+        let found = |pattern: &CallPattern| {
+            codemod
+                .find_calls(pattern)
+                .iter()
+                .map(|x| codemod.source(x.call.span))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec!["foo(bar = 1)", "foo(bar = foo(bar = 5))", "foo(bar = 5)"],
+            found(&CallPattern::new("foo").with_kwarg("bar"))
+        );
+        assert_eq!(
+            vec!["native.foo(bar = 3)"],
+            found(&CallPattern::new("native.foo"))
+        );
+    }
+
+    #[test]
+    fn test_rename_and_set_kwarg() {
+        let res = codemod(
+            r#"
+foo(bar = 1)  # keep
+foo(bar = 2, x = 3)
+foo()
+foo(1,)
+foo(
+    "a",
+    # comment
+    bar = 4,
+)
+"#,
+            &CallPattern::new("foo"),
+            |codemod, call| {
+                codemod.rename_kwarg(call, "bar", "baz");
+                codemod.set_kwarg(call, "x", "True");
+            },
+        );
+        assert_eq!(
+            r#"
+foo(baz = 1, x = True)  # keep
+foo(baz = 2, x = True)
+foo(x = True)
+foo(1, x = True,)
+foo(
+    "a",
+    # comment
+    baz = 4,
+    x = True,
+)
+"#,
+            res
+        );
+    }
+
+    #[test]
+    fn test_remove_kwarg() {
+        let res = codemod(
+            r#"
+foo(bar = 1)
+foo(bar = 1, x = 2)
+foo(x = 2, bar = 1)
+foo(x = 2, bar = 1,)
+foo(
+    x = 2,
+    bar = 1,
+    y = 3,
+)
+"#,
+            &CallPattern::new("foo"),
+            |codemod, call| codemod.remove_kwarg(call, "bar"),
+        );
+        assert_eq!(
+            r#"
+foo()
+foo(x = 2)
+foo(x = 2)
+foo(x = 2,)
+foo(
+    x = 2,
+    y = 3,
+)
+"#,
+            res
+        );
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let module = parse("a = 1\n");
+        let span = |begin: u32, end: u32| Span::new(Pos::new(begin), Pos::new(end));
+        let mut codemod = Codemod::new(&module);
+        codemod.insert(Pos::new(4), "(");
+        codemod.replace(span(4, 5), "2");
+        codemod.insert(Pos::new(5), ")");
+        codemod.insert(Pos::new(4), "-");
+        // Identical edits, e.g. from two rules matching the same node, are applied once.
+        codemod.replace(span(0, 1), "b");
+        codemod.replace(span(0, 1), "b");
+        assert_eq!("b = (-2)\n", codemod.apply().unwrap());
+
+        let mut codemod = Codemod::new(&module);
+        codemod.replace(span(0, 5), "a = 2");
+        codemod.replace(span(4, 5), "3");
+        let err = codemod.apply().unwrap_err().to_string();
+        assert!(
+            err.contains("Overlapping edits: cannot replace `a = 1` with `a = 2` and `1` with `3`"),
+            "{err}"
+        );
+    }
+}
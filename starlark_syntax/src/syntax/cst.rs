@@ -0,0 +1,416 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A lossless concrete syntax tree, which keeps the comments and whitespace that
+//! [`AstModule`] drops, so that tools can rewrite Starlark without losing formatting.
+//!
+//! A [`CstModule`] is the [`AstModule`] of a file together with all of its tokens.
+//! Every byte of the file that is not part of a token is [`Trivia`] attached to the
+//! token before or after it, so printing the tokens and their trivia gives back the
+//! file byte-for-byte. The nodes of the tree are the statements and expressions of the
+//! AST, each covering the tokens in its span.
+
+use std::fmt;
+use std::fmt::Display;
+
+use dupe::Dupe;
+
+use crate::codemap::CodeMap;
+use crate::codemap::Pos;
+use crate::codemap::Span;
+use crate::lexer::Lexer;
+use crate::lexer::Token;
+use crate::syntax::ast::AstExpr;
+use crate::syntax::ast::AstNoPayload;
+use crate::syntax::ast::AstStmt;
+use crate::syntax::module::AstModuleFields;
+use crate::syntax::uniplate::Visit;
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
+
+/// The kind of a piece of [`Trivia`].
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// Spaces and tabs.
+    Whitespace,
+    /// A line break that is not a token: a blank line, a line that only has a comment,
+    /// or a line break inside brackets.
+    Newline,
+    /// A comment, from the `#` to the end of the line.
+    Comment,
+    /// A backslash followed by a line break.
+    LineContinuation,
+}
+
+/// Text between tokens, which does not change the meaning of the program.
+#[derive(Debug, Clone, Copy, Dupe, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub span: Span,
+}
+
+/// A token with the trivia around it.
+///
+/// The trailing trivia of a token is the trivia after it on the same line. All other
+/// trivia, including the comments on the lines before a token, is the leading trivia
+/// of the next token. A [`Token::Newline`] ends a logical line and has no trailing trivia.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstToken {
+    pub token: Token,
+    pub span: Span,
+    pub leading_trivia: Vec<Trivia>,
+    pub trailing_trivia: Vec<Trivia>,
+}
+
+/// Split the text of `source` from `begin` to `end`, which contains no tokens, into trivia.
+fn split_trivia(source: &str, begin: usize, end: usize) -> Vec<Trivia> {
+    let span = |begin: usize, end: usize| Span::new(Pos::new(begin as u32), Pos::new(end as u32));
+    let text = &source[begin..end];
+    let mut res = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let (kind, len) = if rest.starts_with('#') {
+            (
+                TriviaKind::Comment,
+                rest.find(['\r', '\n']).unwrap_or(rest.len()),
+            )
+        } else if rest.starts_with("\\\n") {
+            (TriviaKind::LineContinuation, 2)
+        } else if rest.starts_with("\\\r\n") {
+            (TriviaKind::LineContinuation, 3)
+        } else if rest.starts_with("\r\n") {
+            (TriviaKind::Newline, 2)
+        } else if rest.starts_with('\n') {
+            (TriviaKind::Newline, 1)
+        } else {
+            let len = rest
+                .find(|c: char| !c.is_whitespace() || c == '\n' || c == '\r')
+                .unwrap_or(rest.len());
+            // Anything else is not trivia, but the lexer would have rejected it.
+            (
+                TriviaKind::Whitespace,
+                len.max(rest.chars().next().unwrap().len_utf8()),
+            )
+        };
+        res.push(Trivia {
+            kind,
+            span: span(begin + i, begin + i + len),
+        });
+        i += len;
+    }
+    res
+}
+
+/// Split trivia into the part on the same line as the token before it, and the rest.
+fn split_trailing(mut trivia: Vec<Trivia>) -> (Vec<Trivia>, Vec<Trivia>) {
+    let line_end = trivia
+        .iter()
+        .position(|x| x.kind == TriviaKind::Newline)
+        .unwrap_or(trivia.len());
+    let rest = trivia.split_off(line_end);
+    (trivia, rest)
+}
+
+/// A Starlark module as a lossless concrete syntax tree.
+///
+/// Created with [`parse`](CstModule::parse). Converted to an [`AstModule`] for evaluation
+/// with [`into_ast`](CstModule::into_ast).
+#[derive(Debug, Clone)]
+pub struct CstModule {
+    ast: AstModule,
+    tokens: Vec<CstToken>,
+    /// The trivia after the last token that is not on its line.
+    end_trivia: Vec<Trivia>,
+}
+
+impl CstModule {
+    /// Parse a Starlark module, with the same errors as [`AstModule::parse`].
+    pub fn parse(filename: &str, content: String, dialect: &Dialect) -> crate::Result<Self> {
+        let ast = AstModule::parse(filename, content, dialect)?;
+        let codemap = ast.codemap().dupe();
+        let source = codemap.source();
+
+        let mut spans: Vec<(usize, Token, usize)> = Vec::new();
+        for lexeme in Lexer::new(source, dialect, codemap.dupe()) {
+            let (begin, token, end) = lexeme.map_err(|e| e.into_error())?;
+            let is_token = match token {
+                // Indentation is whitespace, and dedents and the end of the file are empty.
+                Token::Comment(_) | Token::Indent | Token::Dedent => false,
+                // Only the line break at the end of a logical line is a token, not those
+                // of blank lines.
+                Token::Newline => spans
+                    .last()
+                    .is_some_and(|(_, last, _)| *last != Token::Newline),
+                _ => true,
+            };
+            if is_token && begin < end {
+                spans.push((begin, token, end));
+            }
+        }
+
+        let mut tokens: Vec<CstToken> = Vec::with_capacity(spans.len());
+        let mut last_end = 0;
+        for (begin, token, end) in spans {
+            let mut trivia = split_trivia(source, last_end, begin);
+            if let Some(last) = tokens.last_mut() {
+                if last.token != Token::Newline {
+                    let (trailing, rest) = split_trailing(trivia);
+                    last.trailing_trivia = trailing;
+                    trivia = rest;
+                }
+            }
+            tokens.push(CstToken {
+                token,
+                span: Span::new(Pos::new(begin as u32), Pos::new(end as u32)),
+                leading_trivia: trivia,
+                trailing_trivia: Vec::new(),
+            });
+            last_end = end;
+        }
+        let mut end_trivia = split_trivia(source, last_end, source.len());
+        if let Some(last) = tokens.last_mut() {
+            if last.token != Token::Newline {
+                let (trailing, rest) = split_trailing(end_trivia);
+                last.trailing_trivia = trailing;
+                end_trivia = rest;
+            }
+        }
+
+        Ok(CstModule {
+            ast,
+            tokens,
+            end_trivia,
+        })
+    }
+
+    /// The AST of the module.
+    pub fn ast(&self) -> &AstModule {
+        &self.ast
+    }
+
+    /// Convert to the AST of the module, which drops the trivia.
+    pub fn into_ast(self) -> AstModule {
+        self.ast
+    }
+
+    pub fn codemap(&self) -> &CodeMap {
+        self.ast.codemap()
+    }
+
+    /// The text of a token or piece of trivia.
+    pub fn text(&self, span: Span) -> &str {
+        self.codemap().source_span(span)
+    }
+
+    /// All the tokens of the module, in order.
+    pub fn tokens(&self) -> &[CstToken] {
+        &self.tokens
+    }
+
+    /// The trivia at the end of the module, after the line of the last token.
+    pub fn end_trivia(&self) -> &[Trivia] {
+        &self.end_trivia
+    }
+
+    /// The root of the tree, which is the statement of the whole module.
+    pub fn root(&self) -> CstNode<'_> {
+        CstNode {
+            module: self,
+            node: Visit::Stmt(self.ast.statement()),
+        }
+    }
+
+    /// The tokens whose spans are within `span`.
+    fn tokens_in(&self, span: Span) -> &[CstToken] {
+        let begin = self
+            .tokens
+            .partition_point(|x| x.span.begin() < span.begin());
+        let end = self.tokens.partition_point(|x| x.span.end() <= span.end());
+        &self.tokens[begin..end.max(begin)]
+    }
+}
+
+impl Display for CstModule {
+    /// Print the module exactly as it was parsed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            for trivia in &token.leading_trivia {
+                f.write_str(self.text(trivia.span))?;
+            }
+            f.write_str(self.text(token.span))?;
+            for trivia in &token.trailing_trivia {
+                f.write_str(self.text(trivia.span))?;
+            }
+        }
+        for trivia in &self.end_trivia {
+            f.write_str(self.text(trivia.span))?;
+        }
+        Ok(())
+    }
+}
+
+/// A statement or expression in a [`CstModule`], with its tokens.
+#[derive(Clone, Copy, Dupe)]
+pub struct CstNode<'a> {
+    module: &'a CstModule,
+    node: Visit<'a, AstNoPayload>,
+}
+
+impl<'a> CstNode<'a> {
+    pub fn span(&self) -> Span {
+        match self.node {
+            Visit::Stmt(x) => x.span,
+            Visit::Expr(x) => x.span,
+        }
+    }
+
+    /// The source text of the node, without the trivia around it.
+    pub fn text(&self) -> &'a str {
+        self.module.text(self.span())
+    }
+
+    /// The AST of the node, if it is a statement.
+    pub fn as_stmt(&self) -> Option<&'a AstStmt> {
+        match self.node {
+            Visit::Stmt(x) => Some(x),
+            Visit::Expr(_) => None,
+        }
+    }
+
+    /// The AST of the node, if it is an expression.
+    pub fn as_expr(&self) -> Option<&'a AstExpr> {
+        match self.node {
+            Visit::Stmt(_) => None,
+            Visit::Expr(x) => Some(x),
+        }
+    }
+
+    /// The statements and expressions directly inside this node.
+    pub fn children(&self) -> Vec<CstNode<'a>> {
+        let mut res = Vec::new();
+        self.node.visit_children(|node| {
+            res.push(CstNode {
+                module: self.module,
+                node,
+            })
+        });
+        res
+    }
+
+    /// The tokens of the node, including those of its children.
+    pub fn tokens(&self) -> &'a [CstToken] {
+        self.module.tokens_in(self.span())
+    }
+
+    /// The comments before the node, from the lines before it.
+    pub fn leading_comments(&self) -> Vec<&'a str> {
+        match self.tokens().first() {
+            Some(token) => self.comments(&token.leading_trivia),
+            None => Vec::new(),
+        }
+    }
+
+    /// The comments after the node on the line where it ends.
+    pub fn trailing_comments(&self) -> Vec<&'a str> {
+        match self
+            .tokens()
+            .iter()
+            .rev()
+            .find(|x| x.token != Token::Newline)
+        {
+            Some(token) => self.comments(&token.trailing_trivia),
+            None => Vec::new(),
+        }
+    }
+
+    fn comments(&self, trivia: &[Trivia]) -> Vec<&'a str> {
+        trivia
+            .iter()
+            .filter(|x| x.kind == TriviaKind::Comment)
+            .map(|x| self.module.text(x.span))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(program: &str) -> CstModule {
+        CstModule::parse(
+            "test.star",
+            program.to_owned(),
+            &Dialect::AllOptionsInternal,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn round_trips() {
+        for program in [
+            "",
+            "\n\n",
+            "x = 1",
+            "# Only a comment",
+            "x = [\n    1,  # one\n    2,\n]\n",
+            "def f(a, b):\n    # Add them.\n\n    return a + \\\n        b  # sum\n\n\n# Trailing\n",
+            "if x:\r\n    pass\r\nelse:\r\n    y = 'a'\r\n",
+            "f(\n    '''multi\n    line''',\n    # Last\n)",
+        ] {
+            assert_eq!(program, parse(program).to_string());
+        }
+    }
+
+    #[test]
+    fn attaches_trivia() {
+        let module = parse("# Header\n\nx = 1  # one\ny = x\n");
+        let texts: Vec<_> = module
+            .tokens()
+            .iter()
+            .map(|x| module.text(x.span))
+            .collect();
+        assert_eq!(vec!["x", "=", "1", "\n", "y", "=", "x", "\n"], texts);
+        let kinds = |trivia: &[Trivia]| trivia.iter().map(|x| x.kind).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                TriviaKind::Comment,
+                TriviaKind::Newline,
+                TriviaKind::Newline
+            ],
+            kinds(&module.tokens()[0].leading_trivia)
+        );
+        assert_eq!(
+            vec![TriviaKind::Whitespace, TriviaKind::Comment],
+            kinds(&module.tokens()[2].trailing_trivia)
+        );
+    }
+
+    #[test]
+    fn attaches_comments_to_nodes() {
+        let module = parse("# Header\nx = 1  # one\n\ndef f():\n    # Body\n    pass\n");
+        let statements = module.root().children();
+        assert_eq!(2, statements.len());
+        assert_eq!(vec!["# Header"], statements[0].leading_comments());
+        assert_eq!(vec!["# one"], statements[0].trailing_comments());
+        assert!(statements[0].as_stmt().is_some());
+        assert_eq!("x = 1", statements[0].text().trim_end());
+
+        let body = &statements[1].children()[0];
+        assert_eq!(vec!["# Body"], body.leading_comments());
+    }
+}
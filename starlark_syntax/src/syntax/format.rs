@@ -0,0 +1,1100 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A formatter, which prints a module in a canonical layout, in the style of
+//! [buildifier](https://github.com/bazelbuild/buildtools/tree/main/buildifier).
+//!
+//! Blocks are indented by four spaces and runs of blank lines are collapsed to one.
+//! A call, collection or parameter list whose items start on the line after its
+//! opening bracket, or which contains comments, is printed with one item per line
+//! and a trailing comma, otherwise on a single line. The symbols of a `load()` without
+//! comments are sorted, strings use double quotes when that needs no escapes, and
+//! parentheses are added only where they are needed or were written in the source.
+//! Every comment is kept: a comment on its own line stays on its own line before the
+//! code that followed it, and a comment after code stays at the end of the line where
+//! that code is printed.
+
+use std::ops::Range;
+
+use crate::codemap::Pos;
+use crate::codemap::Span;
+use crate::lexer::Token;
+use crate::syntax::ast::ArgumentP;
+use crate::syntax::ast::AssignTargetP;
+use crate::syntax::ast::AstAssignTarget;
+use crate::syntax::ast::AstExpr;
+use crate::syntax::ast::AstLiteral;
+use crate::syntax::ast::AstNoPayload;
+use crate::syntax::ast::AstParameter;
+use crate::syntax::ast::AstStmt;
+use crate::syntax::ast::ClauseP;
+use crate::syntax::ast::ExprP;
+use crate::syntax::ast::ForClauseP;
+use crate::syntax::ast::LoadArgP;
+use crate::syntax::ast::ParameterP;
+use crate::syntax::ast::StmtP;
+use crate::syntax::cst::CstModule;
+use crate::syntax::cst::TriviaKind;
+use crate::syntax::module::AstModuleFields;
+use crate::syntax::unparse::bin_op_precedence;
+use crate::syntax::unparse::precedence;
+use crate::syntax::unparse::PREC_COMPARE;
+use crate::syntax::unparse::PREC_IF;
+use crate::syntax::unparse::PREC_NOT;
+use crate::syntax::unparse::PREC_OR;
+use crate::syntax::unparse::PREC_PRIMARY;
+use crate::syntax::unparse::PREC_TEST;
+use crate::syntax::unparse::PREC_UNARY;
+use crate::syntax::Dialect;
+
+/// The width of one level of indentation.
+const INDENT: &str = "    ";
+
+/// Use double quotes for a string literal in single quotes, unless that would need
+/// escapes. Triple quoted strings are left alone.
+fn normalize_string(text: &str) -> String {
+    let prefix_len = text.find(['\'', '"']).unwrap_or(0);
+    let (prefix, body) = text.split_at(prefix_len);
+    if body.len() >= 2 && body.starts_with('\'') && !body.starts_with("'''") {
+        let inner = &body[1..body.len() - 1];
+        if !inner.contains(['"', '\\']) {
+            return format!("{prefix}\"{inner}\"");
+        }
+    }
+    text.to_owned()
+}
+
+struct Formatter<'a> {
+    module: &'a CstModule,
+    /// The spans of all the comments, in order.
+    comments: Vec<Span>,
+    /// The index of the first comment that is not printed yet.
+    next_comment: usize,
+    out: String,
+    indent: usize,
+    /// Nothing is written on the current output line yet.
+    at_line_start: bool,
+    /// The current output line is the first of a block or bracket, so no blank line
+    /// is written before it.
+    block_start: bool,
+    /// The end of the last source that is printed, which comments on its line follow.
+    last_end: Pos,
+    /// The span of each top level statement in the source, including the lines of
+    /// its comments, and the range of its text in the output.
+    statements: Vec<(Span, Range<usize>)>,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(module: &'a CstModule) -> Self {
+        let comments = module
+            .tokens()
+            .iter()
+            .flat_map(|token| token.leading_trivia.iter().chain(&token.trailing_trivia))
+            .chain(module.end_trivia())
+            .filter(|trivia| trivia.kind == TriviaKind::Comment)
+            .map(|trivia| trivia.span)
+            .collect();
+        Formatter {
+            module,
+            comments,
+            next_comment: 0,
+            out: String::new(),
+            indent: 0,
+            at_line_start: true,
+            block_start: true,
+            last_end: Pos::new(0),
+            statements: Vec::new(),
+        }
+    }
+
+    fn line(&self, pos: Pos) -> usize {
+        self.module.codemap().find_line(pos)
+    }
+
+    fn column(&self, pos: Pos) -> u32 {
+        pos.get()
+            - self
+                .module
+                .codemap()
+                .line_span(self.line(pos))
+                .begin()
+                .get()
+    }
+
+    /// Whether the line before the one of `pos` is blank.
+    fn blank_before(&self, pos: Pos) -> bool {
+        let line = self.line(pos);
+        line > 0
+            && self
+                .module
+                .codemap()
+                .source_line(line - 1)
+                .trim()
+                .is_empty()
+    }
+
+    /// The index of the first token that starts at or after `pos`.
+    fn token_index(&self, pos: Pos) -> usize {
+        self.module
+            .tokens()
+            .partition_point(|token| token.span.begin() < pos)
+    }
+
+    /// The span of the last `token` before `pos`.
+    fn token_before(&self, pos: Pos, token: &Token) -> Span {
+        let tokens = &self.module.tokens()[..self.token_index(pos)];
+        match tokens.iter().rev().find(|x| x.token == *token) {
+            Some(x) => x.span,
+            None => Span::new(pos, pos),
+        }
+    }
+
+    /// The spans of the first `open` bracket at or after `pos`, and of its matching
+    /// closing bracket.
+    fn brackets(&self, pos: Pos, open: &Token) -> (Span, Span) {
+        let tokens = self.module.tokens();
+        let Some(start) = tokens[self.token_index(pos)..]
+            .iter()
+            .position(|x| x.token == *open)
+            .map(|i| i + self.token_index(pos))
+        else {
+            return (Span::new(pos, pos), Span::new(pos, pos));
+        };
+        let mut depth = 0;
+        for token in &tokens[start..] {
+            match token.token {
+                Token::OpeningRound | Token::OpeningSquare | Token::OpeningCurly => depth += 1,
+                Token::ClosingRound | Token::ClosingSquare | Token::ClosingCurly => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return (tokens[start].span, token.span);
+                    }
+                }
+                _ => {}
+            }
+        }
+        (tokens[start].span, tokens[start].span)
+    }
+
+    /// The number of pairs of parentheses directly around `span` in the source.
+    fn paren_layers(&self, span: Span) -> usize {
+        let tokens = self.module.tokens();
+        let before = tokens[..self.token_index(span.begin())]
+            .iter()
+            .rev()
+            .take_while(|x| x.token == Token::OpeningRound)
+            .count();
+        let after = tokens[self.token_index(span.end())..]
+            .iter()
+            .take_while(|x| x.token == Token::ClosingRound)
+            .count();
+        before.min(after)
+    }
+
+    /// The end of the last token within `span`, ignoring line breaks.
+    fn last_token_end(&self, span: Span) -> Pos {
+        let tokens = &self.module.tokens()[..self.token_index(span.end())];
+        match tokens
+            .iter()
+            .rev()
+            .find(|x| x.token != Token::Newline && x.span.end() <= span.end())
+        {
+            Some(x) => x.span.end(),
+            None => span.end(),
+        }
+    }
+
+    fn seen(&mut self, pos: Pos) {
+        self.last_end = self.last_end.max(pos);
+    }
+
+    fn write(&mut self, s: &str) {
+        if self.at_line_start {
+            for _ in 0..self.indent {
+                self.out.push_str(INDENT);
+            }
+            self.at_line_start = false;
+        }
+        self.out.push_str(s);
+    }
+
+    fn comment_text(&self, span: Span) -> &'a str {
+        self.module.text(span).trim_end()
+    }
+
+    fn blank_line(&mut self) {
+        if !self.block_start && !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    /// Whether only commas and line breaks are between the source printed last and `pos`.
+    fn follows_last(&self, pos: Pos) -> bool {
+        self.module.tokens()[self.token_index(self.last_end)..self.token_index(pos)]
+            .iter()
+            .all(|x| matches!(x.token, Token::Newline | Token::Comma))
+    }
+
+    /// End the current output line, moving the comment that follows the source printed
+    /// last on its line, and the comments within it, to the end of the output line.
+    fn newline(&mut self) {
+        if self.at_line_start {
+            return;
+        }
+        let mut first = true;
+        while let Some(&comment) = self.comments.get(self.next_comment) {
+            let within = comment.begin() < self.last_end;
+            if !within
+                && (self.line(comment.begin()) != self.line(self.last_end)
+                    || !self.follows_last(comment.begin()))
+            {
+                break;
+            }
+            if first {
+                self.out.push_str("  ");
+                first = false;
+            } else {
+                self.out.push('\n');
+                self.at_line_start = true;
+            }
+            self.write(self.comment_text(comment));
+            self.next_comment += 1;
+            self.seen(comment.end());
+            if !within {
+                break;
+            }
+        }
+        self.out.push('\n');
+        self.at_line_start = true;
+        self.block_start = false;
+    }
+
+    /// Print the next comment on a line of its own.
+    fn own_line_comment(&mut self) {
+        let comment = self.comments[self.next_comment];
+        if self.blank_before(comment.begin()) {
+            self.blank_line();
+        }
+        self.write(self.comment_text(comment));
+        self.next_comment += 1;
+        self.seen(comment.end());
+        self.out.push('\n');
+        self.at_line_start = true;
+        self.block_start = false;
+    }
+
+    /// Start a line for the source at `pos`, after the comments before it, and after
+    /// a blank line if there was one in the source and `blank` is set.
+    fn start_line(&mut self, pos: Pos, blank: bool) {
+        self.newline();
+        while self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.begin() < pos)
+        {
+            self.own_line_comment();
+        }
+        if blank && self.blank_before(pos) {
+            self.blank_line();
+        }
+    }
+
+    fn module(&mut self) {
+        let root = self.module.ast().statement();
+        let statements = match &root.node {
+            StmtP::Statements(xs) => xs.iter().collect(),
+            _ => vec![root],
+        };
+        let codemap = self.module.codemap();
+        for stmt in statements {
+            self.start_line(stmt.span.begin(), true);
+            let begin = self.out.len();
+            self.stmt(stmt);
+            self.newline();
+            let first_line = codemap.line_span(self.line(stmt.span.begin()));
+            let last_line = codemap.line_span(
+                self.line(Pos::new(
+                    self.last_end
+                        .get()
+                        .saturating_sub(1)
+                        .max(stmt.span.begin().get()),
+                )),
+            );
+            self.statements.push((
+                Span::new(first_line.begin(), last_line.end()),
+                begin..self.out.len(),
+            ));
+        }
+        self.newline();
+        while self.next_comment < self.comments.len() {
+            self.own_line_comment();
+        }
+    }
+
+    fn stmt(&mut self, stmt: &AstStmt) {
+        match &stmt.node {
+            StmtP::Statements(xs) => {
+                for x in xs {
+                    self.stmt(x);
+                }
+            }
+            StmtP::If(cond, body) => {
+                self.start_line(stmt.span.begin(), true);
+                self.write("if ");
+                self.if_body(cond, body, None);
+            }
+            StmtP::IfElse(cond, then_else) => {
+                self.start_line(stmt.span.begin(), true);
+                self.write("if ");
+                self.if_body(cond, &then_else.0, Some(&then_else.1));
+            }
+            StmtP::For(for_) => {
+                self.start_line(stmt.span.begin(), true);
+                self.write("for ");
+                self.target(&for_.var);
+                self.write(" in ");
+                self.expr(&for_.over, PREC_TEST);
+                self.block(&for_.body);
+            }
+            StmtP::While(while_) => {
+                self.start_line(stmt.span.begin(), true);
+                self.write("while ");
+                self.expr(&while_.cond, PREC_TEST);
+                self.block(&while_.body);
+            }
+            StmtP::Def(def) => {
+                self.start_line(stmt.span.begin(), true);
+                self.write("def ");
+                self.write(&def.name.ident);
+                self.seen(def.name.span.end());
+                let brackets = self.brackets(def.name.span.end(), &Token::OpeningRound);
+                self.group(
+                    ("(", ")"),
+                    brackets,
+                    &def.params,
+                    false,
+                    |x| x.span.begin(),
+                    |this, x| this.param(x),
+                );
+                if let Some(return_type) = &def.return_type {
+                    self.write(" -> ");
+                    self.expr(&return_type.expr, PREC_TEST);
+                }
+                self.block(&def.body);
+            }
+            _ => {
+                self.start_line(stmt.span.begin(), true);
+                self.simple_stmt(stmt);
+                let end = self.last_token_end(stmt.span);
+                self.seen(end);
+                self.newline();
+            }
+        }
+    }
+
+    fn simple_stmt(&mut self, stmt: &AstStmt) {
+        match &stmt.node {
+            StmtP::Break => self.write("break"),
+            StmtP::Continue => self.write("continue"),
+            StmtP::Pass => self.write("pass"),
+            StmtP::Return(None) => self.write("return"),
+            StmtP::Return(Some(x)) => {
+                self.write("return ");
+                self.expr(x, PREC_TEST);
+            }
+            StmtP::Expression(x) => self.expr(x, PREC_TEST),
+            StmtP::Assign(assign) => {
+                self.target(&assign.lhs);
+                if let Some(ty) = &assign.ty {
+                    self.write(": ");
+                    self.expr(&ty.expr, PREC_TEST);
+                }
+                self.write(" = ");
+                self.expr(&assign.rhs, PREC_TEST);
+            }
+            StmtP::AssignModify(lhs, op, rhs) => {
+                self.target(lhs);
+                self.write(&op.to_string());
+                self.expr(rhs, PREC_TEST);
+            }
+            StmtP::Load(load) => {
+                let mut args: Vec<&LoadArgP<AstNoPayload>> = load.args.iter().collect();
+                // Comments are printed in source order, so sorting would move them away
+                // from the symbols they are about.
+                let has_comments = self.comments[self.next_comment..]
+                    .iter()
+                    .any(|comment| comment.begin() < stmt.span.end());
+                if !has_comments {
+                    args.sort_by(|a, b| a.local.ident.cmp(&b.local.ident));
+                }
+                // The module is the first item, then the symbols.
+                let items: Vec<Option<&LoadArgP<AstNoPayload>>> = std::iter::once(None)
+                    .chain(args.into_iter().map(Some))
+                    .collect();
+                self.write("load");
+                let brackets = self.brackets(stmt.span.begin(), &Token::OpeningRound);
+                self.group(
+                    ("(", ")"),
+                    brackets,
+                    &items,
+                    false,
+                    |x| match x {
+                        None => load.module.span.begin(),
+                        Some(arg) => arg.local.span.begin().min(arg.their.span.begin()),
+                    },
+                    |this, x| match x {
+                        None => this.string(load.module.span),
+                        Some(arg) => {
+                            if arg.local.span != arg.their.span {
+                                this.write(&arg.local.ident);
+                                this.write(" = ");
+                            }
+                            this.string(arg.their.span);
+                        }
+                    },
+                );
+            }
+            // Compound statements are printed by `stmt`.
+            _ => self.stmt(stmt),
+        }
+    }
+
+    /// Print the condition and blocks of an `if` or `elif`, after the keyword.
+    fn if_body(&mut self, cond: &AstExpr, then_block: &AstStmt, else_block: Option<&AstStmt>) {
+        self.expr(cond, PREC_TEST);
+        self.block(then_block);
+        let Some(else_block) = else_block else {
+            return;
+        };
+        let elif = self.token_before(else_block.span.begin(), &Token::Elif);
+        let is_elif = self.module.tokens()[..self.token_index(else_block.span.begin())]
+            .last()
+            .is_some_and(|x| x.token == Token::Elif);
+        match &else_block.node {
+            StmtP::If(cond, body) if is_elif => {
+                self.start_line(elif.begin(), false);
+                self.write("elif ");
+                self.if_body(cond, body, None);
+            }
+            StmtP::IfElse(cond, then_else) if is_elif => {
+                self.start_line(elif.begin(), false);
+                self.write("elif ");
+                self.if_body(cond, &then_else.0, Some(&then_else.1));
+            }
+            _ => {
+                let else_ = self.token_before(else_block.span.begin(), &Token::Else);
+                self.start_line(else_.begin(), false);
+                self.write("else");
+                self.seen(else_.end());
+                self.block(else_block);
+            }
+        }
+    }
+
+    /// Print the `:` and the indented body of a compound statement.
+    fn block(&mut self, body: &AstStmt) {
+        self.write(":");
+        let colon = self.token_before(body.span.begin(), &Token::Colon);
+        self.seen(colon.end());
+        let mut first = body;
+        while let StmtP::Statements(xs) = &first.node {
+            match xs.first() {
+                Some(x) => first = x,
+                None => break,
+            }
+        }
+        let column = self.column(first.span.begin());
+        // Comments that do not fit at the end of the line go at the start of the block.
+        self.indent += 1;
+        self.newline();
+        self.block_start = true;
+        self.stmt(body);
+        self.newline();
+
+        // Comments after the block that are indented like it belong to it.
+        let end = self.last_token_end(body.span);
+        let tokens = &self.module.tokens()[self.token_index(end)..];
+        let next = tokens
+            .iter()
+            .find(|x| x.token != Token::Newline)
+            .map_or(self.module.codemap().full_span().end(), |x| x.span.begin());
+        while let Some(&comment) = self.comments.get(self.next_comment) {
+            let own_line = self.module.codemap().source_line_at_pos(comment.begin())
+                [..self.column(comment.begin()) as usize]
+                .trim()
+                .is_empty();
+            if comment.begin() >= next || !own_line || self.column(comment.begin()) < column {
+                break;
+            }
+            self.own_line_comment();
+        }
+        self.indent -= 1;
+    }
+
+    /// Print items between brackets, on one line, or one per line with a trailing
+    /// comma if the first item starts on the line after the opening bracket in the
+    /// source or there are comments between the brackets. A single item on one line
+    /// is followed by a comma if `tuple` is set.
+    fn group<T>(
+        &mut self,
+        (open, close): (&str, &str),
+        (open_span, close_span): (Span, Span),
+        items: &[T],
+        tuple: bool,
+        begin: impl Fn(&T) -> Pos,
+        mut item: impl FnMut(&mut Self, &T),
+    ) {
+        self.write(open);
+        self.seen(open_span.end());
+        let tokens = self.module.tokens();
+        let line_break = tokens
+            .get(self.token_index(open_span.end()))
+            .is_some_and(|x| {
+                x.leading_trivia
+                    .iter()
+                    .any(|trivia| trivia.kind == TriviaKind::Newline)
+            });
+        let has_comment = self
+            .comments
+            .get(
+                self.comments
+                    .partition_point(|x| x.begin() < open_span.end()),
+            )
+            .is_some_and(|comment| comment.begin() < close_span.begin());
+        if (line_break && !items.is_empty()) || has_comment {
+            self.indent += 1;
+            self.newline();
+            self.block_start = true;
+            for x in items {
+                self.start_line(begin(x), true);
+                item(self, x);
+                self.write(",");
+                self.newline();
+            }
+            self.start_line(close_span.begin(), false);
+            self.indent -= 1;
+        } else {
+            for (i, x) in items.iter().enumerate() {
+                if i != 0 {
+                    self.write(", ");
+                }
+                item(self, x);
+            }
+            if tuple && items.len() == 1 {
+                self.write(",");
+            }
+        }
+        self.write(close);
+        self.seen(close_span.end());
+    }
+
+    fn string(&mut self, span: Span) {
+        self.write(&normalize_string(self.module.text(span)));
+        self.seen(span.end());
+    }
+
+    fn param(&mut self, param: &AstParameter) {
+        match &param.node {
+            ParameterP::Slash => self.write("/"),
+            ParameterP::NoArgs => self.write("*"),
+            ParameterP::Normal(name, ty, default) => {
+                self.write(&name.ident);
+                if let Some(ty) = ty {
+                    self.write(": ");
+                    self.expr(&ty.expr, PREC_TEST);
+                }
+                if let Some(default) = default {
+                    self.write(" = ");
+                    self.expr(default, PREC_TEST);
+                }
+            }
+            ParameterP::Args(name, ty) | ParameterP::KwArgs(name, ty) => {
+                self.write(if matches!(param.node, ParameterP::Args(..)) {
+                    "*"
+                } else {
+                    "**"
+                });
+                self.write(&name.ident);
+                if let Some(ty) = ty {
+                    self.write(": ");
+                    self.expr(&ty.expr, PREC_TEST);
+                }
+            }
+        }
+        self.seen(param.span.end());
+    }
+
+    fn target(&mut self, target: &AstAssignTarget) {
+        match &target.node {
+            AssignTargetP::Tuple(xs) => {
+                if self.module.text(target.span).starts_with('[') {
+                    let brackets = self.brackets(target.span.begin(), &Token::OpeningSquare);
+                    self.group(
+                        ("[", "]"),
+                        brackets,
+                        xs,
+                        false,
+                        |x| x.span.begin(),
+                        |this, x| this.target(x),
+                    );
+                } else {
+                    self.tuple(
+                        target.span,
+                        xs,
+                        |x| x.span.begin(),
+                        |this, x| this.target(x),
+                    );
+                }
+            }
+            AssignTargetP::Index(array_index) => {
+                self.expr(&array_index.0, PREC_PRIMARY);
+                self.write("[");
+                self.expr(&array_index.1, PREC_TEST);
+                self.write("]");
+            }
+            AssignTargetP::Dot(object, field) => {
+                self.expr(object, PREC_PRIMARY);
+                self.write(".");
+                self.write(field);
+            }
+            AssignTargetP::Identifier(ident) => self.write(&ident.ident),
+        }
+        self.seen(target.span.end());
+    }
+
+    /// Print a tuple, in parentheses if it has them in the source.
+    fn tuple<T>(
+        &mut self,
+        span: Span,
+        items: &[T],
+        begin: impl Fn(&T) -> Pos,
+        mut item: impl FnMut(&mut Self, &T),
+    ) {
+        if items.is_empty() {
+            self.write("()");
+        } else if self.paren_layers(span) > 0 {
+            let tokens = self.module.tokens();
+            let open = tokens[self.token_index(span.begin()) - 1].span;
+            let close = tokens[self.token_index(span.end())].span;
+            self.group(("(", ")"), (open, close), items, true, begin, item);
+        } else {
+            for (i, x) in items.iter().enumerate() {
+                if i != 0 {
+                    self.write(", ");
+                }
+                item(self, x);
+            }
+            if items.len() == 1 {
+                self.write(",");
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &AstExpr, min_precedence: u8) {
+        let layers = self.paren_layers(expr.span);
+        self.expr_in_parens(expr, min_precedence, layers);
+    }
+
+    /// Print an expression, which has `layers` pairs of parentheses around it in the
+    /// source, in parentheses if it had any, or if it binds looser than `min_precedence`.
+    fn expr_in_parens(&mut self, expr: &AstExpr, min_precedence: u8, layers: usize) {
+        // A tuple takes care of its own parentheses.
+        let parens = !matches!(expr.node, ExprP::Tuple(_))
+            && (layers > 0 || precedence(&expr.node) < min_precedence);
+        if parens {
+            self.write("(");
+        }
+        self.expr_node(expr);
+        if parens {
+            self.write(")");
+        }
+        self.seen(expr.span.end());
+    }
+
+    fn expr_node(&mut self, expr: &AstExpr) {
+        match &expr.node {
+            ExprP::Tuple(xs) => self.tuple(
+                expr.span,
+                xs,
+                |x| x.span.begin(),
+                |this, x| this.expr(x, PREC_TEST),
+            ),
+            ExprP::Dot(object, field) => {
+                self.expr(object, PREC_PRIMARY);
+                self.write(".");
+                self.write(field);
+            }
+            ExprP::Call(f, args) => {
+                self.expr(f, PREC_PRIMARY);
+                let brackets = self.brackets(f.span.end(), &Token::OpeningRound);
+                let sole = args.args.len() == 1;
+                self.group(
+                    ("(", ")"),
+                    brackets,
+                    &args.args,
+                    false,
+                    |x| x.span.begin(),
+                    |this, x| match &x.node {
+                        // The parentheses of the call are directly around its only argument.
+                        ArgumentP::Positional(e) if sole => {
+                            let layers = this.paren_layers(e.span).saturating_sub(1);
+                            this.expr_in_parens(e, PREC_TEST, layers);
+                        }
+                        ArgumentP::Positional(e) => this.expr(e, PREC_TEST),
+                        ArgumentP::Named(name, e) => {
+                            this.write(name);
+                            this.write(" = ");
+                            this.expr(e, PREC_TEST);
+                        }
+                        ArgumentP::Args(e) => {
+                            this.write("*");
+                            this.expr(e, PREC_TEST);
+                        }
+                        ArgumentP::KwArgs(e) => {
+                            this.write("**");
+                            this.expr(e, PREC_TEST);
+                        }
+                    },
+                );
+            }
+            ExprP::Index(array_index) => {
+                self.expr(&array_index.0, PREC_PRIMARY);
+                self.write("[");
+                self.expr(&array_index.1, PREC_TEST);
+                self.write("]");
+            }
+            ExprP::Index2(array_index) => {
+                self.expr(&array_index.0, PREC_PRIMARY);
+                self.write("[");
+                self.expr(&array_index.1, PREC_TEST);
+                self.write(", ");
+                self.expr(&array_index.2, PREC_TEST);
+                self.write("]");
+            }
+            ExprP::Slice(array, start, stop, step) => {
+                self.expr(array, PREC_PRIMARY);
+                self.write("[");
+                if let Some(start) = start {
+                    self.expr(start, PREC_TEST);
+                }
+                self.write(":");
+                if let Some(stop) = stop {
+                    self.expr(stop, PREC_TEST);
+                }
+                if let Some(step) = step {
+                    self.write(":");
+                    self.expr(step, PREC_TEST);
+                }
+                self.write("]");
+            }
+            ExprP::Identifier(ident) => self.write(&ident.node.ident),
+            ExprP::Lambda(lambda) => {
+                self.write("lambda");
+                for (i, param) in lambda.params.iter().enumerate() {
+                    self.write(if i == 0 { " " } else { ", " });
+                    self.param(param);
+                }
+                self.write(": ");
+                self.expr(&lambda.body, PREC_TEST);
+            }
+            ExprP::Literal(AstLiteral::String(_)) | ExprP::FString(_) => self.string(expr.span),
+            ExprP::Literal(AstLiteral::Ellipsis) => self.write("..."),
+            ExprP::Literal(_) => self.write(self.module.text(expr.span)),
+            ExprP::Not(x) => {
+                self.write("not ");
+                self.expr(x, PREC_NOT);
+            }
+            ExprP::Minus(x) => {
+                self.write("-");
+                self.expr(x, PREC_UNARY);
+            }
+            ExprP::Plus(x) => {
+                self.write("+");
+                self.expr(x, PREC_UNARY);
+            }
+            ExprP::BitNot(x) => {
+                self.write("~");
+                self.expr(x, PREC_UNARY);
+            }
+            ExprP::Op(lhs, op, rhs) => {
+                let precedence = bin_op_precedence(*op);
+                // Comparisons do not chain, and the other operators are left associative.
+                let lhs_precedence = if precedence == PREC_COMPARE {
+                    precedence + 1
+                } else {
+                    precedence
+                };
+                self.expr(lhs, lhs_precedence);
+                self.write(&op.to_string());
+                self.expr(rhs, precedence + 1);
+            }
+            ExprP::If(cond_then_else) => {
+                let (cond, then_value, else_value) = &**cond_then_else;
+                self.expr(then_value, PREC_OR);
+                self.write(" if ");
+                self.expr(cond, PREC_OR);
+                self.write(" else ");
+                self.expr(else_value, PREC_IF);
+            }
+            ExprP::List(xs) => {
+                let brackets = self.brackets(expr.span.begin(), &Token::OpeningSquare);
+                self.group(
+                    ("[", "]"),
+                    brackets,
+                    xs,
+                    false,
+                    |x| x.span.begin(),
+                    |this, x| this.expr(x, PREC_TEST),
+                );
+            }
+            ExprP::Dict(xs) => {
+                let brackets = self.brackets(expr.span.begin(), &Token::OpeningCurly);
+                self.group(
+                    ("{", "}"),
+                    brackets,
+                    xs,
+                    false,
+                    |(k, _)| k.span.begin(),
+                    |this, (k, v)| {
+                        this.expr(k, PREC_TEST);
+                        this.write(": ");
+                        this.expr(v, PREC_TEST);
+                    },
+                );
+            }
+            ExprP::Set(xs) => {
+                let brackets = self.brackets(expr.span.begin(), &Token::OpeningCurly);
+                self.group(
+                    ("{", "}"),
+                    brackets,
+                    xs,
+                    false,
+                    |x| x.span.begin(),
+                    |this, x| this.expr(x, PREC_TEST),
+                );
+            }
+            ExprP::ListComprehension(x, for_, clauses) => {
+                self.write("[");
+                self.expr(x, PREC_TEST);
+                self.clauses(for_, clauses);
+                self.write("]");
+            }
+            ExprP::DictComprehension(k_v, for_, clauses) => {
+                self.write("{");
+                self.expr(&k_v.0, PREC_TEST);
+                self.write(": ");
+                self.expr(&k_v.1, PREC_TEST);
+                self.clauses(for_, clauses);
+                self.write("}");
+            }
+            ExprP::SetComprehension(x, for_, clauses) => {
+                self.write("{");
+                self.expr(x, PREC_TEST);
+                self.clauses(for_, clauses);
+                self.write("}");
+            }
+        }
+    }
+
+    fn for_clause(&mut self, for_: &ForClauseP<AstNoPayload>) {
+        self.write(" for ");
+        self.target(&for_.var);
+        self.write(" in ");
+        self.expr(&for_.over, PREC_OR);
+    }
+
+    fn clauses(&mut self, for_: &ForClauseP<AstNoPayload>, clauses: &[ClauseP<AstNoPayload>]) {
+        self.for_clause(for_);
+        for clause in clauses {
+            match clause {
+                ClauseP::For(for_) => self.for_clause(for_),
+                ClauseP::If(cond) => {
+                    self.write(" if ");
+                    self.expr(cond, PREC_OR);
+                }
+            }
+        }
+    }
+}
+
+/// Format a module. The result parses to the same AST as the module, except for
+/// the order of the symbols of `load()` statements without comments.
+pub fn format(module: &CstModule) -> String {
+    let mut formatter = Formatter::new(module);
+    formatter.module();
+    formatter.out
+}
+
+/// Parse and format Starlark source code.
+pub fn format_source(filename: &str, content: String, dialect: &Dialect) -> crate::Result<String> {
+    Ok(format(&CstModule::parse(filename, content, dialect)?))
+}
+
+/// Format the top level statements of a module that overlap `span`. Returns the span
+/// of the whole lines of source they cover, and the formatted text to replace them
+/// with, or `None` if no statement overlaps `span`.
+pub fn format_range(module: &CstModule, span: Span) -> Option<(Span, String)> {
+    let mut formatter = Formatter::new(module);
+    formatter.module();
+    let statements: Vec<&(Span, Range<usize>)> = formatter
+        .statements
+        .iter()
+        .filter(|(source, _)| {
+            source.begin() <= span.end()
+                && (span.begin() < source.end() || source.begin() == span.begin())
+        })
+        .collect();
+    let first = statements.first()?;
+    let last = statements.last()?;
+    Some((
+        Span::new(first.0.begin(), last.0.end()),
+        formatter.out[first.1.start..last.1.end].to_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(program: &str) -> String {
+        format_source(
+            "test.star",
+            program.to_owned(),
+            &Dialect::AllOptionsInternal,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn formats_layout() {
+        let program = r#"
+load('b.star', 'z', y = 'x')  # why
+x=[1,2 ,
+  3]
+y = [
+  'a',  # first
+  # before b
+  "b"
+]
+
+
+
+def f(a,b=1,*args,**kwargs):
+  # body
+  if a and (b or c): return -(a+b)*c
+  elif not a: pass
+  else:
+      return lambda x:x
+  # end of f
+z = {'k':(1,),  "l": f(a = 1)}
+"#;
+        assert_eq!(
+            r#"load("b.star", y = "x", "z")  # why
+x = [1, 2, 3]
+y = [
+    "a",  # first
+    # before b
+    "b",
+]
+
+def f(a, b = 1, *args, **kwargs):
+    # body
+    if a and (b or c):
+        return -(a + b) * c
+    elif not a:
+        pass
+    else:
+        return lambda x: x
+    # end of f
+z = {"k": (1,), "l": f(a = 1)}
+"#,
+            fmt(program)
+        );
+    }
+
+    #[test]
+    fn format_is_idempotent() {
+        let program = r#"
+# Leading comment.
+
+def f(
+    x,  # the x
+    y = (1 + 2) * 3,
+):
+    """Docstring."""
+    return [
+        a  # inside
+        for a in x
+        if a > y
+    ]
+
+print(f((1, 2)), x[1:], 'it', a if b else c)
+"#;
+        let once = fmt(program);
+        assert_eq!(once, fmt(&once));
+        assert!(once.contains("# inside"));
+    }
+
+    #[test]
+    fn formats_while() {
+        assert_eq!(
+            "def f(x):\n    while x > 0:\n        x -= 1\n",
+            fmt("def f(x):\n  while x>0: x-=1\n")
+        );
+    }
+
+    #[test]
+    fn formats_set() {
+        assert_eq!(
+            "x = {1, 2}\ny = {a for a in b}\n",
+            fmt("x={1,2}\ny={a   for a in b}\n")
+        );
+    }
+
+    #[test]
+    fn formats_comments_in_brackets() {
+        assert_eq!(
+            "f(\n    a,  # c\n    b,\n    c,  # d\n)\n",
+            fmt("f(a, # c\n b, c, # d\n)\n")
+        );
+        assert_eq!(
+            "x = {\n    1: 2,  # x\n}\ny = [\n    1,  # x\n]\n",
+            fmt("x = {1: 2, # x\n}\ny = [1, # x\n]\n")
+        );
+    }
+
+    #[test]
+    fn keeps_order_of_load_with_comments() {
+        assert_eq!(
+            "load(\n    \"a.star\",\n    # comment for b\n    \"b\",\n    \"a\",\n)\n",
+            fmt("load(\n    'a.star',\n    # comment for b\n    'b',\n    'a',\n)\n")
+        );
+        assert_eq!(
+            "load(\"a.star\", \"a\", \"b\")\n",
+            fmt("load('a.star', 'b', 'a')\n")
+        );
+    }
+
+    #[test]
+    fn formats_range() {
+        let program = "x=1\ny=[1,\n2]\nz=3\n";
+        let module = CstModule::parse("test.star", program.to_owned(), &Dialect::Standard).unwrap();
+        let (span, text) = format_range(&module, Span::new(Pos::new(5), Pos::new(6))).unwrap();
+        assert_eq!("y=[1,\n2]\n", module.text(span));
+        assert_eq!("y = [1, 2]\n", text);
+    }
+}
@@ -281,6 +281,22 @@ fn test_comprehension() {
     );
 }
 
+#[test]
+fn test_set() {
+    assert_eq!(parse("{1, 2, 3}"), "{1, 2, 3}\n");
+    assert_eq!(parse("{1,}"), "{1}\n");
+    assert_eq!(parse("{}"), "{}\n");
+    assert_eq!(
+        parse("{x for x in range(12) if x % 2 == 0}"),
+        "{x for x in range(12) if ((x % 2) == 0)}\n"
+    );
+    parse_fails_with_dialect(
+        "set",
+        &Dialect::Standard,
+        &["x = {1, 2}", "x = {y for y in z}"],
+    );
+}
+
 #[test]
 fn test_lambda() {
     assert_eq!(parse("x = lambda y: y + 1"), "x = (lambda y: (y + 1))\n");
@@ -373,6 +389,56 @@ fn test_test_list_in_index_expr() {
     parse_fail("list_in_index_expr", "x[1, 2] = 3");
 }
 
+#[test]
+fn test_parse_with_recovery() {
+    let program = r#"
+x = 1 +
+def f(a):
+    return a +* 2
+y = [1, 2
+z = f(x)
+"#
+    .trim_start();
+    let (module, errors) =
+        AstModule::parse_with_recovery("recovery", program.to_owned(), &Dialect::Standard);
+
+    let mut out = String::new();
+    writeln!(out, "Program:").unwrap();
+    writeln!(out, "{}", program).unwrap();
+    for err in errors {
+        writeln!(out, "Error:").unwrap();
+        writeln!(out, "{}", err).unwrap();
+    }
+    writeln!(out, "AST:").unwrap();
+    write!(out, "{}", module.unwrap().statement.node).unwrap();
+
+    golden_test_template("src/syntax/grammar_tests/recovery.golden", &out);
+}
+
+#[test]
+fn test_parse_with_recovery_unclosed_bracket() {
+    // Line breaks inside brackets are not the end of a statement, so after an unclosed
+    // bracket the parser can only recover at the end of the file, and the later errors
+    // are not reported.
+    let program = "x = f(1,\ny = 1 +* 2\nz = 2 +* 3\nw = 4\n";
+    let (module, errors) =
+        AstModule::parse_with_recovery("recovery", program.to_owned(), &Dialect::Standard);
+    assert_eq!(1, errors.len());
+    assert_eq!(
+        1,
+        errors[0].span().unwrap().resolve_span().begin.line,
+        "{}",
+        errors[0]
+    );
+    assert_eq!("<error>\n", module.unwrap().statement.to_string());
+}
+
+#[test]
+fn test_parse_reports_first_error() {
+    let err = AstModule::parse("x", "x = 1 +\ny = ]\n".to_owned(), &Dialect::Standard).unwrap_err();
+    assert_eq!(err.span().unwrap().resolve_span().begin.line, 0);
+}
+
 pub fn parse(program: &str) -> String {
     parse_ast(program).statement.to_string()
 }
@@ -17,6 +17,8 @@
 
 //! Code called by the parser to handle complex cases not handled by the grammar.
 
+use lalrpop_util as lu;
+
 use crate::codemap::CodeMap;
 use crate::codemap::Pos;
 use crate::codemap::Span;
@@ -26,6 +28,7 @@ use crate::dot_format_parser::FormatParser;
 use crate::dot_format_parser::FormatToken;
 use crate::eval_exception::EvalException;
 use crate::lexer::lex_exactly_one_identifier;
+use crate::lexer::Token;
 use crate::lexer::TokenFString;
 use crate::slice_vec_ext::VecExt;
 use crate::syntax::ast::AssignIdentP;
@@ -35,15 +38,18 @@ use crate::syntax::ast::AssignTarget;
 use crate::syntax::ast::AssignTargetP;
 use crate::syntax::ast::AstAssignIdent;
 use crate::syntax::ast::AstAssignTarget;
+use crate::syntax::ast::AstBytes;
 use crate::syntax::ast::AstExpr;
 use crate::syntax::ast::AstFString;
 use crate::syntax::ast::AstStmt;
 use crate::syntax::ast::AstString;
 use crate::syntax::ast::AstTypeExpr;
+use crate::syntax::ast::Clause;
 use crate::syntax::ast::Comma;
 use crate::syntax::ast::Expr;
 use crate::syntax::ast::ExprP;
 use crate::syntax::ast::FStringP;
+use crate::syntax::ast::ForClause;
 use crate::syntax::ast::IdentP;
 use crate::syntax::ast::LoadArgP;
 use crate::syntax::ast::LoadP;
@@ -52,6 +58,7 @@ use crate::syntax::ast::StmtP;
 use crate::syntax::ast::ToAst;
 use crate::syntax::ast::TypeExpr;
 use crate::syntax::ast::TypeExprP;
+use crate::syntax::module::parse_error_add_span;
 use crate::syntax::state::ParserState;
 use crate::syntax::type_expr::TypeExprUnpackP;
 use crate::syntax::DialectTypes;
@@ -70,6 +77,43 @@ enum GrammarUtilError {
     LoadRequiresAtLeastTwoArguments,
 }
 
+/// Record a syntax error that the parser recovered from, and return an error statement
+/// covering the code that was skipped.
+pub(crate) fn error_stmt(
+    recovery: lu::ErrorRecovery<usize, Token, EvalException>,
+    state: &mut ParserState,
+) -> AstStmt {
+    let end_of_file = state.codemap.source().len();
+    let (begin, end) = match &recovery.error {
+        lu::ParseError::InvalidToken { location }
+        | lu::ParseError::UnrecognizedEOF { location, .. } => (*location, *location),
+        lu::ParseError::UnrecognizedToken {
+            token: (begin, _, end),
+            ..
+        }
+        | lu::ParseError::ExtraToken {
+            token: (begin, _, end),
+        } => (*begin, *end),
+        lu::ParseError::User { .. } => (end_of_file, end_of_file),
+    };
+    let begin = recovery
+        .dropped_tokens
+        .first()
+        .map_or(begin, |(x, _, _)| begin.min(*x))
+        .min(end_of_file);
+    let end = recovery
+        .dropped_tokens
+        .last()
+        .map_or(end, |(_, _, x)| end.max(*x))
+        .clamp(begin, end_of_file);
+    state.errors.push(parse_error_add_span(
+        recovery.error,
+        end_of_file,
+        state.codemap,
+    ));
+    StmtP::Error.ast(begin, end)
+}
+
 /// Ensure we produce normalised Statements, rather than singleton Statements
 pub fn statements(mut xs: Vec<AstStmt>, begin: usize, end: usize) -> AstStmt {
     if xs.len() == 1 {
@@ -190,6 +234,53 @@ pub(crate) fn check_load(
     })
 }
 
+pub(crate) fn bytes(
+    bytes: Vec<u8>,
+    begin: usize,
+    end: usize,
+    parser_state: &mut ParserState,
+) -> AstBytes {
+    if !parser_state.dialect.enable_bytes {
+        parser_state.error(
+            Span::new(Pos::new(begin as _), Pos::new(end as _)),
+            "Your Starlark dialect must enable bytes to use bytes literals",
+        );
+    }
+    bytes.ast(begin, end)
+}
+
+fn check_set_literals(begin: usize, end: usize, parser_state: &mut ParserState) {
+    if !parser_state.dialect.enable_set_literals {
+        parser_state.error(
+            Span::new(Pos::new(begin as _), Pos::new(end as _)),
+            "Your Starlark dialect must enable set literals to use `{...}` sets",
+        );
+    }
+}
+
+pub(crate) fn set(
+    mut elems: Vec<AstExpr>,
+    last: AstExpr,
+    begin: usize,
+    end: usize,
+    parser_state: &mut ParserState,
+) -> AstExpr {
+    check_set_literals(begin, end, parser_state);
+    elems.push(last);
+    Expr::Set(elems).ast(begin, end)
+}
+
+pub(crate) fn set_comprehension(
+    elem: AstExpr,
+    (for_, clauses): (ForClause, Vec<Clause>),
+    begin: usize,
+    end: usize,
+    parser_state: &mut ParserState,
+) -> AstExpr {
+    check_set_literals(begin, end, parser_state);
+    Expr::SetComprehension(Box::new(elem), Box::new(for_), clauses).ast(begin, end)
+}
+
 pub(crate) fn fstring(
     fstring: TokenFString,
     begin: usize,
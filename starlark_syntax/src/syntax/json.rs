@@ -0,0 +1,802 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A stable JSON representation of [`AstModule`], for tools outside of Rust.
+//!
+//! Build a [`JsonModule`] from a parsed module and serialize it with any serde
+//! serializer. The output is an object:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "filename": "foo.bzl",
+//!   "docstring": "Module docstring, or null.",
+//!   "loads": [{"module": "//:lib.bzl", "span": ..., "symbols": [...]}],
+//!   "body": [<statement>, ...]
+//! }
+//! ```
+//!
+//! Every statement, expression, assignment target, argument and parameter is an
+//! object with a `"kind"` field naming the variant, a `"span"` field, and the
+//! fields of that variant. Blocks (module body, `def`, `if` and `for` bodies) are
+//! arrays of statements. A span is
+//! `{"begin": <position>, "end": <position>}`, where a position is
+//! `{"offset": <byte offset>, "line": <line>, "column": <column>}`.
+//! Lines and columns are 0-based, columns count characters, and offsets count
+//! UTF-8 bytes from the start of the file.
+//!
+//! Some leaf values need care in JSON:
+//!
+//! * `Int` literals carry their value as a decimal string, since Starlark
+//!   integers are unbounded.
+//! * `Float` literals that are not finite serialize as `null`.
+//! * `Bytes` literals carry their value as an array of numbers, since they
+//!   need not be valid UTF-8.
+//! * Operators are the source tokens, e.g. `"+"`, `"not in"` or `"//="`.
+//!
+//! The `version` field is [`JSON_AST_VERSION`]. It changes whenever an existing
+//! field changes meaning or is removed; new node kinds and new fields may be
+//! added without a version change, so consumers should ignore unknown fields.
+//!
+//! Statements:
+//!
+//! * `Break`, `Continue`, `Pass`, `Error` (a syntax error that was recovered from).
+//! * `Return` with `value` (nullable).
+//! * `Expression` with `value`.
+//! * `Assign` with `target`, `type` (nullable) and `value`.
+//! * `AugmentedAssign` with `target`, `op` and `value`.
+//! * `If` with `test`, `body` and `orelse` (empty when there is no `else`;
+//!   an `elif` is a single nested `If`).
+//! * `For` with `target`, `iter` and `body`.
+//! * `While` with `test` and `body`.
+//! * `Def` with `name`, `params`, `return_type` (nullable), `docstring` (nullable)
+//!   and `body`.
+//! * `Load` with `module` and `symbols`, each symbol being
+//!   `{"local": ..., "their": ..., "span": ...}`.
+//!
+//! Expressions:
+//!
+//! * `Identifier` with `name`.
+//! * `Int`, `Float`, `String` and `Bytes` with `value`, and `Ellipsis`.
+//! * `Tuple`, `List` and `Set` with `items`, `Dict` with `entries` of `{"key", "value"}`.
+//! * `Dot` with `object` and `attribute`.
+//! * `Call` with `function` and `args`.
+//! * `Index` with `object` and `index`, `Index2` with `object`, `index0` and `index1`.
+//! * `Slice` with `object`, `start`, `stop` and `step` (all nullable except `object`).
+//! * `Lambda` with `params` and `body`.
+//! * `UnaryOp` with `op` (`not`, `-`, `+` or `~`) and `operand`.
+//! * `BinOp` with `op`, `lhs` and `rhs`.
+//! * `Conditional` with `test`, `then` and `orelse` (`then if test else orelse`).
+//! * `ListComprehension` and `SetComprehension` with `element` and `clauses`,
+//!   `DictComprehension` with `key`, `value` and `clauses`. A clause is `{"kind": "For", "target", "iter"}`
+//!   or `{"kind": "If", "test"}`.
+//! * `FString` with `format` and `expressions`.
+//!
+//! Assignment targets are `Identifier`, `Tuple`, `Index` and `Dot`, with the same
+//! fields as the expressions. Arguments are `Positional`, `Named` (with `name`),
+//! `Args` and `KwArgs`, all with `value`. Parameters are `Normal` (with `name`,
+//! `type` and `default`), `Args` and `KwArgs` (with `name` and `type`), and the
+//! `NoArgs` (`*`) and `Slash` (`/`) markers.
+
+use serde::Serialize;
+
+use crate::codemap::CodeMap;
+use crate::codemap::Pos;
+use crate::codemap::ResolvedPos;
+use crate::codemap::Span;
+use crate::syntax::ast::ArgumentP;
+use crate::syntax::ast::AssignTargetP;
+use crate::syntax::ast::AstArgument;
+use crate::syntax::ast::AstAssignTarget;
+use crate::syntax::ast::AstExpr;
+use crate::syntax::ast::AstLiteral;
+use crate::syntax::ast::AstParameter;
+use crate::syntax::ast::AstStmt;
+use crate::syntax::ast::AstTypeExpr;
+use crate::syntax::ast::Clause;
+use crate::syntax::ast::ClauseP;
+use crate::syntax::ast::ExprP;
+use crate::syntax::ast::ForClause;
+use crate::syntax::ast::Load;
+use crate::syntax::ast::ParameterP;
+use crate::syntax::ast::StmtP;
+use crate::syntax::AstModule;
+
+/// Version of the JSON format, written as the `version` field of [`JsonModule`].
+pub const JSON_AST_VERSION: u32 = 1;
+
+/// A position in the source file.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct JsonPos {
+    /// Byte offset from the start of the file.
+    pub offset: u32,
+    /// 0-based line.
+    pub line: usize,
+    /// 0-based column, in characters.
+    pub column: usize,
+}
+
+/// A range of the source file.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct JsonSpan {
+    pub begin: JsonPos,
+    pub end: JsonPos,
+}
+
+/// A node of the tree: the fields of `node` (including `kind`) plus its `span`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonNode<T> {
+    #[serde(flatten)]
+    pub node: T,
+    pub span: JsonSpan,
+}
+
+pub type JsonStmtNode = JsonNode<JsonStmt>;
+pub type JsonExprNode = JsonNode<JsonExpr>;
+pub type JsonAssignTargetNode = JsonNode<JsonAssignTarget>;
+pub type JsonArgumentNode = JsonNode<JsonArgument>;
+pub type JsonParameterNode = JsonNode<JsonParameter>;
+
+/// The JSON representation of a module. See the [module documentation](self).
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonModule {
+    pub version: u32,
+    pub filename: String,
+    pub docstring: Option<String>,
+    pub loads: Vec<JsonLoad>,
+    pub body: Vec<JsonStmtNode>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonLoad {
+    pub module: String,
+    pub span: JsonSpan,
+    pub symbols: Vec<JsonLoadSymbol>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonLoadSymbol {
+    /// Name bound in the loading module.
+    pub local: String,
+    /// Name exported by the loaded module.
+    pub their: String,
+    pub span: JsonSpan,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum JsonStmt {
+    Break,
+    Continue,
+    Pass,
+    Error,
+    Return {
+        value: Option<JsonExprNode>,
+    },
+    Expression {
+        value: JsonExprNode,
+    },
+    Assign {
+        target: JsonAssignTargetNode,
+        #[serde(rename = "type")]
+        ty: Option<JsonExprNode>,
+        value: JsonExprNode,
+    },
+    AugmentedAssign {
+        target: JsonAssignTargetNode,
+        op: String,
+        value: JsonExprNode,
+    },
+    If {
+        test: JsonExprNode,
+        body: Vec<JsonStmtNode>,
+        orelse: Vec<JsonStmtNode>,
+    },
+    For {
+        target: JsonAssignTargetNode,
+        iter: JsonExprNode,
+        body: Vec<JsonStmtNode>,
+    },
+    While {
+        test: JsonExprNode,
+        body: Vec<JsonStmtNode>,
+    },
+    Def {
+        name: String,
+        params: Vec<JsonParameterNode>,
+        return_type: Option<JsonExprNode>,
+        docstring: Option<String>,
+        body: Vec<JsonStmtNode>,
+    },
+    Load {
+        module: String,
+        symbols: Vec<JsonLoadSymbol>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum JsonExpr {
+    Identifier {
+        name: String,
+    },
+    Int {
+        value: String,
+    },
+    Float {
+        value: f64,
+    },
+    String {
+        value: String,
+    },
+    Bytes {
+        value: Vec<u8>,
+    },
+    Ellipsis,
+    Tuple {
+        items: Vec<JsonExprNode>,
+    },
+    List {
+        items: Vec<JsonExprNode>,
+    },
+    Dict {
+        entries: Vec<JsonDictEntry>,
+    },
+    Set {
+        items: Vec<JsonExprNode>,
+    },
+    Dot {
+        object: Box<JsonExprNode>,
+        attribute: String,
+    },
+    Call {
+        function: Box<JsonExprNode>,
+        args: Vec<JsonArgumentNode>,
+    },
+    Index {
+        object: Box<JsonExprNode>,
+        index: Box<JsonExprNode>,
+    },
+    Index2 {
+        object: Box<JsonExprNode>,
+        index0: Box<JsonExprNode>,
+        index1: Box<JsonExprNode>,
+    },
+    Slice {
+        object: Box<JsonExprNode>,
+        start: Option<Box<JsonExprNode>>,
+        stop: Option<Box<JsonExprNode>>,
+        step: Option<Box<JsonExprNode>>,
+    },
+    Lambda {
+        params: Vec<JsonParameterNode>,
+        body: Box<JsonExprNode>,
+    },
+    UnaryOp {
+        op: String,
+        operand: Box<JsonExprNode>,
+    },
+    BinOp {
+        op: String,
+        lhs: Box<JsonExprNode>,
+        rhs: Box<JsonExprNode>,
+    },
+    Conditional {
+        test: Box<JsonExprNode>,
+        then: Box<JsonExprNode>,
+        orelse: Box<JsonExprNode>,
+    },
+    ListComprehension {
+        element: Box<JsonExprNode>,
+        clauses: Vec<JsonClause>,
+    },
+    DictComprehension {
+        key: Box<JsonExprNode>,
+        value: Box<JsonExprNode>,
+        clauses: Vec<JsonClause>,
+    },
+    SetComprehension {
+        element: Box<JsonExprNode>,
+        clauses: Vec<JsonClause>,
+    },
+    FString {
+        format: String,
+        expressions: Vec<JsonExprNode>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonDictEntry {
+    pub key: JsonExprNode,
+    pub value: JsonExprNode,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum JsonClause {
+    For {
+        target: Box<JsonAssignTargetNode>,
+        iter: JsonExprNode,
+    },
+    If {
+        test: JsonExprNode,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum JsonAssignTarget {
+    Identifier {
+        name: String,
+    },
+    Tuple {
+        items: Vec<JsonAssignTargetNode>,
+    },
+    Index {
+        object: JsonExprNode,
+        index: JsonExprNode,
+    },
+    Dot {
+        object: JsonExprNode,
+        attribute: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum JsonArgument {
+    Positional { value: JsonExprNode },
+    Named { name: String, value: JsonExprNode },
+    Args { value: JsonExprNode },
+    KwArgs { value: JsonExprNode },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum JsonParameter {
+    Normal {
+        name: String,
+        #[serde(rename = "type")]
+        ty: Option<JsonExprNode>,
+        default: Option<JsonExprNode>,
+    },
+    Args {
+        name: String,
+        #[serde(rename = "type")]
+        ty: Option<JsonExprNode>,
+    },
+    KwArgs {
+        name: String,
+        #[serde(rename = "type")]
+        ty: Option<JsonExprNode>,
+    },
+    NoArgs,
+    Slash,
+}
+
+impl JsonModule {
+    /// Convert a parsed module to its JSON representation.
+    pub fn new(module: &AstModule) -> JsonModule {
+        let converter = Converter {
+            codemap: &module.codemap,
+        };
+        let mut loads = Vec::new();
+        for stmt in top_level(&module.statement) {
+            if let StmtP::Load(load) = &stmt.node {
+                loads.push(JsonLoad {
+                    module: load.module.node.clone(),
+                    span: converter.span(stmt.span),
+                    symbols: converter.load_symbols(load),
+                });
+            }
+        }
+        JsonModule {
+            version: JSON_AST_VERSION,
+            filename: module.codemap.filename().to_owned(),
+            docstring: docstring(&module.statement),
+            loads,
+            body: converter.block(&module.statement),
+        }
+    }
+}
+
+/// The statements of a block, with nested `Statements` flattened.
+fn top_level(stmt: &AstStmt) -> Vec<&AstStmt> {
+    fn go<'a>(stmt: &'a AstStmt, out: &mut Vec<&'a AstStmt>) {
+        match &stmt.node {
+            StmtP::Statements(xs) => xs.iter().for_each(|x| go(x, out)),
+            _ => out.push(stmt),
+        }
+    }
+    let mut out = Vec::new();
+    go(stmt, &mut out);
+    out
+}
+
+/// The docstring of a module or `def` body: its first statement, if that is a string literal.
+fn docstring(body: &AstStmt) -> Option<String> {
+    match &top_level(body).first()?.node {
+        StmtP::Expression(AstExpr {
+            node: ExprP::Literal(AstLiteral::String(s)),
+            ..
+        }) => Some(s.node.clone()),
+        _ => None,
+    }
+}
+
+struct Converter<'a> {
+    codemap: &'a CodeMap,
+}
+
+impl<'a> Converter<'a> {
+    fn span(&self, span: Span) -> JsonSpan {
+        let resolved = self.codemap.resolve_span(span);
+        let pos = |offset: Pos, resolved: ResolvedPos| JsonPos {
+            offset: offset.get(),
+            line: resolved.line,
+            column: resolved.column,
+        };
+        JsonSpan {
+            begin: pos(span.begin(), resolved.begin),
+            end: pos(span.end(), resolved.end),
+        }
+    }
+
+    fn node<T>(&self, span: Span, node: T) -> JsonNode<T> {
+        JsonNode {
+            node,
+            span: self.span(span),
+        }
+    }
+
+    fn block(&self, stmt: &AstStmt) -> Vec<JsonStmtNode> {
+        top_level(stmt).into_iter().map(|x| self.stmt(x)).collect()
+    }
+
+    fn load_symbols(&self, load: &Load) -> Vec<JsonLoadSymbol> {
+        load.args
+            .iter()
+            .map(|arg| JsonLoadSymbol {
+                local: arg.local.node.ident.clone(),
+                their: arg.their.node.clone(),
+                span: self.span(arg.span()),
+            })
+            .collect()
+    }
+
+    fn stmt(&self, stmt: &AstStmt) -> JsonStmtNode {
+        let node = match &stmt.node {
+            StmtP::Break => JsonStmt::Break,
+            StmtP::Continue => JsonStmt::Continue,
+            StmtP::Pass => JsonStmt::Pass,
+            StmtP::Error => JsonStmt::Error,
+            StmtP::Return(value) => JsonStmt::Return {
+                value: value.as_ref().map(|x| self.expr(x)),
+            },
+            StmtP::Expression(value) => JsonStmt::Expression {
+                value: self.expr(value),
+            },
+            StmtP::Assign(assign) => JsonStmt::Assign {
+                target: self.assign_target(&assign.lhs),
+                ty: assign.ty.as_ref().map(|x| self.type_expr(x)),
+                value: self.expr(&assign.rhs),
+            },
+            StmtP::AssignModify(target, op, value) => JsonStmt::AugmentedAssign {
+                target: self.assign_target(target),
+                op: op.to_string().trim().to_owned(),
+                value: self.expr(value),
+            },
+            StmtP::Statements(_) => unreachable!("`Statements` is flattened by `block`"),
+            StmtP::If(test, body) => JsonStmt::If {
+                test: self.expr(test),
+                body: self.block(body),
+                orelse: Vec::new(),
+            },
+            StmtP::IfElse(test, bodies) => JsonStmt::If {
+                test: self.expr(test),
+                body: self.block(&bodies.0),
+                orelse: self.block(&bodies.1),
+            },
+            StmtP::For(for_) => JsonStmt::For {
+                target: self.assign_target(&for_.var),
+                iter: self.expr(&for_.over),
+                body: self.block(&for_.body),
+            },
+            StmtP::While(while_) => JsonStmt::While {
+                test: self.expr(&while_.cond),
+                body: self.block(&while_.body),
+            },
+            StmtP::Def(def) => JsonStmt::Def {
+                name: def.name.node.ident.clone(),
+                params: def.params.iter().map(|x| self.param(x)).collect(),
+                return_type: def.return_type.as_ref().map(|x| self.type_expr(x)),
+                docstring: docstring(&def.body),
+                body: self.block(&def.body),
+            },
+            StmtP::Load(load) => JsonStmt::Load {
+                module: load.module.node.clone(),
+                symbols: self.load_symbols(load),
+            },
+        };
+        self.node(stmt.span, node)
+    }
+
+    fn boxed(&self, expr: &AstExpr) -> Box<JsonExprNode> {
+        Box::new(self.expr(expr))
+    }
+
+    fn type_expr(&self, ty: &AstTypeExpr) -> JsonExprNode {
+        self.expr(&ty.node.expr)
+    }
+
+    fn expr(&self, expr: &AstExpr) -> JsonExprNode {
+        let unary = |op: &str, x: &AstExpr| JsonExpr::UnaryOp {
+            op: op.to_owned(),
+            operand: self.boxed(x),
+        };
+        let node = match &expr.node {
+            ExprP::Identifier(ident) => JsonExpr::Identifier {
+                name: ident.node.ident.clone(),
+            },
+            ExprP::Literal(AstLiteral::Int(x)) => JsonExpr::Int {
+                value: x.node.to_string(),
+            },
+            ExprP::Literal(AstLiteral::Float(x)) => JsonExpr::Float { value: x.node },
+            ExprP::Literal(AstLiteral::String(x)) => JsonExpr::String {
+                value: x.node.clone(),
+            },
+            ExprP::Literal(AstLiteral::Bytes(x)) => JsonExpr::Bytes {
+                value: x.node.clone(),
+            },
+            ExprP::Literal(AstLiteral::Ellipsis) => JsonExpr::Ellipsis,
+            ExprP::Tuple(xs) => JsonExpr::Tuple {
+                items: xs.iter().map(|x| self.expr(x)).collect(),
+            },
+            ExprP::List(xs) => JsonExpr::List {
+                items: xs.iter().map(|x| self.expr(x)).collect(),
+            },
+            ExprP::Dict(xs) => JsonExpr::Dict {
+                entries: xs
+                    .iter()
+                    .map(|(k, v)| JsonDictEntry {
+                        key: self.expr(k),
+                        value: self.expr(v),
+                    })
+                    .collect(),
+            },
+            ExprP::Set(xs) => JsonExpr::Set {
+                items: xs.iter().map(|x| self.expr(x)).collect(),
+            },
+            ExprP::Dot(object, attribute) => JsonExpr::Dot {
+                object: self.boxed(object),
+                attribute: attribute.node.clone(),
+            },
+            ExprP::Call(function, args) => JsonExpr::Call {
+                function: self.boxed(function),
+                args: args.args.iter().map(|x| self.argument(x)).collect(),
+            },
+            ExprP::Index(x) => JsonExpr::Index {
+                object: self.boxed(&x.0),
+                index: self.boxed(&x.1),
+            },
+            ExprP::Index2(x) => JsonExpr::Index2 {
+                object: self.boxed(&x.0),
+                index0: self.boxed(&x.1),
+                index1: self.boxed(&x.2),
+            },
+            ExprP::Slice(object, start, stop, step) => JsonExpr::Slice {
+                object: self.boxed(object),
+                start: start.as_ref().map(|x| self.boxed(x)),
+                stop: stop.as_ref().map(|x| self.boxed(x)),
+                step: step.as_ref().map(|x| self.boxed(x)),
+            },
+            ExprP::Lambda(lambda) => JsonExpr::Lambda {
+                params: lambda.params.iter().map(|x| self.param(x)).collect(),
+                body: self.boxed(&lambda.body),
+            },
+            ExprP::Not(x) => unary("not", x),
+            ExprP::Minus(x) => unary("-", x),
+            ExprP::Plus(x) => unary("+", x),
+            ExprP::BitNot(x) => unary("~", x),
+            ExprP::Op(lhs, op, rhs) => JsonExpr::BinOp {
+                op: op.to_string().trim().to_owned(),
+                lhs: self.boxed(lhs),
+                rhs: self.boxed(rhs),
+            },
+            ExprP::If(x) => JsonExpr::Conditional {
+                test: self.boxed(&x.0),
+                then: self.boxed(&x.1),
+                orelse: self.boxed(&x.2),
+            },
+            ExprP::ListComprehension(element, first, clauses) => JsonExpr::ListComprehension {
+                element: self.boxed(element),
+                clauses: self.clauses(first, clauses),
+            },
+            ExprP::DictComprehension(kv, first, clauses) => JsonExpr::DictComprehension {
+                key: self.boxed(&kv.0),
+                value: self.boxed(&kv.1),
+                clauses: self.clauses(first, clauses),
+            },
+            ExprP::SetComprehension(element, first, clauses) => JsonExpr::SetComprehension {
+                element: self.boxed(element),
+                clauses: self.clauses(first, clauses),
+            },
+            ExprP::FString(fstring) => JsonExpr::FString {
+                format: fstring.node.format.node.clone(),
+                expressions: fstring
+                    .node
+                    .expressions
+                    .iter()
+                    .map(|x| self.expr(x))
+                    .collect(),
+            },
+        };
+        self.node(expr.span, node)
+    }
+
+    fn clauses(&self, first: &ForClause, rest: &[Clause]) -> Vec<JsonClause> {
+        let for_clause = |x: &ForClause| JsonClause::For {
+            target: Box::new(self.assign_target(&x.var)),
+            iter: self.expr(&x.over),
+        };
+        let mut res = vec![for_clause(first)];
+        for clause in rest {
+            res.push(match clause {
+                ClauseP::For(x) => for_clause(x),
+                ClauseP::If(x) => JsonClause::If { test: self.expr(x) },
+            });
+        }
+        res
+    }
+
+    fn assign_target(&self, target: &AstAssignTarget) -> JsonAssignTargetNode {
+        let node = match &target.node {
+            AssignTargetP::Identifier(ident) => JsonAssignTarget::Identifier {
+                name: ident.node.ident.clone(),
+            },
+            AssignTargetP::Tuple(xs) => JsonAssignTarget::Tuple {
+                items: xs.iter().map(|x| self.assign_target(x)).collect(),
+            },
+            AssignTargetP::Index(x) => JsonAssignTarget::Index {
+                object: self.expr(&x.0),
+                index: self.expr(&x.1),
+            },
+            AssignTargetP::Dot(object, attribute) => JsonAssignTarget::Dot {
+                object: self.expr(object),
+                attribute: attribute.node.clone(),
+            },
+        };
+        self.node(target.span, node)
+    }
+
+    fn argument(&self, arg: &AstArgument) -> JsonArgumentNode {
+        let node = match &arg.node {
+            ArgumentP::Positional(value) => JsonArgument::Positional {
+                value: self.expr(value),
+            },
+            ArgumentP::Named(name, value) => JsonArgument::Named {
+                name: name.node.clone(),
+                value: self.expr(value),
+            },
+            ArgumentP::Args(value) => JsonArgument::Args {
+                value: self.expr(value),
+            },
+            ArgumentP::KwArgs(value) => JsonArgument::KwArgs {
+                value: self.expr(value),
+            },
+        };
+        self.node(arg.span, node)
+    }
+
+    fn param(&self, param: &AstParameter) -> JsonParameterNode {
+        let ty = |x: &Option<Box<AstTypeExpr>>| x.as_ref().map(|x| self.type_expr(x));
+        let node = match &param.node {
+            ParameterP::Normal(name, t, default) => JsonParameter::Normal {
+                name: name.node.ident.clone(),
+                ty: ty(t),
+                default: default.as_ref().map(|x| self.expr(x)),
+            },
+            ParameterP::Args(name, t) => JsonParameter::Args {
+                name: name.node.ident.clone(),
+                ty: ty(t),
+            },
+            ParameterP::KwArgs(name, t) => JsonParameter::KwArgs {
+                name: name.node.ident.clone(),
+                ty: ty(t),
+            },
+            ParameterP::NoArgs => JsonParameter::NoArgs,
+            ParameterP::Slash => JsonParameter::Slash,
+        };
+        self.node(param.span, node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden_test_template::golden_test_template;
+    use crate::syntax::Dialect;
+
+    fn json_module(program: &str) -> JsonModule {
+        let module = AstModule::parse(
+            "test.star",
+            program.to_owned(),
+            &Dialect::AllOptionsInternal,
+        )
+        .unwrap();
+        JsonModule::new(&module)
+    }
+
+    fn to_json(program: &str) -> serde_json::Value {
+        serde_json::to_value(json_module(program)).unwrap()
+    }
+
+    #[test]
+    fn test_json_golden() {
+        let program = r#""""Module doc."""
+
+load("//:lib.bzl", "a", b = "c")
+
+def f(x: int, *args, y = [1, 2], **kwargs) -> str:
+    """Function doc."""
+    for k, v in {x: y for x in args if x}.items():
+        z[k] += v
+    return "" if x else f"{x}"
+
+e = lambda a: "é" + a.b[1:2] and not -3
+"#;
+        let out = format!(
+            "Program:\n{}\nJSON:\n{}\n",
+            program,
+            serde_json::to_string_pretty(&json_module(program)).unwrap()
+        );
+        golden_test_template("src/syntax/json_tests/module.golden", &out);
+    }
+
+    #[test]
+    fn test_json_spans() {
+        let json = to_json("x = 1\ny = 'é' + z\n");
+        let rhs = &json["body"][1]["value"];
+        assert_eq!("BinOp", rhs["kind"]);
+        assert_eq!("+", rhs["op"]);
+        let z = &rhs["rhs"]["span"];
+        assert_eq!(
+            serde_json::json!({
+                "begin": {"offset": 17, "line": 1, "column": 10},
+                "end": {"offset": 18, "line": 1, "column": 11},
+            }),
+            *z
+        );
+    }
+
+    #[test]
+    fn test_json_big_int() {
+        let json = to_json("x = 123456789012345678901234567890");
+        assert_eq!(
+            "123456789012345678901234567890",
+            json["body"][0]["value"]["value"]
+        );
+    }
+
+    #[test]
+    fn test_json_bytes() {
+        let json = to_json("x = b'hi\\xff'");
+        let value = &json["body"][0]["value"];
+        assert_eq!("Bytes", value["kind"]);
+        assert_eq!(serde_json::json!([104, 105, 255]), value["value"]);
+    }
+}
@@ -33,14 +33,18 @@ use crate::codemap::Spanned;
 use crate::eval_exception::EvalException;
 use crate::lexer::Lexer;
 use crate::lexer::Token;
+use crate::slice_vec_ext::VecExt;
 use crate::syntax::ast::ArgumentP;
 use crate::syntax::ast::AstExpr;
+use crate::syntax::ast::AstPayload;
 use crate::syntax::ast::AstStmt;
+use crate::syntax::ast::AstStmtP;
 use crate::syntax::ast::CallArgsP;
 use crate::syntax::ast::ExprP;
 use crate::syntax::ast::IdentP;
 use crate::syntax::ast::LoadArgP;
 use crate::syntax::ast::Stmt;
+use crate::syntax::ast::StmtP;
 use crate::syntax::grammar::StarlarkParser;
 use crate::syntax::lint_suppressions::LintSuppressions;
 use crate::syntax::lint_suppressions::LintSuppressionsBuilder;
@@ -49,6 +53,20 @@ use crate::syntax::validate::validate_module;
 use crate::syntax::AstLoad;
 use crate::syntax::Dialect;
 
+/// The span of the first [`StmtP::Error`] in `stmt`, if any.
+pub(crate) fn first_error_stmt<P: AstPayload>(stmt: &AstStmtP<P>) -> Option<Span> {
+    if let StmtP::Error = stmt.node {
+        return Some(stmt.span);
+    }
+    let mut res = None;
+    stmt.visit_stmt(|x| {
+        if res.is_none() {
+            res = first_error_stmt(x);
+        }
+    });
+    res
+}
+
 fn one_of(expected: &[String]) -> String {
     let mut result = String::new();
     for (i, e) in expected.iter().enumerate() {
@@ -67,11 +85,11 @@ fn one_of(expected: &[String]) -> String {
 ///
 /// To build this diagnostic, the method needs the file span corresponding
 /// to the parsed file.
-fn parse_error_add_span(
+pub(crate) fn parse_error_add_span(
     err: lu::ParseError<usize, Token, EvalException>,
     pos: usize,
     codemap: &CodeMap,
-) -> crate::Error {
+) -> EvalException {
     let (message, span) = match err {
         lu::ParseError::InvalidToken { location } => (
             "Parse error: invalid token".to_owned(),
@@ -96,14 +114,10 @@ fn parse_error_add_span(
             format!("Parse error: extraneous token {}", t),
             Span::new(Pos::new(x as u32), Pos::new(y as u32)),
         ),
-        lu::ParseError::User { error } => return error.into_error(),
+        lu::ParseError::User { error } => return error,
     };
 
-    crate::Error::new_spanned(
-        crate::ErrorKind::Parser(anyhow::anyhow!(message)),
-        span,
-        codemap,
-    )
+    EvalException::parser_error(message, span, codemap)
 }
 
 /// A representation of a Starlark module abstract syntax tree.
@@ -212,48 +226,105 @@ impl AstModule {
     pub fn parse(filename: &str, content: String, dialect: &Dialect) -> crate::Result<Self> {
         let typecheck = content.contains("@starlark-rust: typecheck");
         let codemap = CodeMap::new(filename.to_owned(), content);
+        let mut errors = Vec::new();
+        let (statement, lint_suppressions) = Self::parse_statement(&codemap, dialect, &mut errors);
+        // The first error comes before the one that stopped the parser, if any.
+        if let Some(err) = errors.into_iter().next() {
+            return Err(err.into_error());
+        }
+        AstModule::create(
+            codemap,
+            statement.map_err(EvalException::into_error)?,
+            dialect,
+            typecheck,
+            lint_suppressions,
+        )
+    }
+
+    /// Parse a Starlark module like [`parse`](AstModule::parse), but continue after syntax
+    /// errors, so that tools like the LSP server can work on code that is being edited.
+    ///
+    /// Returns every error that was found, in the order they were found, and the module
+    /// unless the parser could not recover. Each line with a syntax error is replaced by
+    /// a statement [`Error`](crate::syntax::ast::StmtP::Error) in the module, and
+    /// evaluating a module with such statements fails.
+    ///
+    /// Recovery only happens at the level of statements: there are no error nodes
+    /// inside expressions, so everything from the start of the statement with the
+    /// syntax error to the end of its line is lost. Line breaks inside brackets do not
+    /// end a line, so after an unclosed bracket everything up to the end of the file is
+    /// one error, and no later errors are reported.
+    pub fn parse_with_recovery(
+        filename: &str,
+        content: String,
+        dialect: &Dialect,
+    ) -> (Option<Self>, Vec<crate::Error>) {
+        let typecheck = content.contains("@starlark-rust: typecheck");
+        let codemap = CodeMap::new(filename.to_owned(), content);
+        let mut errors = Vec::new();
+        let (statement, lint_suppressions) = Self::parse_statement(&codemap, dialect, &mut errors);
+        let module = match statement {
+            Ok(statement) => {
+                validate_module(
+                    &statement,
+                    &mut ParserState {
+                        codemap: &codemap,
+                        dialect,
+                        errors: &mut errors,
+                    },
+                );
+                Some(AstModule {
+                    codemap,
+                    statement,
+                    dialect: dialect.clone(),
+                    typecheck,
+                    lint_suppressions,
+                })
+            }
+            Err(err) => {
+                errors.push(err);
+                None
+            }
+        };
+        (module, errors.into_map(EvalException::into_error))
+    }
+
+    /// Run the parser, adding the errors it recovers from to `errors`.
+    fn parse_statement(
+        codemap: &CodeMap,
+        dialect: &Dialect,
+        errors: &mut Vec<EvalException>,
+    ) -> (Result<AstStmt, EvalException>, LintSuppressions) {
         let lexer = Lexer::new(codemap.source(), dialect, codemap.dupe());
         // Store lint suppressions found during parsing
         let mut lint_suppressions_builder = LintSuppressionsBuilder::new();
         // Keep track of block of comments, used for accumulating lint suppressions
         let mut in_comment_block = false;
-        let mut errors = Vec::new();
-        match StarlarkParser::new().parse(
-            &mut ParserState {
-                codemap: &codemap,
-                dialect,
-                errors: &mut errors,
-            },
-            lexer.filter(|token| match token {
-                // Filter out comment tokens and accumulate lint suppressions
-                Ok((start, Token::Comment(comment), end)) => {
-                    lint_suppressions_builder.parse_comment(&codemap, comment, *start, *end);
-                    in_comment_block = true;
-                    false
-                }
-                _ => {
-                    if in_comment_block {
-                        lint_suppressions_builder.end_of_comment_block(&codemap);
-                        in_comment_block = false;
-                    }
-                    true
-                }
-            }),
-        ) {
-            Ok(v) => {
-                if let Some(err) = errors.into_iter().next() {
-                    return Err(err.into_error());
-                }
-                Ok(AstModule::create(
+        let statement = StarlarkParser::new()
+            .parse(
+                &mut ParserState {
                     codemap,
-                    v,
                     dialect,
-                    typecheck,
-                    lint_suppressions_builder.build(),
-                )?)
-            }
-            Err(p) => Err(parse_error_add_span(p, codemap.source().len(), &codemap)),
-        }
+                    errors,
+                },
+                lexer.filter(|token| match token {
+                    // Filter out comment tokens and accumulate lint suppressions
+                    Ok((start, Token::Comment(comment), end)) => {
+                        lint_suppressions_builder.parse_comment(codemap, comment, *start, *end);
+                        in_comment_block = true;
+                        false
+                    }
+                    _ => {
+                        if in_comment_block {
+                            lint_suppressions_builder.end_of_comment_block(codemap);
+                            in_comment_block = false;
+                        }
+                        true
+                    }
+                }),
+            )
+            .map_err(|p| parse_error_add_span(p, codemap.source().len(), codemap));
+        (statement, lint_suppressions_builder.build())
     }
 
     /// Return the file names of all the `load` statements in the module.
@@ -291,6 +362,12 @@ impl AstModule {
         loads
     }
 
+    /// The span of the first statement the parser put in place of a syntax error, if any.
+    /// Only modules from [`parse_with_recovery`](AstModule::parse_with_recovery) have them.
+    pub fn first_error_stmt(&self) -> Option<Span> {
+        first_error_stmt(&self.statement)
+    }
+
     /// Look up a [`Span`] contained in this module to a [`FileSpan`].
     pub fn file_span(&self, x: Span) -> FileSpan {
         self.codemap.file_span(x)
@@ -38,6 +38,7 @@ use crate::syntax::ast::LoadP;
 use crate::syntax::ast::ParameterP;
 use crate::syntax::ast::StmtP;
 use crate::syntax::ast::TypeExprP;
+use crate::syntax::ast::WhileP;
 
 pub trait AstPayloadFunction<A: AstPayload, B: AstPayload> {
     fn map_load(&mut self, import_path: &str, a: A::LoadPayload) -> B::LoadPayload;
@@ -109,6 +110,19 @@ impl<A: AstPayload> ForP<A> {
     }
 }
 
+impl<A: AstPayload> WhileP<A> {
+    pub fn into_map_payload<B: AstPayload>(
+        self,
+        f: &mut impl AstPayloadFunction<A, B>,
+    ) -> WhileP<B> {
+        let WhileP { cond, body } = self;
+        WhileP {
+            cond: cond.into_map_payload(f),
+            body: Box::new(body.into_map_payload(f)),
+        }
+    }
+}
+
 impl<A: AstPayload> StmtP<A> {
     pub fn into_map_payload<B: AstPayload>(
         self,
@@ -145,6 +159,7 @@ impl<A: AstPayload> StmtP<A> {
                 )
             }
             StmtP::For(fr) => StmtP::For(fr.into_map_payload(f)),
+            StmtP::While(w) => StmtP::While(w.into_map_payload(f)),
             StmtP::Def(DefP {
                 name,
                 params,
@@ -159,6 +174,7 @@ impl<A: AstPayload> StmtP<A> {
                 payload: f.map_def(payload),
             }),
             StmtP::Load(load) => StmtP::Load(load.into_map_payload(f)),
+            StmtP::Error => StmtP::Error,
         }
     }
 }
@@ -230,6 +246,7 @@ impl<A: AstPayload> ExprP<A> {
             ExprP::Dict(kvs) => {
                 ExprP::Dict(kvs.into_map(|(k, v)| (k.into_map_payload(f), v.into_map_payload(f))))
             }
+            ExprP::Set(es) => ExprP::Set(es.into_map(|e| e.into_map_payload(f))),
             ExprP::ListComprehension(e, c0, cs) => ExprP::ListComprehension(
                 Box::new(e.into_map_payload(f)),
                 Box::new(c0.into_map_payload(f)),
@@ -243,6 +260,11 @@ impl<A: AstPayload> ExprP<A> {
                     cs.into_map(|c| c.into_map_payload(f)),
                 )
             }
+            ExprP::SetComprehension(e, c0, cs) => ExprP::SetComprehension(
+                Box::new(e.into_map_payload(f)),
+                Box::new(c0.into_map_payload(f)),
+                cs.into_map(|c| c.into_map_payload(f)),
+            ),
             ExprP::FString(fstring) => ExprP::FString(fstring.into_map_payload(f)),
         }
     }
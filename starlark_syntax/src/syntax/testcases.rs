@@ -15,7 +15,16 @@
  * limitations under the License.
  */
 
+use crate::syntax::ast::AstStmt;
+use crate::syntax::ast::StmtP;
+use crate::syntax::cst::CstModule;
+use crate::syntax::cst::TriviaKind;
+use crate::syntax::format;
 use crate::syntax::grammar_tests;
+use crate::syntax::module::AstModuleFields;
+use crate::syntax::unparse;
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
 
 macro_rules! testcases_parse {
     ($($x:expr)*) => {
@@ -214,3 +223,72 @@ fn parsing_testcases() {
         grammar_tests::parse(content);
     }
 }
+
+#[test]
+fn cst_round_trips_testcases() {
+    for (name, content) in TESTCASE_FILES {
+        let module =
+            CstModule::parse(name, (*content).to_owned(), &Dialect::AllOptionsInternal).unwrap();
+        assert_eq!(*content, module.to_string(), "{}", name);
+    }
+}
+
+/// The AST of a module as text, with the symbols of `load()` statements sorted like
+/// the formatter does.
+fn ast_with_sorted_loads(module: &CstModule) -> String {
+    fn sort_loads(stmt: &mut AstStmt) {
+        match &mut stmt.node {
+            StmtP::Load(load) => load.args.sort_by(|a, b| a.local.ident.cmp(&b.local.ident)),
+            _ => stmt.visit_stmt_mut(sort_loads),
+        }
+    }
+    let mut stmt = module.ast().statement().clone();
+    sort_loads(&mut stmt);
+    stmt.to_string()
+}
+
+fn comments(module: &CstModule) -> Vec<&str> {
+    module
+        .tokens()
+        .iter()
+        .flat_map(|token| token.leading_trivia.iter().chain(&token.trailing_trivia))
+        .chain(module.end_trivia())
+        .filter(|trivia| trivia.kind == TriviaKind::Comment)
+        .map(|trivia| module.text(trivia.span).trim_end())
+        .collect()
+}
+
+#[test]
+fn format_testcases() {
+    for (name, content) in TESTCASE_FILES {
+        let module =
+            CstModule::parse(name, (*content).to_owned(), &Dialect::AllOptionsInternal).unwrap();
+        let formatted = format::format(&module);
+        let reparsed =
+            CstModule::parse(name, formatted.clone(), &Dialect::AllOptionsInternal).unwrap();
+        assert_eq!(
+            ast_with_sorted_loads(&module),
+            ast_with_sorted_loads(&reparsed),
+            "{}",
+            name
+        );
+        assert_eq!(comments(&module), comments(&reparsed), "{}", name);
+        assert_eq!(formatted, format::format(&reparsed), "{}", name);
+    }
+}
+
+#[test]
+fn unparse_testcases() {
+    for (name, content) in TESTCASE_FILES {
+        let module =
+            AstModule::parse(name, (*content).to_owned(), &Dialect::AllOptionsInternal).unwrap();
+        let unparsed = unparse::unparse(&module, &Dialect::AllOptionsInternal).unwrap();
+        let reparsed = AstModule::parse(name, unparsed, &Dialect::AllOptionsInternal).unwrap();
+        assert_eq!(
+            unparse::tests::without_spans(module.statement()),
+            unparse::tests::without_spans(reparsed.statement()),
+            "{}",
+            name
+        );
+    }
+}
@@ -226,6 +226,7 @@ impl<'a, P: AstPayload> TypeExprUnpackP<'a, P> {
             }
             ExprP::Literal(AstLiteral::Int(_)) => err("int"),
             ExprP::Literal(AstLiteral::Float(_)) => err("float"),
+            ExprP::Literal(AstLiteral::Bytes(_)) => err("bytes literal"),
             ExprP::Literal(AstLiteral::Ellipsis) => Ok(Spanned {
                 span,
                 node: TypeExprUnpackP::Ellipsis,
@@ -262,8 +263,10 @@ impl<'a, P: AstPayload> TypeExprUnpackP<'a, P> {
                 }
             }
             ExprP::Dict(..) => err("dict"),
+            ExprP::Set(..) => err("set"),
             ExprP::ListComprehension(..) => err("list comprehension"),
             ExprP::DictComprehension(..) => err("dict comprehension"),
+            ExprP::SetComprehension(..) => err("set comprehension"),
             ExprP::FString(..) => err("f-string"),
         }
     }
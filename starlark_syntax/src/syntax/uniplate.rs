@@ -21,6 +21,8 @@
 // Most consistent to use the closure everywhere.
 #![allow(clippy::redundant_closure)]
 
+use dupe::Dupe;
+
 use crate::syntax::ast::AssignP;
 use crate::syntax::ast::AssignTargetP;
 use crate::syntax::ast::AstAssignIdentP;
@@ -38,12 +40,24 @@ use crate::syntax::ast::LambdaP;
 use crate::syntax::ast::ParameterP;
 use crate::syntax::ast::StmtP;
 use crate::syntax::ast::TypeExprP;
+use crate::syntax::ast::WhileP;
 
 pub enum Visit<'a, P: AstPayload> {
     Stmt(&'a AstStmtP<P>),
     Expr(&'a AstExprP<P>),
 }
 
+// Derived implementations would require `P: Copy`.
+impl<P: AstPayload> Clone for Visit<'_, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<P: AstPayload> Copy for Visit<'_, P> {}
+
+impl<P: AstPayload> Dupe for Visit<'_, P> {}
+
 pub enum VisitMut<'a, P: AstPayload> {
     Stmt(&'a mut AstStmtP<P>),
     Expr(&'a mut AstExprP<P>),
@@ -120,6 +134,10 @@ impl<P: AstPayload> StmtP<P> {
                 f(Visit::Expr(over));
                 f(Visit::Stmt(body));
             }
+            StmtP::While(WhileP { cond, body }) => {
+                f(Visit::Expr(cond));
+                f(Visit::Stmt(body));
+            }
             // Nothing else contains nested statements
             StmtP::Break => {}
             StmtP::Continue => {}
@@ -138,6 +156,7 @@ impl<P: AstPayload> StmtP<P> {
                 f(Visit::Expr(rhs));
             }
             StmtP::Load(..) => {}
+            StmtP::Error => {}
         }
     }
 
@@ -174,6 +193,10 @@ impl<P: AstPayload> StmtP<P> {
                 f(VisitMut::Expr(over));
                 f(VisitMut::Stmt(body));
             }
+            StmtP::While(WhileP { cond, body }) => {
+                f(VisitMut::Expr(cond));
+                f(VisitMut::Stmt(body));
+            }
             // Nothing else contains nested statements
             StmtP::Break => {}
             StmtP::Continue => {}
@@ -193,6 +216,7 @@ impl<P: AstPayload> StmtP<P> {
                 f(VisitMut::Expr(rhs));
             }
             StmtP::Load(..) => {}
+            StmtP::Error => {}
         }
     }
 
@@ -434,6 +458,7 @@ impl<P: AstPayload> ExprP<P> {
                 f(x);
                 f(y);
             }),
+            ExprP::Set(x) => x.iter().for_each(|x| f(x)),
             ExprP::ListComprehension(x, for_, y) => {
                 for_.visit_expr(|x| f(x));
                 y.iter().for_each(|x| x.visit_expr(|x| f(x)));
@@ -445,6 +470,11 @@ impl<P: AstPayload> ExprP<P> {
                 f(&x.0);
                 f(&x.1);
             }
+            ExprP::SetComprehension(x, for_, y) => {
+                for_.visit_expr(|x| f(x));
+                y.iter().for_each(|x| x.visit_expr(|x| f(x)));
+                f(x);
+            }
             ExprP::FString(fstring) => {
                 for expr in &fstring.expressions {
                     f(expr);
@@ -534,6 +564,7 @@ impl<P: AstPayload> ExprP<P> {
                 f(x);
                 f(y);
             }),
+            ExprP::Set(x) => x.iter_mut().for_each(|x| f(x)),
             ExprP::ListComprehension(x, for_, y) => {
                 for_.visit_expr_mut(|x| f(x));
                 y.iter_mut().for_each(|x| x.visit_expr_mut(|x| f(x)));
@@ -545,6 +576,11 @@ impl<P: AstPayload> ExprP<P> {
                 f(&mut x.0);
                 f(&mut x.1);
             }
+            ExprP::SetComprehension(x, for_, y) => {
+                for_.visit_expr_mut(|x| f(x));
+                y.iter_mut().for_each(|x| x.visit_expr_mut(|x| f(x)));
+                f(x);
+            }
             ExprP::FString(fstring) => {
                 for expr in &mut fstring.expressions {
                     f(expr);
@@ -0,0 +1,1383 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Turn an AST back into source code.
+//!
+//! Unlike the [`Display`](std::fmt::Display) implementations of the AST, which are
+//! meant for debugging, the unparser produces code which parses back to the same AST,
+//! ignoring spans. Parentheses are only added where the precedence of the operators
+//! requires them, and blocks are indented by four spaces. Unparsing fails if the AST
+//! uses syntax which the given [`Dialect`] does not allow, or has [`StmtP::Error`]
+//! statements from [`AstModule::parse_with_recovery`], so that the output parses
+//! with that dialect.
+//!
+//! Comments and the layout of the original code are not part of the AST, so they are
+//! lost. Use the [formatter](crate::syntax::format) to reformat code while keeping them.
+
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Write;
+
+use crate::codemap::Span;
+use crate::error::Error;
+use crate::error::ErrorKind;
+use crate::syntax::ast::fmt_string_literal;
+use crate::syntax::ast::ArgumentP;
+use crate::syntax::ast::AssignP;
+use crate::syntax::ast::AssignTargetP;
+use crate::syntax::ast::AstAssignTargetP;
+use crate::syntax::ast::AstExprP;
+use crate::syntax::ast::AstLiteral;
+use crate::syntax::ast::AstParameterP;
+use crate::syntax::ast::AstPayload;
+use crate::syntax::ast::AstStmtP;
+use crate::syntax::ast::AstTypeExprP;
+use crate::syntax::ast::BinOp;
+use crate::syntax::ast::ClauseP;
+use crate::syntax::ast::DefP;
+use crate::syntax::ast::ExprP;
+use crate::syntax::ast::FStringP;
+use crate::syntax::ast::ForClauseP;
+use crate::syntax::ast::ForP;
+use crate::syntax::ast::LambdaP;
+use crate::syntax::ast::LoadP;
+use crate::syntax::ast::ParameterP;
+use crate::syntax::ast::StmtP;
+use crate::syntax::ast::WhileP;
+use crate::syntax::module::first_error_stmt;
+use crate::syntax::module::AstModuleFields;
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
+use crate::syntax::DialectTypes;
+
+#[derive(Debug, thiserror::Error)]
+enum UnparseError {
+    #[error("Cannot unparse a statement with a syntax error")]
+    SyntaxError,
+    #[error("Cannot unparse {0}, which is not allowed in this dialect")]
+    NotInDialect(&'static str),
+}
+
+/// The width of one level of indentation.
+const INDENT: &str = "    ";
+
+// The precedence of expressions, from the loosest to the tightest binding.
+pub(crate) const PREC_TEST: u8 = 0;
+pub(crate) const PREC_IF: u8 = 1;
+pub(crate) const PREC_OR: u8 = 2;
+pub(crate) const PREC_AND: u8 = 3;
+pub(crate) const PREC_NOT: u8 = 4;
+pub(crate) const PREC_COMPARE: u8 = 5;
+pub(crate) const PREC_BIT_OR: u8 = 6;
+pub(crate) const PREC_UNARY: u8 = 12;
+pub(crate) const PREC_PRIMARY: u8 = 13;
+
+pub(crate) fn bin_op_precedence(op: BinOp) -> u8 {
+    match op {
+        BinOp::Or => PREC_OR,
+        BinOp::And => PREC_AND,
+        BinOp::Equal
+        | BinOp::NotEqual
+        | BinOp::Less
+        | BinOp::Greater
+        | BinOp::LessOrEqual
+        | BinOp::GreaterOrEqual
+        | BinOp::In
+        | BinOp::NotIn => PREC_COMPARE,
+        BinOp::BitOr => PREC_BIT_OR,
+        BinOp::BitXor => 7,
+        BinOp::BitAnd => 8,
+        BinOp::LeftShift | BinOp::RightShift => 9,
+        BinOp::Add | BinOp::Subtract => 10,
+        BinOp::Multiply | BinOp::Percent | BinOp::Divide | BinOp::FloorDivide => 11,
+    }
+}
+
+pub(crate) fn precedence<P: AstPayload>(expr: &ExprP<P>) -> u8 {
+    match expr {
+        ExprP::Lambda(_) => PREC_TEST,
+        ExprP::If(_) => PREC_IF,
+        ExprP::Op(_, op, _) => bin_op_precedence(*op),
+        ExprP::Not(_) => PREC_NOT,
+        ExprP::Minus(_) | ExprP::Plus(_) | ExprP::BitNot(_) => PREC_UNARY,
+        _ => PREC_PRIMARY,
+    }
+}
+
+/// A string literal, quoted and escaped.
+struct StringLiteral<'a>(&'a str);
+
+impl Display for StringLiteral<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fmt_string_literal(f, self.0)
+    }
+}
+
+/// Unparse a module.
+///
+/// Fails if the module contains a [`StmtP::Error`] statement, or syntax which `dialect`
+/// does not allow.
+pub fn unparse(module: &AstModule, dialect: &Dialect) -> crate::Result<String> {
+    if let Some((span, e)) = check_stmt(module.statement(), dialect) {
+        return Err(Error::new_spanned(
+            ErrorKind::Other(anyhow::Error::new(e)),
+            span,
+            module.codemap(),
+        ));
+    }
+    Ok(unparse_valid_stmt(module.statement()))
+}
+
+/// Unparse a statement, as lines of code at the top level.
+///
+/// Fails if the statement contains a [`StmtP::Error`] statement, or syntax which
+/// `dialect` does not allow.
+pub fn unparse_stmt<P: AstPayload>(stmt: &AstStmtP<P>, dialect: &Dialect) -> crate::Result<String> {
+    if let Some((_, e)) = check_stmt(stmt, dialect) {
+        return Err(Error::new_other(e));
+    }
+    Ok(unparse_valid_stmt(stmt))
+}
+
+/// Unparse an expression.
+///
+/// Fails if the expression uses syntax which `dialect` does not allow.
+pub fn unparse_expr<P: AstPayload>(expr: &AstExprP<P>, dialect: &Dialect) -> crate::Result<String> {
+    if let Some((_, e)) = check_expr(expr, dialect) {
+        return Err(Error::new_other(e));
+    }
+    let mut unparser = Unparser::default();
+    unparser.expr(expr, PREC_TEST);
+    Ok(unparser.out)
+}
+
+/// Unparse a type expression.
+pub fn unparse_type_expr<P: AstPayload>(
+    ty: &AstTypeExprP<P>,
+    dialect: &Dialect,
+) -> crate::Result<String> {
+    unparse_expr(&ty.expr, dialect)
+}
+
+/// The first syntax error in `stmt`, or else a construct in it which `dialect` does not
+/// allow, with its span.
+fn check_stmt<P: AstPayload>(
+    stmt: &AstStmtP<P>,
+    dialect: &Dialect,
+) -> Option<(Span, UnparseError)> {
+    if let Some(span) = first_error_stmt(stmt) {
+        return Some((span, UnparseError::SyntaxError));
+    }
+    let mut res = not_in_dialect_stmt(stmt, dialect, true);
+    stmt.visit_expr(|x| {
+        if res.is_none() {
+            res = not_in_dialect_expr(x, dialect);
+        }
+    });
+    res.map(|(span, what)| (span, UnparseError::NotInDialect(what)))
+}
+
+fn check_expr<P: AstPayload>(
+    expr: &AstExprP<P>,
+    dialect: &Dialect,
+) -> Option<(Span, UnparseError)> {
+    not_in_dialect_expr(expr, dialect).map(|(span, what)| (span, UnparseError::NotInDialect(what)))
+}
+
+/// The same checks as the parser does for the dialect, see `validate_module`.
+fn not_in_dialect_stmt<P: AstPayload>(
+    stmt: &AstStmtP<P>,
+    dialect: &Dialect,
+    top_level: bool,
+) -> Option<(Span, &'static str)> {
+    let types = dialect.enable_types != DialectTypes::Disable;
+    let what = match &stmt.node {
+        StmtP::Def(_) if !dialect.enable_def => Some("`def`"),
+        StmtP::While(_) if !dialect.enable_while => Some("`while`"),
+        StmtP::Load(_) if !dialect.enable_load => Some("`load`"),
+        StmtP::For(_) | StmtP::While(_) | StmtP::If(..) | StmtP::IfElse(..)
+            if top_level && !dialect.enable_top_level_stmt =>
+        {
+            Some("a loop or `if` outside `def`")
+        }
+        StmtP::Assign(AssignP { ty: Some(_), .. }) if !types => Some("a type annotation"),
+        StmtP::Def(DefP {
+            return_type: Some(_),
+            ..
+        }) if !types => Some("a type annotation"),
+        _ => None,
+    };
+    if let Some(what) = what {
+        return Some((stmt.span, what));
+    }
+    if let StmtP::Def(def) = &stmt.node {
+        if let Some(res) = not_in_dialect_params(&def.params, dialect) {
+            return Some(res);
+        }
+    }
+    let top_level = top_level && matches!(stmt.node, StmtP::Statements(_));
+    let mut res = None;
+    stmt.visit_stmt(|x| {
+        if res.is_none() {
+            res = not_in_dialect_stmt(x, dialect, top_level);
+        }
+    });
+    res
+}
+
+fn not_in_dialect_expr<P: AstPayload>(
+    expr: &AstExprP<P>,
+    dialect: &Dialect,
+) -> Option<(Span, &'static str)> {
+    let what = match &expr.node {
+        ExprP::Lambda(_) if !dialect.enable_lambda => Some("`lambda`"),
+        ExprP::Literal(AstLiteral::Bytes(_)) if !dialect.enable_bytes => Some("a bytes literal"),
+        ExprP::Literal(AstLiteral::Ellipsis) if dialect.enable_types == DialectTypes::Disable => {
+            Some("`...`")
+        }
+        ExprP::Set(_) | ExprP::SetComprehension(..) if !dialect.enable_set_literals => {
+            Some("a set literal")
+        }
+        ExprP::FString(_) if !dialect.enable_f_strings => Some("an f-string"),
+        _ => None,
+    };
+    if let Some(what) = what {
+        return Some((expr.span, what));
+    }
+    if let ExprP::Lambda(lambda) = &expr.node {
+        if let Some(res) = not_in_dialect_params(&lambda.params, dialect) {
+            return Some(res);
+        }
+    }
+    let mut res = None;
+    expr.visit_expr(|x| {
+        if res.is_none() {
+            res = not_in_dialect_expr(x, dialect);
+        }
+    });
+    res
+}
+
+fn not_in_dialect_params<P: AstPayload>(
+    params: &[AstParameterP<P>],
+    dialect: &Dialect,
+) -> Option<(Span, &'static str)> {
+    let types = dialect.enable_types != DialectTypes::Disable;
+    params.iter().find_map(|param| {
+        let what = match &param.node {
+            ParameterP::NoArgs if !dialect.enable_keyword_only_arguments => {
+                "keyword-only arguments"
+            }
+            ParameterP::Slash if !dialect.enable_positional_only_arguments => {
+                "positional-only arguments"
+            }
+            ParameterP::Normal(_, Some(_), _)
+            | ParameterP::Args(_, Some(_))
+            | ParameterP::KwArgs(_, Some(_))
+                if !types =>
+            {
+                "a type annotation"
+            }
+            _ => return None,
+        };
+        Some((param.span, what))
+    })
+}
+
+fn unparse_valid_stmt<P: AstPayload>(stmt: &AstStmtP<P>) -> String {
+    let mut unparser = Unparser::default();
+    unparser.stmts(stmt);
+    unparser.out
+}
+
+#[derive(Default)]
+struct Unparser {
+    out: String,
+    indent: usize,
+}
+
+impl Unparser {
+    fn line_start(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    fn comma_separated<T>(&mut self, items: &[T], mut item: impl FnMut(&mut Self, &T)) {
+        for (i, x) in items.iter().enumerate() {
+            if i != 0 {
+                self.out.push_str(", ");
+            }
+            item(self, x);
+        }
+    }
+
+    /// Print the statements of a block, one per line.
+    fn stmts<P: AstPayload>(&mut self, stmt: &AstStmtP<P>) {
+        match &stmt.node {
+            StmtP::Statements(stmts) => {
+                for stmt in stmts {
+                    match &stmt.node {
+                        // Nested statements come from `a; b` in a block.
+                        StmtP::Statements(small) if small.iter().all(|x| is_small(x)) => {
+                            self.line_start();
+                            for (i, x) in small.iter().enumerate() {
+                                if i != 0 {
+                                    self.out.push_str("; ");
+                                }
+                                self.small_stmt(x);
+                            }
+                            self.out.push('\n');
+                        }
+                        _ => self.stmts(stmt),
+                    }
+                }
+            }
+            _ => {
+                self.line_start();
+                self.stmt(stmt);
+            }
+        }
+    }
+
+    /// Print an indented block.
+    fn block<P: AstPayload>(&mut self, stmt: &AstStmtP<P>) {
+        self.indent += 1;
+        match &stmt.node {
+            // Only the top level of a module may be empty.
+            StmtP::Statements(stmts) if stmts.is_empty() => {
+                self.line_start();
+                self.out.push_str("pass\n");
+            }
+            _ => self.stmts(stmt),
+        }
+        self.indent -= 1;
+    }
+
+    /// Print a statement which is not [`StmtP::Statements`], after its indentation.
+    fn stmt<P: AstPayload>(&mut self, stmt: &AstStmtP<P>) {
+        match &stmt.node {
+            StmtP::If(cond, then_block) => self.if_stmt(cond, then_block, None),
+            StmtP::IfElse(cond, then_block_else_block) => {
+                let (then_block, else_block) = &**then_block_else_block;
+                self.if_stmt(cond, then_block, Some(else_block));
+            }
+            StmtP::For(ForP { var, over, body }) => {
+                self.out.push_str("for ");
+                self.assign_target(var);
+                self.out.push_str(" in ");
+                self.expr(over, PREC_TEST);
+                self.out.push_str(":\n");
+                self.block(body);
+            }
+            StmtP::While(WhileP { cond, body }) => {
+                self.out.push_str("while ");
+                self.expr(cond, PREC_TEST);
+                self.out.push_str(":\n");
+                self.block(body);
+            }
+            StmtP::Def(DefP {
+                name,
+                params,
+                return_type,
+                body,
+                payload: _,
+            }) => {
+                write!(self.out, "def {}(", name.ident).unwrap();
+                self.comma_separated(params, |this, x| this.param(x));
+                self.out.push(')');
+                if let Some(return_type) = return_type {
+                    self.out.push_str(" -> ");
+                    self.expr(&return_type.expr, PREC_TEST);
+                }
+                self.out.push_str(":\n");
+                self.block(body);
+            }
+            _ => {
+                self.small_stmt(stmt);
+                self.out.push('\n');
+            }
+        }
+    }
+
+    fn if_stmt<P: AstPayload>(
+        &mut self,
+        cond: &AstExprP<P>,
+        then_block: &AstStmtP<P>,
+        else_block: Option<&AstStmtP<P>>,
+    ) {
+        self.out.push_str("if ");
+        self.expr(cond, PREC_TEST);
+        self.out.push_str(":\n");
+        self.block(then_block);
+        if let Some(else_block) = else_block {
+            self.line_start();
+            match &else_block.node {
+                StmtP::If(..) | StmtP::IfElse(..) => {
+                    self.out.push_str("el");
+                    self.stmt(else_block);
+                }
+                _ => {
+                    self.out.push_str("else:\n");
+                    self.block(else_block);
+                }
+            }
+        }
+    }
+
+    /// Print a statement which can be on a line with others, without a newline.
+    fn small_stmt<P: AstPayload>(&mut self, stmt: &AstStmtP<P>) {
+        match &stmt.node {
+            StmtP::Break => self.out.push_str("break"),
+            StmtP::Continue => self.out.push_str("continue"),
+            StmtP::Pass => self.out.push_str("pass"),
+            StmtP::Error => unreachable!("checked before unparsing"),
+            StmtP::Return(None) => self.out.push_str("return"),
+            StmtP::Return(Some(e)) => {
+                self.out.push_str("return ");
+                self.expr_list(e);
+            }
+            StmtP::Expression(e) => self.expr(e, PREC_TEST),
+            StmtP::Assign(AssignP { lhs, ty, rhs }) => {
+                self.assign_target(lhs);
+                if let Some(ty) = ty {
+                    self.out.push_str(": ");
+                    self.expr(&ty.expr, PREC_TEST);
+                }
+                self.out.push_str(" = ");
+                self.expr_list(rhs);
+            }
+            StmtP::AssignModify(lhs, op, rhs) => {
+                self.assign_target(lhs);
+                write!(self.out, "{}", op).unwrap();
+                self.expr_list(rhs);
+            }
+            StmtP::Load(LoadP {
+                module,
+                args,
+                payload: _,
+            }) => {
+                write!(self.out, "load({}", StringLiteral(module)).unwrap();
+                for arg in args {
+                    self.out.push_str(", ");
+                    if arg.local.ident != arg.their.node {
+                        write!(self.out, "{} = ", arg.local.ident).unwrap();
+                    }
+                    write!(self.out, "{}", StringLiteral(&arg.their)).unwrap();
+                }
+                if args.last().is_some_and(|arg| arg.comma.is_some()) {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            StmtP::If(..)
+            | StmtP::IfElse(..)
+            | StmtP::For(..)
+            | StmtP::While(..)
+            | StmtP::Def(..)
+            | StmtP::Statements(..) => unreachable!("not a small statement"),
+        }
+    }
+
+    /// Print an expression where a tuple does not need parentheses.
+    fn expr_list<P: AstPayload>(&mut self, expr: &AstExprP<P>) {
+        match &expr.node {
+            ExprP::Tuple(xs) if !xs.is_empty() => self.tuple_items(xs),
+            _ => self.expr(expr, PREC_TEST),
+        }
+    }
+
+    fn tuple_items<P: AstPayload>(&mut self, xs: &[AstExprP<P>]) {
+        self.comma_separated(xs, |this, x| this.expr(x, PREC_TEST));
+        if xs.len() == 1 {
+            self.out.push(',');
+        }
+    }
+
+    fn assign_target<P: AstPayload>(&mut self, target: &AstAssignTargetP<P>) {
+        match &target.node {
+            AssignTargetP::Tuple(xs) => {
+                self.out.push('(');
+                self.comma_separated(xs, |this, x| this.assign_target(x));
+                if xs.len() == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            AssignTargetP::Index(array_index) => {
+                self.expr(&array_index.0, PREC_PRIMARY);
+                self.out.push('[');
+                self.expr(&array_index.1, PREC_TEST);
+                self.out.push(']');
+            }
+            AssignTargetP::Dot(object, field) => {
+                self.primary_object(object);
+                write!(self.out, ".{}", field.node).unwrap();
+            }
+            AssignTargetP::Identifier(ident) => self.out.push_str(&ident.ident),
+        }
+    }
+
+    fn param<P: AstPayload>(&mut self, param: &AstParameterP<P>) {
+        let (prefix, name, ty, default) = match &param.node {
+            ParameterP::Slash => return self.out.push('/'),
+            ParameterP::NoArgs => return self.out.push('*'),
+            ParameterP::Normal(name, ty, default) => ("", name, ty, default.as_deref()),
+            ParameterP::Args(name, ty) => ("*", name, ty, None),
+            ParameterP::KwArgs(name, ty) => ("**", name, ty, None),
+        };
+        write!(self.out, "{}{}", prefix, name.ident).unwrap();
+        if let Some(ty) = ty {
+            self.out.push_str(": ");
+            self.expr(&ty.expr, PREC_TEST);
+        }
+        if let Some(default) = default {
+            self.out.push_str(" = ");
+            self.expr(default, PREC_TEST);
+        }
+    }
+
+    /// Print the object of a `.`, where a number needs parentheses so the dot is not
+    /// lexed as a decimal point.
+    fn primary_object<P: AstPayload>(&mut self, object: &AstExprP<P>) {
+        match &object.node {
+            ExprP::Literal(AstLiteral::Int(_) | AstLiteral::Float(_)) => {
+                self.out.push('(');
+                self.expr(object, PREC_TEST);
+                self.out.push(')');
+            }
+            _ => self.expr(object, PREC_PRIMARY),
+        }
+    }
+
+    /// Print an expression, in parentheses if it binds looser than `min_prec`.
+    fn expr<P: AstPayload>(&mut self, expr: &AstExprP<P>, min_prec: u8) {
+        let parens = precedence(&expr.node) < min_prec;
+        if parens {
+            self.out.push('(');
+        }
+        self.expr_inner(expr);
+        if parens {
+            self.out.push(')');
+        }
+    }
+
+    fn expr_inner<P: AstPayload>(&mut self, expr: &AstExprP<P>) {
+        match &expr.node {
+            ExprP::Tuple(xs) => {
+                self.out.push('(');
+                self.tuple_items(xs);
+                self.out.push(')');
+            }
+            ExprP::Dot(object, field) => {
+                self.primary_object(object);
+                write!(self.out, ".{}", field.node).unwrap();
+            }
+            ExprP::Call(f, args) => {
+                self.expr(f, PREC_PRIMARY);
+                self.out.push('(');
+                self.comma_separated(&args.args, |this, arg| match &arg.node {
+                    ArgumentP::Positional(e) => this.expr(e, PREC_TEST),
+                    ArgumentP::Named(name, e) => {
+                        write!(this.out, "{} = ", name.node).unwrap();
+                        this.expr(e, PREC_TEST);
+                    }
+                    ArgumentP::Args(e) => {
+                        this.out.push('*');
+                        this.expr(e, PREC_TEST);
+                    }
+                    ArgumentP::KwArgs(e) => {
+                        this.out.push_str("**");
+                        this.expr(e, PREC_TEST);
+                    }
+                });
+                self.out.push(')');
+            }
+            ExprP::Index(array_index) => {
+                self.expr(&array_index.0, PREC_PRIMARY);
+                self.out.push('[');
+                self.expr(&array_index.1, PREC_TEST);
+                self.out.push(']');
+            }
+            ExprP::Index2(array_index0_index1) => {
+                let (array, index0, index1) = &**array_index0_index1;
+                self.expr(array, PREC_PRIMARY);
+                self.out.push('[');
+                self.expr(index0, PREC_TEST);
+                self.out.push_str(", ");
+                self.expr(index1, PREC_TEST);
+                self.out.push(']');
+            }
+            ExprP::Slice(array, start, stop, step) => {
+                self.expr(array, PREC_PRIMARY);
+                self.out.push('[');
+                if let Some(start) = start {
+                    self.expr(start, PREC_TEST);
+                }
+                self.out.push(':');
+                if let Some(stop) = stop {
+                    self.expr(stop, PREC_TEST);
+                }
+                if let Some(step) = step {
+                    self.out.push(':');
+                    self.expr(step, PREC_TEST);
+                }
+                self.out.push(']');
+            }
+            ExprP::Identifier(ident) => self.out.push_str(&ident.ident),
+            ExprP::Lambda(LambdaP {
+                params,
+                body,
+                payload: _,
+            }) => {
+                self.out.push_str("lambda");
+                if !params.is_empty() {
+                    self.out.push(' ');
+                }
+                self.comma_separated(params, |this, x| this.param(x));
+                self.out.push_str(": ");
+                self.expr(body, PREC_TEST);
+            }
+            ExprP::Literal(AstLiteral::Float(x)) => {
+                if x.is_finite() {
+                    // Unlike `Display`, `Debug` always prints a decimal point or an exponent.
+                    write!(self.out, "{:?}", x.node).unwrap();
+                } else {
+                    // The parser only produces infinity for literals that are too large.
+                    self.out.push_str("1e999");
+                }
+            }
+            ExprP::Literal(x) => write!(self.out, "{}", x).unwrap(),
+            ExprP::Not(x) => {
+                self.out.push_str("not ");
+                self.expr(x, PREC_NOT);
+            }
+            ExprP::Minus(x) => {
+                self.out.push('-');
+                self.expr(x, PREC_UNARY);
+            }
+            ExprP::Plus(x) => {
+                self.out.push('+');
+                self.expr(x, PREC_UNARY);
+            }
+            ExprP::BitNot(x) => {
+                self.out.push('~');
+                self.expr(x, PREC_UNARY);
+            }
+            ExprP::Op(lhs, op, rhs) => {
+                let prec = bin_op_precedence(*op);
+                // Comparisons do not chain, so neither side can be a comparison.
+                let lhs_prec = if prec == PREC_COMPARE { prec + 1 } else { prec };
+                self.expr(lhs, lhs_prec);
+                write!(self.out, "{}", op).unwrap();
+                self.expr(rhs, prec + 1);
+            }
+            ExprP::If(cond_then_else) => {
+                let (cond, then_expr, else_expr) = &**cond_then_else;
+                self.expr(then_expr, PREC_OR);
+                self.out.push_str(" if ");
+                self.expr(cond, PREC_OR);
+                self.out.push_str(" else ");
+                self.expr(else_expr, PREC_TEST);
+            }
+            ExprP::List(xs) => {
+                self.out.push('[');
+                self.comma_separated(xs, |this, x| this.expr(x, PREC_TEST));
+                self.out.push(']');
+            }
+            ExprP::Dict(xs) => {
+                self.out.push('{');
+                self.comma_separated(xs, |this, (k, v)| {
+                    this.expr(k, PREC_TEST);
+                    this.out.push_str(": ");
+                    this.expr(v, PREC_TEST);
+                });
+                self.out.push('}');
+            }
+            ExprP::Set(xs) => {
+                self.out.push('{');
+                self.comma_separated(xs, |this, x| this.expr(x, PREC_TEST));
+                self.out.push('}');
+            }
+            ExprP::ListComprehension(x, for_, clauses) => {
+                self.out.push('[');
+                self.expr(x, PREC_TEST);
+                self.comprehension(for_, clauses);
+                self.out.push(']');
+            }
+            ExprP::DictComprehension(k_v, for_, clauses) => {
+                let (k, v) = &**k_v;
+                self.out.push('{');
+                self.expr(k, PREC_TEST);
+                self.out.push_str(": ");
+                self.expr(v, PREC_TEST);
+                self.comprehension(for_, clauses);
+                self.out.push('}');
+            }
+            ExprP::SetComprehension(x, for_, clauses) => {
+                self.out.push('{');
+                self.expr(x, PREC_TEST);
+                self.comprehension(for_, clauses);
+                self.out.push('}');
+            }
+            ExprP::FString(fstring) => self.fstring(fstring),
+        }
+    }
+
+    fn comprehension<P: AstPayload>(&mut self, for_: &ForClauseP<P>, clauses: &[ClauseP<P>]) {
+        self.for_clause(for_);
+        for clause in clauses {
+            match clause {
+                ClauseP::For(for_) => self.for_clause(for_),
+                ClauseP::If(cond) => {
+                    self.out.push_str(" if ");
+                    self.expr(cond, PREC_OR);
+                }
+            }
+        }
+    }
+
+    fn for_clause<P: AstPayload>(&mut self, for_: &ForClauseP<P>) {
+        self.out.push_str(" for ");
+        self.assign_target(&for_.var);
+        self.out.push_str(" in ");
+        self.expr(&for_.over, PREC_OR);
+    }
+
+    /// Print an f-string, putting the names of the expressions back into the format.
+    fn fstring<P: AstPayload>(&mut self, fstring: &FStringP<P>) {
+        let idents: Option<Vec<&str>> = fstring
+            .expressions
+            .iter()
+            .map(|x| match &x.node {
+                ExprP::Identifier(ident) => Some(ident.ident.as_str()),
+                _ => None,
+            })
+            .collect();
+        let Some(idents) = idents else {
+            // The parser only allows identifiers, so print what an f-string means.
+            write!(self.out, "{}.format(", StringLiteral(&fstring.format)).unwrap();
+            self.comma_separated(&fstring.expressions, |this, x| this.expr(x, PREC_TEST));
+            self.out.push(')');
+            return;
+        };
+        let mut idents = idents.into_iter();
+        let mut content = String::with_capacity(fstring.format.len());
+        let mut rest = fstring.format.as_str();
+        while let Some(i) = rest.find(['{', '}']) {
+            content.push_str(&rest[..i]);
+            rest = &rest[i..];
+            let marker = ["{{", "}}", "{}", "{!r}"]
+                .into_iter()
+                .find(|m| rest.starts_with(m))
+                .unwrap_or(&rest[..1]);
+            match marker {
+                "{}" => write!(content, "{{{}}}", idents.next().unwrap_or_default()).unwrap(),
+                "{!r}" => write!(content, "{{{}!r}}", idents.next().unwrap_or_default()).unwrap(),
+                _ => content.push_str(marker),
+            }
+            rest = &rest[marker.len()..];
+        }
+        content.push_str(rest);
+        write!(self.out, "f{}", StringLiteral(&content)).unwrap();
+    }
+}
+
+/// Whether a statement can be one of several on a line.
+fn is_small<P: AstPayload>(stmt: &AstStmtP<P>) -> bool {
+    !matches!(
+        stmt.node,
+        StmtP::If(..)
+            | StmtP::IfElse(..)
+            | StmtP::For(..)
+            | StmtP::While(..)
+            | StmtP::Def(..)
+            | StmtP::Statements(..)
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use num_bigint::BigInt;
+    use rand::rngs::SmallRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::lexer::TokenInt;
+    use crate::syntax::ast::AssignIdentP;
+    use crate::syntax::ast::AssignOp;
+    use crate::syntax::ast::AstAssignTarget;
+    use crate::syntax::ast::AstExpr;
+    use crate::syntax::ast::AstParameter;
+    use crate::syntax::ast::AstStmt;
+    use crate::syntax::ast::CallArgsP;
+    use crate::syntax::ast::Clause;
+    use crate::syntax::ast::Comma;
+    use crate::syntax::ast::ForClause;
+    use crate::syntax::ast::IdentP;
+    use crate::syntax::ast::LoadArgP;
+    use crate::syntax::ast::ToAst;
+    use crate::syntax::ast::TypeExprP;
+    use crate::syntax::grammar_util::statements;
+    use crate::syntax::Dialect;
+
+    /// The debug representation of an AST, without the spans, to compare structure.
+    pub(crate) fn without_spans(stmt: &AstStmt) -> String {
+        const SPAN: &str = "Span { begin: Pos(";
+        let debug = format!("{:?}", stmt);
+        let mut res = String::with_capacity(debug.len());
+        let mut rest = debug.as_str();
+        while let Some(i) = rest.find(SPAN) {
+            res.push_str(&rest[..i]);
+            rest = &rest[i..];
+            rest = &rest[rest.find('}').unwrap() + 1..];
+        }
+        res.push_str(rest);
+        res
+    }
+
+    fn round_trip(program: &str) -> String {
+        let module = AstModule::parse(
+            "test.star",
+            program.to_owned(),
+            &Dialect::AllOptionsInternal,
+        )
+        .unwrap();
+        unparse(&module, &Dialect::AllOptionsInternal).unwrap()
+    }
+
+    #[test]
+    fn unparses_minimal_parens() {
+        assert_eq!("(a + b) * c\n", round_trip("((a + b) * (c))"));
+        assert_eq!("a - (b - c) - d\n", round_trip("(a - (b - c)) - d"));
+        assert_eq!("(a < b) == c\n", round_trip("(a < b) == c"));
+        assert_eq!(
+            "not (a and b) or -x.y\n",
+            round_trip("(not (a and b)) or (-(x.y))")
+        );
+        assert_eq!(
+            "(a if b else c) if (lambda: d) else e\n",
+            round_trip("(a if b else c) if (lambda: d) else e")
+        );
+        assert_eq!("(1).bit_length()\n", round_trip("(1).bit_length()"));
+        assert_eq!("x = 1, (2,), ()\n", round_trip("x = (1, (2,), ())"));
+        assert_eq!("y = 1.0 + 1e100\n", round_trip("y = 1. + 1e+100"));
+    }
+
+    #[test]
+    fn unparses_statements() {
+        let program = r#"
+load("a.star", "x", y = "z",)
+def f(a, b: int = 1, *args, **kwargs) -> str:
+    if a: pass
+    elif b:
+        return f"{a}{{}}{b!r}\n"
+    else:
+        for (k, v) in [(1, 2)]: k += v; break
+    x = 1; y = 2
+"#;
+        assert_eq!(
+            r#"load("a.star", "x", y = "z",)
+def f(a, b: int = 1, *args, **kwargs) -> str:
+    if a:
+        pass
+    elif b:
+        return f"{a}{{}}{b!r}\n"
+    else:
+        for (k, v) in [(1, 2)]:
+            k += v
+            break
+    x = 1; y = 2
+"#,
+            round_trip(program.trim_start())
+        );
+    }
+
+    #[test]
+    fn unparse_fails_on_syntax_errors() {
+        let (module, errors) = AstModule::parse_with_recovery(
+            "test.star",
+            "x = 1\ndef f():\n    return 1 +\n".to_owned(),
+            &Dialect::Standard,
+        );
+        assert!(!errors.is_empty());
+        let err = unparse(&module.unwrap(), &Dialect::Standard).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Cannot unparse a statement with a syntax error"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn unparse_fails_on_syntax_not_in_dialect() {
+        for (program, what) in [
+            ("def f(x):\n    while x: pass\n", "`while`"),
+            ("x = {1, 2}\n", "a set literal"),
+            ("x = [b'a']\n", "a bytes literal"),
+            ("x = f'{y}'\n", "an f-string"),
+            ("def f(*, x): pass\n", "keyword-only arguments"),
+        ] {
+            let module = AstModule::parse(
+                "test.star",
+                program.to_owned(),
+                &Dialect::AllOptionsInternal,
+            )
+            .unwrap();
+            let err = unparse(&module, &Dialect::Standard).unwrap_err();
+            assert!(
+                err.to_string()
+                    .contains(&format!("Cannot unparse {}, which is not allowed", what)),
+                "{}",
+                err
+            );
+            unparse(&module, &Dialect::AllOptionsInternal).unwrap();
+        }
+    }
+
+    /// Generates random ASTs in the form the parser produces.
+    struct Gen {
+        rng: SmallRng,
+    }
+
+    const NAMES: &[&str] = &["a", "b", "foo", "_bar", "x1"];
+
+    /// The depth of the expressions in generated statements.
+    const EXPR_DEPTH: u32 = 4;
+
+    impl Gen {
+        fn name(&mut self) -> String {
+            NAMES[self.rng.gen_range(0..NAMES.len())].to_owned()
+        }
+
+        fn ident(&mut self) -> AstExpr {
+            ExprP::Identifier(
+                IdentP {
+                    ident: self.name(),
+                    payload: (),
+                }
+                .ast(0, 0),
+            )
+            .ast(0, 0)
+        }
+
+        fn string(&mut self) -> String {
+            const CHARS: &[char] = &['a', ' ', '"', '\'', '\\', '\n', '\t', '{', 'é'];
+            (0..self.rng.gen_range(0..5))
+                .map(|_| CHARS[self.rng.gen_range(0..CHARS.len())])
+                .collect()
+        }
+
+        fn exprs(&mut self, depth: u32, max: usize) -> Vec<AstExpr> {
+            (0..self.rng.gen_range(0..=max))
+                .map(|_| self.expr(depth))
+                .collect()
+        }
+
+        fn assign_target(&mut self, depth: u32, allow_tuple: bool) -> AstAssignTarget {
+            let target = match self.rng.gen_range(0..if allow_tuple { 4 } else { 3 }) {
+                0 => AssignTargetP::Dot(Box::new(self.expr(depth)), self.name().ast(0, 0)),
+                1 => AssignTargetP::Index(Box::new((self.expr(depth), self.expr(depth)))),
+                2 => AssignTargetP::Identifier(
+                    AssignIdentP {
+                        ident: self.name(),
+                        payload: (),
+                    }
+                    .ast(0, 0),
+                ),
+                _ => AssignTargetP::Tuple(
+                    (0..self.rng.gen_range(1..3))
+                        .map(|_| self.assign_target(depth, true))
+                        .collect(),
+                ),
+            };
+            target.ast(0, 0)
+        }
+
+        fn params(&mut self, depth: u32, types: bool) -> Vec<AstParameter> {
+            let mut params = Vec::new();
+            let ty = |this: &mut Self| {
+                if types && this.rng.gen_bool(0.3) {
+                    Some(Box::new(
+                        TypeExprP {
+                            expr: this.ident(),
+                            payload: (),
+                        }
+                        .ast(0, 0),
+                    ))
+                } else {
+                    None
+                }
+            };
+            let name = |i: usize| {
+                AssignIdentP {
+                    ident: format!("p{}", i),
+                    payload: (),
+                }
+                .ast(0, 0)
+            };
+            for i in 0..self.rng.gen_range(0..3) {
+                let ty = ty(self);
+                params.push(ParameterP::Normal(name(i), ty, None));
+            }
+            for i in 3..self.rng.gen_range(3..5) {
+                let ty = ty(self);
+                let default = Box::new(self.expr(depth));
+                params.push(ParameterP::Normal(name(i), ty, Some(default)));
+            }
+            if self.rng.gen_bool(0.3) {
+                params.push(ParameterP::Args(name(5), ty(self)));
+            }
+            if self.rng.gen_bool(0.3) {
+                params.push(ParameterP::KwArgs(name(6), ty(self)));
+            }
+            params.into_iter().map(|p| p.ast(0, 0)).collect()
+        }
+
+        fn for_clause(&mut self, depth: u32) -> ForClause {
+            ForClauseP {
+                var: self.assign_target(depth, true),
+                over: self.expr(depth),
+            }
+        }
+
+        fn clauses(&mut self, depth: u32) -> Vec<Clause> {
+            (0..self.rng.gen_range(0..3))
+                .map(|_| {
+                    if self.rng.gen_bool(0.5) {
+                        ClauseP::For(self.for_clause(depth))
+                    } else {
+                        ClauseP::If(self.expr(depth))
+                    }
+                })
+                .collect()
+        }
+
+        fn expr(&mut self, depth: u32) -> AstExpr {
+            if depth == 0 {
+                return self.ident();
+            }
+            let depth = depth - 1;
+            let boxed = |this: &mut Self| Box::new(this.expr(depth));
+            let opt = |this: &mut Self| {
+                if this.rng.gen_bool(0.5) {
+                    Some(Box::new(this.expr(depth)))
+                } else {
+                    None
+                }
+            };
+            const OPS: &[BinOp] = &[
+                BinOp::Or,
+                BinOp::And,
+                BinOp::Equal,
+                BinOp::Less,
+                BinOp::In,
+                BinOp::NotIn,
+                BinOp::BitOr,
+                BinOp::BitXor,
+                BinOp::BitAnd,
+                BinOp::LeftShift,
+                BinOp::Add,
+                BinOp::Subtract,
+                BinOp::Multiply,
+                BinOp::FloorDivide,
+            ];
+            let expr = match self.rng.gen_range(0..26) {
+                0 => return self.ident(),
+                1 => {
+                    let x = self.rng.gen_range(0..=u64::MAX);
+                    ExprP::Literal(AstLiteral::Int(
+                        match i32::try_from(x) {
+                            Ok(x) => TokenInt::I32(x),
+                            Err(_) => TokenInt::BigInt(BigInt::from(x)),
+                        }
+                        .ast(0, 0),
+                    ))
+                }
+                2 => {
+                    let x = self.rng.gen::<f64>() * 10f64.powi(self.rng.gen_range(-10..300));
+                    ExprP::Literal(AstLiteral::Float(x.ast(0, 0)))
+                }
+                3 => ExprP::Literal(AstLiteral::String(self.string().ast(0, 0))),
+                4 => ExprP::Literal(AstLiteral::Ellipsis),
+                5 => ExprP::Tuple(self.exprs(depth, 3)),
+                6 => ExprP::List(self.exprs(depth, 3)),
+                7 => ExprP::Dict(
+                    (0..self.rng.gen_range(0..3))
+                        .map(|_| (self.expr(depth), self.expr(depth)))
+                        .collect(),
+                ),
+                8 => ExprP::Dot(boxed(self), self.name().ast(0, 0)),
+                9 => {
+                    let mut args: Vec<_> = self
+                        .exprs(depth, 2)
+                        .into_iter()
+                        .map(ArgumentP::Positional)
+                        .collect();
+                    for i in 0..self.rng.gen_range(0..2) {
+                        args.push(ArgumentP::Named(
+                            format!("k{}", i).ast(0, 0),
+                            self.expr(depth),
+                        ));
+                    }
+                    if self.rng.gen_bool(0.3) {
+                        args.push(ArgumentP::Args(self.expr(depth)));
+                    }
+                    if self.rng.gen_bool(0.3) {
+                        args.push(ArgumentP::KwArgs(self.expr(depth)));
+                    }
+                    ExprP::Call(
+                        boxed(self),
+                        CallArgsP {
+                            args: args.into_iter().map(|x| x.ast(0, 0)).collect(),
+                        },
+                    )
+                }
+                10 => ExprP::Index(Box::new((self.expr(depth), self.expr(depth)))),
+                11 => ExprP::Index2(Box::new((
+                    self.expr(depth),
+                    self.expr(depth),
+                    self.expr(depth),
+                ))),
+                12 => ExprP::Slice(boxed(self), opt(self), opt(self), opt(self)),
+                13 => ExprP::Lambda(LambdaP {
+                    params: self.params(depth, false),
+                    body: boxed(self),
+                    payload: (),
+                }),
+                14 => ExprP::Not(boxed(self)),
+                15 => ExprP::Minus(boxed(self)),
+                16 => ExprP::Plus(boxed(self)),
+                17 => ExprP::BitNot(boxed(self)),
+                18 | 19 => {
+                    let op = OPS[self.rng.gen_range(0..OPS.len())];
+                    ExprP::Op(boxed(self), op, boxed(self))
+                }
+                20 => ExprP::If(Box::new((
+                    self.expr(depth),
+                    self.expr(depth),
+                    self.expr(depth),
+                ))),
+                21 => ExprP::ListComprehension(
+                    boxed(self),
+                    Box::new(self.for_clause(depth)),
+                    self.clauses(depth),
+                ),
+                22 => ExprP::DictComprehension(
+                    Box::new((self.expr(depth), self.expr(depth))),
+                    Box::new(self.for_clause(depth)),
+                    self.clauses(depth),
+                ),
+                23 => ExprP::Set(
+                    (0..self.rng.gen_range(1..3))
+                        .map(|_| self.expr(depth))
+                        .collect(),
+                ),
+                24 => ExprP::SetComprehension(
+                    boxed(self),
+                    Box::new(self.for_clause(depth)),
+                    self.clauses(depth),
+                ),
+                _ => {
+                    let mut format = String::new();
+                    let mut expressions = Vec::new();
+                    for _ in 0..self.rng.gen_range(0..4) {
+                        match self.rng.gen_range(0..4) {
+                            0 => format.push_str("{{"),
+                            1 => format.push_str(&self.string().replace(['{', '}'], "")),
+                            2 => {
+                                format.push_str("{}");
+                                expressions.push(self.ident());
+                            }
+                            _ => {
+                                format.push_str("{!r}");
+                                expressions.push(self.ident());
+                            }
+                        }
+                    }
+                    ExprP::FString(
+                        FStringP {
+                            format: format.ast(0, 0),
+                            expressions,
+                        }
+                        .ast(0, 0),
+                    )
+                }
+            };
+            expr.ast(0, 0)
+        }
+
+        fn small_stmt(&mut self, in_def: bool, in_for: bool) -> AstStmt {
+            let stmt = match self.rng.gen_range(0..7) {
+                0 => StmtP::Pass,
+                1 if in_for => StmtP::Break,
+                1 => StmtP::Pass,
+                2 if in_def => StmtP::Return(if self.rng.gen_bool(0.8) {
+                    Some(self.expr(EXPR_DEPTH))
+                } else {
+                    None
+                }),
+                2 | 3 => StmtP::Expression(self.expr(EXPR_DEPTH)),
+                4 => {
+                    let lhs = self.assign_target(EXPR_DEPTH, true);
+                    let ty =
+                        if matches!(lhs.node, AssignTargetP::Tuple(_)) || self.rng.gen_bool(0.7) {
+                            None
+                        } else {
+                            Some(
+                                TypeExprP {
+                                    expr: self.ident(),
+                                    payload: (),
+                                }
+                                .ast(0, 0),
+                            )
+                        };
+                    StmtP::Assign(AssignP {
+                        lhs,
+                        ty,
+                        rhs: self.expr(EXPR_DEPTH),
+                    })
+                }
+                5 => StmtP::AssignModify(
+                    self.assign_target(EXPR_DEPTH, false),
+                    AssignOp::Add,
+                    Box::new(self.expr(EXPR_DEPTH)),
+                ),
+                _ if in_for => StmtP::Continue,
+                _ => StmtP::Pass,
+            };
+            stmt.ast(0, 0)
+        }
+
+        fn stmt(&mut self, depth: u32, in_def: bool, in_for: bool) -> AstStmt {
+            if depth == 0 {
+                return self.small_stmt(in_def, in_for);
+            }
+            let depth = depth - 1;
+            let stmt = match self.rng.gen_range(0..8) {
+                0 => StmtP::If(
+                    self.expr(EXPR_DEPTH),
+                    Box::new(self.block(depth, in_def, in_for)),
+                ),
+                1 => StmtP::IfElse(
+                    self.expr(EXPR_DEPTH),
+                    Box::new((
+                        self.block(depth, in_def, in_for),
+                        if self.rng.gen_bool(0.5) {
+                            self.stmt(depth, in_def, in_for)
+                        } else {
+                            self.block(depth, in_def, in_for)
+                        },
+                    )),
+                ),
+                2 => StmtP::For(ForP {
+                    var: self.assign_target(EXPR_DEPTH, true),
+                    over: self.expr(EXPR_DEPTH),
+                    body: Box::new(self.block(depth, in_def, true)),
+                }),
+                3 => StmtP::Def(DefP {
+                    name: AssignIdentP {
+                        ident: self.name(),
+                        payload: (),
+                    }
+                    .ast(0, 0),
+                    params: self.params(EXPR_DEPTH, true),
+                    return_type: None,
+                    body: Box::new(self.block(depth, true, false)),
+                    payload: (),
+                }),
+                4 => StmtP::Statements(
+                    (0..self.rng.gen_range(2..4))
+                        .map(|_| self.small_stmt(in_def, in_for))
+                        .collect(),
+                ),
+                5 => StmtP::While(WhileP {
+                    cond: self.expr(EXPR_DEPTH),
+                    body: Box::new(self.block(depth, in_def, true)),
+                }),
+                _ => return self.small_stmt(in_def, in_for),
+            };
+            stmt.ast(0, 0)
+        }
+
+        fn block(&mut self, depth: u32, in_def: bool, in_for: bool) -> AstStmt {
+            let stmts = (0..self.rng.gen_range(1..4))
+                .map(|_| self.stmt(depth, in_def, in_for))
+                .collect();
+            statements(stmts, 0, 0)
+        }
+
+        fn module(&mut self) -> AstStmt {
+            let mut stmts = Vec::new();
+            if self.rng.gen_bool(0.3) {
+                let len = self.rng.gen_range(1..3);
+                let trailing_comma = self.rng.gen_bool(0.5);
+                let args = (0..len)
+                    .map(|i| LoadArgP {
+                        local: AssignIdentP {
+                            ident: format!("l{}", i),
+                            payload: (),
+                        }
+                        .ast(0, 0),
+                        their: self.name().ast(0, 0),
+                        comma: (i + 1 < len || trailing_comma).then(|| Comma.ast(0, 0)),
+                    })
+                    .collect();
+                stmts.push(
+                    StmtP::Load(LoadP {
+                        module: "m.star".to_owned().ast(0, 0),
+                        args,
+                        payload: (),
+                    })
+                    .ast(0, 0),
+                );
+            }
+            for _ in 0..self.rng.gen_range(0..4) {
+                stmts.push(self.stmt(3, false, false));
+            }
+            statements(stmts, 0, 0)
+        }
+    }
+
+    #[test]
+    fn unparse_round_trips_random_asts() {
+        let mut gen = Gen {
+            rng: SmallRng::seed_from_u64(0),
+        };
+        for _ in 0..2000 {
+            let stmt = gen.module();
+            let program = unparse_stmt(&stmt, &Dialect::AllOptionsInternal).unwrap();
+            let module = match AstModule::parse(
+                "test.star",
+                program.clone(),
+                &Dialect::AllOptionsInternal,
+            ) {
+                Ok(module) => module,
+                Err(e) => panic!("{}\n{}", program, e),
+            };
+            assert_eq!(
+                without_spans(&stmt),
+                without_spans(module.statement()),
+                "{}",
+                program
+            );
+        }
+    }
+}
@@ -29,6 +29,7 @@ use crate::syntax::ast::ForP;
 use crate::syntax::ast::LambdaP;
 use crate::syntax::ast::ParameterP;
 use crate::syntax::ast::Stmt;
+use crate::syntax::ast::WhileP;
 use crate::syntax::call::CallArgsUnpack;
 use crate::syntax::def::DefParams;
 use crate::syntax::state::ParserState;
@@ -90,7 +91,7 @@ pub(crate) fn validate_module(stmt: &AstStmt, parser_state: &mut ParserState) {
         }
     }
 
-    // Inside a for, we allow continue/break, unless we go beneath a def.
+    // Inside a loop, we allow continue/break, unless we go beneath a def.
     // Inside a def, we allow return.
     // All load's must occur at the top-level.
     // At the top-level we only allow for/if when the dialect permits it.
@@ -98,7 +99,7 @@ pub(crate) fn validate_module(stmt: &AstStmt, parser_state: &mut ParserState) {
         stmt: &AstStmt,
         parser_state: &mut ParserState,
         top_level: bool,
-        inside_for: bool,
+        inside_loop: bool,
         inside_def: bool,
     ) {
         let span = stmt.span;
@@ -118,19 +119,36 @@ pub(crate) fn validate_module(stmt: &AstStmt, parser_state: &mut ParserState) {
                     f(body, parser_state, false, true, inside_def)
                 }
             }
+            Stmt::While(WhileP { body, .. }) => {
+                if !parser_state.dialect.enable_while {
+                    parser_state.error(span, "`while` is not allowed in this dialect")
+                } else if top_level && !parser_state.dialect.enable_top_level_stmt {
+                    parser_state.error(span, "`while` cannot be used outside `def` in this dialect")
+                } else {
+                    f(body, parser_state, false, true, inside_def)
+                }
+            }
             Stmt::If(..) | Stmt::IfElse(..) => {
                 if top_level && !parser_state.dialect.enable_top_level_stmt {
                     parser_state.error(span, "`if` cannot be used outside `def` in this dialect")
                 } else {
                     stmt.node
-                        .visit_stmt(|x| f(x, parser_state, false, inside_for, inside_def))
+                        .visit_stmt(|x| f(x, parser_state, false, inside_loop, inside_def))
                 }
             }
-            Stmt::Break if !inside_for => {
-                parser_state.error(span, "`break` cannot be used outside of a `for` loop")
+            Stmt::Break if !inside_loop => {
+                if parser_state.dialect.enable_while {
+                    parser_state.error(span, "`break` cannot be used outside of a loop")
+                } else {
+                    parser_state.error(span, "`break` cannot be used outside of a `for` loop")
+                }
             }
-            Stmt::Continue if !inside_for => {
-                parser_state.error(span, "`continue` cannot be used outside of a `for` loop")
+            Stmt::Continue if !inside_loop => {
+                if parser_state.dialect.enable_while {
+                    parser_state.error(span, "`continue` cannot be used outside of a loop")
+                } else {
+                    parser_state.error(span, "`continue` cannot be used outside of a `for` loop")
+                }
             }
             Stmt::Return(_) if !inside_def => {
                 parser_state.error(span, "`return` cannot be used outside of a `def` function")
@@ -145,7 +163,7 @@ pub(crate) fn validate_module(stmt: &AstStmt, parser_state: &mut ParserState) {
             }
             _ => stmt
                 .node
-                .visit_stmt(|x| f(x, parser_state, top_level, inside_for, inside_def)),
+                .visit_stmt(|x| f(x, parser_state, top_level, inside_loop, inside_def)),
         }
     }
 
@@ -27,6 +27,8 @@ let client: LanguageClient;
 
 interface AdditionalClientSettings {
     enable_goto_definition: boolean;
+    enable_typecheck: boolean;
+    index_extensions: string[];
 }
 
 /// Get a setting at the path, or throw an error if it's not set.
@@ -41,6 +43,8 @@ function requireSetting<T>(path: string): T {
 function additionalClientSettings(): AdditionalClientSettings {
     return {
         enable_goto_definition: vscode.workspace.getConfiguration().get("starlark.enableGotoDefinition", true),
+        enable_typecheck: vscode.workspace.getConfiguration().get("starlark.enableTypecheck", false),
+        index_extensions: vscode.workspace.getConfiguration().get("starlark.indexExtensions", []),
     };
 }
 
@@ -89,7 +93,11 @@ export function activate(context: ExtensionContext) {
     );
 
     const path: string = requireSetting("starlark.lspPath");
-    const args: [string] = requireSetting("starlark.lspArguments");
+    let args: string[] = requireSetting("starlark.lspArguments");
+    const dialect: string = vscode.workspace.getConfiguration().get("starlark.dialect", "");
+    if (dialect !== "") {
+        args = args.concat(["--dialect", dialect]);
+    }
 
     // Otherwise to spawn the server
     let serverOptions: ServerOptions = { command: path, args: args };